@@ -0,0 +1,67 @@
+//! `cargo bench --bench attacks`: square attack queries over a middlegame,
+//! an open endgame and a closed position.
+
+use ananke::bitboard::{Bitboard, Square};
+use ananke::board::Board;
+use ananke::magic;
+use ananke::types::Color;
+use std::hint::black_box;
+use std::time::Instant;
+
+const ITERATIONS: u32 = 100_000;
+
+const FENS: [&str; 3] = [
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r1bqkb1r/pp3ppp/2n1pn2/2ppP3/3P4/2P2N2/PP1N1PPP/R1BQKB1R w KQkq - 0 1",
+];
+
+fn time(name: &str, mut f: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let ns = start.elapsed().as_nanos() as f64 / ITERATIONS as f64;
+    println!("{:<36} {:>8.1} ns/iter", name, ns);
+}
+
+fn main() {
+    magic::initialize();
+    let boards: Vec<Board> = FENS.iter().map(|f| Board::from_fen(f).unwrap()).collect();
+
+    time("is_square_attacked, 64 squares x 2", || {
+        for board in &boards {
+            for sq in 0..64 {
+                for color in [Color::White, Color::Black] {
+                    black_box(board.is_square_attacked(Square::new(sq), color));
+                }
+            }
+        }
+    });
+
+    // the squares the king crosses when castling either way
+    let paths = [Bitboard::new(0x70), Bitboard::new(0x1C)];
+    time("castling paths, square by square", || {
+        for board in &boards {
+            for path in paths {
+                let mut squares = path;
+                let mut attacked = false;
+                while let Some(sq) = squares.pop_lsb() {
+                    if board.is_square_attacked(sq, Color::Black) {
+                        attacked = true;
+                        break;
+                    }
+                }
+                black_box(attacked);
+            }
+        }
+    });
+
+    time("castling paths, any_square_attacked", || {
+        for board in &boards {
+            for path in paths {
+                black_box(board.any_square_attacked(path, Color::Black));
+            }
+        }
+    });
+}
@@ -0,0 +1,43 @@
+//! `cargo bench --bench movelist`: cost of creating a move list, compared with
+//! the old zero-initialized storage, and generation into a fresh list per node
+//! versus one reused list.
+
+use ananke::board::Board;
+use ananke::magic;
+use ananke::movegen::{self, MoveGenerator};
+use ananke::types::{Move, MoveList};
+use std::hint::black_box;
+use std::time::Instant;
+
+const ITERATIONS: u32 = 1_000_000;
+
+fn time(name: &str, mut f: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let ns = start.elapsed().as_nanos() as f64 / ITERATIONS as f64;
+    println!("{:<28} {:>8.2} ns/iter", name, ns);
+}
+
+fn main() {
+    magic::initialize();
+    let board =
+        Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+
+    time("zeroed [Move; 256]", || {
+        black_box([Move::default(); 256]);
+    });
+    time("MoveList::new", || {
+        black_box(MoveList::new());
+    });
+    time("generate_all (fresh list)", || {
+        black_box(MoveGenerator::new(black_box(&board)).generate_all());
+    });
+    let mut list = MoveList::new();
+    time("generate_into (reused list)", || {
+        movegen::generate_into(black_box(&board), &mut list);
+        black_box(&list);
+    });
+}
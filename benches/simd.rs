@@ -0,0 +1,33 @@
+//! `cargo bench --bench simd`: the network's loops over a 256-wide hidden
+//! layer, the plain versions against the ones `simd` picks.
+
+use ananke::simd::{self, scalar};
+use std::hint::black_box;
+use std::time::Instant;
+
+const ITERATIONS: u32 = 1_000_000;
+const HIDDEN: usize = 256;
+
+fn time(name: &str, mut f: impl FnMut()) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let ns = start.elapsed().as_nanos() as f64 / ITERATIONS as f64;
+    println!("{:<28} {:>8.2} ns/iter", name, ns);
+}
+
+fn main() {
+    println!("avx2: {}", simd::has_avx2());
+    let row: Vec<i16> = (0..HIDDEN as i16).map(|i| i * 7 - 900).collect();
+    let mut acc = vec![0i16; HIDDEN];
+
+    time("add (scalar)", || scalar::add(black_box(&mut acc), &row));
+    time("add (simd)", || simd::add(black_box(&mut acc), &row));
+    time("clipped_dot (scalar)", || {
+        black_box(scalar::clipped_dot(black_box(&acc), &row, 255));
+    });
+    time("clipped_dot (simd)", || {
+        black_box(simd::clipped_dot(black_box(&acc), &row, 255));
+    });
+}
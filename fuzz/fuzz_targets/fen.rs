@@ -0,0 +1,31 @@
+#![no_main]
+
+use ananke::board::Board;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(board) = Board::from_fen(text) else {
+        return;
+    };
+    board.validate().expect("from_fen accepted an invalid board");
+    let fen = board.to_fen();
+    let again = Board::from_fen(&fen).expect("to_fen output doesn't parse");
+    assert_eq!(again.to_fen(), fen);
+
+    // the lenient parser lets a king that can be taken through; there's no
+    // playing on from there
+    if board.opponent_in_check() {
+        return;
+    }
+
+    // every legal move must lead to another valid board
+    for m in board.legal_moves().iter() {
+        board
+            .make_move(*m)
+            .validate()
+            .expect("make_move produced an invalid board");
+    }
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+use ananke::pgn::Pgn;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(pgn) = Pgn::parse(text) else {
+        return;
+    };
+    // whatever we accept, we must be able to write back out and read again
+    let written = pgn.to_string();
+    let again = Pgn::parse(&written).expect("written PGN doesn't parse");
+    assert_eq!(again.moves, pgn.moves);
+    assert_eq!(again.result, pgn.result);
+});
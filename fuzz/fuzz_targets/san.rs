@@ -0,0 +1,26 @@
+#![no_main]
+
+use ananke::board::Board;
+use ananke::san;
+use libfuzzer_sys::fuzz_target;
+
+// first line is the position, the rest is the move
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let (fen, mv) = text.split_once('\n').unwrap_or((ananke::board::START_FEN, text));
+    let Ok(board) = Board::from_fen(fen) else {
+        return;
+    };
+    // a king that can be taken is let through by the lenient parser, but
+    // writing SAN plays the move to see if it checks, and the capture
+    // leaves a board with no king
+    if board.opponent_in_check() {
+        return;
+    }
+    if let Ok(m) = san::from_san(&board, mv) {
+        assert!(board.legal_moves().iter().any(|legal| *legal == m));
+        assert_eq!(san::from_san(&board, &san::to_san(&board, m)), Ok(m));
+    }
+});
@@ -0,0 +1,46 @@
+#![no_main]
+
+use ananke::uci::Uci;
+use libfuzzer_sys::fuzz_target;
+
+// the weakest UCI_Elo's node budget caps every go, however it is limited
+const CAP: [&str; 2] = [
+    "setoption name UCI_LimitStrength value true",
+    "setoption name UCI_Elo value 0",
+];
+
+// one command per line, through the same handler the UCI loop uses
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let mut uci = Uci::new();
+    let mut out = Vec::new();
+    for line in CAP {
+        uci.handle(line, &mut out).unwrap();
+    }
+    let mut gos = 0;
+    for line in text.lines() {
+        // options that would lift the cap, read files off the disk or
+        // allocate gigabytes of hash
+        let option = line.to_ascii_lowercase();
+        if option.contains("limitstrength")
+            || option.contains("uci_elo")
+            || option.contains("evalfile")
+            || option.contains("configfile")
+            || option.contains("hash")
+        {
+            continue;
+        }
+        if !uci.handle(line, &mut out).unwrap() {
+            break;
+        }
+        if line.split_whitespace().next() == Some("go") {
+            gos += 1;
+        }
+    }
+    // every go is answered, exactly once
+    let out = String::from_utf8(out).unwrap();
+    let answers = out.lines().filter(|l| l.starts_with("bestmove ")).count();
+    assert_eq!(answers, gos);
+});
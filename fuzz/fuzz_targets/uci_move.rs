@@ -0,0 +1,19 @@
+#![no_main]
+
+use ananke::board::Board;
+use libfuzzer_sys::fuzz_target;
+
+// first line is the position, the rest is the move
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let (fen, mv) = text.split_once('\n').unwrap_or((ananke::board::START_FEN, text));
+    let Ok(board) = Board::from_fen(fen) else {
+        return;
+    };
+    if let Ok(m) = board.parse_uci_move(mv) {
+        assert!(board.legal_moves().iter().any(|legal| *legal == m));
+        assert_eq!(m.to_string(), mv);
+    }
+});
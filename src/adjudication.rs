@@ -0,0 +1,266 @@
+use crate::types::{ByColor, Color, GameResult};
+
+/// Thresholds for ending engine-vs-engine games early. Scores are centipawns
+/// from the reporting engine's point of view; move counts are full moves.
+#[derive(Debug, Copy, Clone)]
+pub struct AdjudicationRules {
+    /// Resign once the loser reports <= -resign_score and the winner
+    /// >= resign_score for `resign_moves` consecutive moves. None disables.
+    pub resign_score: Option<i32>,
+    pub resign_moves: u32,
+    /// Draw once both engines report |score| <= draw_score for `draw_moves`
+    /// consecutive moves, starting after `draw_move_number`. None disables.
+    pub draw_score: Option<i32>,
+    pub draw_move_number: u32,
+    pub draw_moves: u32,
+}
+
+impl Default for AdjudicationRules {
+    fn default() -> Self {
+        AdjudicationRules {
+            resign_score: Some(1000),
+            resign_moves: 3,
+            draw_score: Some(10),
+            draw_move_number: 40,
+            draw_moves: 8,
+        }
+    }
+}
+
+/// Tracks engine scores over a game and decides when the rules trigger.
+/// Feed it one score per ply, in move order.
+///
+/// ```
+/// use ananke::adjudication::{AdjudicationRules, Adjudicator};
+/// use ananke::types::{Color, GameResult};
+///
+/// // both engines agree White is winning, three moves running
+/// let mut adjudicator = Adjudicator::new(AdjudicationRules::default());
+/// assert_eq!(adjudicator.record(Color::White, 1200), None);
+/// for _ in 0..2 {
+///     assert_eq!(adjudicator.record(Color::Black, -1200), None);
+///     assert_eq!(adjudicator.record(Color::White, 1200), None);
+/// }
+/// assert_eq!(adjudicator.record(Color::Black, -1200), None);
+/// assert_eq!(
+///     adjudicator.record(Color::White, 1200),
+///     Some(GameResult::WhiteWins)
+/// );
+/// ```
+pub struct Adjudicator {
+    rules: AdjudicationRules,
+    plies: u32,
+    last_score: ByColor<Option<i32>>,
+    resign_streak: u32,
+    resign_loser: Option<Color>,
+    draw_streak: u32,
+    contempt: ByColor<i32>,
+}
+
+impl Adjudicator {
+    pub fn new(rules: AdjudicationRules) -> Self {
+        Adjudicator {
+            rules,
+            plies: 0,
+            last_score: ByColor::default(),
+            resign_streak: 0,
+            resign_loser: None,
+            draw_streak: 0,
+            contempt: ByColor::default(),
+        }
+    }
+
+    /// The contempt `color`'s engine searches with. Its draws score
+    /// `-contempt` for it, so that much is added back to its scores before
+    /// the thresholds are applied: a drawn position then reads as level.
+    pub fn set_contempt(&mut self, color: Color, contempt: i32) {
+        self.contempt[color] = contempt;
+    }
+
+    /// Record the score `mover`'s engine reported for the move it just made.
+    /// Returns a result once either rule has held long enough.
+    pub fn record(&mut self, mover: Color, score: i32) -> Option<GameResult> {
+        self.plies += 1;
+        self.last_score[mover] = Some(score + self.contempt[mover]);
+
+        let (Some(white), Some(black)) =
+            (self.last_score[Color::White], self.last_score[Color::Black])
+        else {
+            return None;
+        };
+
+        if let Some(threshold) = self.rules.resign_score {
+            // both engines must agree on who is lost
+            let loser = if white <= -threshold && black >= threshold {
+                Some(Color::White)
+            } else if black <= -threshold && white >= threshold {
+                Some(Color::Black)
+            } else {
+                None
+            };
+            if loser.is_some() && loser == self.resign_loser {
+                self.resign_streak += 1;
+            } else {
+                self.resign_streak = if loser.is_some() { 1 } else { 0 };
+            }
+            self.resign_loser = loser;
+
+            // streaks count plies, rules count full moves
+            if let Some(loser) = loser
+                && self.resign_streak >= 2 * self.rules.resign_moves
+            {
+                return Some(GameResult::win_for(loser.opposite()));
+            }
+        }
+
+        if let Some(threshold) = self.rules.draw_score {
+            let moves_played = self.plies / 2;
+            if moves_played >= self.rules.draw_move_number
+                && white.abs() <= threshold
+                && black.abs() <= threshold
+            {
+                self.draw_streak += 1;
+            } else {
+                self.draw_streak = 0;
+            }
+            if self.draw_streak >= 2 * self.rules.draw_moves {
+                return Some(GameResult::Draw);
+            }
+        }
+
+        None
+    }
+}
+
+/// The most the concession scores and move counts may be set to, as
+/// options.
+pub const MAX_CONCESSION_SCORE: i32 = 10_000;
+pub const MAX_RESIGN_MOVES: u32 = 100;
+
+/// Moves the engine lets pass after offering a draw before it offers again,
+/// so as not to pester an opponent that has declined.
+pub const DRAW_OFFER_MOVES: u32 = 10;
+
+/// When ananke itself gives up, takes a draw or offers one, judged from
+/// its own search scores (centipawns, its point of view). Set from the
+/// ResignScore, ResignMoves, AcceptDraws and AcceptDrawScore options.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ConcessionRules {
+    /// Resign after scoring <= -resign_score on `resign_moves` consecutive
+    /// moves. None never resigns.
+    pub resign_score: Option<i32>,
+    pub resign_moves: u32,
+    /// Accept a draw offer when scoring <= accept_draw_score, and offer
+    /// one then too. Neither happens without `accept_draws`.
+    pub accept_draws: bool,
+    pub accept_draw_score: i32,
+}
+
+impl Default for ConcessionRules {
+    fn default() -> Self {
+        ConcessionRules {
+            resign_score: None,
+            resign_moves: 5,
+            accept_draws: false,
+            accept_draw_score: 0,
+        }
+    }
+}
+
+/// Tracks the engine's own scores move by move.
+///
+/// ```
+/// use ananke::adjudication::{ConcessionRules, Concessions};
+///
+/// let mut concessions = Concessions::new(ConcessionRules {
+///     resign_score: Some(800),
+///     resign_moves: 2,
+///     accept_draws: true,
+///     accept_draw_score: -100,
+/// });
+/// assert!(!concessions.record(-900));
+/// assert!(concessions.accept_draw());
+/// assert!(concessions.offer_draw());
+/// assert!(concessions.record(-900));
+/// // offered a move ago: not again yet
+/// assert!(!concessions.offer_draw());
+/// ```
+pub struct Concessions {
+    rules: ConcessionRules,
+    losing_streak: u32,
+    last_score: Option<i32>,
+    contempt: i32,
+    // moves recorded since the last draw offer, None before the first
+    since_offer: Option<u32>,
+}
+
+impl Concessions {
+    pub fn new(rules: ConcessionRules) -> Self {
+        Concessions {
+            rules,
+            losing_streak: 0,
+            last_score: None,
+            contempt: 0,
+            since_offer: None,
+        }
+    }
+
+    /// Change the rules mid-game, keeping the scores seen so far.
+    pub fn set_rules(&mut self, rules: ConcessionRules) {
+        self.rules = rules;
+    }
+
+    pub fn rules(&self) -> ConcessionRules {
+        self.rules
+    }
+
+    /// The contempt the engine searches with, added back to its scores as
+    /// in `Adjudicator::set_contempt`. Otherwise a dead draw, scored
+    /// `-contempt`, would look like a small disadvantage.
+    pub fn set_contempt(&mut self, contempt: i32) {
+        self.contempt = contempt;
+    }
+
+    /// Record the score of the move just searched. Returns true once the
+    /// engine should resign.
+    pub fn record(&mut self, score: i32) -> bool {
+        let score = score + self.contempt;
+        self.last_score = Some(score);
+        self.since_offer = self.since_offer.map(|n| n + 1);
+        if self
+            .rules
+            .resign_score
+            .is_some_and(|threshold| score <= -threshold)
+        {
+            self.losing_streak += 1;
+        } else {
+            self.losing_streak = 0;
+        }
+        self.should_resign()
+    }
+
+    /// Has the engine scored badly enough for long enough to resign?
+    pub fn should_resign(&self) -> bool {
+        self.rules.resign_score.is_some() && self.losing_streak >= self.rules.resign_moves
+    }
+
+    /// Should the engine accept a draw offer, given its latest score?
+    pub fn accept_draw(&self) -> bool {
+        self.rules.accept_draws
+            && self
+                .last_score
+                .is_some_and(|score| score <= self.rules.accept_draw_score)
+    }
+
+    /// Should the engine offer a draw with the move it's about to play? It
+    /// does when it would accept one, but only every `DRAW_OFFER_MOVES`
+    /// moves.
+    pub fn offer_draw(&mut self) -> bool {
+        let due = self.since_offer.is_none_or(|n| n >= DRAW_OFFER_MOVES);
+        if due && self.accept_draw() {
+            self.since_offer = Some(0);
+            return true;
+        }
+        false
+    }
+}
@@ -0,0 +1,129 @@
+//! A search to await rather than block on, for async code such as web
+//! services. The search runs on a thread of its own; its handle is a
+//! `Future` for the result, and cancelling it sets the search's stop flag,
+//! so that it ends with the best it has found. Plain std: any executor
+//! can drive the handle, and none is needed to just `wait` for it.
+
+use crate::board::Board;
+use crate::engine::Engine;
+use crate::search::{Limits, SearchResult};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, mpsc};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+/// A search under way, or done. Dropping it cancels the search.
+pub struct SearchHandle {
+    shared: Arc<Shared>,
+    stop: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+    done: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    result: Option<SearchResult>,
+    waker: Option<Waker>,
+}
+
+/// Search `board`, after the UCI `moves`, with a new engine. Fails, as
+/// `Engine::set_position` does, if the moves or the position are illegal.
+///
+/// ```
+/// use ananke::async_search::search_async;
+/// use ananke::board::Board;
+/// use ananke::search::Limits;
+///
+/// let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+/// let handle = search_async(board, "", Limits::depth(3)).unwrap();
+/// assert_eq!(handle.wait().best_move.unwrap().to_string(), "a1a8");
+/// ```
+pub fn search_async(board: Board, moves: &str, limits: Limits) -> Result<SearchHandle, String> {
+    search_async_with(Engine::new, board, moves, limits)
+}
+
+/// As `search_async`, with the engine `make` builds on the search's
+/// thread, since an engine can't be sent between threads. The limits'
+/// stop flag is replaced by the handle's.
+pub fn search_async_with(
+    make: impl FnOnce() -> Engine + Send + 'static,
+    board: Board,
+    moves: &str,
+    mut limits: Limits,
+) -> Result<SearchHandle, String> {
+    let stop = Arc::new(AtomicBool::new(false));
+    limits.stop = Some(stop.clone());
+    let shared = Arc::new(Shared::default());
+    let finished = shared.clone();
+    let moves = moves.to_string();
+    // the position is checked before the handle is given out
+    let (checked, position) = mpsc::sync_channel(1);
+    thread::spawn(move || {
+        let mut engine = make();
+        if let Err(e) = engine.set_position(board, &moves) {
+            let _ = checked.send(Err(e));
+            return;
+        }
+        let _ = checked.send(Ok(()));
+        let result = engine.think(limits);
+        let mut state = finished.state.lock().unwrap();
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        finished.done.notify_all();
+    });
+    position
+        .recv()
+        .map_err(|_| "the search thread failed".to_string())??;
+    Ok(SearchHandle { shared, stop })
+}
+
+impl SearchHandle {
+    /// Stop the search; the result is the best it found before stopping.
+    pub fn cancel(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.shared.state.lock().unwrap().result.is_some()
+    }
+
+    /// Block until the search is over.
+    pub fn wait(self) -> SearchResult {
+        let mut state = self.shared.state.lock().unwrap();
+        loop {
+            if let Some(result) = state.result.take() {
+                return result;
+            }
+            state = self.shared.done.wait(state).unwrap();
+        }
+    }
+}
+
+impl Drop for SearchHandle {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+impl Future for SearchHandle {
+    type Output = SearchResult;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<SearchResult> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(context.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
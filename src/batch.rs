@@ -0,0 +1,126 @@
+//! Bulk analysis for data pipelines: a file of FEN or EPD lines in, one
+//! row per position out, as CSV or JSON lines, searched in parallel on an
+//! `EnginePool`. Rows come out in the order the positions came in.
+
+use crate::corpus::parse_line;
+use crate::json;
+use crate::pool::{EnginePool, Job};
+use crate::search::{self, Limits};
+use std::io::{self, BufRead, Write};
+
+// positions handed to the pool at a time: enough to keep every engine
+// busy, few enough that rows come out as the file is worked through
+const CHUNK: usize = 256;
+
+/// The CSV columns, in order.
+pub const CSV_HEADER: &str = "fen,bestmove,cp,mate,depth,nodes,pv";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    /// A header and then a row per position. cp or mate is empty, as the
+    /// score is a mate or not; all but the FEN are empty for a position
+    /// with no moves.
+    Csv,
+    /// `json::analysis` per position, one to a line.
+    JsonLines,
+}
+
+/// What became of the lines read.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct BatchStats {
+    pub read: usize,
+    /// Lines that weren't positions, or were positions no engine would
+    /// take: those with the side not to move in check.
+    pub invalid: usize,
+    pub analysed: usize,
+}
+
+/// Analyse every position in `input` within `limits`, writing a row for
+/// each to `out`. Blank lines and `#` comments are skipped without
+/// counting.
+///
+/// ```
+/// use ananke::batch::{Format, analyse_lines};
+/// use ananke::pool::EnginePool;
+/// use ananke::search::Limits;
+///
+/// let input = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\nnonsense\n";
+/// let mut out = Vec::new();
+/// let pool = EnginePool::new(2);
+/// let stats = analyse_lines(input.as_bytes(), &mut out, &pool, &Limits::depth(2), Format::Csv)
+///     .unwrap();
+/// assert_eq!((stats.read, stats.invalid, stats.analysed), (2, 1, 1));
+/// let out = String::from_utf8(out).unwrap();
+/// assert_eq!(
+///     out.lines().nth(1).unwrap(),
+///     "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1,a1a8,,1,1,17,a1a8"
+/// );
+/// ```
+pub fn analyse_lines(
+    input: impl BufRead,
+    mut out: impl Write,
+    pool: &EnginePool,
+    limits: &Limits,
+    format: Format,
+) -> io::Result<BatchStats> {
+    let mut stats = BatchStats::default();
+    if format == Format::Csv {
+        writeln!(out, "{}", CSV_HEADER)?;
+    }
+    let mut chunk = Vec::new();
+    let mut lines = input.lines();
+    loop {
+        let line = lines.next().transpose()?;
+        if let Some(line) = &line {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            stats.read += 1;
+            match parse_line(line) {
+                Ok((board, _)) => chunk.push(Job::new(board, limits.clone())),
+                Err(_) => stats.invalid += 1,
+            }
+        }
+        if chunk.len() == CHUNK || (line.is_none() && !chunk.is_empty()) {
+            let fens: Vec<String> = chunk.iter().map(|job| job.board.to_fen()).collect();
+            let results = pool.analyse(std::mem::take(&mut chunk), &mut |_| {});
+            for (fen, result) in fens.iter().zip(results) {
+                let Ok(lines) = result else {
+                    stats.invalid += 1;
+                    continue;
+                };
+                stats.analysed += 1;
+                match format {
+                    Format::Csv => write_row(&mut out, fen, &lines)?,
+                    Format::JsonLines => writeln!(out, "{}", json::analysis(fen, &lines))?,
+                }
+            }
+            out.flush()?;
+        }
+        if line.is_none() {
+            return Ok(stats);
+        }
+    }
+}
+
+fn write_row(out: &mut impl Write, fen: &str, lines: &[search::SearchResult]) -> io::Result<()> {
+    let Some(best) = lines.first() else {
+        return writeln!(out, "{},,,,,,", fen);
+    };
+    let (cp, mate) = match search::mate_in_moves(best.score) {
+        Some(moves) => (String::new(), moves.to_string()),
+        None => (best.score.to_string(), String::new()),
+    };
+    let pv: Vec<String> = best.pv.iter().map(|m| m.to_string()).collect();
+    writeln!(
+        out,
+        "{},{},{},{},{},{},{}",
+        fen,
+        best.best_move.map_or(String::new(), |m| m.to_string()),
+        cp,
+        mate,
+        best.depth,
+        best.nodes,
+        pv.join(" ")
+    )
+}
@@ -71,13 +71,36 @@ pub enum Square {
 }
 
 impl Square {
-    /// Turn an index (0-63) into a Square. Crashes if out of bounds in debug mode.
+    /// Turn an index (0-63) into a Square. Crashes if out of bounds in debug mode,
+    /// but is unchecked in release, so only use it on indices the engine computed
+    /// itself. Anything parsed from input goes through `try_new`.
     #[inline]
     pub fn new(index: u8) -> Self {
         debug_assert!(index < 64, "Square index out of bounds: {}", index);
         unsafe { std::mem::transmute(index) }
     }
 
+    /// Turn an index into a Square, or None if it isn't 0-63.
+    #[inline]
+    pub fn try_new(index: u8) -> Option<Self> {
+        // Square is one byte with exactly the discriminants 0..64
+        (index < 64).then(|| unsafe { std::mem::transmute::<u8, Square>(index) })
+    }
+
+    /// Parse a square name like "e4". Returns None for anything else.
+    pub fn from_algebraic(name: &str) -> Option<Self> {
+        let bytes = name.as_bytes();
+        if bytes.len() != 2 {
+            return None;
+        }
+        let file = bytes[0].wrapping_sub(b'a');
+        let rank = bytes[1].wrapping_sub(b'1');
+        if file > 7 || rank > 7 {
+            return None;
+        }
+        Square::try_new(rank * 8 + file)
+    }
+
     /// Which rank (0-7) is this square on? 0 is White's first rank.
     #[inline]
     pub fn rank(&self) -> u8 {
@@ -91,6 +114,13 @@ impl Square {
     }
 }
 
+// Lowercase algebraic name, e.g. "e4"
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.file()) as char, self.rank() + 1)
+    }
+}
+
 /// A 64-bit integer where each bit represents a square on the board.
 /// If bit 3 is set, there's a piece on D4 (square 3).
 #[derive(Debug, PartialEq, Eq, PartialOrd, Clone, Copy, Default)]
@@ -140,6 +170,12 @@ impl Bitboard {
         }
     }
 
+    /// Mirror the board top to bottom: rank 1 swaps with rank 8, and so on.
+    #[inline]
+    pub fn flip_vertical(&self) -> Self {
+        Bitboard(self.0.swap_bytes())
+    }
+
     /// Pull off the lowest set bit and return it. Use this to loop through pieces:
     /// `while let Some(sq) = bb.pop_lsb() { ... }`
     #[inline]
@@ -148,6 +184,35 @@ impl Bitboard {
         self.0 &= self.0 - 1;
         Some(lsb)
     }
+
+    /// Every subset of the set bits, starting with the empty set and ending
+    /// with the full set, in increasing numeric order. 2^count() items, so
+    /// only for small sets like blocker masks.
+    pub fn subsets(self) -> Subsets {
+        Subsets {
+            set: self.0,
+            next: Some(0),
+        }
+    }
+}
+
+/// Iterator returned by `Bitboard::subsets`. Steps with the Carry-Rippler
+/// trick: `(subset - set) & set` carries through the unset bits.
+pub struct Subsets {
+    set: u64,
+    next: Option<u64>,
+}
+
+impl Iterator for Subsets {
+    type Item = Bitboard;
+
+    fn next(&mut self) -> Option<Bitboard> {
+        let subset = self.next?;
+        let following = subset.wrapping_sub(self.set) & self.set;
+        // wrapping back round to the empty set means we're done
+        self.next = (following != 0).then_some(following);
+        Some(Bitboard(subset))
+    }
 }
 
 // Bitwise operators so we can write bb1 | bb2 and bb1 & bb2 naturally
@@ -1,12 +1,16 @@
 use crate::bitboard::{Bitboard, Square};
-use crate::types::{CastlingRights, Color, Move, PieceType};
+use crate::material::MaterialKey;
+use crate::movegen::{CASTLING_PATHS, KINGSIDE, MoveGenerator, QUEENSIDE};
+use crate::profile::{self, Section};
+use crate::san;
+use crate::types::{ByColor, ByPiece, CastlingRights, Color, Move, MoveFlag, MoveList, PieceType};
+use crate::zobrist;
 
 #[derive(Clone)]
 pub struct Board {
-    pub white_pieces: [Bitboard; 6],
-    pub black_pieces: [Bitboard; 6],
-    pub white_occupancy: Bitboard,
-    pub black_occupancy: Bitboard,
+    pub pieces: ByColor<ByPiece<Bitboard>>,
+    /// Each side's pieces together; `all_occupancy` is both sides.
+    pub occupancy: ByColor<Bitboard>,
     pub all_occupancy: Bitboard,
     pub side_to_move: Color,
 
@@ -14,8 +18,71 @@ pub struct Board {
     pub castling_rights: CastlingRights,
     pub en_passant_sq: Option<Square>,
     pub halfmove_clock: u8,
+    pub fullmove_number: u16,
+    /// Piece counts, kept in step by `make_move`. Call `update_material_key`
+    /// after editing the piece bitboards directly.
+    pub material_key: MaterialKey,
+    /// Zobrist hash of the position, also kept in step by `make_move`. Call
+    /// `update_hash` after editing the board directly.
+    pub hash: u64,
+    /// Whether each side has castled, set by `make_move` for the eval's
+    /// king safety. A FEN doesn't say, so a board read from one starts out
+    /// with neither; the hash leaves it out too.
+    pub castled: ByColor<bool>,
 }
 
+/// The standard starting position.
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+// castling rights cleared when a move touches each square
+const CASTLING_RIGHTS_LOST: [u8; 64] = {
+    let mut table = [0u8; 64];
+    table[Square::A1 as usize] = CastlingRights::WHITE_QUEENSIDE;
+    table[Square::E1 as usize] = CastlingRights::WHITE_KINGSIDE | CastlingRights::WHITE_QUEENSIDE;
+    table[Square::H1 as usize] = CastlingRights::WHITE_KINGSIDE;
+    table[Square::A8 as usize] = CastlingRights::BLACK_QUEENSIDE;
+    table[Square::E8 as usize] = CastlingRights::BLACK_KINGSIDE | CastlingRights::BLACK_QUEENSIDE;
+    table[Square::H8 as usize] = CastlingRights::BLACK_KINGSIDE;
+    table
+};
+
+/// What `make_move_in_place` overwrites and `unmake_move` can't work out
+/// from the move alone.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct UndoInfo {
+    pub captured: Option<PieceType>,
+    pub castling_rights: CastlingRights,
+    pub en_passant_sq: Option<Square>,
+    pub halfmove_clock: u8,
+    pub fullmove_number: u16,
+    // cheaper to put back than to update twice
+    pub material_key: MaterialKey,
+    pub hash: u64,
+}
+
+fn castle_side(flag: MoveFlag) -> Option<usize> {
+    match flag {
+        MoveFlag::KingCastle => Some(KINGSIDE),
+        MoveFlag::QueenCastle => Some(QUEENSIDE),
+        _ => None,
+    }
+}
+
+// the pawn taken by an en passant capture landing on `to`
+fn en_passant_victim(to: Square, us: Color) -> Square {
+    if us == Color::White {
+        Square::new((to as u8) - 8)
+    } else {
+        Square::new((to as u8) + 8)
+    }
+}
+
+// boards get handed to search threads; keep them plain data
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Board>();
+};
+
 impl Default for Board {
     fn default() -> Self {
         Self::new()
@@ -25,33 +92,84 @@ impl Default for Board {
 impl Board {
     pub fn new() -> Self {
         Board {
-            white_pieces: [Bitboard::EMPTY; 6],
-            black_pieces: [Bitboard::EMPTY; 6],
-            white_occupancy: Bitboard::EMPTY,
-            black_occupancy: Bitboard::EMPTY,
+            pieces: ByColor::default(),
+            occupancy: ByColor::default(),
             all_occupancy: Bitboard::EMPTY,
             side_to_move: Color::White,
             castling_rights: CastlingRights::new(),
             en_passant_sq: None,
             halfmove_clock: 0,
+            fullmove_number: 1,
+            material_key: MaterialKey(0),
+            hash: 0,
+            castled: ByColor::default(),
         }
     }
 
     pub fn update_occupancies(&mut self) {
-        self.white_occupancy = Bitboard::EMPTY;
-        self.black_occupancy = Bitboard::EMPTY;
-
-        for bb in self.white_pieces.iter() {
-            self.white_occupancy |= *bb;
-        }
-        for bb in self.black_pieces.iter() {
-            self.black_occupancy |= *bb;
+        for color in [Color::White, Color::Black] {
+            self.occupancy[color] = self.pieces[color]
+                .iter()
+                .fold(Bitboard::EMPTY, |acc, bb| acc | *bb);
         }
+        self.all_occupancy = self.occupancy[Color::White] | self.occupancy[Color::Black];
+    }
 
-        self.all_occupancy = self.white_occupancy | self.black_occupancy;
+    /// Recount the material key from the piece bitboards.
+    pub fn update_material_key(&mut self) {
+        self.material_key = MaterialKey::of(self);
     }
 
+    /// Recompute the Zobrist hash from scratch.
+    pub fn update_hash(&mut self) {
+        self.hash = zobrist::hash(self);
+    }
+
+    /// The standard starting position.
+    pub fn startpos() -> Self {
+        Self::from_fen(START_FEN).unwrap()
+    }
+
+    /// Parse a FEN string. The halfmove and fullmove fields are optional.
+    ///
+    /// ```
+    /// use ananke::board::Board;
+    ///
+    /// let board = Board::from_fen("8/8/8/4k3/8/8/4P3/4K3 w - - 0 1").unwrap();
+    /// assert_eq!(board.to_fen(), "8/8/8/4k3/8/8/4P3/4K3 w - - 0 1");
+    /// assert!(Board::from_fen("not a fen").is_err());
+    /// ```
     pub fn from_fen(fen: &str) -> Result<Self, String> {
+        let mut board = Self::parse_fen(fen)?;
+        board.drop_impossible_rights();
+        board.update_hash();
+        board.validate()?;
+        Ok(board)
+    }
+
+    /// Like `from_fen`, but also rejects positions that couldn't arise in a
+    /// game (see `is_reachable_sanity`), castling and en passant fields that
+    /// don't match the pieces included, where `from_fen` drops them.
+    ///
+    /// ```
+    /// use ananke::board::Board;
+    ///
+    /// let fen = "4k3/8/8/8/8/8/8/4K3 w KQkq e6 0 1";
+    /// assert!(Board::from_fen(fen).is_ok());
+    /// assert!(Board::from_fen_strict(fen).is_err());
+    /// ```
+    pub fn from_fen_strict(fen: &str) -> Result<Self, String> {
+        let mut board = Self::parse_fen(fen)?;
+        // before anything is cleaned up, so the fields are seen as written
+        if let Some(reason) = board.unreachable_reason() {
+            return Err(format!("Unreachable position: {}", reason));
+        }
+        board.update_hash();
+        Ok(board)
+    }
+
+    // the fields as written, checked for syntax only
+    fn parse_fen(fen: &str) -> Result<Self, String> {
         let mut board = Board::new();
         let parts: Vec<&str> = fen.split_whitespace().collect();
 
@@ -67,11 +185,11 @@ impl Board {
 
         for (rank_idx, row) in rows.iter().enumerate() {
             let rank = 7 - rank_idx as u8;
-            let mut file = 0;
+            let mut file = 0u8;
 
             for char in row.chars() {
                 if char.is_ascii_digit() {
-                    file += char.to_digit(10).unwrap() as u8;
+                    file = file.saturating_add(char.to_digit(10).unwrap() as u8);
                 } else {
                     let piece_type = match char.to_ascii_lowercase() {
                         'p' => PieceType::Pawn,
@@ -88,24 +206,26 @@ impl Board {
                     } else {
                         Color::Black
                     };
-                    let square = Square::new(rank * 8 + file);
-
-                    let idx = piece_type as usize;
-                    if color == Color::White {
-                        board.white_pieces[idx].set_bit(square);
-                    } else {
-                        board.black_pieces[idx].set_bit(square);
+                    if file > 7 {
+                        return Err(format!("Invalid FEN: too many squares in row {}", row));
                     }
+                    let square = Square::try_new(rank * 8 + file)
+                        .ok_or_else(|| format!("Invalid FEN: bad square in row {}", row))?;
+
+                    board.pieces[color][piece_type].set_bit(square);
                     file += 1;
                 }
             }
+            if file != 8 {
+                return Err(format!("Invalid FEN: row {} doesn't cover 8 squares", row));
+            }
         }
 
         // 2. Side to move
-        board.side_to_move = if parts[1] == "w" {
-            Color::White
-        } else {
-            Color::Black
+        board.side_to_move = match parts[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            other => return Err(format!("Invalid side to move: {}", other)),
         };
 
         // 3. Castling rights
@@ -124,19 +244,9 @@ impl Board {
         // 4. En passant target square
         if parts.len() > 3 && parts[3] != "-" {
             let ep_str = parts[3];
-            if ep_str.len() != 2 {
-                return Err(format!("Invalid en passant: {}", ep_str));
-            }
-            let file_char = ep_str.chars().nth(0).unwrap();
-            let rank_char = ep_str.chars().nth(1).unwrap();
-
-            let file = file_char as u8 - b'a';
-            let rank = rank_char as u8 - b'1';
-
-            if file > 7 || rank > 7 {
-                return Err(format!("Invalid en passant square: {}", ep_str));
-            }
-            board.en_passant_sq = Some(Square::new(rank * 8 + file));
+            let ep_sq = Square::from_algebraic(ep_str)
+                .ok_or_else(|| format!("Invalid en passant square: {}", ep_str))?;
+            board.en_passant_sq = Some(ep_sq);
         }
 
         // 5. Halfmove clock (optional, default 0)
@@ -144,270 +254,753 @@ impl Board {
             board.halfmove_clock = parts[4].parse().unwrap_or(0);
         }
 
+        // 6. Fullmove number (optional, default 1)
+        if parts.len() > 5 {
+            board.fullmove_number = parts[5].parse().unwrap_or(1).max(1);
+        }
+
         board.update_occupancies();
+        board.update_material_key();
         Ok(board)
     }
 
-    // core logic: execute a move
-    pub fn make_move(&self, m: Move) -> Board {
-        let mut next = self.clone();
-
-        let from = m.from();
-        let to = m.to();
-        let flag = m.flag();
-        let us = self.side_to_move;
-        let them = us.opposite();
-
-        // 1. Move the piece
-        let piece_type = self
-            .get_piece_type_at(from, us)
-            .expect("No piece at from square");
-        next.remove_piece(piece_type, us, from);
-        next.add_piece(piece_type, us, to);
-
-        // 2. Handle Castling
-        if piece_type == PieceType::King && (from as i8 - to as i8).abs() == 2 {
-            // Kingside castling
-            if to as u8 > from as u8 {
-                let rook_from = if us == Color::White {
-                    Square::H1
-                } else {
-                    Square::H8
-                };
-                let rook_to = if us == Color::White {
-                    Square::F1
-                } else {
-                    Square::F8
-                };
-                next.remove_piece(PieceType::Rook, us, rook_from);
-                next.add_piece(PieceType::Rook, us, rook_to);
+    // FENs in the wild often carry castling or en passant fields that don't
+    // match the pieces. Those would let movegen conjure up a rook or capture
+    // an empty square, so quietly drop them instead of rejecting the FEN.
+    fn drop_impossible_rights(&mut self) {
+        for (right, king, rook, color) in [
+            (
+                CastlingRights::WHITE_KINGSIDE,
+                Square::E1,
+                Square::H1,
+                Color::White,
+            ),
+            (
+                CastlingRights::WHITE_QUEENSIDE,
+                Square::E1,
+                Square::A1,
+                Color::White,
+            ),
+            (
+                CastlingRights::BLACK_KINGSIDE,
+                Square::E8,
+                Square::H8,
+                Color::Black,
+            ),
+            (
+                CastlingRights::BLACK_QUEENSIDE,
+                Square::E8,
+                Square::A8,
+                Color::Black,
+            ),
+        ] {
+            if self.piece_at(king) != Some((color, PieceType::King))
+                || self.piece_at(rook) != Some((color, PieceType::Rook))
+            {
+                self.castling_rights.remove(right);
             }
-            // Queenside castling
-            else {
-                let rook_from = if us == Color::White {
-                    Square::A1
-                } else {
-                    Square::A8
-                };
-                let rook_to = if us == Color::White {
-                    Square::D1
+        }
+
+        if let Some(ep_sq) = self.en_passant_sq {
+            let us = self.side_to_move;
+            let ep_rank = if us == Color::White { 5 } else { 2 };
+            // the pawn that just double-pushed, and the square it came from.
+            // Only worked out on the right rank: elsewhere they could be off
+            // the board
+            let plausible = ep_sq.rank() == ep_rank && {
+                let (pushed, origin) = if us == Color::White {
+                    (ep_sq as u8 - 8, ep_sq as u8 + 8)
                 } else {
-                    Square::D8
+                    (ep_sq as u8 + 8, ep_sq as u8 - 8)
                 };
-                next.remove_piece(PieceType::Rook, us, rook_from);
-                next.add_piece(PieceType::Rook, us, rook_to);
+                self.piece_at(ep_sq).is_none()
+                    && self.piece_at(Square::new(origin)).is_none()
+                    && self.piece_at(Square::new(pushed)) == Some((us.opposite(), PieceType::Pawn))
+            };
+            if !plausible {
+                self.en_passant_sq = None;
             }
-            // Castling removes all castling rights for this side
-            next.castling_rights.remove(match us {
-                Color::White => CastlingRights::WHITE_KINGSIDE | CastlingRights::WHITE_QUEENSIDE,
-                Color::Black => CastlingRights::BLACK_KINGSIDE | CastlingRights::BLACK_QUEENSIDE,
-            });
         }
+    }
 
-        // 3. Handle Captures
-        if m.is_capture() {
-            if flag == Move::EP_CAPTURE {
-                let cap_sq = if us == Color::White {
-                    Square::new((to as u8) - 8)
-                } else {
-                    Square::new((to as u8) + 8)
-                };
-                next.remove_piece(PieceType::Pawn, them, cap_sq);
-            } else {
-                let captured_type = self
-                    .get_piece_type_at(to, them)
-                    .expect("Capture but no enemy");
-                next.remove_piece(captured_type, them, to);
+    /// Check that the position could be played from: one king per side and
+    /// no pawns on the first or last rank. `from_fen` rejects anything that
+    /// fails this.
+    pub fn validate(&self) -> Result<(), String> {
+        for color in [Color::White, Color::Black] {
+            let kings = self.pieces(color, PieceType::King).count();
+            if kings != 1 {
+                return Err(format!("Invalid position: {:?} has {} kings", color, kings));
+            }
+        }
+        let back_ranks = Bitboard::new(0xFF000000000000FF);
+        let pawns =
+            self.pieces(Color::White, PieceType::Pawn) | self.pieces(Color::Black, PieceType::Pawn);
+        if (pawns & back_ranks) != Bitboard::EMPTY {
+            return Err("Invalid position: pawn on the first or last rank".to_string());
+        }
+        Ok(())
+    }
 
-                // Capturing a rook removes castling rights for that side
-                if captured_type == PieceType::Rook {
-                    if them == Color::White {
-                        if to == Square::A1 {
-                            next.castling_rights.remove(CastlingRights::WHITE_QUEENSIDE);
-                        }
-                        if to == Square::H1 {
-                            next.castling_rights.remove(CastlingRights::WHITE_KINGSIDE);
-                        }
-                    } else {
-                        if to == Square::A8 {
-                            next.castling_rights.remove(CastlingRights::BLACK_QUEENSIDE);
-                        }
-                        if to == Square::H8 {
-                            next.castling_rights.remove(CastlingRights::BLACK_KINGSIDE);
+    /// Write the position as a FEN string.
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                match self.piece_at(Square::new(rank * 8 + file)) {
+                    None => empty += 1,
+                    Some((color, pt)) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
                         }
+                        let c = match pt {
+                            PieceType::Pawn => 'p',
+                            PieceType::Knight => 'n',
+                            PieceType::Bishop => 'b',
+                            PieceType::Rook => 'r',
+                            PieceType::Queen => 'q',
+                            PieceType::King => 'k',
+                        };
+                        fen.push(if color == Color::White {
+                            c.to_ascii_uppercase()
+                        } else {
+                            c
+                        });
                     }
                 }
             }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                fen.push('/');
+            }
         }
 
-        // 4. Handle Promotions
-        if m.is_promotion() {
-            next.remove_piece(PieceType::Pawn, us, to);
-            let promo_type = match flag {
-                Move::N_PROMO | Move::N_PROMO_CAP => PieceType::Knight,
-                Move::B_PROMO | Move::B_PROMO_CAP => PieceType::Bishop,
-                Move::R_PROMO | Move::R_PROMO_CAP => PieceType::Rook,
-                Move::Q_PROMO | Move::Q_PROMO_CAP => PieceType::Queen,
-                _ => panic!("Invalid promo flag"),
-            };
-            next.add_piece(promo_type, us, to);
+        fen.push_str(if self.side_to_move == Color::White {
+            " w "
+        } else {
+            " b "
+        });
+
+        if self.castling_rights.has_any() {
+            for (mask, c) in [
+                (CastlingRights::WHITE_KINGSIDE, 'K'),
+                (CastlingRights::WHITE_QUEENSIDE, 'Q'),
+                (CastlingRights::BLACK_KINGSIDE, 'k'),
+                (CastlingRights::BLACK_QUEENSIDE, 'q'),
+            ] {
+                if self.castling_rights.0 & mask != 0 {
+                    fen.push(c);
+                }
+            }
+        } else {
+            fen.push('-');
         }
 
-        // 5. Handle Castling Rights (king or rook moved)
-        if piece_type == PieceType::King {
-            next.castling_rights.remove(match us {
-                Color::White => CastlingRights::WHITE_KINGSIDE | CastlingRights::WHITE_QUEENSIDE,
-                Color::Black => CastlingRights::BLACK_KINGSIDE | CastlingRights::BLACK_QUEENSIDE,
-            });
+        match self.en_passant_sq {
+            Some(sq) => fen.push_str(&format!(" {}", sq)),
+            None => fen.push_str(" -"),
         }
-        if piece_type == PieceType::Rook {
-            if from == Square::A1 || to == Square::A1 {
-                next.castling_rights.remove(CastlingRights::WHITE_QUEENSIDE);
+        fen.push_str(&format!(
+            " {} {}",
+            self.halfmove_clock, self.fullmove_number
+        ));
+        fen
+    }
+
+    /// The same position with colors reversed: every piece changes color and is
+    /// mirrored to the opposite rank, and side to move, castling rights and the
+    /// en passant square follow. Evaluation should be exactly negated and perft
+    /// counts unchanged, which makes this handy for symmetry checks and for
+    /// doubling training data.
+    pub fn color_flipped(&self) -> Board {
+        let mut flipped = Board::new();
+        for color in [Color::White, Color::Black] {
+            for pt in PieceType::iter() {
+                flipped.pieces[color.opposite()][pt] = self.pieces[color][pt].flip_vertical();
             }
-            if from == Square::H1 || to == Square::H1 {
-                next.castling_rights.remove(CastlingRights::WHITE_KINGSIDE);
+        }
+        flipped.side_to_move = self.side_to_move.opposite();
+
+        // white rights live in the low two bits, black in the next two
+        let rights = self.castling_rights.0;
+        flipped.castling_rights =
+            CastlingRights(((rights & 0b0011) << 2) | ((rights & 0b1100) >> 2));
+
+        flipped.en_passant_sq = self.en_passant_sq.map(|sq| Square::new(sq as u8 ^ 56));
+        flipped.halfmove_clock = self.halfmove_clock;
+        flipped.fullmove_number = self.fullmove_number;
+        flipped.update_occupancies();
+        flipped.update_material_key();
+        flipped.update_hash();
+        flipped
+    }
+
+    /// Parse a move in UCI notation ("e2e4", "e7e8q") and match it against the
+    /// legal moves of this position.
+    ///
+    /// ```
+    /// use ananke::board::Board;
+    ///
+    /// let board = Board::startpos();
+    /// let m = board.parse_uci_move("e2e4").unwrap();
+    /// let board = board.make_move(m);
+    /// assert!(board.parse_uci_move("e2e4").is_err());
+    /// ```
+    pub fn parse_uci_move(&self, text: &str) -> Result<Move, String> {
+        if !(4..=5).contains(&text.len()) || !text.is_ascii() {
+            return Err(format!("Invalid move: {}", text));
+        }
+        let from =
+            Square::from_algebraic(&text[0..2]).ok_or_else(|| format!("Invalid move: {}", text))?;
+        let to =
+            Square::from_algebraic(&text[2..4]).ok_or_else(|| format!("Invalid move: {}", text))?;
+        let promo = match &text[4..] {
+            "" => None,
+            "n" => Some(PieceType::Knight),
+            "b" => Some(PieceType::Bishop),
+            "r" => Some(PieceType::Rook),
+            "q" => Some(PieceType::Queen),
+            _ => return Err(format!("Invalid promotion: {}", text)),
+        };
+
+        self.legal_moves()
+            .iter()
+            .copied()
+            .find(|m| m.from() == from && m.to() == to && m.promotion_piece() == promo)
+            .ok_or_else(|| format!("Illegal move: {}", text))
+    }
+
+    /// Play a whitespace-separated list of UCI moves, as in
+    /// "position startpos moves e2e4 c7c5". Every move is checked for legality;
+    /// on error the board is left untouched.
+    pub fn apply_uci_moves(&mut self, moves: &str) -> Result<(), String> {
+        let mut board = self.clone();
+        for (i, text) in moves.split_whitespace().enumerate() {
+            let m = board
+                .parse_uci_move(text)
+                .map_err(|e| format!("{} (move {})", e, i + 1))?;
+            board = board.make_move(m);
+        }
+        *self = board;
+        Ok(())
+    }
+
+    /// Number and write a list of moves in SAN, e.g. "1. e4, 2. d4, 3. Nf3".
+    /// Every move must be legal in this position.
+    pub fn format_moves(&self, list: &MoveList) -> String {
+        list.iter()
+            .enumerate()
+            .map(|(i, m)| format!("{}. {}", i + 1, san::to_san(self, *m)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// A pseudo-legal move is legal if it doesn't leave our own king attacked.
+    pub fn is_legal(&self, m: Move) -> bool {
+        !self.make_move(m).opponent_in_check()
+    }
+
+    /// All legal moves, straight from the legal generator.
+    ///
+    /// ```
+    /// use ananke::board::Board;
+    ///
+    /// let board = Board::startpos();
+    /// assert_eq!(board.legal_moves().len(), 20);
+    /// assert_eq!(board.count_legal_moves(), 20);
+    /// ```
+    pub fn legal_moves(&self) -> MoveList {
+        MoveGenerator::new(self).generate_legal()
+    }
+
+    /// Number of legal moves, counted without building a move list.
+    pub fn count_legal_moves(&self) -> usize {
+        crate::legal::count_legal_moves(self)
+    }
+
+    /// Neither side can possibly mate: bare kings, a single minor piece, or
+    /// only bishops all on the same square color. Used as the practical test
+    /// for a dead position.
+    pub fn has_insufficient_material(&self) -> bool {
+        let heavy = [PieceType::Pawn, PieceType::Rook, PieceType::Queen];
+        if heavy.iter().any(|&pt| {
+            self.pieces(Color::White, pt) != Bitboard::EMPTY
+                || self.pieces(Color::Black, pt) != Bitboard::EMPTY
+        }) {
+            return false;
+        }
+        let knights = self.pieces(Color::White, PieceType::Knight)
+            | self.pieces(Color::Black, PieceType::Knight);
+        let bishops = self.pieces(Color::White, PieceType::Bishop)
+            | self.pieces(Color::Black, PieceType::Bishop);
+        let minors = knights.count() + bishops.count();
+        if minors <= 1 {
+            return true;
+        }
+        // any number of bishops on one color can't mate without help
+        const LIGHT_SQUARES: u64 = 0x55AA55AA55AA55AA;
+        knights == Bitboard::EMPTY
+            && (bishops.0 & LIGHT_SQUARES == 0 || bishops.0 & !LIGHT_SQUARES == 0)
+    }
+
+    /// Could `color` ever mate with the pieces it has: more than a lone
+    /// minor piece, or bishops on both square colors? The opponent's pieces
+    /// aren't looked at, so mates that need them to help are not counted.
+    ///
+    /// ```
+    /// use ananke::board::Board;
+    /// use ananke::types::Color;
+    ///
+    /// let board = Board::from_fen("4k3/4p3/8/8/8/8/8/2B1KN2 w - - 0 1").unwrap();
+    /// assert!(board.has_mating_material(Color::White));
+    /// assert!(board.has_mating_material(Color::Black));
+    /// let board = Board::from_fen("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1").unwrap();
+    /// assert!(!board.has_mating_material(Color::White));
+    /// ```
+    pub fn has_mating_material(&self, color: Color) -> bool {
+        let heavy = [PieceType::Pawn, PieceType::Rook, PieceType::Queen];
+        if heavy
+            .iter()
+            .any(|&pt| self.pieces(color, pt) != Bitboard::EMPTY)
+        {
+            return true;
+        }
+        let knights = self.pieces(color, PieceType::Knight);
+        let bishops = self.pieces(color, PieceType::Bishop);
+        if knights.count() + bishops.count() <= 1 {
+            return false;
+        }
+        const LIGHT_SQUARES: u64 = 0x55AA55AA55AA55AA;
+        knights != Bitboard::EMPTY
+            || (bishops.0 & LIGHT_SQUARES != 0 && bishops.0 & !LIGHT_SQUARES != 0)
+    }
+
+    /// Cheap necessary conditions for the position to be reachable from the
+    /// starting position: piece counts that promotions can explain, at most
+    /// two checkers, the side that just moved not in check, and castling and
+    /// en passant rights that match the pieces. Passing doesn't prove the
+    /// position is reachable; failing proves it isn't.
+    pub fn is_reachable_sanity(&self) -> bool {
+        self.unreachable_reason().is_none()
+    }
+
+    fn unreachable_reason(&self) -> Option<&'static str> {
+        if self.validate().is_err() {
+            return Some("invalid kings or pawns");
+        }
+        if self.opponent_in_check() {
+            return Some("side not to move is in check");
+        }
+        for color in [Color::White, Color::Black] {
+            if self.occupancy[color].count() > 16 {
+                return Some("more than 16 pieces for one side");
             }
-            if from == Square::A8 || to == Square::A8 {
-                next.castling_rights.remove(CastlingRights::BLACK_QUEENSIDE);
+            // every piece beyond the starting set must be a promoted pawn
+            let count = |pt| self.pieces(color, pt).count();
+            let promoted = count(PieceType::Knight).saturating_sub(2)
+                + count(PieceType::Bishop).saturating_sub(2)
+                + count(PieceType::Rook).saturating_sub(2)
+                + count(PieceType::Queen).saturating_sub(1);
+            if count(PieceType::Pawn) + promoted > 8 {
+                return Some("more promoted pieces than missing pawns");
             }
-            if from == Square::H8 || to == Square::H8 {
-                next.castling_rights.remove(CastlingRights::BLACK_KINGSIDE);
+        }
+
+        let us = self.side_to_move;
+        let them = self.occupancy[us.opposite()];
+        let checkers = self.attackers_to(self.get_king_square(us), self.all_occupancy) & them;
+        if checkers.count() > 2 {
+            return Some("more than two checkers");
+        }
+
+        let mut sane = self.clone();
+        sane.drop_impossible_rights();
+        if sane.castling_rights != self.castling_rights {
+            return Some("castling rights without king and rook at home");
+        }
+        if sane.en_passant_sq != self.en_passant_sq {
+            return Some("en passant square without a double-pushed pawn");
+        }
+        None
+    }
+
+    /// Is the side to move in check?
+    pub fn in_check(&self) -> bool {
+        let us = self.side_to_move;
+        self.is_square_attacked(self.get_king_square(us), us.opposite())
+    }
+
+    /// Enemy pieces giving check to the side to move.
+    pub fn checkers(&self) -> Bitboard {
+        let us = self.side_to_move;
+        self.attackers_to(self.get_king_square(us), self.all_occupancy)
+            & self.occupancy[us.opposite()]
+    }
+
+    /// Is the side that just moved in check? Never true in a legal game: it
+    /// would mean the king can be captured. `from_fen_strict` rejects such
+    /// positions; `from_fen` lets them through.
+    pub fn opponent_in_check(&self) -> bool {
+        let them = self.side_to_move.opposite();
+        self.is_square_attacked(self.get_king_square(them), self.side_to_move)
+    }
+
+    /// Same position for repetition purposes: same pieces on the same squares,
+    /// same side to move, castling rights and en passant square. Move clocks
+    /// are ignored.
+    pub fn is_same_position(&self, other: &Board) -> bool {
+        self.pieces == other.pieces
+            && self.side_to_move == other.side_to_move
+            && self.castling_rights == other.castling_rights
+            && self.en_passant_sq == other.en_passant_sq
+    }
+
+    /// The position after `m`, leaving this one untouched.
+    pub fn make_move(&self, m: Move) -> Board {
+        let mut next = self.clone();
+        next.make_move_in_place(m);
+        next
+    }
+
+    /// Play `m` on this board. The returned `UndoInfo` is what
+    /// `unmake_move` needs to take it back, so deep loops can walk the tree
+    /// on one board instead of cloning at every node.
+    pub fn make_move_in_place(&mut self, m: Move) -> UndoInfo {
+        let _profile = profile::scope(Section::MakeMove);
+        let from = m.from();
+        let to = m.to();
+        let flag = m.flag();
+        let us = self.side_to_move;
+        let them = us.opposite();
+
+        let mut undo = UndoInfo {
+            captured: None,
+            castling_rights: self.castling_rights,
+            en_passant_sq: self.en_passant_sq,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            material_key: self.material_key,
+            hash: self.hash,
+        };
+
+        // 1. Handle Captures, before anything lands on the square
+        if m.is_capture() {
+            if flag == MoveFlag::EnPassant {
+                self.remove_piece(PieceType::Pawn, them, en_passant_victim(to, us));
+                self.material_key.remove(them, PieceType::Pawn);
+                undo.captured = Some(PieceType::Pawn);
+            } else {
+                let captured_type = self
+                    .get_piece_type_at(to, them)
+                    .expect("Capture but no enemy");
+                self.remove_piece(captured_type, them, to);
+                self.material_key.remove(them, captured_type);
+                undo.captured = Some(captured_type);
             }
         }
 
+        // 2. Move the piece
+        let piece_type = self
+            .get_piece_type_at(from, us)
+            .expect("No piece at from square");
+        self.remove_piece(piece_type, us, from);
+        self.add_piece(piece_type, us, to);
+
+        // 3. Handle Castling
+        if let Some(side) = castle_side(flag) {
+            let path = &CASTLING_PATHS[us as usize][side];
+            self.remove_piece(PieceType::Rook, us, path.rook_from);
+            self.add_piece(PieceType::Rook, us, path.rook_to);
+            self.castled[us] = true;
+        }
+
+        // 4. Handle Promotions
+        if let Some(promo_type) = m.promotion_piece() {
+            self.remove_piece(PieceType::Pawn, us, to);
+            self.add_piece(promo_type, us, to);
+            self.material_key.remove(us, PieceType::Pawn);
+            self.material_key.add(us, promo_type);
+        }
+
+        // 5. Handle Castling Rights: anything leaving or landing on a king or
+        // rook home square (moves, captures, castling itself) clears its rights
+        self.castling_rights
+            .remove(CASTLING_RIGHTS_LOST[from as usize] | CASTLING_RIGHTS_LOST[to as usize]);
+        self.hash ^= zobrist::castling_key(undo.castling_rights)
+            ^ zobrist::castling_key(self.castling_rights);
+
         // 6. Update State
-        next.side_to_move = them;
-        next.en_passant_sq = None;
+        self.side_to_move = them;
+        self.hash ^= zobrist::side_key();
+        if let Some(ep_sq) = self.en_passant_sq.take() {
+            self.hash ^= zobrist::en_passant_key(ep_sq);
+        }
+
+        if piece_type == PieceType::Pawn || m.is_capture() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock = self.halfmove_clock.saturating_add(1);
+        }
+        if us == Color::Black {
+            // a FEN can start the count anywhere up to u16::MAX
+            self.fullmove_number = self.fullmove_number.saturating_add(1);
+        }
 
-        if flag == Move::DOUBLE_PAWN_PUSH {
+        if flag == MoveFlag::DoublePawnPush {
             let ep_sq = if us == Color::White {
                 Square::new((from as u8) + 8)
             } else {
                 Square::new((from as u8) - 8)
             };
-            next.en_passant_sq = Some(ep_sq);
+            self.en_passant_sq = Some(ep_sq);
+            self.hash ^= zobrist::en_passant_key(ep_sq);
         }
 
-        next.update_occupancies();
-        next
+        self.update_occupancies();
+        undo
+    }
+
+    /// Take back `m`, which must be the last move made with
+    /// `make_move_in_place`, using the `UndoInfo` it returned.
+    pub fn unmake_move(&mut self, m: Move, undo: UndoInfo) {
+        let _profile = profile::scope(Section::MakeMove);
+        let from = m.from();
+        let to = m.to();
+        let flag = m.flag();
+        let us = self.side_to_move.opposite();
+        let them = self.side_to_move;
+
+        // a promoted piece goes back as the pawn it was
+        let piece_type = match m.promotion_piece() {
+            Some(promo_type) => {
+                self.remove_piece(promo_type, us, to);
+                self.add_piece(PieceType::Pawn, us, to);
+                PieceType::Pawn
+            }
+            None => self
+                .get_piece_type_at(to, us)
+                .expect("No piece at to square"),
+        };
+        self.remove_piece(piece_type, us, to);
+        self.add_piece(piece_type, us, from);
+
+        if let Some(side) = castle_side(flag) {
+            let path = &CASTLING_PATHS[us as usize][side];
+            self.remove_piece(PieceType::Rook, us, path.rook_to);
+            self.add_piece(PieceType::Rook, us, path.rook_from);
+            // a side only ever castles once
+            self.castled[us] = false;
+        }
+
+        if let Some(captured_type) = undo.captured {
+            let cap_sq = if flag == MoveFlag::EnPassant {
+                en_passant_victim(to, us)
+            } else {
+                to
+            };
+            self.add_piece(captured_type, them, cap_sq);
+        }
+
+        self.side_to_move = us;
+        self.castling_rights = undo.castling_rights;
+        self.en_passant_sq = undo.en_passant_sq;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.material_key = undo.material_key;
+        self.hash = undo.hash;
+        self.update_occupancies();
     }
 
     // --- HELPERS ---
 
-    fn get_piece_type_at(&self, sq: Square, color: Color) -> Option<PieceType> {
-        let pieces = if color == Color::White {
-            &self.white_pieces
+    /// Bitboard of one color's pieces of a given type.
+    #[inline]
+    pub fn pieces(&self, color: Color, pt: PieceType) -> Bitboard {
+        self.pieces[color][pt]
+    }
+
+    /// The color and type of whatever stands on `sq`, if anything.
+    pub fn piece_at(&self, sq: Square) -> Option<(Color, PieceType)> {
+        let color = if self.occupancy[Color::White].get_bit(sq) {
+            Color::White
+        } else if self.occupancy[Color::Black].get_bit(sq) {
+            Color::Black
         } else {
-            &self.black_pieces
+            return None;
         };
-        for (i, bb) in pieces.iter().enumerate() {
-            if bb.get_bit(sq) {
-                return Some(match i {
-                    0 => PieceType::Pawn,
-                    1 => PieceType::Knight,
-                    2 => PieceType::Bishop,
-                    3 => PieceType::Rook,
-                    4 => PieceType::Queen,
-                    5 => PieceType::King,
-                    _ => unreachable!(),
-                });
-            }
+        self.get_piece_type_at(sq, color).map(|pt| (color, pt))
+    }
+
+    pub fn get_piece_type_at(&self, sq: Square, color: Color) -> Option<PieceType> {
+        // empty squares are the common case when probing, skip the scan
+        if !self.occupancy[color].get_bit(sq) {
+            return None;
         }
-        None
+        PieceType::iter().find(|&pt| self.pieces[color][pt].get_bit(sq))
     }
 
+    #[inline]
     fn remove_piece(&mut self, pt: PieceType, color: Color, sq: Square) {
-        if color == Color::White {
-            self.white_pieces[pt as usize].clear_bit(sq);
-        } else {
-            self.black_pieces[pt as usize].clear_bit(sq);
-        }
+        self.pieces[color][pt].clear_bit(sq);
+        self.hash ^= zobrist::piece_key(color, pt, sq);
     }
 
+    #[inline]
     fn add_piece(&mut self, pt: PieceType, color: Color, sq: Square) {
-        if color == Color::White {
-            self.white_pieces[pt as usize].set_bit(sq);
-        } else {
-            self.black_pieces[pt as usize].set_bit(sq);
-        }
+        self.pieces[color][pt].set_bit(sq);
+        self.hash ^= zobrist::piece_key(color, pt, sq);
     }
 
     pub fn get_king_square(&self, color: Color) -> Square {
-        let kings = if color == Color::White {
-            self.white_pieces[PieceType::King as usize]
-        } else {
-            self.black_pieces[PieceType::King as usize]
-        };
-        kings.lsb_index().expect("Board has no King!")
+        self.pieces[color][PieceType::King]
+            .lsb_index()
+            .expect("Board has no King!")
     }
 
-    pub fn is_square_attacked(&self, sq: Square, attacker: Color) -> bool {
-        // 1. Check if an enemy Pawn attacks us
-        let is_white_attacker = attacker == Color::White;
-        if is_white_attacker {
-            let white_pawns = self.white_pieces[PieceType::Pawn as usize];
-            // If we pretend to be a Black pawn here, do we hit a White pawn?
-            let attacks = crate::movegen::generate_pawn_attacks(sq, Color::Black);
-            if (attacks & white_pawns).count() > 0 {
-                return true;
-            }
-        } else {
-            let black_pawns = self.black_pieces[PieceType::Pawn as usize];
-            let attacks = crate::movegen::generate_pawn_attacks(sq, Color::White);
-            if (attacks & black_pawns).count() > 0 {
-                return true;
-            }
+    /// Every square attacked by `color` in one pass over its pieces, e.g. for
+    /// heatmaps. Squares holding `color`'s own pieces count when defended.
+    ///
+    /// ```
+    /// use ananke::bitboard::Square;
+    /// use ananke::board::Board;
+    /// use ananke::types::Color;
+    ///
+    /// let attacked = Board::startpos().attack_map(Color::White);
+    /// // the pawns cover the whole third rank, and nothing reaches the fourth
+    /// assert_eq!(attacked.count(), 22);
+    /// assert!(attacked.get_bit(Square::A3) && attacked.get_bit(Square::H3));
+    /// assert!(!attacked.get_bit(Square::E4));
+    /// // the pieces behind the pawns are defended, the rooks in the corners
+    /// // aren't
+    /// assert!(attacked.get_bit(Square::D2) && attacked.get_bit(Square::D1));
+    /// assert!(!attacked.get_bit(Square::A1) && !attacked.get_bit(Square::H1));
+    /// ```
+    pub fn attack_map(&self, color: Color) -> Bitboard {
+        let mut attacks =
+            crate::movegen::pawn_attacks_bb(color, self.pieces(color, PieceType::Pawn));
+
+        let queens = self.pieces(color, PieceType::Queen);
+        let mut knights = self.pieces(color, PieceType::Knight);
+        let mut diagonals = self.pieces(color, PieceType::Bishop) | queens;
+        let mut orthogonals = self.pieces(color, PieceType::Rook) | queens;
+
+        while let Some(sq) = knights.pop_lsb() {
+            attacks |= crate::movegen::generate_knight_attacks(sq);
+        }
+        while let Some(sq) = diagonals.pop_lsb() {
+            attacks |= crate::magic::get_bishop_attacks(sq, self.all_occupancy);
+        }
+        while let Some(sq) = orthogonals.pop_lsb() {
+            attacks |= crate::magic::get_rook_attacks(sq, self.all_occupancy);
         }
+        if let Some(sq) = self.pieces(color, PieceType::King).lsb_index() {
+            attacks |= crate::movegen::generate_king_attacks(sq);
+        }
+        attacks
+    }
 
-        // 2. Check Knights
-        let knights = if is_white_attacker {
-            self.white_pieces[PieceType::Knight as usize]
-        } else {
-            self.black_pieces[PieceType::Knight as usize]
-        };
-        if (crate::movegen::generate_knight_attacks(sq) & knights).count() > 0 {
+    /// Pieces of `color` that are protected by another piece of the same color.
+    pub fn defended_pieces(&self, color: Color) -> Bitboard {
+        self.attack_map(color) & self.occupancy[color]
+    }
+
+    /// Every piece of either color that attacks `sq`, using `occupancy` for slider
+    /// blocking. Passing a reduced occupancy reveals x-ray attackers, which SEE needs.
+    pub fn attackers_to(&self, sq: Square, occupancy: Bitboard) -> Bitboard {
+        let (white, black) = (&self.pieces[Color::White], &self.pieces[Color::Black]);
+        let both = |pt: PieceType| white[pt] | black[pt];
+
+        let knights = both(PieceType::Knight);
+        let kings = both(PieceType::King);
+        let queens = both(PieceType::Queen);
+        let rooks = both(PieceType::Rook) | queens;
+        let bishops = both(PieceType::Bishop) | queens;
+
+        // a white pawn attacks sq if a black pawn on sq would attack it, and vice versa
+        (crate::movegen::generate_pawn_attacks(sq, Color::Black) & white[PieceType::Pawn])
+            | (crate::movegen::generate_pawn_attacks(sq, Color::White) & black[PieceType::Pawn])
+            | (crate::movegen::generate_knight_attacks(sq) & knights)
+            | (crate::movegen::generate_king_attacks(sq) & kings)
+            | (crate::magic::get_rook_attacks(sq, occupancy) & rooks)
+            | (crate::magic::get_bishop_attacks(sq, occupancy) & bishops)
+    }
+
+    /// Is `sq` attacked by any piece of `attacker`? Cheap leaper lookups go
+    /// first; the magic lookups are skipped entirely when the attacker has no
+    /// slider of that kind, which is common in endgames.
+    pub fn is_square_attacked(&self, sq: Square, attacker: Color) -> bool {
+        let _profile = profile::scope(Section::Attacks);
+        let pieces = &self.pieces[attacker];
+
+        // a pawn of ours attacks sq if a pawn of the other colour on sq would hit it
+        let pawns = pieces[PieceType::Pawn];
+        if (crate::movegen::generate_pawn_attacks(sq, attacker.opposite()) & pawns)
+            != Bitboard::EMPTY
+        {
             return true;
         }
-
-        // 3. Check King
-        let kings = if is_white_attacker {
-            self.white_pieces[PieceType::King as usize]
-        } else {
-            self.black_pieces[PieceType::King as usize]
-        };
-        if (crate::movegen::generate_king_attacks(sq) & kings).count() > 0 {
+        let knights = pieces[PieceType::Knight];
+        if (crate::movegen::generate_knight_attacks(sq) & knights) != Bitboard::EMPTY {
             return true;
         }
-
-        // 4. Check Rooks/Queens
-        let rooks = if is_white_attacker {
-            self.white_pieces[PieceType::Rook as usize]
-        } else {
-            self.black_pieces[PieceType::Rook as usize]
-        };
-        let queens = if is_white_attacker {
-            self.white_pieces[PieceType::Queen as usize]
-        } else {
-            self.black_pieces[PieceType::Queen as usize]
-        };
-
-        let rook_attacks = crate::magic::get_rook_attacks(sq, self.all_occupancy);
-        if (rook_attacks & (rooks | queens)).count() > 0 {
+        let kings = pieces[PieceType::King];
+        if (crate::movegen::generate_king_attacks(sq) & kings) != Bitboard::EMPTY {
             return true;
         }
 
-        // 5. Check Bishops/Queens
-        let bishops = if is_white_attacker {
-            self.white_pieces[PieceType::Bishop as usize]
-        } else {
-            self.black_pieces[PieceType::Bishop as usize]
-        };
+        let queens = pieces[PieceType::Queen];
+        let rooks = pieces[PieceType::Rook] | queens;
+        if rooks != Bitboard::EMPTY
+            && (crate::magic::get_rook_attacks(sq, self.all_occupancy) & rooks) != Bitboard::EMPTY
+        {
+            return true;
+        }
+        let bishops = pieces[PieceType::Bishop] | queens;
+        bishops != Bitboard::EMPTY
+            && (crate::magic::get_bishop_attacks(sq, self.all_occupancy) & bishops)
+                != Bitboard::EMPTY
+    }
 
-        let bishop_attacks = crate::magic::get_bishop_attacks(sq, self.all_occupancy);
-        if (bishop_attacks & (bishops | queens)).count() > 0 {
+    /// Is any square in `squares` attacked by `attacker`? Pawn, knight and
+    /// king attacks are computed once for the whole set, so only sliders are
+    /// looked up per square. Used for castling paths.
+    pub fn any_square_attacked(&self, squares: Bitboard, attacker: Color) -> bool {
+        let _profile = profile::scope(Section::Attacks);
+        let pieces = &self.pieces[attacker];
+
+        let mut leaper_attacks = crate::movegen::pawn_attacks_bb(attacker, pieces[PieceType::Pawn]);
+        let mut knights = pieces[PieceType::Knight];
+        while let Some(from) = knights.pop_lsb() {
+            leaper_attacks |= crate::movegen::generate_knight_attacks(from);
+        }
+        let mut kings = pieces[PieceType::King];
+        while let Some(from) = kings.pop_lsb() {
+            leaper_attacks |= crate::movegen::generate_king_attacks(from);
+        }
+        if (leaper_attacks & squares) != Bitboard::EMPTY {
             return true;
         }
 
+        let queens = pieces[PieceType::Queen];
+        let rooks = pieces[PieceType::Rook] | queens;
+        let bishops = pieces[PieceType::Bishop] | queens;
+        if (rooks | bishops) == Bitboard::EMPTY {
+            return false;
+        }
+        let mut remaining = squares;
+        while let Some(sq) = remaining.pop_lsb() {
+            if (crate::magic::get_rook_attacks(sq, self.all_occupancy) & rooks) != Bitboard::EMPTY
+                || (crate::magic::get_bishop_attacks(sq, self.all_occupancy) & bishops)
+                    != Bitboard::EMPTY
+            {
+                return true;
+            }
+        }
         false
     }
 }
@@ -0,0 +1,59 @@
+//! Engine identification pulled from Cargo metadata, so `id name`, `--version`
+//! and match logs always agree with the crate version that was built.
+
+pub const NAME: &str = env!("CARGO_PKG_NAME");
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const AUTHORS: &str = env!("CARGO_PKG_AUTHORS");
+
+/// Cargo features and relevant target features this binary was built with.
+pub fn features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "profile") {
+        features.push("profile");
+    }
+    if cfg!(feature = "render") {
+        features.push("render");
+    }
+    if cfg!(target_feature = "popcnt") {
+        features.push("popcnt");
+    }
+    if cfg!(target_feature = "bmi2") {
+        features.push("bmi2");
+    }
+    features
+}
+
+/// The UCI `id` block: "id name ananke 0.1.0" and "id author ...".
+pub fn uci_id() -> String {
+    format!(
+        "id name {} {}\nid author {}",
+        NAME,
+        VERSION,
+        AUTHORS.replace(':', ", ")
+    )
+}
+
+/// One-line build description, e.g.
+/// "ananke 0.1.0 (x86_64-linux, release, features: popcnt)".
+pub fn build_string() -> String {
+    let profile = if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    };
+    let features = features();
+    let features = if features.is_empty() {
+        "none".to_string()
+    } else {
+        features.join(", ")
+    };
+    format!(
+        "{} {} ({}-{}, {}, features: {})",
+        NAME,
+        VERSION,
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        profile,
+        features
+    )
+}
@@ -0,0 +1,157 @@
+use crate::types::{ByColor, Color};
+use std::time::{Duration, Instant};
+
+/// How the per-move time bonus is given.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClockMode {
+    /// Fischer: the bonus is added after every move, whatever it took.
+    Increment,
+    /// Simple (US) delay: the clock doesn't run for the first `bonus` of
+    /// each move.
+    Delay,
+    /// Bronstein: after the move, give back the time used, up to `bonus`.
+    Bronstein,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub bonus: Duration,
+    pub mode: ClockMode,
+}
+
+impl TimeControl {
+    /// Base plus Fischer increment, e.g. 3+2 is `fischer(180s, 2s)`.
+    pub fn fischer(base: Duration, increment: Duration) -> Self {
+        TimeControl {
+            base,
+            bonus: increment,
+            mode: ClockMode::Increment,
+        }
+    }
+}
+
+/// A two-player chess clock. The side to move's clock runs until it presses;
+/// a press that uses more time than is left flags that side.
+///
+/// Time can be charged from the wall clock (`press_clock`) or passed in
+/// explicitly (`press_clock_after`), so simulated games and real ones use
+/// the same rules.
+///
+/// ```
+/// use ananke::clock::{Clock, TimeControl};
+/// use ananke::types::Color;
+/// use std::time::Duration;
+///
+/// // 3+2
+/// let control = TimeControl::fischer(Duration::from_secs(180), Duration::from_secs(2));
+/// let mut clock = Clock::new(control, Color::White);
+/// clock.press_clock_after(Duration::from_secs(10)).unwrap();
+/// assert_eq!(clock.remaining(Color::White), Duration::from_secs(172));
+/// assert_eq!(clock.to_move(), Color::Black);
+/// // Black overstays and loses on time
+/// assert_eq!(clock.press_clock_after(Duration::from_secs(181)), Err(Color::Black));
+/// assert_eq!(clock.flagged(), Some(Color::Black));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Clock {
+    control: TimeControl,
+    remaining: ByColor<Duration>,
+    to_move: Color,
+    turn_started: Option<Instant>,
+    flagged: Option<Color>,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl, to_move: Color) -> Self {
+        Clock {
+            control,
+            remaining: ByColor::new(control.base, control.base),
+            to_move,
+            turn_started: None,
+            flagged: None,
+        }
+    }
+
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    pub fn to_move(&self) -> Color {
+        self.to_move
+    }
+
+    /// Time left for `color` as of its last press (the running clock isn't
+    /// deducted; see `remaining_now`).
+    pub fn remaining(&self, color: Color) -> Duration {
+        self.remaining[color]
+    }
+
+    /// Time left for `color` right now, counting the running turn.
+    pub fn remaining_now(&self, color: Color) -> Duration {
+        let left = self.remaining(color);
+        match self.turn_started {
+            Some(started) if color == self.to_move && self.flagged.is_none() => {
+                left.saturating_sub(self.charge(started.elapsed()))
+            }
+            _ => left,
+        }
+    }
+
+    /// The side whose flag fell, if any.
+    pub fn flagged(&self) -> Option<Color> {
+        self.flagged.or_else(|| {
+            // a flag can fall while the clock is running
+            let running = self.turn_started?;
+            (self.charge(running.elapsed()) > self.remaining(self.to_move)).then_some(self.to_move)
+        })
+    }
+
+    /// Start the side to move's clock, e.g. when the game begins.
+    pub fn start(&mut self) {
+        self.turn_started = Some(Instant::now());
+    }
+
+    /// End the current turn using wall-clock time since the turn started.
+    /// Starts the opponent's clock. Returns the flagged side on flag fall.
+    pub fn press_clock(&mut self) -> Result<(), Color> {
+        let elapsed = self.turn_started.map_or(Duration::ZERO, |t| t.elapsed());
+        self.press_clock_after(elapsed)?;
+        self.start();
+        Ok(())
+    }
+
+    /// End the current turn, charging `elapsed` to the side to move.
+    pub fn press_clock_after(&mut self, elapsed: Duration) -> Result<(), Color> {
+        if let Some(color) = self.flagged {
+            return Err(color);
+        }
+        let us = self.to_move;
+        let left = self.remaining[us];
+        let charged = self.charge(elapsed);
+        if charged > left {
+            self.remaining[us] = Duration::ZERO;
+            self.flagged = Some(us);
+            self.turn_started = None;
+            return Err(us);
+        }
+
+        let bonus = match self.control.mode {
+            ClockMode::Increment => self.control.bonus,
+            ClockMode::Delay => Duration::ZERO,
+            ClockMode::Bronstein => elapsed.min(self.control.bonus),
+        };
+        self.remaining[us] = left - charged + bonus;
+        self.to_move = us.opposite();
+        self.turn_started = None;
+        Ok(())
+    }
+
+    // time taken off the clock for a move lasting `elapsed`
+    fn charge(&self, elapsed: Duration) -> Duration {
+        match self.control.mode {
+            ClockMode::Delay => elapsed.saturating_sub(self.control.bonus),
+            _ => elapsed,
+        }
+    }
+}
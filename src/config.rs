@@ -0,0 +1,136 @@
+//! Config files: engine options and search parameters set in one go, at
+//! startup with `--config <path>` or later with UCI's `ConfigFile` option,
+//! so that an experiment or a deployment is the same every time without a
+//! long list of `setoption`s.
+//!
+//! The format is a small subset of TOML: one `name = value` per line, the
+//! name bare or, when it has spaces, in double quotes; values are whole
+//! numbers, `true` or `false`, or double-quoted strings. `#` starts a
+//! comment, and `[section]` headers may group the lines but don't change
+//! what they mean. Names are the UCI option names, and those of the search
+//! parameters in `params`.
+//!
+//! ```toml
+//! # a tuning run
+//! Hash = 64
+//! "Move Overhead" = 30
+//!
+//! [params]
+//! FutilityMargin = 180
+//! ```
+
+use std::fs;
+
+/// One setting, and the line it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub line: usize,
+    pub name: String,
+    pub value: String,
+}
+
+/// Read and parse the file at `path`.
+pub fn load(path: &str) -> Result<Vec<Entry>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    parse(&text).map_err(|e| format!("{}:{}", path, e))
+}
+
+/// The settings in `text`, in order. A line that isn't one is an error
+/// naming it.
+///
+/// ```
+/// use ananke::config::parse;
+///
+/// let entries = parse("[options]\n\"Move Overhead\" = 30 # ms\nEvalFile = \"a b.nnue\"\n").unwrap();
+/// assert_eq!(entries[0].name, "Move Overhead");
+/// assert_eq!(entries[0].value, "30");
+/// assert_eq!(entries[1].value, "a b.nnue");
+/// assert_eq!(parse("Hash 64").unwrap_err(), "1: expected name = value");
+/// ```
+pub fn parse(text: &str) -> Result<Vec<Entry>, String> {
+    let mut entries = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line_number = i + 1;
+        let entry = parse_line(line).map_err(|e| format!("{}: {}", line_number, e))?;
+        if let Some((name, value)) = entry {
+            entries.push(Entry {
+                line: line_number,
+                name,
+                value,
+            });
+        }
+    }
+    Ok(entries)
+}
+
+// None for a line with nothing to set: blank, a comment, or a header
+fn parse_line(line: &str) -> Result<Option<(String, String)>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    if let Some(header) = line.strip_prefix('[') {
+        return match header.split_once(']') {
+            Some((_, rest)) if is_comment(rest) => Ok(None),
+            _ => Err("bad section header".to_string()),
+        };
+    }
+    let (name, rest) = if let Some(quoted) = line.strip_prefix('"') {
+        quoted_string(quoted)?
+    } else {
+        let end = line
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(line.len());
+        (line[..end].to_string(), &line[end..])
+    };
+    let Some(rest) = rest.trim_start().strip_prefix('=') else {
+        return Err("expected name = value".to_string());
+    };
+    if name.is_empty() {
+        return Err("expected name = value".to_string());
+    }
+    let rest = rest.trim_start();
+    let (value, rest) = if let Some(quoted) = rest.strip_prefix('"') {
+        quoted_string(quoted)?
+    } else {
+        let end = rest.find(['#', ' ', '\t']).unwrap_or(rest.len());
+        let value = &rest[..end];
+        let is_number = value
+            .strip_prefix(['-', '+'])
+            .unwrap_or(value)
+            .parse::<u64>()
+            .is_ok();
+        if !(is_number || value == "true" || value == "false") {
+            return Err(format!("bad value: {}", value));
+        }
+        (value.to_string(), &rest[end..])
+    };
+    if !is_comment(rest) {
+        return Err(format!("unexpected: {}", rest.trim()));
+    }
+    Ok(Some((name, value)))
+}
+
+// a basic string whose opening quote has been read: its contents, with
+// `\"` and `\\` unescaped, and what follows the closing quote
+fn quoted_string(text: &str) -> Result<(String, &str), String> {
+    let mut value = String::new();
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, &text[i + 1..])),
+            '\\' => match chars.next() {
+                Some((_, c @ ('"' | '\\'))) => value.push(c),
+                _ => return Err("bad escape in string".to_string()),
+            },
+            _ => value.push(c),
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+// nothing left on the line but, maybe, a comment
+fn is_comment(rest: &str) -> bool {
+    let rest = rest.trim_start();
+    rest.is_empty() || rest.starts_with('#')
+}
@@ -0,0 +1,87 @@
+//! Contempt: how much worse than level a draw looks to the engine. With
+//! contempt `c` the search scores every draw as `-c` for the side it is
+//! playing, so it steers clear of draws against weaker opposition or when
+//! the game has been going its way, and welcomes them otherwise. Adjudication
+//! adds the same `c` back to the engine's scores so that its drawish
+//! evaluations still read as level there.
+
+/// Contempt never goes beyond this either way.
+pub const MAX_CONTEMPT: i32 = 100;
+// rating points per centipawn of contempt
+const ELO_PER_CP: i32 = 10;
+const MAX_RATING_CONTEMPT: i32 = 50;
+// how many of our latest scores make the trend, and how strongly it counts
+const TREND_MOVES: usize = 4;
+const TREND_DIVISOR: i32 = 8;
+const MAX_TREND_CONTEMPT: i32 = 50;
+
+/// Contempt settings. The default is a small fixed contempt, no rating
+/// information and no trend.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Contempt {
+    /// Off for analysis: draws are scored exactly 0 whatever the rest says.
+    pub enabled: bool,
+    /// Fixed part, in centipawns.
+    pub base: i32,
+    /// Our rating and the opponent's, when the GUI tells us both.
+    pub ratings: Option<(u32, u32)>,
+    /// Follow the trend of our own recent search scores.
+    pub dynamic: bool,
+}
+
+impl Default for Contempt {
+    fn default() -> Self {
+        Contempt {
+            enabled: true,
+            base: 10,
+            ratings: None,
+            dynamic: false,
+        }
+    }
+}
+
+impl Contempt {
+    /// Contempt in centipawns for the next search, given the engine's own
+    /// scores so far this game (oldest first, its point of view).
+    ///
+    /// ```
+    /// use ananke::contempt::Contempt;
+    ///
+    /// let fixed = Contempt::default();
+    /// assert_eq!(fixed.value(&[]), 10);
+    ///
+    /// // 300 points stronger, and a game going our way: draws look bad
+    /// let dynamic = Contempt { ratings: Some((2300, 2000)), dynamic: true, ..fixed };
+    /// assert_eq!(dynamic.value(&[80, 120, 160, 200]), 10 + 30 + 17);
+    ///
+    /// let analysis = Contempt { enabled: false, ..dynamic };
+    /// assert_eq!(analysis.value(&[80, 120, 160, 200]), 0);
+    /// ```
+    pub fn value(&self, scores: &[i32]) -> i32 {
+        if !self.enabled {
+            return 0;
+        }
+        let rating = self.ratings.map_or(0, |(ours, theirs)| {
+            ((ours as i32 - theirs as i32) / ELO_PER_CP)
+                .clamp(-MAX_RATING_CONTEMPT, MAX_RATING_CONTEMPT)
+        });
+        let trend = if self.dynamic {
+            trend(scores) / TREND_DIVISOR
+        } else {
+            0
+        };
+        (self.base + rating + trend.clamp(-MAX_TREND_CONTEMPT, MAX_TREND_CONTEMPT))
+            .clamp(-MAX_CONTEMPT, MAX_CONTEMPT)
+    }
+}
+
+// average of the latest scores, with mates and huge scores cut down so one
+// of them doesn't swamp the rest
+fn trend(scores: &[i32]) -> i32 {
+    let recent = &scores[scores.len().saturating_sub(TREND_MOVES)..];
+    if recent.is_empty() {
+        return 0;
+    }
+    let sum: i32 = recent.iter().map(|&s| s.clamp(-1000, 1000)).sum();
+    sum / recent.len() as i32
+}
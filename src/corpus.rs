@@ -0,0 +1,182 @@
+//! Position corpora for tuning and data generation: read FEN/EPD lines (or
+//! the positions of PGN games), drop repeats by Zobrist key, keep what
+//! passes a filter, and write the survivors back out.
+//!
+//! Only the position fields of a line are parsed. Whatever follows them
+//! (EPD opcodes, a result, an eval label) is passed through untouched, so
+//! labelled data stays labelled.
+
+use crate::board::Board;
+use crate::pgn::PgnReader;
+use crate::search;
+use crate::types::GameResult;
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+use std::ops::RangeInclusive;
+
+/// What a position has to satisfy to be kept. The default keeps everything.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Filter {
+    /// Drop positions where the side to move is in check.
+    pub skip_in_check: bool,
+    /// Allowed number of pieces on the board, kings and pawns included.
+    pub pieces: Option<RangeInclusive<u32>>,
+    /// Allowed search score in centipawns, from the side to move. Mate
+    /// scores are outside any sensible range, so those go too.
+    pub eval: Option<RangeInclusive<i32>>,
+    /// Depth of the search behind `eval`; shallow, so that big corpora go
+    /// through in reasonable time.
+    pub eval_depth: u8,
+}
+
+impl Filter {
+    pub fn keeps(&self, board: &Board) -> bool {
+        if self.skip_in_check && board.in_check() {
+            return false;
+        }
+        if let Some(pieces) = &self.pieces
+            && !pieces.contains(&board.all_occupancy.count())
+        {
+            return false;
+        }
+        // last, it is by far the slowest
+        if let Some(eval) = &self.eval {
+            let score = search::search(board, self.eval_depth.max(1)).score;
+            if !eval.contains(&score) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Where every position read ended up.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct CorpusStats {
+    pub read: usize,
+    /// Lines (or PGN games) that couldn't be parsed.
+    pub invalid: usize,
+    pub duplicates: usize,
+    pub filtered: usize,
+    pub written: usize,
+}
+
+/// The position at the start of an EPD or FEN line, and the rest of the
+/// line. The move counters are taken if present; EPD lines don't have them.
+///
+/// ```
+/// use ananke::corpus::parse_line;
+///
+/// let (board, rest) = parse_line("4k3/8/8/8/8/8/4P3/4K3 w - - bm e4; id \"x\";").unwrap();
+/// assert_eq!(board.to_fen(), "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+/// assert_eq!(rest, "bm e4; id \"x\";");
+///
+/// let (_, rest) = parse_line("4k3/8/8/8/8/8/4P3/4K3 b - - 3 40 [0.5]").unwrap();
+/// assert_eq!(rest, "[0.5]");
+/// ```
+pub fn parse_line(line: &str) -> Result<(Board, &str), String> {
+    let mut rest = line.trim();
+    let mut fields = Vec::new();
+    while fields.len() < 6 {
+        let Some((field, tail)) = split_field(rest) else {
+            break;
+        };
+        // the counters are optional: stop at the first field that isn't one
+        if fields.len() >= 4 && field.parse::<u32>().is_err() {
+            break;
+        }
+        fields.push(field);
+        rest = tail;
+    }
+    if fields.len() < 4 {
+        return Err(format!("Not a position: {}", line));
+    }
+    let board = Board::from_fen(&fields.join(" "))?;
+    Ok((board, rest))
+}
+
+fn split_field(text: &str) -> Option<(&str, &str)> {
+    if text.is_empty() {
+        return None;
+    }
+    match text.split_once(char::is_whitespace) {
+        Some((field, rest)) => Some((field, rest.trim_start())),
+        None => Some((text, "")),
+    }
+}
+
+/// Deduplicate and filter EPD/FEN lines from `input` into `out`. The first
+/// line seen for a position wins. Blank lines and `#` comments are skipped
+/// without counting.
+pub fn filter_lines(
+    input: impl BufRead,
+    mut out: impl Write,
+    filter: &Filter,
+) -> io::Result<CorpusStats> {
+    let mut stats = CorpusStats::default();
+    let mut seen = HashSet::new();
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        stats.read += 1;
+        let Ok((board, _)) = parse_line(&line) else {
+            stats.invalid += 1;
+            continue;
+        };
+        if keep(&board, filter, &mut seen, &mut stats) {
+            writeln!(out, "{}", line.trim())?;
+        }
+    }
+    Ok(stats)
+}
+
+/// The positions a move was played from in each game's main line, as FENs
+/// labelled with the result from white's side ("[1.0]", "[0.5]", "[0.0]"),
+/// deduplicated and filtered like `filter_lines`. Unfinished games are
+/// skipped: without a result there's nothing to label their positions with.
+pub fn positions_from_pgn(
+    input: impl BufRead,
+    mut out: impl Write,
+    filter: &Filter,
+) -> io::Result<CorpusStats> {
+    let mut stats = CorpusStats::default();
+    let mut seen = HashSet::new();
+    for game in PgnReader::new(input) {
+        let Ok(game) = game else {
+            stats.invalid += 1;
+            continue;
+        };
+        let label = match game.result {
+            Some(GameResult::WhiteWins) => "[1.0]",
+            Some(GameResult::Draw) => "[0.5]",
+            Some(GameResult::BlackWins) => "[0.0]",
+            None => continue,
+        };
+        let mut board = game.start.clone();
+        for m in game.mainline() {
+            stats.read += 1;
+            if keep(&board, filter, &mut seen, &mut stats) {
+                writeln!(out, "{} {}", board.to_fen(), label)?;
+            }
+            board.make_move_in_place(m);
+        }
+    }
+    Ok(stats)
+}
+
+// dedup before filtering: a repeat is dropped as a repeat even if the
+// first copy was filtered out, and it saves searching it twice
+fn keep(board: &Board, filter: &Filter, seen: &mut HashSet<u64>, stats: &mut CorpusStats) -> bool {
+    if !seen.insert(board.hash) {
+        stats.duplicates += 1;
+        false
+    } else if !filter.keeps(board) {
+        stats.filtered += 1;
+        false
+    } else {
+        stats.written += 1;
+        true
+    }
+}
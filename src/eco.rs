@@ -0,0 +1,255 @@
+use crate::board::Board;
+use crate::san;
+use crate::types::Move;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// An ECO code such as "C60": volume A-E and a number 0-99.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EcoCode {
+    pub volume: char,
+    pub number: u8,
+}
+
+impl EcoCode {
+    fn parse(code: &str) -> Self {
+        EcoCode {
+            volume: code.chars().next().unwrap(),
+            number: code[1..].parse().unwrap(),
+        }
+    }
+}
+
+impl fmt::Display for EcoCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{:02}", self.volume, self.number)
+    }
+}
+
+// (code, name, SAN line from the starting position)
+const OPENINGS: &[(&str, &str, &str)] = &[
+    ("A00", "Polish Opening", "b4"),
+    ("A01", "Nimzo-Larsen Attack", "b3"),
+    ("A02", "Bird Opening", "f4"),
+    ("A04", "Zukertort Opening", "Nf3"),
+    ("A10", "English Opening", "c4"),
+    ("A20", "English Opening: King's English Variation", "c4 e5"),
+    ("A40", "Queen's Pawn Game", "d4"),
+    ("A43", "Benoni Defense: Old Benoni", "d4 c5"),
+    ("A45", "Indian Defense", "d4 Nf6"),
+    ("A45", "Trompowsky Attack", "d4 Nf6 Bg5"),
+    ("A50", "Indian Defense: Normal Variation", "d4 Nf6 c4"),
+    ("A56", "Benoni Defense", "d4 Nf6 c4 c5"),
+    ("A57", "Benko Gambit", "d4 Nf6 c4 c5 d5 b5"),
+    ("A80", "Dutch Defense", "d4 f5"),
+    ("B00", "King's Pawn Game", "e4"),
+    ("B01", "Scandinavian Defense", "e4 d5"),
+    ("B02", "Alekhine Defense", "e4 Nf6"),
+    ("B06", "Modern Defense", "e4 g6"),
+    ("B07", "Pirc Defense", "e4 d6"),
+    ("B10", "Caro-Kann Defense", "e4 c6"),
+    (
+        "B12",
+        "Caro-Kann Defense: Advance Variation",
+        "e4 c6 d4 d5 e5",
+    ),
+    (
+        "B13",
+        "Caro-Kann Defense: Exchange Variation",
+        "e4 c6 d4 d5 exd5 cxd5",
+    ),
+    ("B20", "Sicilian Defense", "e4 c5"),
+    (
+        "B21",
+        "Sicilian Defense: Smith-Morra Gambit",
+        "e4 c5 d4 cxd4 c3",
+    ),
+    ("B22", "Sicilian Defense: Alapin Variation", "e4 c5 c3"),
+    ("B23", "Sicilian Defense: Closed", "e4 c5 Nc3"),
+    ("B27", "Sicilian Defense", "e4 c5 Nf3"),
+    ("B30", "Sicilian Defense: Old Sicilian", "e4 c5 Nf3 Nc6"),
+    ("B40", "Sicilian Defense: French Variation", "e4 c5 Nf3 e6"),
+    ("B50", "Sicilian Defense", "e4 c5 Nf3 d6"),
+    ("B54", "Sicilian Defense: Open", "e4 c5 Nf3 d6 d4 cxd4 Nxd4"),
+    (
+        "B70",
+        "Sicilian Defense: Dragon Variation",
+        "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 g6",
+    ),
+    (
+        "B90",
+        "Sicilian Defense: Najdorf Variation",
+        "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6",
+    ),
+    ("C00", "French Defense", "e4 e6"),
+    (
+        "C01",
+        "French Defense: Exchange Variation",
+        "e4 e6 d4 d5 exd5",
+    ),
+    ("C02", "French Defense: Advance Variation", "e4 e6 d4 d5 e5"),
+    (
+        "C03",
+        "French Defense: Tarrasch Variation",
+        "e4 e6 d4 d5 Nd2",
+    ),
+    (
+        "C11",
+        "French Defense: Classical Variation",
+        "e4 e6 d4 d5 Nc3 Nf6",
+    ),
+    (
+        "C15",
+        "French Defense: Winawer Variation",
+        "e4 e6 d4 d5 Nc3 Bb4",
+    ),
+    ("C20", "King's Pawn Game", "e4 e5"),
+    ("C21", "Center Game", "e4 e5 d4"),
+    ("C23", "Bishop's Opening", "e4 e5 Bc4"),
+    ("C25", "Vienna Game", "e4 e5 Nc3"),
+    ("C30", "King's Gambit", "e4 e5 f4"),
+    ("C33", "King's Gambit Accepted", "e4 e5 f4 exf4"),
+    ("C40", "King's Knight Opening", "e4 e5 Nf3"),
+    ("C41", "Philidor Defense", "e4 e5 Nf3 d6"),
+    ("C42", "Petrov's Defense", "e4 e5 Nf3 Nf6"),
+    (
+        "C44",
+        "King's Knight Opening: Normal Variation",
+        "e4 e5 Nf3 Nc6",
+    ),
+    ("C44", "Scotch Game", "e4 e5 Nf3 Nc6 d4"),
+    ("C45", "Scotch Game", "e4 e5 Nf3 Nc6 d4 exd4 Nxd4"),
+    ("C46", "Three Knights Opening", "e4 e5 Nf3 Nc6 Nc3"),
+    ("C47", "Four Knights Game", "e4 e5 Nf3 Nc6 Nc3 Nf6"),
+    ("C50", "Italian Game", "e4 e5 Nf3 Nc6 Bc4"),
+    ("C50", "Italian Game: Giuoco Piano", "e4 e5 Nf3 Nc6 Bc4 Bc5"),
+    (
+        "C51",
+        "Italian Game: Evans Gambit",
+        "e4 e5 Nf3 Nc6 Bc4 Bc5 b4",
+    ),
+    (
+        "C53",
+        "Italian Game: Classical Variation",
+        "e4 e5 Nf3 Nc6 Bc4 Bc5 c3",
+    ),
+    (
+        "C55",
+        "Italian Game: Two Knights Defense",
+        "e4 e5 Nf3 Nc6 Bc4 Nf6",
+    ),
+    ("C60", "Ruy Lopez", "e4 e5 Nf3 Nc6 Bb5"),
+    ("C65", "Ruy Lopez: Berlin Defense", "e4 e5 Nf3 Nc6 Bb5 Nf6"),
+    (
+        "C68",
+        "Ruy Lopez: Exchange Variation",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Bxc6",
+    ),
+    (
+        "C70",
+        "Ruy Lopez: Morphy Defense",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Ba4",
+    ),
+    (
+        "C84",
+        "Ruy Lopez: Closed",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Be7",
+    ),
+    ("D00", "Queen's Pawn Game", "d4 d5"),
+    ("D00", "Queen's Pawn Game: London System", "d4 d5 Bf4"),
+    ("D02", "Queen's Pawn Game: Zukertort Variation", "d4 d5 Nf3"),
+    ("D06", "Queen's Gambit", "d4 d5 c4"),
+    (
+        "D07",
+        "Queen's Gambit Declined: Chigorin Defense",
+        "d4 d5 c4 Nc6",
+    ),
+    ("D10", "Slav Defense", "d4 d5 c4 c6"),
+    ("D20", "Queen's Gambit Accepted", "d4 d5 c4 dxc4"),
+    ("D30", "Queen's Gambit Declined", "d4 d5 c4 e6"),
+    ("D43", "Semi-Slav Defense", "d4 d5 c4 c6 Nf3 Nf6 Nc3 e6"),
+    ("D80", "Grünfeld Defense", "d4 Nf6 c4 g6 Nc3 d5"),
+    ("E00", "Indian Defense: East Indian Defense", "d4 Nf6 c4 e6"),
+    ("E01", "Catalan Opening", "d4 Nf6 c4 e6 g3"),
+    (
+        "E10",
+        "Indian Defense: Anti-Nimzo-Indian",
+        "d4 Nf6 c4 e6 Nf3",
+    ),
+    ("E12", "Queen's Indian Defense", "d4 Nf6 c4 e6 Nf3 b6"),
+    ("E20", "Nimzo-Indian Defense", "d4 Nf6 c4 e6 Nc3 Bb4"),
+    ("E60", "King's Indian Defense", "d4 Nf6 c4 g6"),
+    ("E61", "King's Indian Defense", "d4 Nf6 c4 g6 Nc3"),
+    (
+        "E70",
+        "King's Indian Defense: Normal Variation",
+        "d4 Nf6 c4 g6 Nc3 Bg7 e4",
+    ),
+    (
+        "E90",
+        "King's Indian Defense: Normal Variation",
+        "d4 Nf6 c4 g6 Nc3 Bg7 e4 d6 Nf3",
+    ),
+];
+
+// one node per move played from the starting position
+#[derive(Default)]
+struct Node {
+    children: Vec<(Move, Node)>,
+    opening: Option<(EcoCode, &'static str)>,
+}
+
+fn trie() -> &'static Node {
+    static TRIE: OnceLock<Node> = OnceLock::new();
+    TRIE.get_or_init(|| {
+        let mut root = Node::default();
+        for &(code, name, line) in OPENINGS {
+            let mut board = Board::startpos();
+            let mut node = &mut root;
+            for text in line.split_whitespace() {
+                let m = san::from_san(&board, text).expect("bad move in opening table");
+                board = board.make_move(m);
+                let index = match node.children.iter().position(|(child, _)| *child == m) {
+                    Some(index) => index,
+                    None => {
+                        node.children.push((m, Node::default()));
+                        node.children.len() - 1
+                    }
+                };
+                node = &mut node.children[index].1;
+            }
+            node.opening = Some((EcoCode::parse(code), name));
+        }
+        root
+    })
+}
+
+/// The most specific named opening that the game starting with `moves`
+/// (from the standard starting position) follows.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::eco::classify_opening;
+///
+/// let mut board = Board::startpos();
+/// let mut moves = Vec::new();
+/// for uci in ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5"] {
+///     let m = board.parse_uci_move(uci).unwrap();
+///     board = board.make_move(m);
+///     moves.push(m);
+/// }
+/// let (code, name) = classify_opening(&moves).unwrap();
+/// assert_eq!((code.to_string().as_str(), name), ("C60", "Ruy Lopez"));
+/// ```
+pub fn classify_opening(moves: &[Move]) -> Option<(EcoCode, &'static str)> {
+    let mut node = trie();
+    let mut found = None;
+    for m in moves {
+        match node.children.iter().find(|(child, _)| child == m) {
+            Some((_, child)) => node = child,
+            None => break,
+        }
+        found = node.opening.or(found);
+    }
+    found
+}
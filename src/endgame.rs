@@ -0,0 +1,161 @@
+//! Endgames the generic eval plays badly, picked out by their material
+//! signature and scored by a specialist instead. The material table looks
+//! the specialist up once per signature, so the dispatch costs nothing at
+//! the nodes where there isn't one.
+//!
+//! - A queen or a rook, with or without more, against a bare king: the
+//!   material is won already, and what counts is driving the king to the
+//!   edge with our own close behind, where it can be mated. Centrality
+//!   alone would keep our king in the middle, away from the action.
+//! - Rook and pawn against rook: the generic score says a pawn up, but
+//!   with the defending king in front of the pawn (the Philidor) it's
+//!   drawn, and with the pawn on the seventh, its king in front of it and
+//!   the defending king cut off (the Lucena) it's won.
+
+use crate::bitboard::Square;
+use crate::board::Board;
+use crate::material::MaterialKey;
+use crate::types::{Color, PieceType};
+use crate::values;
+
+// mop-up: per step the losing king is from the centre, and per step the
+// kings are closer than the farthest they can be apart
+const EDGE_BONUS: i32 = 10;
+const CLOSE_KINGS_BONUS: i32 = 4;
+// rook endings: the Philidor leaves this share of the generic score, and
+// the Lucena adds this much to it
+const PHILIDOR_DIVISOR: i32 = 4;
+const LUCENA_BONUS: i32 = 200;
+
+/// A specialist, and the side it scores for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Endgame {
+    /// `strong` has a queen or a rook, maybe more, and the other side only
+    /// its king.
+    MopUp { strong: Color },
+    /// `strong` has a rook and a pawn, the other side a rook.
+    RookAndPawn { strong: Color },
+}
+
+// piece counts, pawns to queens
+const BARE: [u32; 5] = [0; 5];
+const ROOK_AND_PAWN: [u32; 5] = [1, 0, 0, 1, 0];
+const ONE_ROOK: [u32; 5] = [0, 0, 0, 1, 0];
+const ROOK: usize = 3;
+const QUEEN: usize = 4;
+
+fn counts(key: MaterialKey, color: Color) -> [u32; 5] {
+    [
+        PieceType::Pawn,
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+    ]
+    .map(|pt| key.count(color, pt))
+}
+
+impl Endgame {
+    /// The specialist for this material, if there is one.
+    ///
+    /// ```
+    /// use ananke::board::Board;
+    /// use ananke::endgame::Endgame;
+    /// use ananke::material::MaterialKey;
+    /// use ananke::types::Color;
+    ///
+    /// let board = Board::from_fen("8/8/8/4k3/8/8/8/R3K3 w - - 0 1").unwrap();
+    /// let key = MaterialKey::of(&board);
+    /// assert_eq!(
+    ///     Endgame::for_material(key),
+    ///     Some(Endgame::MopUp { strong: Color::White })
+    /// );
+    /// assert_eq!(Endgame::for_material(MaterialKey::of(&Board::startpos())), None);
+    /// ```
+    pub fn for_material(key: MaterialKey) -> Option<Endgame> {
+        for strong in [Color::White, Color::Black] {
+            let ours = counts(key, strong);
+            let theirs = counts(key, strong.opposite());
+            if theirs == BARE && (ours[ROOK] > 0 || ours[QUEEN] > 0) {
+                return Some(Endgame::MopUp { strong });
+            }
+            if ours == ROOK_AND_PAWN && theirs == ONE_ROOK {
+                return Some(Endgame::RookAndPawn { strong });
+            }
+        }
+        None
+    }
+
+    /// The score of `board`, in centipawns from the side to move's point
+    /// of view, given what the generic eval made of it.
+    pub fn evaluate(self, board: &Board, generic: i32) -> i32 {
+        let (strong, score) = match self {
+            Endgame::MopUp { strong } => (strong, mop_up(board, strong)),
+            Endgame::RookAndPawn { strong } => {
+                let generic = if board.side_to_move == strong {
+                    generic
+                } else {
+                    -generic
+                };
+                (strong, rook_and_pawn(board, strong, generic))
+            }
+        };
+        if board.side_to_move == strong {
+            score
+        } else {
+            -score
+        }
+    }
+}
+
+fn king(board: &Board, color: Color) -> Square {
+    board
+        .pieces(color, PieceType::King)
+        .lsb_index()
+        .expect("a side without a king")
+}
+
+// steps from the nearest of the four centre squares, 0 to 6
+fn centre_distance(sq: Square) -> i32 {
+    let from_middle = |x: u8| (3 - x as i32).max(x as i32 - 4);
+    from_middle(sq.file()) + from_middle(sq.rank())
+}
+
+fn distance(a: Square, b: Square) -> i32 {
+    (a.file() as i32 - b.file() as i32).abs() + (a.rank() as i32 - b.rank() as i32).abs()
+}
+
+// for `strong`: its material, and more the nearer the bare king is to the
+// edge and the nearer our king is to it
+fn mop_up(board: &Board, strong: Color) -> i32 {
+    let (ours, theirs) = (king(board, strong), king(board, strong.opposite()));
+    values::material(board, strong)
+        + EDGE_BONUS * centre_distance(theirs)
+        + CLOSE_KINGS_BONUS * (14 - distance(ours, theirs))
+}
+
+// for `strong`, starting from the generic score as it sees it
+fn rook_and_pawn(board: &Board, strong: Color, generic: i32) -> i32 {
+    let pawn = board
+        .pieces(strong, PieceType::Pawn)
+        .lsb_index()
+        .expect("a rook and pawn ending without its pawn");
+    // ranks counted from the strong side's end of the board
+    let rank = |sq: Square| match strong {
+        Color::White => sq.rank() as i32,
+        Color::Black => 7 - sq.rank() as i32,
+    };
+    let files_from_pawn = |sq: Square| (sq.file() as i32 - pawn.file() as i32).abs();
+    let (ours, theirs) = (king(board, strong), king(board, strong.opposite()));
+    if files_from_pawn(theirs) <= 1 && rank(theirs) > rank(pawn) {
+        generic / PHILIDOR_DIVISOR
+    } else if rank(pawn) == 6
+        && rank(ours) >= 6
+        && files_from_pawn(ours) <= 1
+        && files_from_pawn(theirs) >= 2
+    {
+        generic + LUCENA_BONUS
+    } else {
+        generic
+    }
+}
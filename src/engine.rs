@@ -0,0 +1,468 @@
+//! The protocol-independent engine core: the game being played and the move
+//! choice. The UCI and xboard front ends only translate text to calls here.
+
+use crate::adjudication::{ConcessionRules, Concessions};
+use crate::board::Board;
+use crate::contempt::Contempt;
+use crate::eval::{Evaluator, Hce};
+use crate::game::Game;
+use crate::params::Params;
+use crate::rng::Rng;
+use crate::search::{AlphaBeta, Limits, Progress, SearchResult, Searcher, is_mate_score};
+use crate::stats::Predictions;
+use crate::timeman::TimeBudget;
+use crate::tt::DEFAULT_HASH_MB;
+use crate::types::{Color, Move};
+use crate::variety::{self, VARIETY_LINES, VarietyRules};
+
+/// Search depth when the GUI doesn't ask for one.
+pub const DEFAULT_DEPTH: u8 = 4;
+
+pub struct Engine {
+    game: Game,
+    node_budget: Option<u64>,
+    contempt: Contempt,
+    evaluator: Box<dyn Evaluator>,
+    searcher: Box<dyn Searcher>,
+    // asked for with set_hash_size and set_params, kept for searchers set
+    // later
+    hash_mb: usize,
+    params: Params,
+    deterministic: bool,
+    // our own search scores this game, for dynamic contempt
+    scores: Vec<i32>,
+    // and for resigning and draws
+    concessions: Concessions,
+    // the position the last search expected next: after its best move
+    // and the reply it predicted
+    expected: Option<u64>,
+    predictions: Predictions,
+    variety: VarietyRules,
+    // what picks the move played when there's variety; it runs on from
+    // game to game, so that each game goes its own way
+    variety_rng: Rng,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Engine {
+            game: Game::new(),
+            node_budget: None,
+            contempt: Contempt::default(),
+            evaluator: Box::new(Hce::default()),
+            searcher: Box::new(AlphaBeta::new()),
+            hash_mb: DEFAULT_HASH_MB,
+            params: Params::default(),
+            deterministic: false,
+            scores: Vec::new(),
+            concessions: Concessions::new(ConcessionRules::default()),
+            expected: None,
+            predictions: Predictions::default(),
+            variety: VarietyRules::default(),
+            variety_rng: variety::rng(VarietyRules::default().seed),
+        }
+    }
+
+    /// Forget the current game and start over from the starting position,
+    /// clearing the searcher's hash table too.
+    pub fn new_game(&mut self) {
+        self.game = Game::new();
+        self.scores.clear();
+        self.concessions = Concessions::new(self.concessions.rules());
+        self.expected = None;
+        self.predictions = Predictions::default();
+        self.searcher.clear();
+    }
+
+    /// Replace the game with `board` followed by the UCI `moves`. The old
+    /// game is kept if any move is illegal, or if `board` has the side not
+    /// to move in check: `from_fen` lets that through, but there's no
+    /// playing on with a king that can be taken.
+    pub fn set_position(&mut self, board: Board, moves: &str) -> Result<(), String> {
+        if board.opponent_in_check() {
+            return Err("Illegal position: side not to move is in check".to_string());
+        }
+        let mut game = Game::from_board(board);
+        game.apply_uci_moves(moves)?;
+        self.game = game;
+        Ok(())
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn board(&self) -> &Board {
+        self.game.board()
+    }
+
+    /// Play one move given in UCI notation.
+    pub fn play_uci(&mut self, text: &str) -> Result<Move, String> {
+        let m = self.board().parse_uci_move(text)?;
+        self.game.play(m)?;
+        Ok(m)
+    }
+
+    /// Cap every search at `nodes` per move, e.g. from
+    /// `strength::nodes_for_elo`, or lift the cap with None.
+    pub fn set_node_budget(&mut self, nodes: Option<u64>) {
+        self.node_budget = nodes;
+    }
+
+    pub fn node_budget(&self) -> Option<u64> {
+        self.node_budget
+    }
+
+    pub fn set_contempt(&mut self, contempt: Contempt) {
+        self.contempt = contempt;
+    }
+
+    pub fn contempt(&self) -> Contempt {
+        self.contempt
+    }
+
+    /// Score positions with `evaluator` from the next search on.
+    pub fn set_evaluator(&mut self, evaluator: Box<dyn Evaluator>) {
+        self.evaluator = evaluator;
+    }
+
+    pub fn evaluator(&self) -> &dyn Evaluator {
+        self.evaluator.as_ref()
+    }
+
+    /// Search with `searcher` from the next search on, with its hash table
+    /// at the size last set, and the parameters last set.
+    pub fn set_searcher(&mut self, mut searcher: Box<dyn Searcher>) {
+        searcher.set_hash_size(self.hash_mb);
+        searcher.set_params(&self.params);
+        self.searcher = searcher;
+    }
+
+    pub fn searcher(&self) -> &dyn Searcher {
+        self.searcher.as_ref()
+    }
+
+    /// Resize the searcher's hash table to `mb` megabytes, between
+    /// searches. Returns the size in use, 0 if the searcher keeps no table.
+    pub fn set_hash_size(&mut self, mb: usize) -> usize {
+        self.hash_mb = mb;
+        self.searcher.set_hash_size(mb)
+    }
+
+    pub fn hash_size(&self) -> usize {
+        self.hash_mb
+    }
+
+    /// Set the search parameter `name` to `value`, as `Params::set` does.
+    pub fn set_param(&mut self, name: &str, value: &str) -> Result<(), String> {
+        self.params.set(name, value)?;
+        self.searcher.set_params(&self.params);
+        Ok(())
+    }
+
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// Search reproducibly from now on: every search runs as
+    /// `Limits::deterministic`, starts from an empty hash table, and
+    /// contempt leaves out the dynamic part that follows the game's earlier
+    /// scores. Then the same position, options and limits always give the
+    /// same result.
+    pub fn set_deterministic(&mut self, deterministic: bool) {
+        self.deterministic = deterministic;
+    }
+
+    pub fn deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// Set the variety in the opening. A new seed starts the picks over
+    /// from it.
+    pub fn set_variety(&mut self, rules: VarietyRules) {
+        if rules.seed != self.variety.seed {
+            self.variety_rng = variety::rng(rules.seed);
+        }
+        self.variety = rules;
+    }
+
+    pub fn variety(&self) -> VarietyRules {
+        self.variety
+    }
+
+    /// How often this game's searches guessed the opponent's reply.
+    pub fn predictions(&self) -> Predictions {
+        self.predictions
+    }
+
+    /// When to resign, and to offer and accept draws, from the next search
+    /// on. The scores of the game so far still count.
+    pub fn set_concession_rules(&mut self, rules: ConcessionRules) {
+        self.concessions.set_rules(rules);
+    }
+
+    pub fn concession_rules(&self) -> ConcessionRules {
+        self.concessions.rules()
+    }
+
+    /// Should the side to move resign rather than play the move just
+    /// searched? Only ever true under the concession rules.
+    pub fn should_resign(&self) -> bool {
+        self.concessions.should_resign()
+    }
+
+    /// Resign the game for the side to move.
+    pub fn resign(&mut self) -> Result<(), String> {
+        self.game.resign(self.board().side_to_move)
+    }
+
+    /// Offer a draw along with the move just searched, if the concession
+    /// rules say to. The offer goes on record in the game, for the side to
+    /// move.
+    pub fn offer_draw(&mut self) -> bool {
+        let us = self.board().side_to_move;
+        self.concessions.offer_draw() && self.game.offer_draw(us).is_ok()
+    }
+
+    /// The opponent of `us` offers a draw: agree to it, ending the game,
+    /// if the last search scored low enough.
+    pub fn answer_draw_offer(&mut self, us: Color) -> bool {
+        if !self.concessions.accept_draw() {
+            return false;
+        }
+        self.game
+            .offer_draw(us.opposite())
+            .and_then(|_| self.game.accept_draw(us))
+            .is_ok()
+    }
+
+    /// The contempt the next search will use, in centipawns.
+    pub fn current_contempt(&self) -> i32 {
+        if self.deterministic {
+            self.contempt.value(&[])
+        } else {
+            self.contempt.value(&self.scores)
+        }
+    }
+
+    /// Search the current position within `limits`, and within the node
+    /// budget if there is one, with draws scored by the current contempt.
+    /// There is a best move whenever the position has a legal one, even if
+    /// the game is already drawn by rule.
+    ///
+    /// ```
+    /// use ananke::board::Board;
+    /// use ananke::engine::Engine;
+    /// use ananke::search::Limits;
+    ///
+    /// let mut engine = Engine::new();
+    /// engine.set_position(Board::startpos(), "f2f3 e7e5 g2g4").unwrap();
+    /// let result = engine.think(Limits::depth(2));
+    /// assert_eq!(result.best_move.unwrap().to_string(), "d8h4");
+    /// engine.play_uci("d8h4").unwrap();
+    /// assert!(engine.game().is_over());
+    /// ```
+    pub fn think(&mut self, limits: Limits) -> SearchResult {
+        self.think_with_info(limits, &mut |_| {})
+    }
+
+    /// `think`, handing `info` the searcher's progress reports as they come.
+    pub fn think_with_info(
+        &mut self,
+        limits: Limits,
+        info: &mut dyn FnMut(Progress),
+    ) -> SearchResult {
+        if self.deterministic {
+            self.searcher.clear();
+        }
+        // a search with moves left out doesn't score the position itself
+        let whole = limits.excluded.is_empty();
+        if whole && self.variety.applies(self.board()) {
+            return self.think_varied(limits, info);
+        }
+        if whole {
+            self.check_prediction();
+        }
+        let result = self.search(limits, info);
+        if whole {
+            self.record(&result);
+        }
+        result
+    }
+
+    /// The best `lines` moves, best first, each with its own line and
+    /// score: MultiPV. Each depth is searched for the first line, then for
+    /// the second with the first line's move left out, and so on, so that
+    /// every line is as deep as the others. Once a depth is done its lines
+    /// are sorted by score, and `info` hears of each, numbered from 1. The
+    /// limits and node budget hold for all of it together, and a line's
+    /// `nodes` are the total so far. There are fewer lines when there are
+    /// fewer legal moves.
+    ///
+    /// ```
+    /// use ananke::board::Board;
+    /// use ananke::engine::Engine;
+    /// use ananke::search::{Limits, MATE};
+    ///
+    /// let mut engine = Engine::new();
+    /// let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    /// engine.set_position(board, "").unwrap();
+    /// let lines = engine.think_multipv(Limits::depth(3), 3, &mut |_, _| {});
+    /// assert_eq!(lines.len(), 3);
+    /// assert_eq!(lines[0].best_move.unwrap().to_string(), "a1a8");
+    /// assert_eq!(lines[0].score, MATE - 1);
+    /// assert!(lines[1].score <= lines[0].score && lines[2].score <= lines[1].score);
+    /// ```
+    pub fn think_multipv(
+        &mut self,
+        limits: Limits,
+        lines: usize,
+        info: &mut dyn FnMut(usize, &SearchResult),
+    ) -> Vec<SearchResult> {
+        self.check_prediction();
+        let results = self.multipv(limits, lines, info);
+        if let Some(best) = results.first() {
+            self.record(best);
+        }
+        results
+    }
+
+    // `think` in the opening with variety: the best few lines, and one of
+    // them close enough to the best, at random. Its progress reports are
+    // the best line's at each depth
+    fn think_varied(&mut self, limits: Limits, info: &mut dyn FnMut(Progress)) -> SearchResult {
+        self.check_prediction();
+        let lines = self.multipv(limits.clone(), VARIETY_LINES, &mut |line, result| {
+            if line == 1 {
+                info(Progress::Result(result));
+            }
+        });
+        let result = match variety::pick(&lines, self.variety.margin, &mut self.variety_rng) {
+            Some(picked) => picked.clone(),
+            // stopped before a line was found
+            None => self.search(limits, info),
+        };
+        self.record(&result);
+        result
+    }
+
+    // the lines of `think_multipv`, without counting them as the game's
+    fn multipv(
+        &mut self,
+        limits: Limits,
+        lines: usize,
+        info: &mut dyn FnMut(usize, &SearchResult),
+    ) -> Vec<SearchResult> {
+        if self.deterministic {
+            self.searcher.clear();
+        }
+        let clock = limits.clock();
+        let node_limit = match (limits.nodes, self.node_budget) {
+            (Some(nodes), Some(budget)) => Some(nodes.min(budget)),
+            (nodes, budget) => nodes.or(budget),
+        };
+        let time = limits.time_budget().filter(|_| !self.deterministic);
+        let mut results: Vec<SearchResult> = Vec::new();
+        let mut nodes = 0;
+        'deepening: for depth in 1..=limits.depth.max(1) {
+            let mut excluded = limits.excluded.clone();
+            let mut found: Vec<SearchResult> = Vec::new();
+            for _ in 0..lines {
+                let elapsed = clock.elapsed(nodes);
+                let nodes_left = node_limit.map(|n| n.saturating_sub(nodes));
+                if nodes_left == Some(0) || time.is_some_and(|t| elapsed >= t.maximum) {
+                    break 'deepening;
+                }
+                // each search has what's left of the maximum, as its
+                // optimum too: when to stop deepening is decided out here
+                let sub_limits = Limits {
+                    depth,
+                    nodes: nodes_left,
+                    time: time.map(|t| TimeBudget {
+                        optimum: t.maximum - elapsed,
+                        maximum: t.maximum - elapsed,
+                    }),
+                    excluded: excluded.clone(),
+                    ..limits.clone()
+                };
+                let result = self.search(sub_limits, &mut |_| {});
+                nodes += result.nodes;
+                // a mate ends a search before its depth; anything else that
+                // does was cut off, and the lines from the last depth stand,
+                // unless there are none
+                let finished = result.depth == depth || is_mate_score(result.score);
+                if !finished {
+                    if results.is_empty() {
+                        results = found;
+                        if result.best_move.is_some() {
+                            results.push(result);
+                        }
+                        results.sort_by_key(|line| -line.score);
+                    }
+                    break 'deepening;
+                }
+                let Some(m) = result.best_move else {
+                    break;
+                };
+                excluded.push(m);
+                found.push(result);
+            }
+            // a line searched later can come out better than one before it
+            found.sort_by_key(|line| -line.score);
+            for (line, result) in found.iter_mut().enumerate() {
+                result.nodes = nodes;
+                info(line + 1, result);
+            }
+            results = found;
+            // as with one line, no new depth once half the optimum is gone
+            if time.is_some_and(|t| clock.elapsed(nodes) >= t.optimum / 2) {
+                break;
+            }
+        }
+        results
+    }
+
+    // one search of the current position, with the engine's contempt and
+    // node budget, and deterministic if it's set to be
+    fn search(&mut self, mut limits: Limits, info: &mut dyn FnMut(Progress)) -> SearchResult {
+        limits.contempt = self.current_contempt();
+        self.concessions.set_contempt(limits.contempt);
+        limits.deterministic |= self.deterministic;
+        if let Some(budget) = self.node_budget {
+            limits.nodes = Some(limits.nodes.map_or(budget, |n| n.min(budget)));
+        }
+        self.searcher
+            .search(self.game.board(), limits, self.evaluator.as_mut(), info)
+    }
+
+    // the result of searching the position itself: its score goes towards
+    // dynamic contempt and the concessions, and its line says what to
+    // expect next
+    fn record(&mut self, result: &SearchResult) {
+        if result.best_move.is_some() {
+            self.scores.push(result.score);
+            self.concessions.record(result.score);
+        }
+        self.expected = match result.pv[..] {
+            [m, reply, ..] => Some(self.board().make_move(m).make_move(reply).hash),
+            _ => None,
+        };
+    }
+
+    // before searching the position itself: is it the one the last search
+    // expected?
+    fn check_prediction(&mut self) {
+        if let Some(expected) = self.expected.take() {
+            self.predictions.made += 1;
+            if self.board().hash == expected {
+                self.predictions.hits += 1;
+            }
+        }
+    }
+}
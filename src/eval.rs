@@ -0,0 +1,176 @@
+//! Static evaluation: material and its imbalance terms, plus a small bonus
+//! for pieces near the centre, and for the king too as the pieces come
+//! off. While they're still on, a king that has castled, or still can, is
+//! worth something over one that has lost the chance. A few endgames are
+//! handed to the specialists in `endgame` instead. Deliberately simple; it
+//! only has to give the search something to aim for.
+//!
+//! The search doesn't call `evaluate` directly but goes through the
+//! `Evaluator` trait, so other evaluations (the network in `nnue`, or
+//! anything experimental) can be swapped in at runtime.
+
+use crate::bitboard::Bitboard;
+use crate::board::{Board, UndoInfo};
+use crate::material::{MaterialEntry, MaterialTable};
+use crate::types::{Color, Move, PieceType};
+use crate::values::{self, MAX_PHASE};
+use std::cell::RefCell;
+
+/// A static evaluation for the search. Only `evaluate` is required; the
+/// hooks let an evaluation that keeps state (an NNUE accumulator, say)
+/// follow the search's moves instead of starting from scratch at every
+/// node. The search calls `reset` once with its root position, then
+/// `on_make_move` after each move it makes and `on_unmake_move` after
+/// taking it back, always in pairs.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::eval::Evaluator;
+/// use ananke::search::{Limits, search_with_evaluator};
+/// use ananke::values;
+///
+/// // counts material and nothing else
+/// struct Material;
+///
+/// impl Evaluator for Material {
+///     fn evaluate(&self, board: &Board) -> i32 {
+///         let us = board.side_to_move;
+///         values::material(board, us) - values::material(board, us.opposite())
+///     }
+///
+///     fn name(&self) -> &str {
+///         "material"
+///     }
+/// }
+///
+/// // the rook takes the loose queen
+/// let board = Board::from_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").unwrap();
+/// let result = search_with_evaluator(&board, Limits::depth(2), &mut Material);
+/// assert_eq!(result.best_move.unwrap().to_string(), "d1d5");
+/// ```
+pub trait Evaluator {
+    /// Score of `board` in centipawns from the side to move's point of view.
+    fn evaluate(&self, board: &Board) -> i32;
+
+    /// A search is starting from `board`.
+    fn reset(&mut self, _board: &Board) {}
+
+    /// `m` has just been made, giving `board`; `undo` has what it took.
+    fn on_make_move(&mut self, _board: &Board, _m: Move, _undo: &UndoInfo) {}
+
+    /// The last move made has been taken back.
+    fn on_unmake_move(&mut self) {}
+
+    /// Short name for logs and the UCI `info string`.
+    fn name(&self) -> &str;
+}
+
+/// The hand-crafted evaluation above, as an `Evaluator`. Scores the same
+/// as `evaluate`, but looks the material terms up in its own
+/// `MaterialTable` rather than working them out at every node.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::eval::{self, Evaluator, Hce};
+///
+/// let hce = Hce::default();
+/// assert_eq!(hce.evaluate(&Board::startpos()), 0);
+/// // a knight up, from either side's point of view
+/// let board = Board::from_fen("r1bqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+/// assert!(hce.evaluate(&board) > 250);
+/// assert_eq!(hce.evaluate(&board), eval::evaluate(&board));
+/// ```
+#[derive(Default)]
+pub struct Hce {
+    material: RefCell<MaterialTable>,
+}
+
+impl Evaluator for Hce {
+    fn evaluate(&self, board: &Board) -> i32 {
+        let material = self.material.borrow_mut().probe(board);
+        evaluate_with(board, &material)
+    }
+
+    fn name(&self) -> &str {
+        "hce"
+    }
+}
+
+// centipawns for standing on each square, highest in the middle. Symmetric
+// top to bottom, so both colors use it as is.
+#[rustfmt::skip]
+const CENTRALITY: [i32; 64] = [
+    0,  2,  4,  5,  5,  4,  2,  0,
+    2,  6,  8, 10, 10,  8,  6,  2,
+    4,  8, 14, 18, 18, 14,  8,  4,
+    5, 10, 18, 25, 25, 18, 10,  5,
+    5, 10, 18, 25, 25, 18, 10,  5,
+    4,  8, 14, 18, 18, 14,  8,  4,
+    2,  6,  8, 10, 10,  8,  6,  2,
+    0,  2,  4,  5,  5,  4,  2,  0,
+];
+
+// a castled king, and each right to castle of a king that hasn't yet, with
+// all the pieces on; scaled down to nothing by the endgame. A right is
+// worth less than castling: it's only a chance to
+const CASTLED_BONUS: i32 = 30;
+const CASTLING_RIGHT_BONUS: i32 = 10;
+
+/// Score of `board` in centipawns from the side to move's point of view.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::eval::evaluate;
+///
+/// // White is a queen up; Black to move sees it as a deficit
+/// let board = Board::from_fen("4k3/8/8/8/8/8/8/3QK3 b - - 0 1").unwrap();
+/// assert!(evaluate(&board) < -800);
+/// ```
+pub fn evaluate(board: &Board) -> i32 {
+    evaluate_with(board, &MaterialEntry::compute(board.material_key))
+}
+
+// `material` is the entry for the board's material key
+fn evaluate_with(board: &Board, material: &MaterialEntry) -> i32 {
+    let us = board.side_to_move;
+    let imbalance = match us {
+        Color::White => material.imbalance,
+        Color::Black => -material.imbalance,
+    };
+    let score = side_score(board, us, material.phase)
+        - side_score(board, us.opposite(), material.phase)
+        + imbalance;
+    match material.endgame {
+        Some(endgame) => endgame.evaluate(board, score),
+        None => score,
+    }
+}
+
+fn side_score(board: &Board, color: Color, phase: i32) -> i32 {
+    let mut score = values::material(board, color);
+    for pt in [PieceType::Pawn, PieceType::Knight, PieceType::Bishop] {
+        score += centrality(board.pieces(color, pt));
+    }
+    // the king belongs in the middle only once there's little left to
+    // attack it with
+    let king = centrality(board.pieces(color, PieceType::King));
+    score + king * (MAX_PHASE - phase) / MAX_PHASE + king_shelter(board, color) * phase / MAX_PHASE
+}
+
+// the middlegame bonus for having castled, or for still being able to
+fn king_shelter(board: &Board, color: Color) -> i32 {
+    if board.castled[color] {
+        return CASTLED_BONUS;
+    }
+    let rights = &board.castling_rights;
+    CASTLING_RIGHT_BONUS
+        * (rights.can_castle_kingside(color) as i32 + rights.can_castle_queenside(color) as i32)
+}
+
+fn centrality(mut pieces: Bitboard) -> i32 {
+    let mut bonus = 0;
+    while let Some(sq) = pieces.pop_lsb() {
+        bonus += CENTRALITY[sq as usize];
+    }
+    bonus
+}
@@ -0,0 +1,319 @@
+use crate::board::Board;
+use crate::clock::{Clock, TimeControl};
+use crate::types::{Color, GameResult, Move};
+use std::time::Duration;
+
+/// Why a game ended.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    /// 75 moves without a capture or pawn move; automatic.
+    SeventyFiveMoveRule,
+    /// Same position five times; automatic.
+    FivefoldRepetition,
+    /// Neither side has mating material; automatic.
+    DeadPosition,
+    /// 50 moves without a capture or pawn move; needs a claim.
+    FiftyMoveRule,
+    /// Same position three times; needs a claim.
+    ThreefoldRepetition,
+    /// A flag fell. A loss, or a draw if the opponent couldn't mate anyway:
+    /// a bare king, or king and a lone knight or bishop.
+    Timeout,
+    Resignation,
+    /// A draw offer was accepted.
+    Agreement,
+}
+
+/// A game from a starting position: the moves played, every position along
+/// the way (for repetitions) and the result once it is decided.
+///
+/// Automatic endings are applied as soon as a move creates them. Claimable
+/// draws only end the game through `claim_draw`, since GUIs and servers let
+/// the player decide.
+#[derive(Clone)]
+pub struct Game {
+    positions: Vec<Board>,
+    moves: Vec<Move>,
+    result: Option<(GameResult, Termination)>,
+    clock: Option<Clock>,
+    draw_offer: Option<Color>,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Game {
+    /// A game from the standard starting position.
+    pub fn new() -> Self {
+        Self::from_board(Board::startpos())
+    }
+
+    pub fn from_board(board: Board) -> Self {
+        let mut game = Game {
+            positions: vec![board],
+            moves: Vec::new(),
+            result: None,
+            clock: None,
+            draw_offer: None,
+        };
+        // a position can be over before any move is played
+        game.result = game.automatic_ending();
+        game
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Self, String> {
+        Board::from_fen(fen).map(Self::from_board)
+    }
+
+    /// The current position.
+    pub fn board(&self) -> &Board {
+        self.positions.last().unwrap()
+    }
+
+    pub fn start_board(&self) -> &Board {
+        &self.positions[0]
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn result(&self) -> Option<GameResult> {
+        self.result.map(|(result, _)| result)
+    }
+
+    pub fn termination(&self) -> Option<Termination> {
+        self.result.map(|(_, termination)| termination)
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Put the game on a clock. The side to move's time starts now.
+    pub fn set_clock(&mut self, control: TimeControl) {
+        let mut clock = Clock::new(control, self.board().side_to_move);
+        clock.start();
+        self.clock = Some(clock);
+    }
+
+    pub fn clock(&self) -> Option<&Clock> {
+        self.clock.as_ref()
+    }
+
+    /// Play a legal move. Fails if the game is over or the move is illegal.
+    /// With a clock, the mover's clock is pressed using wall-clock time.
+    pub fn play(&mut self, m: Move) -> Result<(), String> {
+        self.play_with(m, None)
+    }
+
+    /// Like `play`, but charge `elapsed` to the mover's clock instead of
+    /// measuring it, for simulated games.
+    pub fn play_timed(&mut self, m: Move, elapsed: Duration) -> Result<(), String> {
+        self.play_with(m, Some(elapsed))
+    }
+
+    /// Has the side to move run out of time while thinking? Ends the game if
+    /// so; call it while waiting on a player that may never answer.
+    pub fn check_flag(&mut self) -> bool {
+        if self.is_over() {
+            return false;
+        }
+        match self.clock.as_ref().and_then(Clock::flagged) {
+            Some(color) => {
+                self.result = Some(self.timeout(color));
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn play_with(&mut self, m: Move, elapsed: Option<Duration>) -> Result<(), String> {
+        if self.is_over() {
+            return Err("Game is over".to_string());
+        }
+        if !self.board().legal_moves().iter().any(|legal| *legal == m) {
+            return Err(format!("Illegal move: {}", m));
+        }
+        // a move made after the flag fell doesn't count
+        if let Some(clock) = &mut self.clock {
+            let pressed = match elapsed {
+                Some(elapsed) => clock.press_clock_after(elapsed),
+                None => clock.press_clock(),
+            };
+            if let Err(color) = pressed {
+                self.result = Some(self.timeout(color));
+                return Err(format!("{:?} lost on time", color));
+            }
+        }
+        // moving instead of accepting declines the opponent's offer
+        let mover = self.board().side_to_move;
+        if self.draw_offer == Some(mover.opposite()) {
+            self.draw_offer = None;
+        }
+        let next = self.board().make_move(m);
+        self.positions.push(next);
+        self.moves.push(m);
+        self.result = self.automatic_ending();
+        Ok(())
+    }
+
+    /// Play a move given in UCI notation, e.g. "e2e4" or "e7e8q".
+    ///
+    /// ```
+    /// use ananke::game::{Game, Termination};
+    /// use ananke::types::GameResult;
+    ///
+    /// let mut game = Game::new();
+    /// for m in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+    ///     game.play_uci(m).unwrap();
+    /// }
+    /// assert_eq!(game.result(), Some(GameResult::BlackWins));
+    /// assert_eq!(game.termination(), Some(Termination::Checkmate));
+    /// ```
+    pub fn play_uci(&mut self, text: &str) -> Result<(), String> {
+        let m = self.board().parse_uci_move(text)?;
+        self.play(m)
+    }
+
+    /// Play a whitespace-separated list of UCI moves, as in "position
+    /// startpos moves e2e4 c7c5", recording each in the history as `play`
+    /// does. On error the game is left untouched.
+    ///
+    /// ```
+    /// use ananke::game::Game;
+    ///
+    /// let mut game = Game::new();
+    /// game.apply_uci_moves("g1f3 g8f6 f3g1 f6g8").unwrap();
+    /// assert_eq!(game.moves().len(), 4);
+    /// assert_eq!(game.repetition_count(), 2);
+    /// assert!(game.apply_uci_moves("e2e4 e7e5 e1e3").is_err());
+    /// assert_eq!(game.moves().len(), 4);
+    /// ```
+    pub fn apply_uci_moves(&mut self, moves: &str) -> Result<(), String> {
+        let mut game = self.clone();
+        for (i, text) in moves.split_whitespace().enumerate() {
+            game.play_uci(text)
+                .map_err(|e| format!("{} (move {})", e, i + 1))?;
+        }
+        *self = game;
+        Ok(())
+    }
+
+    /// How many times the current position has occurred, counting now.
+    /// Only positions since the last capture or pawn move can match.
+    pub fn repetition_count(&self) -> usize {
+        let current = self.board();
+        let reversible = current.halfmove_clock as usize;
+        self.positions
+            .iter()
+            .rev()
+            .take(reversible + 1)
+            .step_by(2)
+            .filter(|board| board.is_same_position(current))
+            .count()
+    }
+
+    /// A draw the side to move could claim right now, if any.
+    pub fn can_claim_draw(&self) -> Option<Termination> {
+        if self.is_over() {
+            return None;
+        }
+        if self.repetition_count() >= 3 {
+            Some(Termination::ThreefoldRepetition)
+        } else if self.board().halfmove_clock >= 100 {
+            Some(Termination::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    /// Claim a draw by threefold repetition or the 50-move rule.
+    pub fn claim_draw(&mut self) -> Result<Termination, String> {
+        let termination = self
+            .can_claim_draw()
+            .ok_or_else(|| "No draw to claim".to_string())?;
+        self.result = Some((GameResult::Draw, termination));
+        Ok(termination)
+    }
+
+    /// `color` resigns; the opponent wins.
+    pub fn resign(&mut self, color: Color) -> Result<(), String> {
+        if self.is_over() {
+            return Err("Game is over".to_string());
+        }
+        self.result = Some((
+            GameResult::win_for(color.opposite()),
+            Termination::Resignation,
+        ));
+        Ok(())
+    }
+
+    /// `color` offers a draw. It stands until the opponent accepts it or
+    /// declines by playing a move.
+    pub fn offer_draw(&mut self, color: Color) -> Result<(), String> {
+        if self.is_over() {
+            return Err("Game is over".to_string());
+        }
+        self.draw_offer = Some(color);
+        Ok(())
+    }
+
+    /// The side with a draw offer on the table, if any.
+    pub fn draw_offer(&self) -> Option<Color> {
+        self.draw_offer
+    }
+
+    /// `color` accepts the opponent's pending draw offer.
+    pub fn accept_draw(&mut self, color: Color) -> Result<(), String> {
+        if self.is_over() {
+            return Err("Game is over".to_string());
+        }
+        if self.draw_offer != Some(color.opposite()) {
+            return Err("No draw offer to accept".to_string());
+        }
+        self.draw_offer = None;
+        self.result = Some((GameResult::Draw, Termination::Agreement));
+        Ok(())
+    }
+
+    // losing on time is only a loss if the opponent has something to mate with
+    fn timeout(&self, flagged: Color) -> (GameResult, Termination) {
+        if !self.board().has_mating_material(flagged.opposite()) {
+            (GameResult::Draw, Termination::Timeout)
+        } else {
+            (
+                GameResult::win_for(flagged.opposite()),
+                Termination::Timeout,
+            )
+        }
+    }
+
+    // endings that apply without anyone asking; mate beats the move rules
+    fn automatic_ending(&self) -> Option<(GameResult, Termination)> {
+        let board = self.board();
+        if board.count_legal_moves() == 0 {
+            return Some(if board.in_check() {
+                let winner = board.side_to_move.opposite();
+                (GameResult::win_for(winner), Termination::Checkmate)
+            } else {
+                (GameResult::Draw, Termination::Stalemate)
+            });
+        }
+        let termination = if self.repetition_count() >= 5 {
+            Termination::FivefoldRepetition
+        } else if board.halfmove_clock >= 150 {
+            Termination::SeventyFiveMoveRule
+        } else if board.has_insufficient_material() {
+            Termination::DeadPosition
+        } else {
+            return None;
+        };
+        Some((GameResult::Draw, termination))
+    }
+}
@@ -0,0 +1,230 @@
+//! Just enough JSON for the analysis server and batch analysis: reading a
+//! flat object of strings, numbers and booleans, and writing analysis
+//! out. Anything nested is refused rather than half read.
+
+use crate::search::{self, SearchResult};
+use std::fmt::Write;
+
+/// A value in a flat object.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Number(f64),
+    Bool(bool),
+    Null,
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The value as a whole number no less than 0, if it is one.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::Number(n) if n >= 0.0 && n.fract() == 0.0 && n <= u64::MAX as f64 => {
+                Some(n as u64)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The members of the object in `text`, in order.
+///
+/// ```
+/// use ananke::json::{Value, parse_object};
+///
+/// let members = parse_object(r#"{"fen": "8/8/8/8/8/8/8/8 w - - 0 1", "depth": 6}"#).unwrap();
+/// assert_eq!(members[1], ("depth".to_string(), Value::Number(6.0)));
+/// assert!(parse_object(r#"{"moves": ["e2e4"]}"#).is_err());
+/// ```
+pub fn parse_object(text: &str) -> Result<Vec<(String, Value)>, String> {
+    let mut parser = Parser {
+        chars: text.chars().peekable(),
+    };
+    parser.expect('{')?;
+    let mut members = Vec::new();
+    if parser.peek() == Some('}') {
+        parser.next();
+    } else {
+        loop {
+            let name = parser.string()?;
+            parser.expect(':')?;
+            members.push((name, parser.value()?));
+            match parser.next() {
+                Some(',') => {}
+                Some('}') => break,
+                _ => return Err("JSON: expected ',' or '}'".to_string()),
+            }
+        }
+    }
+    if parser.peek().is_some() {
+        return Err("JSON: more after the object".to_string());
+    }
+    Ok(members)
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Parser<'_> {
+    // the next character that isn't whitespace, left in place
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+        self.chars.peek().copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        self.peek();
+        self.chars.next()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(format!("JSON: expected '{}'", expected)),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(s),
+                Some('\\') => {
+                    let escaped = match self.chars.next() {
+                        Some(c @ ('"' | '\\' | '/')) => c,
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let hex: String = self.chars.by_ref().take(4).collect();
+                            u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or("JSON: bad \\u escape")?
+                        }
+                        _ => return Err("JSON: bad escape".to_string()),
+                    };
+                    s.push(escaped);
+                }
+                Some(c) => s.push(c),
+                None => return Err("JSON: unterminated string".to_string()),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('"') => self.string().map(Value::String),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let mut number = String::new();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|&c| c.is_ascii_digit() || "+-.eE".contains(c))
+                {
+                    number.push(c);
+                }
+                number
+                    .parse()
+                    .map(Value::Number)
+                    .map_err(|_| format!("JSON: bad number {}", number))
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let mut word = String::new();
+                while let Some(c) = self.chars.next_if(char::is_ascii_alphabetic) {
+                    word.push(c);
+                }
+                match word.as_str() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "null" => Ok(Value::Null),
+                    _ => Err(format!("JSON: unknown value {}", word)),
+                }
+            }
+            _ => Err("JSON: expected a string, number or boolean".to_string()),
+        }
+    }
+}
+
+/// `s` as a JSON string, quotes and all.
+pub fn string(s: &str) -> String {
+    let mut out = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// `{"cp": n}`, or `{"mate": n}` in moves, negative when the side to move
+/// is the one getting mated, as UCI has it.
+pub fn score(score: i32) -> String {
+    match search::mate_in_moves(score) {
+        Some(moves) => format!("{{\"mate\":{}}}", moves),
+        None => format!("{{\"cp\":{}}}", score),
+    }
+}
+
+/// The analysis of `fen`: the best move, null when there is none, and
+/// each line with its number, depth, score, nodes and moves.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::engine::Engine;
+/// use ananke::json;
+/// use ananke::search::Limits;
+///
+/// let fen = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+/// let mut engine = Engine::new();
+/// engine.set_position(Board::from_fen(fen).unwrap(), "").unwrap();
+/// let result = engine.think(Limits::depth(1));
+/// assert_eq!(
+///     json::analysis(fen, &[result]),
+///     r#"{"fen":"6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1","bestmove":"a1a8","lines":[{"multipv":1,"depth":1,"score":{"mate":1},"nodes":17,"pv":["a1a8"]}]}"#
+/// );
+/// ```
+pub fn analysis(fen: &str, lines: &[SearchResult]) -> String {
+    let best = lines
+        .first()
+        .and_then(|best| best.best_move)
+        .map_or("null".to_string(), |m| string(&m.to_string()));
+    let mut out = format!(
+        "{{\"fen\":{},\"bestmove\":{},\"lines\":[",
+        string(fen),
+        best
+    );
+    for (i, line) in lines.iter().enumerate() {
+        let pv: Vec<String> = line.pv.iter().map(|m| string(&m.to_string())).collect();
+        let _ = write!(
+            out,
+            "{}{{\"multipv\":{},\"depth\":{},\"score\":{},\"nodes\":{},\"pv\":[{}]}}",
+            if i == 0 { "" } else { "," },
+            i + 1,
+            line.depth,
+            score(line.score),
+            line.nodes,
+            pv.join(",")
+        );
+    }
+    out.push_str("]}");
+    out
+}
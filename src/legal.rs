@@ -0,0 +1,265 @@
+use crate::bitboard::{Bitboard, Square};
+use crate::board::Board;
+use crate::magic;
+use crate::movegen::{
+    CASTLING_PATHS, KINGSIDE, QUEENSIDE, generate_king_attacks, generate_knight_attacks,
+    generate_pawn_attacks,
+};
+use crate::profile::{self, Section};
+use crate::types::{Color, Move, MoveFlag, PieceType};
+
+/// Squares strictly between two squares on a shared rank, file or diagonal.
+/// Empty if the squares aren't aligned.
+pub fn between(a: Square, b: Square) -> Bitboard {
+    let dr = b.rank() as i8 - a.rank() as i8;
+    let df = b.file() as i8 - a.file() as i8;
+    let a_bb = Bitboard::new(1u64 << a as u8);
+    let b_bb = Bitboard::new(1u64 << b as u8);
+
+    if a == b {
+        Bitboard::EMPTY
+    } else if dr == 0 || df == 0 {
+        magic::get_rook_attacks(a, b_bb) & magic::get_rook_attacks(b, a_bb)
+    } else if dr.abs() == df.abs() {
+        magic::get_bishop_attacks(a, b_bb) & magic::get_bishop_attacks(b, a_bb)
+    } else {
+        Bitboard::EMPTY
+    }
+}
+
+/// Check and pin information for the side to move, computed once per
+/// position. Any move that respects these masks is legal, apart from king
+/// moves and en passant, which need their own attack tests.
+pub struct Legality {
+    pub king_sq: Square,
+    /// Enemy pieces giving check.
+    pub checkers: Bitboard,
+    /// Squares a non-king move must land on: everything when not in check,
+    /// the checker or a blocking square when in single check, nothing when
+    /// in double check.
+    pub check_mask: Bitboard,
+    /// Our pieces pinned to our king.
+    pub pinned: Bitboard,
+    pins: [(Square, Bitboard); 8],
+    pin_count: usize,
+}
+
+impl Legality {
+    pub fn new(board: &Board) -> Self {
+        let us = board.side_to_move;
+        let them = us.opposite();
+        let king_sq = board.get_king_square(us);
+        let (own, enemies) = (board.occupancy[us], board.occupancy[them]);
+
+        let checkers = board.attackers_to(king_sq, board.all_occupancy) & enemies;
+        let check_mask = match checkers.count() {
+            0 => Bitboard::UNIVERSE,
+            1 => {
+                let checker = checkers.lsb_index().unwrap();
+                between(king_sq, checker) | checkers
+            }
+            _ => Bitboard::EMPTY,
+        };
+
+        // enemy sliders that would hit our king if only enemy pieces blocked
+        let queens = board.pieces(them, PieceType::Queen);
+        let mut snipers = (magic::get_rook_attacks(king_sq, enemies)
+            & (board.pieces(them, PieceType::Rook) | queens))
+            | (magic::get_bishop_attacks(king_sq, enemies)
+                & (board.pieces(them, PieceType::Bishop) | queens));
+
+        let mut legality = Legality {
+            king_sq,
+            checkers,
+            check_mask,
+            pinned: Bitboard::EMPTY,
+            pins: [(Square::A1, Bitboard::EMPTY); 8],
+            pin_count: 0,
+        };
+
+        while let Some(sniper) = snipers.pop_lsb() {
+            let blockers = between(king_sq, sniper) & board.all_occupancy;
+            if blockers.count() == 1 && (blockers & own) != Bitboard::EMPTY {
+                let pinned_sq = blockers.lsb_index().unwrap();
+                legality.pinned |= blockers;
+                // a pinned piece may only slide along the pin or take the pinner
+                let ray = between(king_sq, sniper) | Bitboard::new(1u64 << sniper as u8);
+                legality.pins[legality.pin_count] = (pinned_sq, ray);
+                legality.pin_count += 1;
+            }
+        }
+        legality
+    }
+
+    /// Squares a piece on `sq` may move to without exposing the king:
+    /// the pin ray if it is pinned, otherwise everything.
+    pub fn pin_mask(&self, sq: Square) -> Bitboard {
+        if !self.pinned.get_bit(sq) {
+            return Bitboard::UNIVERSE;
+        }
+        self.pins[..self.pin_count]
+            .iter()
+            .find(|(pinned_sq, _)| *pinned_sq == sq)
+            .map_or(Bitboard::UNIVERSE, |(_, ray)| *ray)
+    }
+
+    /// Can the king step to `to`? Checked with the king lifted off the board,
+    /// so it can't hide behind itself from a slider.
+    pub fn king_can_move_to(&self, board: &Board, to: Square) -> bool {
+        let mut occupancy = board.all_occupancy;
+        occupancy.clear_bit(self.king_sq);
+        let them = board.occupancy[board.side_to_move.opposite()];
+        (board.attackers_to(to, occupancy) & them) == Bitboard::EMPTY
+    }
+
+    /// Can the pawn on `from` take en passant? The masks don't cover it:
+    /// two pawns leave the rank at once, which can expose the king to a
+    /// rook or queen along it, and when in check the pawn taken may be the
+    /// checker even though the capture lands elsewhere. So look at the
+    /// board as it would be afterwards.
+    pub fn en_passant_allowed(&self, board: &Board, from: Square, ep_sq: Square) -> bool {
+        let us = board.side_to_move;
+        let victim = if us == Color::White {
+            ep_sq as u8 - 8
+        } else {
+            ep_sq as u8 + 8
+        };
+        let victim = Bitboard::new(1u64 << victim);
+        let occupancy = (board.all_occupancy & !victim & !Bitboard::new(1u64 << from as u8))
+            | Bitboard::new(1u64 << ep_sq as u8);
+        let them = board.occupancy[us.opposite()] & !victim;
+        (board.attackers_to(self.king_sq, occupancy) & them) == Bitboard::EMPTY
+    }
+
+    /// Is the pseudo-legal move `m` legal? Answered from the masks, plus a
+    /// look at the board after the capture for en passant.
+    pub fn allows(&self, board: &Board, m: Move) -> bool {
+        let (from, to) = (m.from(), m.to());
+        if from == self.king_sq {
+            // the generator already checked the castling path for attacks
+            return m.flag() == MoveFlag::KingCastle
+                || m.flag() == MoveFlag::QueenCastle
+                || self.king_can_move_to(board, to);
+        }
+        if m.flag() == MoveFlag::EnPassant {
+            return self.en_passant_allowed(board, from, to);
+        }
+        // check_mask is empty in double check, so only king moves get here
+        self.check_mask.get_bit(to) && self.pin_mask(from).get_bit(to)
+    }
+}
+
+/// Count legal moves without building a move list: destination sets are
+/// masked by the check and pin information and popcounted. Promotions count
+/// once per promotion piece, matching the generator.
+pub fn count_legal_moves(board: &Board) -> usize {
+    let _profile = profile::scope(Section::LegalCount);
+    let legality = Legality::new(board);
+    let us = board.side_to_move;
+    let own = board.occupancy[us];
+    let mut count = 0;
+
+    // king moves are always possible, even in double check
+    let mut king_targets = generate_king_attacks(legality.king_sq) & !own;
+    while let Some(to) = king_targets.pop_lsb() {
+        if legality.king_can_move_to(board, to) {
+            count += 1;
+        }
+    }
+    if legality.checkers.count() > 1 {
+        return count;
+    }
+
+    let occupancy = board.all_occupancy;
+    let targets = !own & legality.check_mask;
+
+    let mut knights = board.pieces(us, PieceType::Knight) & !legality.pinned;
+    while let Some(from) = knights.pop_lsb() {
+        count += (generate_knight_attacks(from) & targets).count() as usize;
+    }
+
+    let queens = board.pieces(us, PieceType::Queen);
+    let mut diagonals = board.pieces(us, PieceType::Bishop) | queens;
+    while let Some(from) = diagonals.pop_lsb() {
+        let attacks = magic::get_bishop_attacks(from, occupancy);
+        count += (attacks & targets & legality.pin_mask(from)).count() as usize;
+    }
+    let mut orthogonals = board.pieces(us, PieceType::Rook) | queens;
+    while let Some(from) = orthogonals.pop_lsb() {
+        let attacks = magic::get_rook_attacks(from, occupancy);
+        count += (attacks & targets & legality.pin_mask(from)).count() as usize;
+    }
+
+    count += count_pawn_moves(board, &legality, targets);
+
+    if legality.checkers == Bitboard::EMPTY {
+        count += count_castling_moves(board, &legality);
+    }
+    count
+}
+
+fn count_pawn_moves(board: &Board, legality: &Legality, targets: Bitboard) -> usize {
+    let us = board.side_to_move;
+    let white = us == Color::White;
+    let enemies = board.occupancy[us.opposite()];
+    let empty = !board.all_occupancy;
+    let (start_rank, promotion_rank) = if white { (1, 7) } else { (6, 0) };
+    let mut count = 0;
+
+    let mut pawns = board.pieces(us, PieceType::Pawn);
+    while let Some(from) = pawns.pop_lsb() {
+        let allowed = targets & legality.pin_mask(from);
+
+        let mut destinations = generate_pawn_attacks(from, us) & enemies;
+        let push = if white {
+            from as u8 + 8
+        } else {
+            from as u8 - 8
+        };
+        if empty.get_bit(Square::new(push)) {
+            destinations.set_bit(Square::new(push));
+            if from.rank() == start_rank {
+                let double = if white { push + 8 } else { push - 8 };
+                if empty.get_bit(Square::new(double)) {
+                    destinations.set_bit(Square::new(double));
+                }
+            }
+        }
+
+        let n = (destinations & allowed).count() as usize;
+        let promotes = Square::new(push).rank() == promotion_rank;
+        count += if promotes { n * 4 } else { n };
+    }
+
+    if let Some(ep_sq) = board.en_passant_sq {
+        let mut capturers =
+            generate_pawn_attacks(ep_sq, us.opposite()) & board.pieces(us, PieceType::Pawn);
+        while let Some(from) = capturers.pop_lsb() {
+            if legality.en_passant_allowed(board, from, ep_sq) {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+fn count_castling_moves(board: &Board, legality: &Legality) -> usize {
+    let us = board.side_to_move;
+    let them = us.opposite();
+    let rights = board.castling_rights;
+
+    [
+        (KINGSIDE, rights.can_castle_kingside(us)),
+        (QUEENSIDE, rights.can_castle_queenside(us)),
+    ]
+    .into_iter()
+    .filter(|&(side, allowed)| {
+        let path = &CASTLING_PATHS[us as usize][side];
+        if !allowed || legality.king_sq != path.king_from {
+            return false;
+        }
+        (board.all_occupancy & path.empty) == Bitboard::EMPTY
+            && !board.any_square_attacked(path.king_path, them)
+    })
+    .count()
+}
@@ -1,6 +1,76 @@
+//! ananke: a bitboard chess engine.
+//!
+//! From a FEN to a move:
+//!
+//! ```
+//! use ananke::board::Board;
+//! use ananke::engine::Engine;
+//! use ananke::san;
+//! use ananke::search::Limits;
+//!
+//! // back-rank mate in one
+//! let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+//! let mut engine = Engine::new();
+//! engine.set_position(board.clone(), "").unwrap();
+//! let best = engine.think(Limits::depth(3)).best_move.unwrap();
+//! // what a GUI would get back as "bestmove a1a8"
+//! assert_eq!(best.to_string(), "a1a8");
+//! assert_eq!(san::to_san(&board, best), "Ra8#");
+//! ```
+
+pub mod adjudication;
+pub mod async_search;
+pub mod batch;
 pub mod bitboard;
 pub mod board;
+pub mod build_info;
+pub mod clock;
+pub mod config;
+pub mod contempt;
+pub mod corpus;
+pub mod eco;
+pub mod endgame;
+pub mod engine;
+pub mod eval;
+pub mod game;
+pub mod json;
+pub mod legal;
 pub mod magic;
+pub mod mate;
+pub mod material;
+pub mod mcts;
+pub mod metrics;
 pub mod movegen;
+pub mod movepick;
+pub mod nnue;
+pub mod params;
 pub mod perft;
+pub mod perpetual;
+pub mod pgn;
+pub mod playout;
+pub mod pool;
+pub mod profile;
+#[cfg(feature = "render")]
+pub mod render;
+pub mod report;
+pub mod rng;
+pub mod san;
+pub mod search;
+pub mod see;
+pub mod server;
+pub mod signals;
+pub mod simd;
+pub mod stats;
+pub mod strength;
+pub mod testing;
+pub mod timeman;
+pub mod transpose;
+pub mod tree;
+pub mod tt;
 pub mod types;
+pub mod uci;
+pub mod values;
+pub mod variety;
+pub mod worker;
+pub mod xboard;
+pub mod zobrist;
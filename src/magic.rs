@@ -1,27 +1,7 @@
 use crate::bitboard::{Bitboard, Square};
 use crate::movegen::{generate_bishop_attacks_slow, generate_rook_attacks_slow};
-
-// Simple Xorshift32 random number generator
-struct Rng(u32);
-impl Rng {
-    fn next(&mut self) -> u32 {
-        self.0 ^= self.0 << 13;
-        self.0 ^= self.0 >> 17;
-        self.0 ^= self.0 << 5;
-        self.0
-    }
-    fn rand_u64(&mut self) -> u64 {
-        let n1 = (self.next() as u64) & 0xFFFF;
-        let n2 = (self.next() as u64) & 0xFFFF;
-        let n3 = (self.next() as u64) & 0xFFFF;
-        let n4 = (self.next() as u64) & 0xFFFF;
-        n1 | (n2 << 16) | (n3 << 32) | (n4 << 48)
-    }
-    // Generate a sparse random number (fewer bits set = more magic-like)
-    fn rand_sparse(&mut self) -> u64 {
-        self.rand_u64() & self.rand_u64() & self.rand_u64()
-    }
-}
+use crate::rng::Rng;
+use std::sync::OnceLock;
 
 // How many blocker bits each square has (determines table size)
 const ROOK_BITS: [u32; 64] = [
@@ -35,22 +15,20 @@ const BISHOP_BITS: [u32; 64] = [
     5, 5, 7, 9, 9, 7, 5, 5, 5, 5, 7, 7, 7, 7, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 6, 5, 5, 5, 5, 5, 5, 6,
 ];
 
-// Precomputed magic attack tables
-pub static mut ROOK_MAGICS: [MagicEntry; 64] = [MagicEntry {
-    mask: Bitboard(0),
-    magic: 0,
-    shift: 0,
-    offset: 0,
-}; 64];
-pub static mut BISHOP_MAGICS: [MagicEntry; 64] = [MagicEntry {
-    mask: Bitboard(0),
-    magic: 0,
-    shift: 0,
-    offset: 0,
-}; 64];
-
-pub static mut ROOK_TABLE: [Bitboard; 102400] = [Bitboard(0); 102400];
-pub static mut BISHOP_TABLE: [Bitboard; 5248] = [Bitboard(0); 5248];
+// Precomputed magic attack tables. Built once on first use and read-only
+// afterwards, so lookups are safe from any thread.
+struct MagicTables {
+    rook_magics: [MagicEntry; 64],
+    bishop_magics: [MagicEntry; 64],
+    rook_table: Vec<Bitboard>,
+    bishop_table: Vec<Bitboard>,
+}
+
+static TABLES: OnceLock<MagicTables> = OnceLock::new();
+
+fn tables() -> &'static MagicTables {
+    TABLES.get_or_init(build_tables)
+}
 
 #[derive(Copy, Clone, Debug)]
 pub struct MagicEntry {
@@ -62,19 +40,17 @@ pub struct MagicEntry {
 
 // fast lookups
 pub fn get_rook_attacks(sq: Square, blockers: Bitboard) -> Bitboard {
-    unsafe {
-        let entry = &ROOK_MAGICS[sq as usize];
-        let idx = ((blockers.0 & entry.mask.0).wrapping_mul(entry.magic)) >> entry.shift;
-        ROOK_TABLE[(entry.offset as usize) + (idx as usize)]
-    }
+    let tables = tables();
+    let entry = &tables.rook_magics[sq as usize];
+    let idx = ((blockers.0 & entry.mask.0).wrapping_mul(entry.magic)) >> entry.shift;
+    tables.rook_table[(entry.offset as usize) + (idx as usize)]
 }
 
 pub fn get_bishop_attacks(sq: Square, blockers: Bitboard) -> Bitboard {
-    unsafe {
-        let entry = &BISHOP_MAGICS[sq as usize];
-        let idx = ((blockers.0 & entry.mask.0).wrapping_mul(entry.magic)) >> entry.shift;
-        BISHOP_TABLE[(entry.offset as usize) + (idx as usize)]
-    }
+    let tables = tables();
+    let entry = &tables.bishop_magics[sq as usize];
+    let idx = ((blockers.0 & entry.mask.0).wrapping_mul(entry.magic)) >> entry.shift;
+    tables.bishop_table[(entry.offset as usize) + (idx as usize)]
 }
 
 // table generation
@@ -111,19 +87,6 @@ fn mask_bishop(sq: Square) -> Bitboard {
     mask
 }
 
-// Turn an index into an occupancy pattern (which bits are set)
-fn get_occupancy_variation(index: usize, bits_in_mask: i32, mask: Bitboard) -> Bitboard {
-    let mut occupancy = Bitboard::EMPTY;
-    let mut m = mask;
-    for i in 0..bits_in_mask {
-        let bit_sq = m.pop_lsb().unwrap();
-        if (index & (1 << i)) != 0 {
-            occupancy.set_bit(bit_sq);
-        }
-    }
-    occupancy
-}
-
 // Find a magic number that maps all occupancies to unique attacks
 fn find_magic(sq: Square, bits: u32, is_rook: bool) -> (u64, Vec<Bitboard>) {
     let mask = if is_rook {
@@ -132,34 +95,31 @@ fn find_magic(sq: Square, bits: u32, is_rook: bool) -> (u64, Vec<Bitboard>) {
         mask_bishop(sq)
     };
     let n = mask.count();
-    let num_occupancies = 1 << n;
 
     // Precompute all occupancy variations and their attacks
-    let mut occupancies = vec![Bitboard::EMPTY; num_occupancies];
-    let mut attacks = vec![Bitboard::EMPTY; num_occupancies];
-
-    for i in 0..num_occupancies {
-        occupancies[i] = get_occupancy_variation(i, n as i32, mask);
-        attacks[i] = if is_rook {
-            generate_rook_attacks_slow(sq, occupancies[i])
-        } else {
-            generate_bishop_attacks_slow(sq, occupancies[i])
-        };
-    }
+    let occupancies: Vec<Bitboard> = mask.subsets().collect();
+    let attacks: Vec<Bitboard> = occupancies
+        .iter()
+        .map(|&occupancy| {
+            if is_rook {
+                generate_rook_attacks_slow(sq, occupancy)
+            } else {
+                generate_bishop_attacks_slow(sq, occupancy)
+            }
+        })
+        .collect();
 
-    let mut rng = Rng(1804289383);
+    let mut rng = Rng::default();
     let size = 1 << bits;
     let mut table = vec![Bitboard::EMPTY; size];
 
     // Keep trying random numbers until we find one that works
     loop {
-        let magic = rng.rand_sparse();
+        let magic = rng.sparse_u64();
 
         // Quick filter: good magics spread bits around
-        if (mask.0.wrapping_mul(magic) & 0xFF00000000000000).count_ones() < 6 {
-            if n >= 6 {
-                continue;
-            }
+        if (mask.0.wrapping_mul(magic) & 0xFF00000000000000).count_ones() < 6 && n >= 6 {
+            continue;
         }
 
         let shift = 64 - bits;
@@ -170,11 +130,11 @@ fn find_magic(sq: Square, bits: u32, is_rook: bool) -> (u64, Vec<Bitboard>) {
         }
 
         // Try to fill the table
-        for i in 0..num_occupancies {
-            let idx = (occupancies[i].0.wrapping_mul(magic) >> shift) as usize;
+        for (occupancy, &attack) in occupancies.iter().zip(&attacks) {
+            let idx = (occupancy.0.wrapping_mul(magic) >> shift) as usize;
             if table[idx] == Bitboard::EMPTY {
-                table[idx] = attacks[i];
-            } else if table[idx] != attacks[i] {
+                table[idx] = attack;
+            } else if table[idx] != attack {
                 fail = true;
                 break;
             }
@@ -186,47 +146,55 @@ fn find_magic(sq: Square, bits: u32, is_rook: bool) -> (u64, Vec<Bitboard>) {
 }
 
 // initialization
+
+/// Build the attack tables up front. Optional, since lookups build them on
+/// first use, but keeps the cost out of the first search or perft.
 pub fn initialize() {
     println!("Initializing Magic Bitboards...");
+    tables();
+    println!("Magic initialization complete.");
+}
+
+fn build_tables() -> MagicTables {
+    let empty = MagicEntry {
+        mask: Bitboard(0),
+        magic: 0,
+        shift: 0,
+        offset: 0,
+    };
+    let mut tables = MagicTables {
+        rook_magics: [empty; 64],
+        bishop_magics: [empty; 64],
+        rook_table: Vec::with_capacity(102400),
+        bishop_table: Vec::with_capacity(5248),
+    };
 
     // Build rook tables
-    let mut rook_offset = 0;
     for i in 0..64 {
         let sq = Square::new(i);
         let bits = ROOK_BITS[i as usize];
         let (magic, table) = find_magic(sq, bits, true);
-        unsafe {
-            ROOK_MAGICS[i as usize] = MagicEntry {
-                mask: mask_rook(sq),
-                magic,
-                shift: 64 - bits,
-                offset: rook_offset,
-            };
-            for (j, &att) in table.iter().enumerate() {
-                ROOK_TABLE[(rook_offset as usize) + j] = att;
-            }
-            rook_offset += 1 << bits;
-        }
+        tables.rook_magics[i as usize] = MagicEntry {
+            mask: mask_rook(sq),
+            magic,
+            shift: 64 - bits,
+            offset: tables.rook_table.len() as u32,
+        };
+        tables.rook_table.extend(table);
     }
 
     // Build bishop tables
-    let mut bishop_offset = 0;
     for i in 0..64 {
         let sq = Square::new(i);
         let bits = BISHOP_BITS[i as usize];
         let (magic, table) = find_magic(sq, bits, false);
-        unsafe {
-            BISHOP_MAGICS[i as usize] = MagicEntry {
-                mask: mask_bishop(sq),
-                magic,
-                shift: 64 - bits,
-                offset: bishop_offset,
-            };
-            for (j, &att) in table.iter().enumerate() {
-                BISHOP_TABLE[(bishop_offset as usize) + j] = att;
-            }
-            bishop_offset += 1 << bits;
-        }
+        tables.bishop_magics[i as usize] = MagicEntry {
+            mask: mask_bishop(sq),
+            magic,
+            shift: 64 - bits,
+            offset: tables.bishop_table.len() as u32,
+        };
+        tables.bishop_table.extend(table);
     }
-    println!("Magic initialization complete.");
+    tables
 }
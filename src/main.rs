@@ -1,27 +1,409 @@
-use ananke::board::Board;
+use ananke::batch::{self, Format};
+use ananke::board::{Board, START_FEN};
+use ananke::build_info;
+use ananke::corpus::{self, Filter};
 use ananke::magic;
-use ananke::perft;
+use ananke::mate;
+use ananke::perft::{self, WorkResult, WorkUnit};
+use ananke::pool::{self, EnginePool};
+use ananke::rng;
+use ananke::san;
+use ananke::search::{self, Limits};
+use ananke::server;
+use ananke::signals;
+use ananke::strength;
+use ananke::uci::Uci;
+use ananke::xboard::Xboard;
+use std::io::{BufRead, Write};
 
 fn main() {
-    magic::initialize();
+    // ananke --version
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 && (args[1] == "--version" || args[1] == "-V") {
+        println!("{}", build_info::build_string());
+        return;
+    }
 
-    // test starting position
-    let start_fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
-    println!("\nloading start position: {}", start_fen);
-    let board = Board::from_fen(start_fen).unwrap();
-    perft::perft_driver(&board, 1);
+    // ananke solve "<fen>" <moves>
+    // ananke bench [depth]
+    // ananke perft <depth> ["<fen>"]
+    // ananke perft-check <depth> ["<fen>"]
+    // ananke perft-split <depth> <split depth> ["<fen>"] > units
+    // ananke perft-worker < units > results
+    // ananke perft-merge units [results ...]
+    // ananke calibrate <nodes a> <nodes b> [pairs]
+    // ananke corpus [options] < positions > kept
+    // ananke serve [address] [engines]
+    // ananke analyze-fens [options] [file] > rows
+    // ananke search-tree <depth> <plies> "<fen>" [--dot] > tree
+    // ananke --config <path>: the settings in the file, then as below
+    // anything else: speak UCI or xboard on stdin/stdout
+    match args.get(1).map(String::as_str) {
+        Some("solve") => {
+            magic::initialize();
+            solve(&args[2..]);
+        }
+        Some("bench") => {
+            magic::initialize();
+            let depth = args.get(2).and_then(|d| d.parse().ok()).unwrap_or(4);
+            perft::bench(depth);
+        }
+        Some("perft") => {
+            magic::initialize();
+            perft_command(&args[2..]);
+        }
+        Some("perft-check") => {
+            magic::initialize();
+            perft_check(&args[2..]);
+        }
+        // these speak in lines on stdout, so no magic::initialize banner
+        Some("perft-split") => perft_split(&args[2..]),
+        Some("perft-worker") => perft_worker(),
+        Some("perft-merge") => perft_merge(&args[2..]),
+        Some("calibrate") => calibrate(&args[2..]),
+        Some("corpus") => corpus_command(&args[2..]),
+        Some("serve") => serve(&args[2..]),
+        Some("analyze-fens") => analyze_fens(&args[2..]),
+        Some("search-tree") => search_tree(&args[2..]),
+        Some("--config") => {
+            let Some(path) = args.get(2) else {
+                exit_with("usage: ananke --config <path>");
+            };
+            if let Err(e) = protocol_loop(Some(path)) {
+                exit_with(&e.to_string());
+            }
+        }
+        _ => {
+            if let Err(e) = protocol_loop(None) {
+                exit_with(&e.to_string());
+            }
+        }
+    }
+}
+
+// the GUI's first line picks the protocol: "xboard" for CECP, else UCI.
+// The config file, if any, is applied once it's known which. SIGINT or
+// SIGTERM comes in as a quit
+fn protocol_loop(config: Option<&str>) -> std::io::Result<()> {
+    let mut out = std::io::stdout();
+    signals::install();
+    // not the stdin lock: it's read on another thread
+    let mut input = signals::quit_on_signal(std::io::BufReader::new(std::io::stdin()));
+    let mut first = String::new();
+    input.read_line(&mut first)?;
+
+    if first.trim() == "xboard" {
+        let mut xboard = Xboard::new();
+        if let Some(path) = config
+            && let Err(e) = xboard.load_config(path)
+        {
+            exit_with(&e);
+        }
+        xboard.run(input, out)
+    } else {
+        let mut uci = Uci::new();
+        if let Some(path) = config
+            && let Err(e) = uci.load_config(path)
+        {
+            exit_with(&e);
+        }
+        if uci.handle(&first, &mut out)? {
+            out.flush()?;
+            uci.run(input, out)?;
+        }
+        Ok(())
+    }
+}
+
+fn perft_command(args: &[String]) {
+    let Some(depth) = args.first().and_then(|d| d.parse().ok()) else {
+        eprintln!("usage: ananke perft <depth> [\"<fen>\"]");
+        std::process::exit(1);
+    };
+    let fen = args.get(1).map_or(START_FEN, String::as_str);
+    match Board::from_fen(fen) {
+        Ok(board) => perft::perft_driver(&board, depth),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+// perft that checks every unmake restores the board, and that making in
+// place agrees with copy-make, for movegen work
+fn perft_check(args: &[String]) {
+    let Some(depth) = args.first().and_then(|d| d.parse().ok()) else {
+        exit_with("usage: ananke perft-check <depth> [\"<fen>\"]");
+    };
+    let fen = args.get(1).map_or(START_FEN, String::as_str);
+    let board = Board::from_fen(fen).unwrap_or_else(|e| exit_with(&e));
+    match perft::perft_checked(&board, depth) {
+        Ok(nodes) => println!("nodes {}, every unmake restored the board", nodes),
+        Err(e) => exit_with(&e),
+    }
+    match perft::perft_cross_checked(&board, depth) {
+        Ok(nodes) => println!("nodes {}, in-place and copy-make agree", nodes),
+        Err(e) => exit_with(&e),
+    }
+}
+
+// coordinator side: print one work unit per line
+fn perft_split(args: &[String]) {
+    let (Some(depth), Some(split_depth)) = (
+        args.first().and_then(|d| d.parse().ok()),
+        args.get(1).and_then(|d| d.parse().ok()),
+    ) else {
+        eprintln!("usage: ananke perft-split <depth> <split depth> [\"<fen>\"]");
+        std::process::exit(1);
+    };
+    let fen = args.get(2).map_or(START_FEN, String::as_str);
+    let board = Board::from_fen(fen).unwrap_or_else(|e| exit_with(&e));
+    for unit in perft::split(&board, depth, split_depth) {
+        println!("{}", unit.to_line());
+    }
+}
+
+// worker side: units in on stdin, results out on stdout as each finishes
+fn perft_worker() {
+    let mut out = std::io::stdout();
+    for line in std::io::stdin().lock().lines() {
+        let line = line.unwrap_or_else(|e| exit_with(&e.to_string()));
+        if line.trim().is_empty() {
+            continue;
+        }
+        let result = WorkUnit::from_line(&line)
+            .and_then(|unit| unit.run())
+            .unwrap_or_else(|e| exit_with(&e));
+        writeln!(out, "{}", result.to_line()).unwrap();
+        out.flush().unwrap();
+    }
+}
+
+// coordinator side: check every unit came back and print the total
+fn perft_merge(args: &[String]) {
+    let Some(units_path) = args.first() else {
+        eprintln!("usage: ananke perft-merge <units file> [results files...]");
+        std::process::exit(1);
+    };
+    let read =
+        |path: &str| std::fs::read_to_string(path).unwrap_or_else(|e| exit_with(&e.to_string()));
+    let units: Vec<WorkUnit> = read(units_path)
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| WorkUnit::from_line(l).unwrap_or_else(|e| exit_with(&e)))
+        .collect();
+
+    // results from the named files, or stdin if there are none
+    let text = if args.len() > 1 {
+        args[1..]
+            .iter()
+            .map(|p| read(p))
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        std::io::read_to_string(std::io::stdin()).unwrap_or_else(|e| exit_with(&e.to_string()))
+    };
+    let results: Vec<WorkResult> = text
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| WorkResult::from_line(l).unwrap_or_else(|e| exit_with(&e)))
+        .collect();
+
+    match perft::merge(&units, &results) {
+        Ok(total) => println!("total nodes: {}", total),
+        Err(e) => exit_with(&e),
+    }
+}
 
-    // test position with castling
-    let castling_fen = "r3k2r/pppp1ppp/8/4p3/8/8/PPPP1PPP/R3K1NR w KQkq - 0 1";
-    println!("\nloading castling test: {}", castling_fen);
-    let board = Board::from_fen(castling_fen).unwrap();
-    perft::perft_driver(&board, 1);
+fn exit_with(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+// self-play between two node budgets, for the strength ladder
+fn calibrate(args: &[String]) {
+    let (Some(nodes_a), Some(nodes_b)) = (
+        args.first().and_then(|n| n.parse().ok()),
+        args.get(1).and_then(|n| n.parse().ok()),
+    ) else {
+        eprintln!("usage: ananke calibrate <nodes a> <nodes b> [pairs]");
+        std::process::exit(1);
+    };
+    let pairs = args.get(2).and_then(|n| n.parse().ok()).unwrap_or(50);
+
+    let score = strength::play_match(nodes_a, nodes_b, pairs, rng::DEFAULT_SEED);
+    println!(
+        "{} vs {} nodes: +{} ={} -{} ({:.1}%), {:+.0} elo",
+        nodes_a,
+        nodes_b,
+        score.wins,
+        score.draws,
+        score.losses,
+        100.0 * score.score(),
+        score.elo_difference()
+    );
+}
+
+const CORPUS_USAGE: &str = "usage: ananke corpus [--pgn] [--no-check] [--pieces <min> <max>] \
+[--eval <min> <max>] [--depth <n>] < input > output";
+
+// dedup and filter a FEN/EPD file (or the positions of a PGN file) for
+// tuning; the tally goes to stderr
+fn corpus_command(args: &[String]) {
+    let mut filter = Filter {
+        eval_depth: 2,
+        ..Filter::default()
+    };
+    let mut pgn = false;
+    let mut args = args.iter();
+    let number = |args: &mut std::slice::Iter<String>| -> i32 {
+        args.next()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or_else(|| exit_with(CORPUS_USAGE))
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--pgn" => pgn = true,
+            "--no-check" => filter.skip_in_check = true,
+            "--pieces" => {
+                let (min, max) = (number(&mut args), number(&mut args));
+                filter.pieces = Some(min.max(0) as u32..=max.max(0) as u32);
+            }
+            "--eval" => filter.eval = Some(number(&mut args)..=number(&mut args)),
+            "--depth" => filter.eval_depth = number(&mut args).clamp(1, 255) as u8,
+            _ => exit_with(CORPUS_USAGE),
+        }
+    }
+
+    let input = std::io::stdin().lock();
+    let out = std::io::BufWriter::new(std::io::stdout().lock());
+    let stats = if pgn {
+        corpus::positions_from_pgn(input, out, &filter)
+    } else {
+        corpus::filter_lines(input, out, &filter)
+    }
+    .unwrap_or_else(|e| exit_with(&e.to_string()));
+    eprintln!(
+        "read {}, invalid {}, duplicates {}, filtered {}, written {}",
+        stats.read, stats.invalid, stats.duplicates, stats.filtered, stats.written
+    );
+}
 
-    // "KiwiPete" - A famous position for debugging move generators.
-    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+// analysis over HTTP until killed; by default on localhost, with an
+// engine per core
+fn serve(args: &[String]) {
+    let address = args.first().map_or("127.0.0.1:8080", String::as_str);
+    let engines = args
+        .get(1)
+        .and_then(|n| n.parse().ok())
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+    let listener =
+        std::net::TcpListener::bind(address).unwrap_or_else(|e| exit_with(&e.to_string()));
+    eprintln!("serving analysis on {} with {} engines", address, engines);
+    if let Err(e) = server::serve(listener, &EnginePool::new(engines)) {
+        exit_with(&e.to_string());
+    }
+}
+
+const ANALYZE_USAGE: &str = "usage: ananke analyze-fens [--depth <n>] [--movetime <ms>] \
+[--nodes <n>] [--engines <n>] [--json] [file] > rows";
+
+// a search per FEN/EPD line of the file (or stdin), as CSV or JSON lines
+// on stdout; the tally goes to stderr
+fn analyze_fens(args: &[String]) {
+    let (mut depth, mut movetime, mut nodes, mut engines) = (None, None, None, None);
+    let (mut format, mut path) = (Format::Csv, None);
+    let mut args = args.iter();
+    let number = |args: &mut std::slice::Iter<String>| -> u64 {
+        args.next()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or_else(|| exit_with(ANALYZE_USAGE))
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => depth = Some(number(&mut args).clamp(1, u8::MAX as u64) as u8),
+            "--movetime" => movetime = Some(std::time::Duration::from_millis(number(&mut args))),
+            "--nodes" => nodes = Some(number(&mut args)),
+            "--engines" => engines = Some(number(&mut args).max(1) as usize),
+            "--json" => format = Format::JsonLines,
+            _ if !arg.starts_with("--") && path.is_none() => path = Some(arg),
+            _ => exit_with(ANALYZE_USAGE),
+        }
+    }
+    let limits = pool::analysis_limits(depth, movetime, nodes);
+    let engines =
+        engines.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+    let pool = EnginePool::new(engines);
+
+    let out = std::io::BufWriter::new(std::io::stdout().lock());
+    let stats = match path {
+        Some(path) => {
+            let file = std::fs::File::open(path).unwrap_or_else(|e| exit_with(&e.to_string()));
+            batch::analyse_lines(std::io::BufReader::new(file), out, &pool, &limits, format)
+        }
+        None => batch::analyse_lines(std::io::stdin().lock(), out, &pool, &limits, format),
+    }
+    .unwrap_or_else(|e| exit_with(&e.to_string()));
+    eprintln!(
+        "read {}, invalid {}, analysed {}",
+        stats.read, stats.invalid, stats.analysed
+    );
+}
+
+const TREE_USAGE: &str = "usage: ananke search-tree <depth> <plies> \"<fen>\" [--dot] > tree";
+
+// the tree of a search to `depth`, down to `plies` from the root, as JSON
+// or a graphviz graph on stdout
+fn search_tree(args: &[String]) {
+    let [depth, plies, fen, rest @ ..] = args else {
+        exit_with(TREE_USAGE);
+    };
+    let dot = match rest {
+        [] => false,
+        [flag] if flag == "--dot" => true,
+        _ => exit_with(TREE_USAGE),
+    };
+    let (Ok(depth), Ok(plies)) = (depth.parse(), plies.parse()) else {
+        exit_with(TREE_USAGE);
+    };
+    let board = Board::from_fen(fen).unwrap_or_else(|e| exit_with(&e));
+    let (result, tree) = search::search_tree(&board, Limits::depth(depth), plies);
+    if dot {
+        print!("{}", tree.to_dot());
+    } else {
+        println!("{}", tree.to_json());
+    }
+    eprintln!(
+        "depth {} best {} nodes {}, {} kept",
+        result.depth,
+        result
+            .best_move
+            .map_or("none".to_string(), |m| m.to_string()),
+        result.nodes,
+        tree.nodes.len()
+    );
+}
 
-    println!("\nloading kiwi pete: {}", fen);
-    let board = Board::from_fen(fen).unwrap();
+fn solve(args: &[String]) {
+    if args.is_empty() {
+        eprintln!("usage: ananke solve \"<fen>\" [max moves]");
+        std::process::exit(1);
+    }
+    let board = match Board::from_fen(&args[0]) {
+        Ok(board) => board,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let max_moves = args.get(1).and_then(|n| n.parse().ok()).unwrap_or(3);
 
-    perft::perft_driver(&board, 2);
+    match mate::find_mate(&board, max_moves) {
+        Some(line) => {
+            let moves = san::line_to_san(&board, &line);
+            println!("mate in {}: {}", line.len().div_ceil(2), moves.join(" "));
+        }
+        None => println!("no mate <= {}", max_moves),
+    }
 }
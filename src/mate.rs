@@ -0,0 +1,67 @@
+use crate::board::Board;
+use crate::types::Move;
+
+/// Find the shortest forced mate for the side to move in at most `max_moves` moves.
+/// Returns the mating line (attacker and defender moves alternating), or None
+/// if there is no mate within the limit.
+pub fn find_mate(board: &Board, max_moves: u8) -> Option<Vec<Move>> {
+    // iterative deepening guarantees the first mate found is the shortest one
+    for n in 1..=max_moves {
+        if let Some(line) = mate_in(board, n) {
+            return Some(line);
+        }
+    }
+    None
+}
+
+// Attacker to move: look for a move that mates in `n` against every defense.
+fn mate_in(board: &Board, n: u8) -> Option<Vec<Move>> {
+    for (m, next) in legal_successors(board) {
+        // on the last move only checks can mate, so skip everything else
+        if n == 1 && !next.in_check() {
+            continue;
+        }
+        if let Some(mut line) = refute_all(&next, n) {
+            line.insert(0, m);
+            return Some(line);
+        }
+    }
+    None
+}
+
+// Defender to move after the attacker's move: every reply must still lose
+// within `n - 1` more attacker moves. The returned line follows the defense
+// that holds out the longest.
+fn refute_all(board: &Board, n: u8) -> Option<Vec<Move>> {
+    let replies = legal_successors(board);
+    if replies.is_empty() {
+        // checkmate is what we want; stalemate is a draw
+        return if board.in_check() {
+            Some(Vec::new())
+        } else {
+            None
+        };
+    }
+    if n == 1 {
+        return None;
+    }
+
+    let mut longest: Option<(u8, Vec<Move>)> = None;
+    for (reply, next) in replies {
+        // shortest mate against this particular reply
+        let (k, mut line) = (1..n).find_map(|k| mate_in(&next, k).map(|l| (k, l)))?;
+        if longest.as_ref().is_none_or(|(best, _)| k > *best) {
+            line.insert(0, reply);
+            longest = Some((k, line));
+        }
+    }
+    longest.map(|(_, line)| line)
+}
+
+fn legal_successors(board: &Board) -> Vec<(Move, Board)> {
+    board
+        .legal_moves()
+        .iter()
+        .map(|m| (*m, board.make_move(*m)))
+        .collect()
+}
@@ -0,0 +1,201 @@
+//! Material signatures and a small cache of everything that depends only on
+//! them (game phase, imbalance terms, the endgame specialist), so eval can
+//! look those up instead of recounting pieces at every node.
+
+use crate::board::Board;
+use crate::endgame::Endgame;
+use crate::types::{Color, PieceType};
+use crate::values::{MAX_PHASE, PHASE_WEIGHTS};
+
+/// Piece counts per side packed six bits each: pawns through queens, white
+/// in the low 30 bits and black above. Six bits hold any count a board can
+/// have, even the 62 knights a lenient FEN allows. Kings aren't counted.
+/// Boards keep theirs up to date in `make_move`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct MaterialKey(pub u64);
+
+impl MaterialKey {
+    #[inline]
+    const fn shift(color: Color, pt: PieceType) -> u32 {
+        (color as u32 * 5 + pt as u32) * 6
+    }
+
+    /// Count the pieces on `board` from scratch.
+    pub fn of(board: &Board) -> Self {
+        let mut key = MaterialKey(0);
+        for color in [Color::White, Color::Black] {
+            for pt in [
+                PieceType::Pawn,
+                PieceType::Knight,
+                PieceType::Bishop,
+                PieceType::Rook,
+                PieceType::Queen,
+            ] {
+                key.0 |= (board.pieces(color, pt).count() as u64) << Self::shift(color, pt);
+            }
+        }
+        key
+    }
+
+    #[inline]
+    pub fn count(&self, color: Color, pt: PieceType) -> u32 {
+        if pt == PieceType::King {
+            return 1;
+        }
+        ((self.0 >> Self::shift(color, pt)) & 0x3F) as u32
+    }
+
+    #[inline]
+    pub fn add(&mut self, color: Color, pt: PieceType) {
+        if pt != PieceType::King {
+            self.0 += 1 << Self::shift(color, pt);
+        }
+    }
+
+    #[inline]
+    pub fn remove(&mut self, color: Color, pt: PieceType) {
+        if pt != PieceType::King {
+            self.0 -= 1 << Self::shift(color, pt);
+        }
+    }
+
+    /// Game phase, as in `values::phase`.
+    pub fn phase(&self) -> i32 {
+        let total: i32 = [Color::White, Color::Black]
+            .into_iter()
+            .flat_map(|color| {
+                [
+                    PieceType::Knight,
+                    PieceType::Bishop,
+                    PieceType::Rook,
+                    PieceType::Queen,
+                ]
+                .map(|pt| self.count(color, pt) as i32 * PHASE_WEIGHTS[pt.index()])
+            })
+            .sum();
+        total.min(MAX_PHASE)
+    }
+}
+
+// Second-degree imbalance polynomial from Stockfish 11: every pair of piece
+// kinds (ours with ours, ours with theirs) gets a coefficient. Index 0 is
+// "has the bishop pair", then pawn through queen. Lower triangle only.
+const QUADRATIC_OURS: [[i32; 6]; 6] = [
+    [1438, 0, 0, 0, 0, 0],
+    [40, 38, 0, 0, 0, 0],
+    [32, 255, -62, 0, 0, 0],
+    [0, 104, 4, 0, 0, 0],
+    [-26, -2, 47, 105, -208, 0],
+    [-189, 24, 117, 133, -134, -6],
+];
+const QUADRATIC_THEIRS: [[i32; 6]; 6] = [
+    [0, 0, 0, 0, 0, 0],
+    [36, 0, 0, 0, 0, 0],
+    [9, 63, 0, 0, 0, 0],
+    [59, 65, 42, 0, 0, 0],
+    [46, 39, 24, -24, 0, 0],
+    [97, 100, -42, 137, 268, 0],
+];
+// the polynomial comes out in 1/16ths of Stockfish units, roughly 200 a pawn
+const IMBALANCE_DIVISOR: i32 = 16 * 2;
+
+// bishop pair flag, then pawn..queen counts
+fn imbalance_counts(key: MaterialKey, color: Color) -> [i32; 6] {
+    let count = |pt| key.count(color, pt) as i32;
+    [
+        (count(PieceType::Bishop) >= 2) as i32,
+        count(PieceType::Pawn),
+        count(PieceType::Knight),
+        count(PieceType::Bishop),
+        count(PieceType::Rook),
+        count(PieceType::Queen),
+    ]
+}
+
+/// Stockfish-style imbalance for one side: how its pieces work together and
+/// against the opponent's, beyond their individual values.
+fn side_imbalance(ours: &[i32; 6], theirs: &[i32; 6]) -> i32 {
+    let mut bonus = 0;
+    for pt1 in 0..6 {
+        if ours[pt1] == 0 {
+            continue;
+        }
+        let v: i32 = (0..=pt1)
+            .map(|pt2| {
+                QUADRATIC_OURS[pt1][pt2] * ours[pt2] + QUADRATIC_THEIRS[pt1][pt2] * theirs[pt2]
+            })
+            .sum();
+        bonus += ours[pt1] * v;
+    }
+    bonus
+}
+
+/// Everything derived from a material key.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MaterialEntry {
+    pub key: MaterialKey,
+    pub phase: i32,
+    /// Centipawns from White's point of view, on top of plain piece values:
+    /// bishop pair, pawn-count effects and piece-pair interactions such as
+    /// rook against two minors.
+    pub imbalance: i32,
+    /// The specialist that scores this material instead of the generic
+    /// eval, if there is one.
+    pub endgame: Option<Endgame>,
+}
+
+impl MaterialEntry {
+    pub fn compute(key: MaterialKey) -> Self {
+        let white = imbalance_counts(key, Color::White);
+        let black = imbalance_counts(key, Color::Black);
+        let imbalance = side_imbalance(&white, &black) - side_imbalance(&black, &white);
+        MaterialEntry {
+            key,
+            phase: key.phase(),
+            imbalance: imbalance / IMBALANCE_DIVISOR,
+            endgame: Endgame::for_material(key),
+        }
+    }
+}
+
+/// Fixed-size cache of material entries, indexed by a hash of the key. A
+/// game only ever sees a few hundred signatures, so this rarely misses.
+pub struct MaterialTable {
+    entries: Vec<Option<MaterialEntry>>,
+    shift: u32,
+}
+
+impl MaterialTable {
+    /// A table with 2^bits entries.
+    pub fn new(bits: u32) -> Self {
+        assert!((1..=24).contains(&bits), "material table size out of range");
+        MaterialTable {
+            entries: vec![None; 1 << bits],
+            shift: 64 - bits,
+        }
+    }
+
+    /// The entry for `board`'s material, computed and stored on a miss.
+    pub fn probe(&mut self, board: &Board) -> MaterialEntry {
+        let key = board.material_key;
+        let index = (key.0.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> self.shift) as usize;
+        match self.entries[index] {
+            Some(entry) if entry.key == key => entry,
+            _ => {
+                let entry = MaterialEntry::compute(key);
+                self.entries[index] = Some(entry);
+                entry
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+}
+
+impl Default for MaterialTable {
+    fn default() -> Self {
+        Self::new(13)
+    }
+}
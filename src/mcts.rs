@@ -0,0 +1,314 @@
+//! Monte Carlo tree search, as a `Searcher` to put next to alpha-beta.
+//!
+//! Every iteration walks down the tree picking children by UCB1, expands
+//! the first position it reaches that hasn't been seen, and backs the
+//! evaluation of that position (turned into a chance to win, 0 to 1) up the
+//! path it came down. There are no random playouts: the evaluator is the
+//! whole rollout. The move tried most often at the root is the answer.
+
+use crate::board::{Board, UndoInfo};
+use crate::eval::Evaluator;
+use crate::search::{INFINITY, Limits, MATE, Progress, SearchResult, Searcher};
+use crate::stats::SearchStats;
+use crate::types::{Move, MoveList};
+use std::sync::atomic::Ordering;
+
+/// UCB1's exploration constant unless set otherwise: about √2.
+pub const DEFAULT_EXPLORATION: f32 = 1.4;
+// the tree stops growing (and the search with it) at this many nodes
+const MAX_TREE_NODES: usize = 1 << 21;
+// iterations for a search limited by depth alone, doubled per ply
+const BASE_ITERATIONS: u64 = 100;
+// reported scores stay short of the mate range
+const MAX_SCORE: i32 = 10_000;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum State {
+    // not reached yet, so no children
+    New,
+    Expanded,
+    // no moves, in check
+    Mated,
+    // stalemate or the fifty-move rule, worth this to the side to move
+    Drawn(f32),
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Node {
+    // the move from the parent to here
+    m: Move,
+    visits: u32,
+    // sum of the results for the side that played `m`
+    total: f32,
+    first_child: u32,
+    children: u8,
+    state: State,
+}
+
+impl Node {
+    fn new(m: Move) -> Self {
+        Node {
+            m,
+            visits: 0,
+            total: 0.0,
+            first_child: 0,
+            children: 0,
+            state: State::New,
+        }
+    }
+}
+
+/// UCT search. The tree is kept between searches only to reuse its memory.
+///
+/// Without a node or time limit, `Limits::depth` sets the number of
+/// iterations instead (doubling per ply, as an alpha-beta search's cost
+/// would). Time limits stop it at the optimum time, since it can stop
+/// anywhere, and `stop` as soon as it's set. Nodes are iterations, and the
+/// depth reported is the deepest the tree has grown.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::eval::Hce;
+/// use ananke::mcts::Mcts;
+/// use ananke::search::{Limits, Searcher};
+///
+/// // the rook is free
+/// let board = Board::from_fen("4k3/8/8/3r4/8/4N3/8/4K3 w - - 0 1").unwrap();
+/// let result = Mcts::new().search(&board, Limits::nodes(2000), &mut Hce::default(), &mut |_| {});
+/// assert_eq!(result.best_move.unwrap().to_string(), "e3d5");
+/// assert_eq!(result.nodes, 2000);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Mcts {
+    /// How much UCB1 favours moves tried less often over ones that have
+    /// done well so far.
+    pub exploration: f32,
+    tree: Vec<Node>,
+}
+
+impl Default for Mcts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Mcts {
+    pub fn new() -> Self {
+        Mcts {
+            exploration: DEFAULT_EXPLORATION,
+            tree: Vec::new(),
+        }
+    }
+
+    fn add_children(&mut self, node: usize, moves: &MoveList) {
+        self.tree[node].first_child = self.tree.len() as u32;
+        self.tree[node].children = moves.len() as u8;
+        self.tree[node].state = State::Expanded;
+        self.tree.extend(moves.iter().map(|m| Node::new(*m)));
+    }
+
+    // first reached: children for `node`, and the value of `board` to its
+    // side to move
+    fn expand(&mut self, node: usize, board: &Board, evaluator: &dyn Evaluator, draw: f32) -> f32 {
+        let moves = board.legal_moves();
+        let state = if moves.is_empty() && board.in_check() {
+            State::Mated
+        } else if moves.is_empty() || board.halfmove_clock >= 100 {
+            State::Drawn(draw)
+        } else {
+            self.add_children(node, &moves);
+            return win_chance(evaluator.evaluate(board));
+        };
+        self.tree[node].state = state;
+        match state {
+            State::Drawn(value) => value,
+            _ => 0.0,
+        }
+    }
+
+    // UCB1: untried children first, in move order
+    fn select(&self, node: usize) -> usize {
+        let parent = &self.tree[node];
+        let log_visits = (parent.visits.max(1) as f32).ln();
+        let first = parent.first_child as usize;
+        let mut best = (first, f32::NEG_INFINITY);
+        for i in first..first + parent.children as usize {
+            let child = &self.tree[i];
+            if child.visits == 0 {
+                return i;
+            }
+            let visits = child.visits as f32;
+            let ucb = child.total / visits + self.exploration * (log_visits / visits).sqrt();
+            if ucb > best.1 {
+                best = (i, ucb);
+            }
+        }
+        best.0
+    }
+
+    // the most tried root move, and what it's worth; `fallback` scores a
+    // root none of whose moves have been tried
+    fn result(&self, depth: u8, nodes: u64, fallback: i32) -> SearchResult {
+        let root = &self.tree[0];
+        let first = root.first_child as usize;
+        let children = &self.tree[first..first + root.children as usize];
+        let mean = |n: &Node| n.total / n.visits.max(1) as f32;
+        let best = children
+            .iter()
+            .reduce(|best, n| {
+                if (n.visits, mean(n)) > (best.visits, mean(best)) {
+                    n
+                } else {
+                    best
+                }
+            })
+            .unwrap();
+        let score = match best.state {
+            State::Mated => MATE - 1,
+            _ if best.visits == 0 => fallback,
+            _ => centipawns(mean(best)),
+        };
+        SearchResult {
+            best_move: Some(best.m),
+            pv: vec![best.m],
+            score,
+            depth,
+            nodes,
+            root_moves: Vec::new(),
+            stats: SearchStats::default(),
+        }
+    }
+}
+
+impl Searcher for Mcts {
+    fn search(
+        &mut self,
+        board: &Board,
+        limits: Limits,
+        evaluator: &mut dyn Evaluator,
+        info: &mut dyn FnMut(Progress),
+    ) -> SearchResult {
+        let moves = board.legal_moves();
+        if moves.is_empty() {
+            return SearchResult {
+                best_move: None,
+                pv: Vec::new(),
+                score: if board.in_check() {
+                    -MATE
+                } else {
+                    -limits.contempt
+                },
+                depth: 0,
+                nodes: 0,
+                root_moves: Vec::new(),
+                stats: SearchStats::default(),
+            };
+        }
+        let mut searched = MoveList::new();
+        for &m in moves.iter().filter(|&&m| !limits.is_excluded(m)) {
+            searched.push(m);
+        }
+        if searched.is_empty() {
+            return SearchResult {
+                best_move: None,
+                pv: Vec::new(),
+                score: -INFINITY,
+                depth: 0,
+                nodes: 0,
+                root_moves: Vec::new(),
+                stats: SearchStats::default(),
+            };
+        }
+        let clock = limits.clock();
+        let budget = match (limits.nodes, limits.time_budget()) {
+            (Some(nodes), _) => nodes,
+            (None, Some(_)) => u64::MAX,
+            (None, None) => BASE_ITERATIONS << limits.depth.min(40),
+        };
+        // draws as the side to move in `board` sees them
+        let root_side = board.side_to_move;
+        let draw = |board: &Board| {
+            win_chance(if board.side_to_move == root_side {
+                -limits.contempt
+            } else {
+                limits.contempt
+            })
+        };
+
+        evaluator.reset(board);
+        let fallback = evaluator.evaluate(board);
+        self.tree.clear();
+        self.tree.push(Node::new(Move::default()));
+        // the root is searched even if the fifty moves are up, as alpha-beta does
+        self.add_children(0, &searched);
+
+        let mut scratch = board.clone();
+        let mut path: Vec<usize> = Vec::new();
+        let mut played: Vec<(Move, UndoInfo)> = Vec::new();
+        let (mut nodes, mut depth) = (0, 0);
+        while nodes < budget && self.tree.len() + 256 <= MAX_TREE_NODES {
+            if nodes % 256 == 0
+                && (limits
+                    .time_budget()
+                    .is_some_and(|t| clock.elapsed(nodes) >= t.optimum)
+                    || limits
+                        .stop
+                        .as_ref()
+                        .is_some_and(|s| s.load(Ordering::Relaxed)))
+            {
+                break;
+            }
+            // down to a new position or the end of the game
+            path.clear();
+            path.push(0);
+            let mut node = 0;
+            let mut value = loop {
+                match self.tree[node].state {
+                    State::Mated => break 0.0,
+                    State::Drawn(value) => break value,
+                    State::New => break self.expand(node, &scratch, evaluator, draw(&scratch)),
+                    State::Expanded => {
+                        node = self.select(node);
+                        let m = self.tree[node].m;
+                        let undo = scratch.make_move_in_place(m);
+                        evaluator.on_make_move(&scratch, m, &undo);
+                        played.push((m, undo));
+                        path.push(node);
+                    }
+                }
+            };
+            nodes += 1;
+
+            // and back up, each node scored for the side that moved into it
+            for &n in path.iter().rev() {
+                self.tree[n].visits += 1;
+                self.tree[n].total += 1.0 - value;
+                value = 1.0 - value;
+            }
+            let reached = played.len();
+            while let Some((m, undo)) = played.pop() {
+                scratch.unmake_move(m, undo);
+                evaluator.on_unmake_move();
+            }
+            if reached > depth as usize {
+                depth = reached.min(u8::MAX as usize) as u8;
+                info(Progress::Result(&self.result(depth, nodes, fallback)));
+            }
+        }
+        self.result(depth, nodes, fallback)
+    }
+
+    fn name(&self) -> &str {
+        "mcts"
+    }
+}
+
+// the expected result for the side to move, from an eval in centipawns
+fn win_chance(cp: i32) -> f32 {
+    1.0 / (1.0 + 10f32.powf(-cp as f32 / 400.0))
+}
+
+fn centipawns(win_chance: f32) -> i32 {
+    let cp = -400.0 * (1.0 / win_chance - 1.0).log10();
+    cp.clamp(-MAX_SCORE as f32, MAX_SCORE as f32).round() as i32
+}
@@ -0,0 +1,98 @@
+/// Evals beyond this are treated as decided; also keeps mate scores from
+/// dominating averages (same ceiling Lichess uses).
+pub const CP_CEILING: i32 = 1000;
+
+/// Winning chances (0-100) for the player with a `cp` centipawn advantage,
+/// using Lichess's logistic model.
+///
+/// ```
+/// use ananke::metrics::win_percent;
+///
+/// assert_eq!(win_percent(0), 50.0);
+/// assert!(win_percent(300) > 75.0);
+/// // mate scores count as decided, no more
+/// assert_eq!(win_percent(30_000), win_percent(1000));
+/// ```
+pub fn win_percent(cp: i32) -> f64 {
+    let cp = cp.clamp(-CP_CEILING, CP_CEILING) as f64;
+    50.0 + 50.0 * (2.0 / (1.0 + (-0.00368208 * cp).exp()) - 1.0)
+}
+
+/// Accuracy (0-100) of a single move given the mover's winning chances before
+/// and after it.
+pub fn move_accuracy(win_before: f64, win_after: f64) -> f64 {
+    let drop = (win_before - win_after).max(0.0);
+    (103.1668 * (-0.04354 * drop).exp() - 3.1669).clamp(0.0, 100.0)
+}
+
+/// Centipawns lost by one move; evals are from the mover's point of view.
+pub fn centipawn_loss(before: i32, after: i32) -> i32 {
+    let before = before.clamp(-CP_CEILING, CP_CEILING);
+    let after = after.clamp(-CP_CEILING, CP_CEILING);
+    (before - after).max(0)
+}
+
+/// Average centipawn loss over one player's moves, given (before, after) eval
+/// pairs from that player's point of view.
+pub fn average_centipawn_loss(evals: &[(i32, i32)]) -> f64 {
+    if evals.is_empty() {
+        return 0.0;
+    }
+    let total: i32 = evals.iter().map(|&(b, a)| centipawn_loss(b, a)).sum();
+    total as f64 / evals.len() as f64
+}
+
+/// Lichess-style game accuracy for one player, given (before, after) eval pairs
+/// from that player's point of view. Averages a volatility-weighted mean and a
+/// harmonic mean of per-move accuracies, so a single blunder in a sharp
+/// position counts for more than noise in a dead one.
+///
+/// ```
+/// use ananke::metrics::game_accuracy;
+///
+/// // never a centipawn lost
+/// assert!(game_accuracy(&[(20, 20), (35, 40), (10, 10)]) > 99.0);
+/// // one blunder in three moves
+/// assert!(game_accuracy(&[(20, 20), (35, -400), (-400, -400)]) < 80.0);
+/// ```
+pub fn game_accuracy(evals: &[(i32, i32)]) -> f64 {
+    if evals.is_empty() {
+        return 100.0;
+    }
+
+    let accuracies: Vec<f64> = evals
+        .iter()
+        .map(|&(b, a)| move_accuracy(win_percent(b), win_percent(a)))
+        .collect();
+
+    // volatility of the win% curve around each move
+    let wins: Vec<f64> = evals.iter().map(|&(b, _)| win_percent(b)).collect();
+    let window = (evals.len() / 10).clamp(2, 8);
+    let weights: Vec<f64> = (0..wins.len())
+        .map(|i| {
+            let lo = i.saturating_sub(window / 2);
+            let hi = (lo + window).min(wins.len());
+            std_dev(&wins[lo..hi]).clamp(0.5, 12.0)
+        })
+        .collect();
+
+    let weight_sum: f64 = weights.iter().sum();
+    let weighted_mean = accuracies
+        .iter()
+        .zip(&weights)
+        .map(|(a, w)| a * w)
+        .sum::<f64>()
+        / weight_sum;
+
+    // an accuracy of 0 would make the harmonic mean undefined
+    let harmonic_mean =
+        accuracies.len() as f64 / accuracies.iter().map(|a| 1.0 / a.max(1.0)).sum::<f64>();
+
+    (weighted_mean + harmonic_mean) / 2.0
+}
+
+fn std_dev(values: &[f64]) -> f64 {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt()
+}
@@ -1,7 +1,9 @@
 use crate::bitboard::{Bitboard, Square};
 use crate::board::Board;
+use crate::legal::{Legality, between};
 use crate::magic;
-use crate::types::{Color, Move, MoveList, PieceType};
+use crate::profile::{self, Section};
+use crate::types::{Color, Move, MoveFlag, MoveList, PieceType};
 
 // leaper attack generators
 
@@ -112,343 +114,734 @@ pub fn generate_rook_attacks_slow(sq: Square, blockers: Bitboard) -> Bitboard {
     attacks
 }
 
+// castling paths
+
+pub const KINGSIDE: usize = 0;
+pub const QUEENSIDE: usize = 1;
+
+/// Everything needed to validate and play one castling move. The masks are
+/// derived from the king and rook start/target squares, so the same checks
+/// work for Chess960 setups where the rook can start on any file.
+#[derive(Debug, Copy, Clone)]
+pub struct CastlingPath {
+    pub king_from: Square,
+    pub king_to: Square,
+    pub rook_from: Square,
+    pub rook_to: Square,
+    /// Squares that must be empty: everything the king and rook cross or land
+    /// on, except the two castling pieces themselves.
+    pub empty: Bitboard,
+    /// Squares the king starts on, crosses and lands on; none may be attacked.
+    pub king_path: Bitboard,
+}
+
+impl CastlingPath {
+    pub const fn new(
+        king_from: Square,
+        king_to: Square,
+        rook_from: Square,
+        rook_to: Square,
+    ) -> Self {
+        let castlers = (1u64 << king_from as u8) | (1u64 << rook_from as u8);
+        let king_span = rank_span(king_from, king_to);
+        let rook_span = rank_span(rook_from, rook_to);
+        CastlingPath {
+            king_from,
+            king_to,
+            rook_from,
+            rook_to,
+            empty: Bitboard((king_span | rook_span) & !castlers),
+            king_path: Bitboard(king_span),
+        }
+    }
+}
+
+// all squares from a to b inclusive, both on the same rank
+const fn rank_span(a: Square, b: Square) -> u64 {
+    let (lo, hi) = if (a as u8) < (b as u8) {
+        (a as u8, b as u8)
+    } else {
+        (b as u8, a as u8)
+    };
+    (u64::MAX >> (63 - hi)) & (u64::MAX << lo)
+}
+
+/// Standard chess castling paths, indexed by [color][KINGSIDE / QUEENSIDE].
+pub const CASTLING_PATHS: [[CastlingPath; 2]; 2] = [
+    [
+        CastlingPath::new(Square::E1, Square::G1, Square::H1, Square::F1),
+        CastlingPath::new(Square::E1, Square::C1, Square::A1, Square::D1),
+    ],
+    [
+        CastlingPath::new(Square::E8, Square::G8, Square::H8, Square::F8),
+        CastlingPath::new(Square::E8, Square::C8, Square::A8, Square::D8),
+    ],
+];
+
 // move generator
 
 pub struct MoveGenerator<'a> {
     board: &'a Board,
-    moves: MoveList,
 }
 
 impl<'a> MoveGenerator<'a> {
     pub fn new(board: &'a Board) -> Self {
-        MoveGenerator {
-            board,
-            moves: MoveList::new(),
-        }
+        MoveGenerator { board }
     }
 
-    pub fn generate_all(mut self) -> MoveList {
-        self.generate_pawn_moves();
-        self.generate_knight_moves();
-        self.generate_king_moves();
-        self.generate_slider_moves();
-        self.moves
+    /// All pseudo-legal moves: moves that may still leave the king in check.
+    /// Use `Board::legal_moves` when only legal ones are wanted.
+    ///
+    /// ```
+    /// use ananke::board::Board;
+    /// use ananke::movegen::MoveGenerator;
+    ///
+    /// // the pinned knight still gets its pseudo-legal moves
+    /// let board = Board::from_fen("4k3/8/8/8/4r3/8/4N3/4K3 w - - 0 1").unwrap();
+    /// let pseudo = MoveGenerator::new(&board).generate_all();
+    /// assert_eq!(pseudo.len(), board.legal_moves().len() + 6);
+    /// ```
+    pub fn generate_all(self) -> MoveList {
+        let mut moves = MoveList::new();
+        generate_into(self.board, &mut moves);
+        moves
     }
 
-    fn generate_pawn_moves(&mut self) {
-        let white = self.board.side_to_move == Color::White;
-        let (pawns, enemies) = if white {
-            (
-                self.board.white_pieces[PieceType::Pawn as usize],
-                self.board.black_occupancy,
-            )
-        } else {
-            (
-                self.board.black_pieces[PieceType::Pawn as usize],
-                self.board.white_occupancy,
-            )
-        };
+    /// Only the legal moves. Checks and pins are worked out once up front
+    /// and the destination sets masked with them, so nothing is played out
+    /// to see whether it leaves the king in check.
+    ///
+    /// ```
+    /// use ananke::board::Board;
+    /// use ananke::movegen::MoveGenerator;
+    ///
+    /// // the en passant capture would open the rank to the rook
+    /// let board = Board::from_fen("8/8/8/K2pP2r/8/8/8/4k3 w - d6 0 1").unwrap();
+    /// let legal = MoveGenerator::new(&board).generate_legal();
+    /// assert!(legal.iter().all(|m| m.to_string() != "e5d6"));
+    /// assert_eq!(legal.len(), 6);
+    /// ```
+    pub fn generate_legal(self) -> MoveList {
+        let mut moves = MoveList::new();
+        generate_legal_into(self.board, &mut moves);
+        moves
+    }
 
-        let empty = !self.board.all_occupancy;
-        let promotion_rank = if white { 7 } else { 0 };
+    /// Only the moves that might get out of check from `checkers`; see
+    /// `generate_evasions_into`.
+    ///
+    /// ```
+    /// use ananke::board::Board;
+    /// use ananke::movegen::MoveGenerator;
+    ///
+    /// // the rook checks along the file: take it, block it, or step aside
+    /// let board = Board::from_fen("4r1k1/8/8/8/8/8/1B1N4/4K3 w - - 0 1").unwrap();
+    /// let evasions = MoveGenerator::new(&board).generate_evasions(board.checkers());
+    /// let mut moves: Vec<String> = evasions.iter().map(|m| m.to_string()).collect();
+    /// moves.sort();
+    /// assert_eq!(moves, ["b2e5", "d2e4", "e1d1", "e1f1", "e1f2"]);
+    /// ```
+    pub fn generate_evasions(self, checkers: Bitboard) -> MoveList {
+        let mut moves = MoveList::new();
+        generate_evasions_into(self.board, checkers, &mut moves);
+        moves
+    }
 
-        // single push
-        let single_push = if white {
-            (pawns.0 << 8) & empty.0
-        } else {
-            (pawns.0 >> 8) & empty.0
-        };
-        let mut bb = Bitboard::new(single_push);
-        while let Some(to_sq) = bb.pop_lsb() {
-            let from_sq = Square::new(if white {
-                to_sq as u8 - 8
-            } else {
-                to_sq as u8 + 8
-            });
-
-            // handle promotions
-            if to_sq.rank() == promotion_rank {
-                self.moves.push(Move::new(from_sq, to_sq, Move::N_PROMO));
-                self.moves.push(Move::new(from_sq, to_sq, Move::B_PROMO));
-                self.moves.push(Move::new(from_sq, to_sq, Move::R_PROMO));
-                self.moves.push(Move::new(from_sq, to_sq, Move::Q_PROMO));
-            } else {
-                self.moves.push(Move::new(from_sq, to_sq, Move::QUIET));
-            }
+    /// Pseudo-legal captures of the enemy piece on `sq`, least valuable
+    /// attacker first. Includes en passant when `sq` holds the pawn that just
+    /// double-pushed. Empty if there's no enemy piece on `sq`.
+    ///
+    /// ```
+    /// use ananke::bitboard::Square;
+    /// use ananke::board::Board;
+    /// use ananke::movegen::MoveGenerator;
+    ///
+    /// let board = Board::from_fen("4k3/8/3p4/4n3/3P4/5N2/8/4KQ2 w - - 0 1").unwrap();
+    /// let captures = MoveGenerator::new(&board).generate_captures_to(Square::E5);
+    /// let moves: Vec<String> = captures.iter().map(|m| m.to_string()).collect();
+    /// assert_eq!(moves, ["d4e5", "f3e5"]);
+    /// // nothing of Black's on e4
+    /// assert!(MoveGenerator::new(&board).generate_captures_to(Square::E4).is_empty());
+    /// ```
+    pub fn generate_captures_to(self, sq: Square) -> MoveList {
+        let board = self.board;
+        let us = board.side_to_move;
+        let mut moves = MoveList::new();
+        if board.get_piece_type_at(sq, us.opposite()).is_none() {
+            return moves;
         }
 
-        // double push
-        let double_push = if white {
-            ((single_push << 8) & empty.0) & 0x000000FF00000000
-        } else {
-            ((single_push >> 8) & empty.0) & 0x000000FF00000000
-        };
+        let promotes = sq.rank() == if us == Color::White { 7 } else { 0 };
+        let attackers = board.attackers_to(sq, board.all_occupancy);
+        for pt in PieceType::ALL {
+            let mut from_squares = attackers & board.pieces(us, pt);
+            while let Some(from) = from_squares.pop_lsb() {
+                if pt == PieceType::Pawn && promotes {
+                    for flag in MoveFlag::PROMOTION_CAPTURES {
+                        moves.push(Move::new(from, sq, flag));
+                    }
+                } else {
+                    moves.push(Move::new(from, sq, MoveFlag::Capture));
+                }
+            }
+        }
 
-        let mut bb = Bitboard::new(double_push);
-        while let Some(to_sq) = bb.pop_lsb() {
-            let from_sq = Square::new(if white {
-                to_sq as u8 - 16
+        // the pawn on sq can also be taken by landing behind it
+        if let Some(ep_sq) = board.en_passant_sq {
+            let passed = if us == Color::White {
+                ep_sq as u8 - 8
             } else {
-                to_sq as u8 + 16
-            });
-            self.moves
-                .push(Move::new(from_sq, to_sq, Move::DOUBLE_PAWN_PUSH));
+                ep_sq as u8 + 8
+            };
+            if passed == sq as u8 {
+                let mut capturers =
+                    generate_pawn_attacks(ep_sq, us.opposite()) & board.pieces(us, PieceType::Pawn);
+                while let Some(from) = capturers.pop_lsb() {
+                    moves.push(Move::new(from, ep_sq, MoveFlag::EnPassant));
+                }
+            }
         }
+        moves
+    }
+}
 
-        // captures
-        let (left_attack, right_attack) = if white {
-            (
-                (pawns.0 << 7) & 0x7F7F7F7F7F7F7F7F,
-                (pawns.0 << 9) & 0xFEFEFEFEFEFEFEFE,
-            )
-        } else {
-            (
-                (pawns.0 >> 9) & 0x7F7F7F7F7F7F7F7F,
-                (pawns.0 >> 7) & 0xFEFEFEFEFEFEFEFE,
-            )
-        };
+/// Refill `moves` with the pseudo-legal moves of `board`. Lets a search keep
+/// one list per ply instead of building a fresh one at every node.
+pub fn generate_into(board: &Board, moves: &mut MoveList) {
+    let _profile = profile::scope(Section::MoveGen);
+    fill(
+        board,
+        moves,
+        None,
+        Kinds::All,
+        Bitboard::UNIVERSE,
+        Bitboard::UNIVERSE,
+    );
+}
 
-        // regular captures
-        let mut left_bb = Bitboard::new(left_attack & enemies.0);
-        while let Some(to_sq) = left_bb.pop_lsb() {
-            let from_sq = Square::new(if white {
-                to_sq as u8 - 7
-            } else {
-                to_sq as u8 + 9
-            });
+/// Refill `moves` with the pseudo-legal captures and promotions of `board`
+/// (en passant and under-promotions included): the moves quiescence looks
+/// at, and the first stage of move picking.
+pub fn generate_noisy_into(board: &Board, moves: &mut MoveList) {
+    let _profile = profile::scope(Section::MoveGen);
+    fill(
+        board,
+        moves,
+        None,
+        Kinds::Noisy,
+        Bitboard::UNIVERSE,
+        Bitboard::UNIVERSE,
+    );
+}
 
-            if to_sq.rank() == promotion_rank {
-                self.moves
-                    .push(Move::new(from_sq, to_sq, Move::N_PROMO_CAP));
-                self.moves
-                    .push(Move::new(from_sq, to_sq, Move::B_PROMO_CAP));
-                self.moves
-                    .push(Move::new(from_sq, to_sq, Move::R_PROMO_CAP));
-                self.moves
-                    .push(Move::new(from_sq, to_sq, Move::Q_PROMO_CAP));
-            } else {
-                self.moves.push(Move::new(from_sq, to_sq, Move::CAPTURE));
-            }
-        }
+/// Refill `moves` with the rest of the pseudo-legal moves: neither
+/// captures nor promotions. Castling is one of these.
+pub fn generate_quiets_into(board: &Board, moves: &mut MoveList) {
+    let _profile = profile::scope(Section::MoveGen);
+    fill(
+        board,
+        moves,
+        None,
+        Kinds::Quiet,
+        Bitboard::UNIVERSE,
+        Bitboard::UNIVERSE,
+    );
+}
 
-        let mut right_bb = Bitboard::new(right_attack & enemies.0);
-        while let Some(to_sq) = right_bb.pop_lsb() {
-            let from_sq = Square::new(if white {
-                to_sq as u8 - 9
-            } else {
-                to_sq as u8 + 7
-            });
+/// Refill `moves` with the legal moves of `board`.
+pub fn generate_legal_into(board: &Board, moves: &mut MoveList) {
+    let _profile = profile::scope(Section::MoveGen);
+    let legality = Legality::new(board);
+    fill(
+        board,
+        moves,
+        Some(&legality),
+        Kinds::All,
+        legality.check_mask,
+        Bitboard::UNIVERSE,
+    );
+}
 
-            if to_sq.rank() == promotion_rank {
-                self.moves
-                    .push(Move::new(from_sq, to_sq, Move::N_PROMO_CAP));
-                self.moves
-                    .push(Move::new(from_sq, to_sq, Move::B_PROMO_CAP));
-                self.moves
-                    .push(Move::new(from_sq, to_sq, Move::R_PROMO_CAP));
-                self.moves
-                    .push(Move::new(from_sq, to_sq, Move::Q_PROMO_CAP));
+/// Refill `moves` with the check evasions of `board`, whose side to move is
+/// in check from `checkers`: king moves off the checkers' lines, captures of
+/// a lone checker, and interpositions against a lone slider. Pins and other
+/// attacks on the king's new square aren't looked at, so the moves are
+/// pseudo-legal like `generate_into`'s, but far fewer.
+pub fn generate_evasions_into(board: &Board, checkers: Bitboard, moves: &mut MoveList) {
+    let _profile = profile::scope(Section::MoveGen);
+    let king_sq = board.get_king_square(board.side_to_move);
+    // in double check the king has to move
+    let mask = match checkers.lsb_index() {
+        Some(checker) if checkers.count() == 1 => between(king_sq, checker) | checkers,
+        _ => Bitboard::EMPTY,
+    };
+    // with the king lifted off, so it can't step back along a slider's line
+    let mut occupancy = board.all_occupancy;
+    occupancy.clear_bit(king_sq);
+    let mut attacked = Bitboard::EMPTY;
+    let mut remaining = checkers;
+    while let Some(checker) = remaining.pop_lsb() {
+        attacked |= piece_attacks(board, checker, occupancy);
+    }
+    fill(board, moves, None, Kinds::All, mask, !attacked);
+}
+
+/// Could `m` have come from `generate_into` for `board`? Moves remembered
+/// from elsewhere in the tree, like killers, have to pass this before
+/// they're played here.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::movegen::is_pseudo_legal;
+///
+/// let board = Board::startpos();
+/// let e4 = board.parse_uci_move("e2e4").unwrap();
+/// assert!(is_pseudo_legal(&board, e4));
+/// // black's turn now, and white's pawn has gone anyway
+/// assert!(!is_pseudo_legal(&board.make_move(e4), e4));
+/// ```
+pub fn is_pseudo_legal(board: &Board, m: Move) -> bool {
+    let us = board.side_to_move;
+    let (from, to, flag) = (m.from(), m.to(), m.flag());
+    let Some(piece) = board.get_piece_type_at(from, us) else {
+        return false;
+    };
+    if board.occupancy[us].get_bit(to) {
+        return false;
+    }
+    match flag {
+        MoveFlag::KingCastle | MoveFlag::QueenCastle => {
+            let (side, allowed) = if flag == MoveFlag::KingCastle {
+                (KINGSIDE, board.castling_rights.can_castle_kingside(us))
             } else {
-                self.moves.push(Move::new(from_sq, to_sq, Move::CAPTURE));
-            }
+                (QUEENSIDE, board.castling_rights.can_castle_queenside(us))
+            };
+            let path = &CASTLING_PATHS[us as usize][side];
+            return allowed
+                && piece == PieceType::King
+                && from == path.king_from
+                && to == path.king_to
+                && (board.all_occupancy & path.empty) == Bitboard::EMPTY
+                && !board.any_square_attacked(path.king_path, us.opposite());
+        }
+        MoveFlag::EnPassant => {
+            return piece == PieceType::Pawn
+                && board.en_passant_sq == Some(to)
+                && generate_pawn_attacks(from, us).get_bit(to);
         }
+        _ => {}
+    }
+    // captures land on an enemy piece, everything else on an empty square
+    if m.is_capture() != board.occupancy[us.opposite()].get_bit(to) {
+        return false;
+    }
+    if piece != PieceType::Pawn {
+        return !m.is_promotion()
+            && flag != MoveFlag::DoublePawnPush
+            && piece_attacks(board, from, board.all_occupancy).get_bit(to);
+    }
+    let promotes = to.rank() == if us == Color::White { 7 } else { 0 };
+    if m.is_promotion() != promotes {
+        return false;
+    }
+    let pawn = Bitboard::new(1u64 << from as u8);
+    let empty = !board.all_occupancy;
+    let targets = if m.is_capture() {
+        generate_pawn_attacks(from, us)
+    } else if flag == MoveFlag::DoublePawnPush {
+        pawn_double_pushes(us, pawn, empty)
+    } else {
+        pawn_single_pushes(us, pawn, empty)
+    };
+    targets.get_bit(to)
+}
 
-        // en passant captures
-        if let Some(ep_sq) = self.board.en_passant_sq {
-            let ep_bitboard = Bitboard::new(1u64 << (ep_sq as u8));
+// squares the piece on `sq` attacks, with sliders blocked by `occupancy`
+fn piece_attacks(board: &Board, sq: Square, occupancy: Bitboard) -> Bitboard {
+    let color = if board.occupancy[Color::White].get_bit(sq) {
+        Color::White
+    } else {
+        Color::Black
+    };
+    match board.get_piece_type_at(sq, color) {
+        Some(PieceType::Pawn) => generate_pawn_attacks(sq, color),
+        Some(PieceType::Knight) => generate_knight_attacks(sq),
+        Some(PieceType::Bishop) => magic::get_bishop_attacks(sq, occupancy),
+        Some(PieceType::Rook) => magic::get_rook_attacks(sq, occupancy),
+        Some(PieceType::Queen) => {
+            magic::get_bishop_attacks(sq, occupancy) | magic::get_rook_attacks(sq, occupancy)
+        }
+        Some(PieceType::King) => generate_king_attacks(sq),
+        None => Bitboard::EMPTY,
+    }
+}
 
-            // check if left capture is possible
-            if (left_attack & ep_bitboard.0) != 0 {
-                let from_sq = if white {
-                    Square::new((ep_sq as u8) - 7)
-                } else {
-                    Square::new((ep_sq as u8) + 9)
-                };
-                self.moves.push(Move::new(from_sq, ep_sq, Move::EP_CAPTURE));
-            }
+// which moves a Filler produces
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Kinds {
+    All,
+    // captures and promotions
+    Noisy,
+    // everything else
+    Quiet,
+}
 
-            // check if right capture is possible
-            if (right_attack & ep_bitboard.0) != 0 {
-                let from_sq = if white {
-                    Square::new((ep_sq as u8) - 9)
-                } else {
-                    Square::new((ep_sq as u8) + 7)
-                };
-                self.moves.push(Move::new(from_sq, ep_sq, Move::EP_CAPTURE));
-            }
+fn fill(
+    board: &Board,
+    moves: &mut MoveList,
+    legality: Option<&Legality>,
+    kinds: Kinds,
+    mask: Bitboard,
+    king_mask: Bitboard,
+) {
+    moves.clear();
+    // pick the color once; everything below is monomorphized per side
+    if board.side_to_move == Color::White {
+        Filler::<true> {
+            board,
+            moves,
+            legality,
+            kinds,
+            mask,
+            king_mask,
         }
+        .generate();
+    } else {
+        Filler::<false> {
+            board,
+            moves,
+            legality,
+            kinds,
+            mask,
+            king_mask,
+        }
+        .generate();
     }
+}
 
-    fn generate_knight_moves(&mut self) {
-        let white = self.board.side_to_move == Color::White;
-        let mut knights = if white {
-            self.board.white_pieces[PieceType::Knight as usize]
-        } else {
-            self.board.black_pieces[PieceType::Knight as usize]
-        };
+const RANK_1: u64 = 0x00000000000000FF;
+const RANK_4: u64 = 0x00000000FF000000;
+const RANK_5: u64 = 0x000000FF00000000;
+const RANK_8: u64 = 0xFF00000000000000;
+const NOT_A_FILE: u64 = 0xFEFEFEFEFEFEFEFE;
+const NOT_H_FILE: u64 = 0x7F7F7F7F7F7F7F7F;
+
+// does the actual generation for one side, appending to a borrowed list.
+// With `legality` set only legal moves come out. Moves other than the
+// king's must land on `mask` (the en passant victim counts as landed on),
+// the king's on `king_mask`.
+struct Filler<'a, const WHITE: bool> {
+    board: &'a Board,
+    moves: &'a mut MoveList,
+    legality: Option<&'a Legality>,
+    kinds: Kinds,
+    mask: Bitboard,
+    king_mask: Bitboard,
+}
 
-        let friends = if white {
-            self.board.white_occupancy
-        } else {
-            self.board.black_occupancy
-        };
-        let enemies = if white {
-            self.board.black_occupancy
-        } else {
-            self.board.white_occupancy
-        };
+impl<const WHITE: bool> Filler<'_, WHITE> {
+    const US: Color = if WHITE { Color::White } else { Color::Black };
+    const THEM: Color = if WHITE { Color::Black } else { Color::White };
 
-        while let Some(from_sq) = knights.pop_lsb() {
-            let attacks = generate_knight_attacks(from_sq) & !friends;
-            let mut moves_bb = attacks;
-            while let Some(to_sq) = moves_bb.pop_lsb() {
-                let flag = if enemies.get_bit(to_sq) {
-                    Move::CAPTURE
-                } else {
-                    Move::QUIET
-                };
-                self.moves.push(Move::new(from_sq, to_sq, flag));
-            }
+    #[inline(always)]
+    fn generate(&mut self) {
+        // in double check only the king can move
+        if self.mask == Bitboard::EMPTY {
+            self.generate_king_moves();
+            return;
+        }
+        self.generate_pawn_moves();
+        self.generate_piece_moves();
+        // castling never gets out of check
+        if self.mask == Bitboard::UNIVERSE && self.kinds != Kinds::Noisy {
+            self.generate_castling_moves();
         }
     }
 
-    fn generate_king_moves(&mut self) {
-        let white = self.board.side_to_move == Color::White;
-        let mut kings = if white {
-            self.board.white_pieces[PieceType::King as usize]
-        } else {
-            self.board.black_pieces[PieceType::King as usize]
-        };
+    // where the piece on `from` may go without exposing the king
+    #[inline(always)]
+    fn allowed(&self, from: Square) -> Bitboard {
+        match self.legality {
+            Some(legality) => self.mask & legality.pin_mask(from),
+            None => self.mask,
+        }
+    }
 
-        let friends = if white {
-            self.board.white_occupancy
-        } else {
-            self.board.black_occupancy
-        };
-        let enemies = if white {
-            self.board.black_occupancy
-        } else {
-            self.board.white_occupancy
-        };
+    #[inline(always)]
+    fn friends(&self) -> Bitboard {
+        self.board.occupancy[Self::US]
+    }
 
-        if let Some(from_sq) = kings.pop_lsb() {
-            let attacks = generate_king_attacks(from_sq) & !friends;
-            let mut moves_bb = attacks;
-            while let Some(to_sq) = moves_bb.pop_lsb() {
-                let flag = if enemies.get_bit(to_sq) {
-                    Move::CAPTURE
-                } else {
-                    Move::QUIET
-                };
-                self.moves.push(Move::new(from_sq, to_sq, flag));
+    #[inline(always)]
+    fn enemies(&self) -> Bitboard {
+        self.board.occupancy[Self::THEM]
+    }
+
+    // pawn moves whose from square is `back` squares behind the target
+    #[inline(always)]
+    fn push_pawn_targets(&mut self, mut targets: u64, back: i8, flag: MoveFlag) {
+        while targets != 0 {
+            let to = targets.trailing_zeros() as u8;
+            targets &= targets - 1;
+            let from = Square::new((to as i8 - back) as u8);
+            self.moves.push(Move::new(from, Square::new(to), flag));
+        }
+    }
+
+    #[inline(always)]
+    fn push_promotions(&mut self, mut targets: u64, back: i8, flags: [MoveFlag; 4]) {
+        while targets != 0 {
+            let to = targets.trailing_zeros() as u8;
+            targets &= targets - 1;
+            let from = Square::new((to as i8 - back) as u8);
+            let to = Square::new(to);
+            for flag in flags {
+                self.moves.push(Move::new(from, to, flag));
             }
+        }
+    }
 
-            // castling moves
-            self.generate_castling_moves(from_sq, white);
+    // captures first, then quiet moves: no per-target flag test
+    #[inline(always)]
+    fn push_piece_targets(&mut self, from: Square, targets: Bitboard) {
+        if self.kinds != Kinds::Quiet {
+            let mut captures = targets & self.enemies();
+            while let Some(to) = captures.pop_lsb() {
+                self.moves.push(Move::new(from, to, MoveFlag::Capture));
+            }
+        }
+        if self.kinds != Kinds::Noisy {
+            let mut quiets = targets & !self.board.all_occupancy;
+            while let Some(to) = quiets.pop_lsb() {
+                self.moves.push(Move::new(from, to, MoveFlag::Quiet));
+            }
         }
     }
 
-    fn generate_castling_moves(&mut self, king_sq: Square, white: bool) {
-        let color = if white { Color::White } else { Color::Black };
-        let (king_start, _rook_qs_start, _rook_ks_start, ks_target, qs_target) = if white {
-            (Square::E1, Square::A1, Square::H1, Square::G1, Square::C1)
-        } else {
-            (Square::E8, Square::A8, Square::H8, Square::G8, Square::C8)
+    // unpinned pawns all at once; pinned ones (rare) one by one, each with
+    // its own pin ray
+    fn generate_pawn_moves(&mut self) {
+        let pawns = self.board.pieces(Self::US, PieceType::Pawn);
+        let Some(legality) = self.legality else {
+            self.generate_pawn_moves_from(pawns, self.mask);
+            return;
         };
+        self.generate_pawn_moves_from(pawns & !legality.pinned, self.mask);
+        let mut pinned = pawns & legality.pinned;
+        while let Some(from) = pinned.pop_lsb() {
+            let pawn = Bitboard::new(1u64 << from as u8);
+            self.generate_pawn_moves_from(pawn, self.allowed(from));
+        }
+    }
 
-        let rights = self.board.castling_rights;
+    // pushes and captures of `pawns` landing inside `mask`
+    fn generate_pawn_moves_from(&mut self, pawns: Bitboard, mask: Bitboard) {
+        let empty = !self.board.all_occupancy;
+        let (up, promotion_rank) = if WHITE { (8, RANK_8) } else { (-8, RANK_1) };
 
-        // check if king is on starting square
-        if king_sq != king_start {
+        let single_push = (pawn_single_pushes(Self::US, pawns, empty) & mask).0;
+        if self.kinds != Kinds::Noisy {
+            let double_push = (pawn_double_pushes(Self::US, pawns, empty) & mask).0;
+            self.push_pawn_targets(single_push & !promotion_rank, up, MoveFlag::Quiet);
+            self.push_pawn_targets(double_push, 2 * up, MoveFlag::DoublePawnPush);
+        }
+        if self.kinds == Kinds::Quiet {
             return;
         }
+        self.push_promotions(single_push & promotion_rank, up, MoveFlag::PROMOTIONS);
 
-        // kingside castling
-        if rights.can_castle_kingside(color) {
-            // check squares between king and rook are empty
-            let f_sq = if white { Square::F1 } else { Square::F8 };
-            let g_sq = if white { Square::G1 } else { Square::G8 };
-
-            if !self.board.all_occupancy.get_bit(f_sq) && !self.board.all_occupancy.get_bit(g_sq) {
-                // check king is not in check and doesn't pass through check
-                let them = if white { Color::Black } else { Color::White };
-                if !self.board.is_square_attacked(king_start, them)
-                    && !self.board.is_square_attacked(f_sq, them)
-                    && !self.board.is_square_attacked(g_sq, them)
+        // captures toward the a-file and toward the h-file
+        let (pawns, enemies) = (pawns.0, self.enemies().0);
+        let (west, east) = if WHITE {
+            ((pawns << 7) & NOT_H_FILE, (pawns << 9) & NOT_A_FILE)
+        } else {
+            ((pawns >> 9) & NOT_H_FILE, (pawns >> 7) & NOT_A_FILE)
+        };
+        let (west_back, east_back) = if WHITE { (7, 9) } else { (-9, -7) };
+        for (attacks, back) in [(west, west_back), (east, east_back)] {
+            let captures = attacks & enemies & mask.0;
+            self.push_pawn_targets(captures & !promotion_rank, back, MoveFlag::Capture);
+            self.push_promotions(
+                captures & promotion_rank,
+                back,
+                MoveFlag::PROMOTION_CAPTURES,
+            );
+            if let Some(ep_sq) = self.board.en_passant_sq
+                && attacks & (1u64 << ep_sq as u8) != 0
+                && (mask.get_bit(ep_sq) || mask.get_bit(Square::new((ep_sq as i8 - up) as u8)))
+            {
+                // masks can't see a pin along the rank through both pawns
+                let from = Square::new((ep_sq as i8 - back) as u8);
+                if self
+                    .legality
+                    .is_none_or(|l| l.en_passant_allowed(self.board, from, ep_sq))
                 {
-                    self.moves
-                        .push(Move::new(king_start, ks_target, Move::K_CASTLE));
+                    self.moves.push(Move::new(from, ep_sq, MoveFlag::EnPassant));
                 }
             }
         }
+    }
 
-        // queenside castling
-        if rights.can_castle_queenside(color) {
-            // check squares between king and rook are empty
-            let d_sq = if white { Square::D1 } else { Square::D8 };
-            let c_sq = if white { Square::C1 } else { Square::C8 };
-            let b_sq = if white { Square::B1 } else { Square::B8 };
+    fn generate_piece_moves(&mut self) {
+        let not_friends = !self.friends();
+        let occupancy = self.board.all_occupancy;
 
-            if !self.board.all_occupancy.get_bit(d_sq)
-                && !self.board.all_occupancy.get_bit(c_sq)
-                && !self.board.all_occupancy.get_bit(b_sq)
-            {
-                // check king is not in check and doesn't pass through check
-                let them = if white { Color::Black } else { Color::White };
-                if !self.board.is_square_attacked(king_start, them)
-                    && !self.board.is_square_attacked(d_sq, them)
-                    && !self.board.is_square_attacked(c_sq, them)
-                {
-                    self.moves
-                        .push(Move::new(king_start, qs_target, Move::Q_CASTLE));
-                }
-            }
+        let mut knights = self.board.pieces(Self::US, PieceType::Knight);
+        while let Some(from) = knights.pop_lsb() {
+            let targets = generate_knight_attacks(from) & not_friends & self.allowed(from);
+            self.push_piece_targets(from, targets);
+        }
+
+        self.generate_king_moves();
+
+        let mut rooks = self.board.pieces(Self::US, PieceType::Rook);
+        while let Some(from) = rooks.pop_lsb() {
+            let attacks = magic::get_rook_attacks(from, occupancy);
+            self.push_piece_targets(from, attacks & not_friends & self.allowed(from));
+        }
+        let mut bishops = self.board.pieces(Self::US, PieceType::Bishop);
+        while let Some(from) = bishops.pop_lsb() {
+            let attacks = magic::get_bishop_attacks(from, occupancy);
+            self.push_piece_targets(from, attacks & not_friends & self.allowed(from));
+        }
+        let mut queens = self.board.pieces(Self::US, PieceType::Queen);
+        while let Some(from) = queens.pop_lsb() {
+            let attacks = magic::get_rook_attacks(from, occupancy)
+                | magic::get_bishop_attacks(from, occupancy);
+            self.push_piece_targets(from, attacks & not_friends & self.allowed(from));
         }
     }
 
-    fn generate_slider_moves(&mut self) {
-        let white = self.board.side_to_move == Color::White;
-        let friends = if white {
-            self.board.white_occupancy
-        } else {
-            self.board.black_occupancy
-        };
-        let enemies = if white {
-            self.board.black_occupancy
-        } else {
-            self.board.white_occupancy
+    fn generate_king_moves(&mut self) {
+        let king = self.board.pieces(Self::US, PieceType::King);
+        let Some(from) = king.lsb_index() else {
+            return;
         };
+        let mut targets = generate_king_attacks(from) & !self.friends() & self.king_mask;
+        if let Some(legality) = self.legality {
+            let mut safe = Bitboard::EMPTY;
+            while let Some(to) = targets.pop_lsb() {
+                if legality.king_can_move_to(self.board, to) {
+                    safe.set_bit(to);
+                }
+            }
+            targets = safe;
+        }
+        self.push_piece_targets(from, targets);
+    }
 
-        let mut generate = |piece_type: PieceType, is_rook: bool, is_bishop: bool| {
-            let mut pieces = if white {
-                self.board.white_pieces[piece_type as usize]
-            } else {
-                self.board.black_pieces[piece_type as usize]
-            };
+    fn generate_castling_moves(&mut self) {
+        let rights = self.board.castling_rights;
+        let king = self.board.pieces(Self::US, PieceType::King);
 
-            while let Some(from_sq) = pieces.pop_lsb() {
-                let mut attacks = Bitboard::EMPTY;
-                if is_rook {
-                    attacks |= magic::get_rook_attacks(from_sq, self.board.all_occupancy);
-                }
-                if is_bishop {
-                    attacks |= magic::get_bishop_attacks(from_sq, self.board.all_occupancy);
-                }
-                attacks &= !friends;
-                while let Some(to_sq) = attacks.pop_lsb() {
-                    let flag = if enemies.get_bit(to_sq) {
-                        Move::CAPTURE
-                    } else {
-                        Move::QUIET
-                    };
-                    self.moves.push(Move::new(from_sq, to_sq, flag));
-                }
+        for (side, allowed, flag) in [
+            (
+                KINGSIDE,
+                rights.can_castle_kingside(Self::US),
+                MoveFlag::KingCastle,
+            ),
+            (
+                QUEENSIDE,
+                rights.can_castle_queenside(Self::US),
+                MoveFlag::QueenCastle,
+            ),
+        ] {
+            let path = &CASTLING_PATHS[Self::US as usize][side];
+            if !allowed || !king.get_bit(path.king_from) {
+                continue;
             }
-        };
+            // nothing may stand between king and rook or on their targets
+            if (self.board.all_occupancy & path.empty) != Bitboard::EMPTY {
+                continue;
+            }
+            // king may not castle out of, through, or into check
+            if !self.board.any_square_attacked(path.king_path, Self::THEM) {
+                self.moves
+                    .push(Move::new(path.king_from, path.king_to, flag));
+            }
+        }
+    }
+}
+
+/// Squares the `color` pawns in `pawns` can push to one step forward.
+///
+/// ```
+/// use ananke::bitboard::{Bitboard, Square};
+/// use ananke::movegen::{pawn_double_pushes, pawn_single_pushes};
+/// use ananke::types::Color;
+///
+/// let bb = |squares: &[Square]| {
+///     let mut bb = Bitboard::EMPTY;
+///     squares.iter().for_each(|&sq| bb.set_bit(sq));
+///     bb
+/// };
+/// // the d-pawn is blocked, the h-pawn has already moved
+/// let pawns = bb(&[Square::D2, Square::E2, Square::H3]);
+/// let empty = !(pawns | bb(&[Square::D3]));
+/// assert_eq!(
+///     pawn_single_pushes(Color::White, pawns, empty),
+///     bb(&[Square::E3, Square::H4])
+/// );
+/// assert_eq!(pawn_double_pushes(Color::White, pawns, empty), bb(&[Square::E4]));
+///
+/// let pawns = bb(&[Square::E7]);
+/// assert_eq!(pawn_single_pushes(Color::Black, pawns, !pawns), bb(&[Square::E6]));
+/// assert_eq!(pawn_double_pushes(Color::Black, pawns, !pawns), bb(&[Square::E5]));
+/// ```
+#[inline]
+pub fn pawn_single_pushes(color: Color, pawns: Bitboard, empty: Bitboard) -> Bitboard {
+    let pushed = if color == Color::White {
+        pawns.0 << 8
+    } else {
+        pawns.0 >> 8
+    };
+    Bitboard(pushed) & empty
+}
 
-        generate(PieceType::Rook, true, false);
-        generate(PieceType::Bishop, false, true);
-        generate(PieceType::Queen, true, true);
+/// Squares the `color` pawns in `pawns` can reach with a double push from
+/// their starting rank.
+#[inline]
+pub fn pawn_double_pushes(color: Color, pawns: Bitboard, empty: Bitboard) -> Bitboard {
+    let single = pawn_single_pushes(color, pawns, empty);
+    let double_rank = if color == Color::White {
+        RANK_4
+    } else {
+        RANK_5
+    };
+    pawn_single_pushes(color, single, empty) & Bitboard(double_rank)
+}
+
+/// Every square attacked by the `color` pawns in `pawns`, all at once.
+///
+/// ```
+/// use ananke::bitboard::{Bitboard, Square};
+/// use ananke::movegen::pawn_attacks_bb;
+/// use ananke::types::Color;
+///
+/// let bb = |squares: &[Square]| {
+///     let mut bb = Bitboard::EMPTY;
+///     squares.iter().for_each(|&sq| bb.set_bit(sq));
+///     bb
+/// };
+/// // pawns on the a- and h-files attack inwards only, never round the
+/// // edge onto the other side of the board
+/// let pawns = bb(&[Square::A4, Square::H4, Square::D4]);
+/// assert_eq!(
+///     pawn_attacks_bb(Color::White, pawns),
+///     bb(&[Square::B5, Square::G5, Square::C5, Square::E5])
+/// );
+/// assert_eq!(
+///     pawn_attacks_bb(Color::Black, pawns),
+///     bb(&[Square::B3, Square::G3, Square::C3, Square::E3])
+/// );
+/// ```
+#[inline]
+pub fn pawn_attacks_bb(color: Color, pawns: Bitboard) -> Bitboard {
+    let (east, west) = (pawns.0 & NOT_H_FILE, pawns.0 & NOT_A_FILE);
+    if color == Color::White {
+        Bitboard((east << 9) | (west << 7))
+    } else {
+        Bitboard((east >> 7) | (west >> 9))
     }
 }
 
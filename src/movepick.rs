@@ -0,0 +1,259 @@
+//! Staged move picking. Moves come out in the order most likely to cut the
+//! search off early, and each stage is only generated once the earlier ones
+//! have run dry, so a node that cuts off on its first move never generates
+//! or sorts the quiet moves at all:
+//!
+//! 1. the transposition table move
+//! 2. captures and promotions that don't lose material, best first
+//! 3. the two killer moves
+//! 4. the other quiet moves
+//! 5. captures that lose material by static exchange
+//!
+//! In check there are only two stages: the table move, then the evasions
+//! best first. The quiescence picker only has the captures and promotions
+//! that don't lose material.
+
+use crate::bitboard::Bitboard;
+use crate::board::Board;
+use crate::movegen::{
+    generate_evasions_into, generate_noisy_into, generate_quiets_into, is_pseudo_legal,
+};
+use crate::see;
+use crate::types::{Move, MoveList};
+use crate::values;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Stage {
+    TtMove,
+    GenerateNoisy,
+    GoodNoisy,
+    Killer1,
+    Killer2,
+    GenerateQuiets,
+    Quiets,
+    BadNoisy,
+    GenerateEvasions,
+    Evasions,
+    Done,
+}
+
+/// Hands out the pseudo-legal moves of a position one at a time, each at
+/// most once. The board is passed to every `next` call rather than kept, as
+/// the search plays the moves out on it in between; it has to be back in the
+/// position the picker was made for each time.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::movepick::MovePicker;
+///
+/// // the knight takes the undefended rook before anything else happens
+/// let board = Board::from_fen("4k3/8/8/3r4/8/4N3/8/4K3 w - - 0 1").unwrap();
+/// let mut picker = MovePicker::new(&board, None, [None; 2]);
+/// assert_eq!(picker.next(&board).unwrap().to_string(), "e3d5");
+/// let mut count = 1;
+/// while picker.next(&board).is_some() {
+///     count += 1;
+/// }
+/// assert_eq!(count, 13);
+/// ```
+pub struct MovePicker {
+    stage: Stage,
+    checkers: Bitboard,
+    tt_move: Option<Move>,
+    killers: [Option<Move>; 2],
+    // only the good noisy moves, no killers, quiets or losing captures
+    quiescence: bool,
+    // the current stage's moves, with their scores while they're sorted
+    moves: MoveList,
+    scores: [i32; 256],
+    index: usize,
+    // losing captures, put off until the end
+    bad: MoveList,
+}
+
+impl MovePicker {
+    /// Every move of `board`, starting with `tt_move` and trying `killers`
+    /// (quiet moves that cut off at this ply elsewhere) before the other
+    /// quiet moves. Neither has to be pseudo-legal here; ones that aren't
+    /// are skipped.
+    pub fn new(board: &Board, tt_move: Option<Move>, killers: [Option<Move>; 2]) -> Self {
+        MovePicker {
+            stage: Stage::TtMove,
+            checkers: board.checkers(),
+            tt_move,
+            killers,
+            quiescence: false,
+            moves: MoveList::new(),
+            scores: [0; 256],
+            index: 0,
+            bad: MoveList::new(),
+        }
+    }
+
+    /// Only the captures and promotions that don't lose material, best
+    /// first. The losing captures are left out altogether.
+    pub fn quiescence() -> Self {
+        MovePicker {
+            stage: Stage::GenerateNoisy,
+            checkers: Bitboard::EMPTY,
+            tt_move: None,
+            killers: [None; 2],
+            quiescence: true,
+            moves: MoveList::new(),
+            scores: [0; 256],
+            index: 0,
+            bad: MoveList::new(),
+        }
+    }
+
+    /// The next move to try, or None once they've all been handed out.
+    pub fn next(&mut self, board: &Board) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::TtMove => {
+                    self.stage = if self.checkers == Bitboard::EMPTY {
+                        Stage::GenerateNoisy
+                    } else {
+                        Stage::GenerateEvasions
+                    };
+                    if let Some(m) = self.tt_move
+                        && is_pseudo_legal(board, m)
+                    {
+                        return Some(m);
+                    }
+                }
+                Stage::GenerateNoisy => {
+                    generate_noisy_into(board, &mut self.moves);
+                    self.score(board);
+                    self.stage = Stage::GoodNoisy;
+                }
+                Stage::GoodNoisy => {
+                    while let Some(m) = self.pick_best() {
+                        if Some(m) == self.tt_move {
+                            continue;
+                        }
+                        if m.is_capture() && see::see(board, m) < 0 {
+                            if !self.quiescence {
+                                self.bad.push(m);
+                            }
+                            continue;
+                        }
+                        return Some(m);
+                    }
+                    self.stage = if self.quiescence {
+                        Stage::Done
+                    } else {
+                        Stage::Killer1
+                    };
+                    self.index = 0;
+                }
+                Stage::Killer1 => {
+                    self.stage = Stage::Killer2;
+                    if let Some(m) = self.killers[0]
+                        && self.is_usable_killer(board, m)
+                    {
+                        return Some(m);
+                    }
+                }
+                Stage::Killer2 => {
+                    self.stage = Stage::GenerateQuiets;
+                    if let Some(m) = self.killers[1]
+                        && self.killers[0] != Some(m)
+                        && self.is_usable_killer(board, m)
+                    {
+                        return Some(m);
+                    }
+                }
+                Stage::GenerateQuiets => {
+                    generate_quiets_into(board, &mut self.moves);
+                    self.index = 0;
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => {
+                    while let Some(&m) = self.moves.as_slice().get(self.index) {
+                        self.index += 1;
+                        if Some(m) != self.tt_move && !self.killers.contains(&Some(m)) {
+                            return Some(m);
+                        }
+                    }
+                    self.stage = Stage::BadNoisy;
+                    self.index = 0;
+                }
+                Stage::BadNoisy => {
+                    if let Some(&m) = self.bad.as_slice().get(self.index) {
+                        self.index += 1;
+                        return Some(m);
+                    }
+                    self.stage = Stage::Done;
+                }
+                Stage::GenerateEvasions => {
+                    generate_evasions_into(board, self.checkers, &mut self.moves);
+                    self.score(board);
+                    self.stage = Stage::Evasions;
+                }
+                Stage::Evasions => {
+                    while let Some(m) = self.pick_best() {
+                        if Some(m) != self.tt_move {
+                            return Some(m);
+                        }
+                    }
+                    self.stage = Stage::Done;
+                }
+                Stage::Done => return None,
+            }
+        }
+    }
+
+    // a killer from elsewhere in the tree that's a quiet move here too, and
+    // not the one already tried from the table
+    fn is_usable_killer(&self, board: &Board, m: Move) -> bool {
+        Some(m) != self.tt_move && !m.is_capture() && !m.is_promotion() && is_pseudo_legal(board, m)
+    }
+
+    fn score(&mut self, board: &Board) {
+        for (score, m) in self.scores.iter_mut().zip(self.moves.iter()) {
+            *score = order_score(board, *m);
+        }
+        self.index = 0;
+    }
+
+    // selection sort, one step at a time: with an early cutoff most of the
+    // list is never sorted
+    fn pick_best(&mut self) -> Option<Move> {
+        let len = self.moves.len();
+        if self.index >= len {
+            return None;
+        }
+        let mut best = self.index;
+        for i in self.index + 1..len {
+            if self.scores[i] > self.scores[best] {
+                best = i;
+            }
+        }
+        self.moves.as_mut_slice().swap(self.index, best);
+        self.scores.swap(self.index, best);
+        self.index += 1;
+        Some(self.moves.as_slice()[self.index - 1])
+    }
+}
+
+/// How promising `m` looks before searching it: captures by most valuable
+/// victim then least valuable attacker, and promotions by what they gain,
+/// all well above the quiet moves, which score 0.
+pub fn order_score(board: &Board, m: Move) -> i32 {
+    let us = board.side_to_move;
+    let mut score = 0;
+    if m.is_capture() {
+        let victim = board
+            .get_piece_type_at(m.to(), us.opposite())
+            .map_or(values::PAWN, values::piece_value);
+        let attacker = board
+            .get_piece_type_at(m.from(), us)
+            .map_or(0, values::piece_value);
+        score += 10 * victim - attacker / 10 + 100_000;
+    }
+    if let Some(pt) = m.promotion_piece() {
+        score += values::promotion_gain(pt) + 100_000;
+    }
+    score
+}
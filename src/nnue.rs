@@ -0,0 +1,318 @@
+//! A small NNUE evaluation: 768 piece-square inputs per perspective, one
+//! hidden layer with clipped ReLU, and a single output, quantized to
+//! integers. The hidden layer (the accumulator) is kept up to date move by
+//! move through the `Evaluator` hooks, so a node only pays for the pieces
+//! that moved. Both loops over the hidden layer run in `simd`.
+//!
+//! Network files are little-endian: the bytes `ANNU`, the hidden size as a
+//! u32, then i16 feature weights (768 rows of `hidden`), i16 feature
+//! biases (`hidden`), i16 output weights (`hidden` for the side to move,
+//! then `hidden` for the other side) and one i16 output bias. Feature
+//! weights and biases are scaled by `QA`, output weights by `QB`, and the
+//! output bias by `QA * QB`.
+
+use crate::bitboard::Square;
+use crate::board::{Board, UndoInfo};
+use crate::eval::Evaluator;
+use crate::movegen::{CASTLING_PATHS, KINGSIDE, QUEENSIDE};
+use crate::simd::{self, add, sub};
+use crate::types::{ByColor, Color, Move, MoveFlag, PieceType};
+use std::sync::Arc;
+
+pub const QA: i32 = 255;
+pub const QB: i32 = 64;
+/// The network's output times this is the score in centipawns.
+pub const SCALE: i32 = 400;
+// evaluations are clamped to this, well short of the mate scores
+const MAX_EVAL: i32 = 10_000;
+const MAGIC: &[u8; 4] = b"ANNU";
+const INPUTS: usize = 768;
+
+/// The weights of a network, shared by every evaluator that uses it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Network {
+    pub hidden: usize,
+    pub feature_weights: Vec<i16>,
+    pub feature_biases: Vec<i16>,
+    pub output_weights: Vec<i16>,
+    pub output_bias: i16,
+}
+
+impl Network {
+    /// Parse a network file; see the module docs for the layout.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Network, String> {
+        let Some(rest) = bytes.strip_prefix(MAGIC) else {
+            return Err("Not a network file: bad magic".to_string());
+        };
+        if rest.len() < 4 {
+            return Err("Network file truncated".to_string());
+        }
+        let hidden = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        if hidden == 0 || hidden > 4096 {
+            return Err(format!("Unsupported hidden size: {}", hidden));
+        }
+        let values: Vec<i16> = rest[4..]
+            .chunks_exact(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        let expected = INPUTS * hidden + hidden + 2 * hidden + 1;
+        if values.len() != expected || rest[4..].len() % 2 != 0 {
+            return Err(format!(
+                "Network file has {} weights, expected {} for hidden size {}",
+                values.len(),
+                expected,
+                hidden
+            ));
+        }
+        let (feature_weights, rest) = values.split_at(INPUTS * hidden);
+        let (feature_biases, rest) = rest.split_at(hidden);
+        let (output_weights, rest) = rest.split_at(2 * hidden);
+        Ok(Network {
+            hidden,
+            feature_weights: feature_weights.to_vec(),
+            feature_biases: feature_biases.to_vec(),
+            output_weights: output_weights.to_vec(),
+            output_bias: rest[0],
+        })
+    }
+
+    pub fn load(path: &str) -> Result<Network, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+        Network::from_bytes(&bytes).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /// The file form of the network, as `from_bytes` reads it.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend((self.hidden as u32).to_le_bytes());
+        let weights = self
+            .feature_weights
+            .iter()
+            .chain(&self.feature_biases)
+            .chain(&self.output_weights)
+            .chain(std::iter::once(&self.output_bias));
+        for w in weights {
+            bytes.extend(w.to_le_bytes());
+        }
+        bytes
+    }
+
+    // accumulator rows added for a piece, one per perspective
+    fn feature_row(&self, feature: usize) -> &[i16] {
+        &self.feature_weights[feature * self.hidden..(feature + 1) * self.hidden]
+    }
+
+    // the hidden layer of `board` from scratch, both perspectives
+    fn refresh(&self, board: &Board, white: &mut [i16], black: &mut [i16]) {
+        white.copy_from_slice(&self.feature_biases);
+        black.copy_from_slice(&self.feature_biases);
+        for color in [Color::White, Color::Black] {
+            for pt in PieceType::ALL {
+                let mut pieces = board.pieces(color, pt);
+                while let Some(sq) = pieces.pop_lsb() {
+                    add(
+                        white,
+                        self.feature_row(feature(Color::White, color, pt, sq)),
+                    );
+                    add(
+                        black,
+                        self.feature_row(feature(Color::Black, color, pt, sq)),
+                    );
+                }
+            }
+        }
+    }
+
+    // the output layer, from the side to move's accumulator and the other.
+    // Summed in i64: a large enough network with big weights overflows i32
+    fn output(&self, ours: &[i16], theirs: &[i16]) -> i32 {
+        let (our_weights, their_weights) = self.output_weights.split_at(self.hidden);
+        let sum = simd::clipped_dot(ours, our_weights, QA as i16)
+            + simd::clipped_dot(theirs, their_weights, QA as i16);
+        let eval = (sum + self.output_bias as i64) * SCALE as i64 / (QA * QB) as i64;
+        eval.clamp(-MAX_EVAL as i64, MAX_EVAL as i64) as i32
+    }
+}
+
+// input index of a `color` `pt` on `sq` as `perspective` sees it: its own
+// pieces first, and the board flipped for black so both sides share weights
+fn feature(perspective: Color, color: Color, pt: PieceType, sq: Square) -> usize {
+    let side = if color == perspective { 0 } else { 1 };
+    let sq = if perspective == Color::White {
+        sq as usize
+    } else {
+        sq as usize ^ 56
+    };
+    side * 384 + pt as usize * 64 + sq
+}
+
+/// An `Evaluator` running a `Network`, with one accumulator per ply of the
+/// search. A board the accumulators weren't built for (one `evaluate` is
+/// handed without the hooks having followed it) is evaluated from scratch.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::eval::Evaluator;
+/// use ananke::nnue::{Network, Nnue};
+/// use ananke::search::{Limits, search_with_evaluator};
+/// use std::sync::Arc;
+///
+/// // an untrained network: every weight zero, so only the bias counts
+/// let hidden = 8;
+/// let network = Network {
+///     hidden,
+///     feature_weights: vec![0; 768 * hidden],
+///     feature_biases: vec![0; hidden],
+///     output_weights: vec![0; 2 * hidden],
+///     output_bias: 0,
+/// };
+/// let mut nnue = Nnue::new(Arc::new(network));
+/// assert_eq!(nnue.evaluate(&Board::startpos()), 0);
+/// let result = search_with_evaluator(&Board::startpos(), Limits::depth(2), &mut nnue);
+/// assert!(result.best_move.is_some());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Nnue {
+    network: Arc<Network>,
+    // ply after ply: white's accumulator, then black's
+    stack: Vec<i16>,
+    // the hash of the position each ply's accumulators belong to
+    hashes: Vec<u64>,
+    ply: usize,
+}
+
+impl Nnue {
+    pub fn new(network: Arc<Network>) -> Self {
+        Nnue {
+            network,
+            stack: Vec::new(),
+            hashes: Vec::new(),
+            ply: 0,
+        }
+    }
+
+    pub fn network(&self) -> &Arc<Network> {
+        &self.network
+    }
+
+    fn slot(&self, ply: usize) -> std::ops::Range<usize> {
+        let size = 2 * self.network.hidden;
+        ply * size..(ply + 1) * size
+    }
+
+    // the feature changes `m` made, as (color, piece, square, added), read
+    // off the board after it
+    fn changes(
+        board: &Board,
+        m: Move,
+        undo: &UndoInfo,
+    ) -> [Option<(Color, PieceType, Square, bool)>; 4] {
+        let us = board.side_to_move.opposite();
+        let them = board.side_to_move;
+        let (from, to) = (m.from(), m.to());
+        let Some(landed) = board.get_piece_type_at(to, us) else {
+            return [None; 4];
+        };
+        let moved = if m.is_promotion() {
+            PieceType::Pawn
+        } else {
+            landed
+        };
+        let mut changes = [
+            Some((us, moved, from, false)),
+            Some((us, landed, to, true)),
+            None,
+            None,
+        ];
+        match m.flag() {
+            MoveFlag::EnPassant => {
+                let victim = if us == Color::White {
+                    to as u8 - 8
+                } else {
+                    to as u8 + 8
+                };
+                changes[2] = Some((them, PieceType::Pawn, Square::new(victim), false));
+            }
+            MoveFlag::KingCastle | MoveFlag::QueenCastle => {
+                let side = if m.flag() == MoveFlag::KingCastle {
+                    KINGSIDE
+                } else {
+                    QUEENSIDE
+                };
+                let path = &CASTLING_PATHS[us as usize][side];
+                changes[2] = Some((us, PieceType::Rook, path.rook_from, false));
+                changes[3] = Some((us, PieceType::Rook, path.rook_to, true));
+            }
+            _ => {
+                if let Some(captured) = undo.captured {
+                    changes[2] = Some((them, captured, to, false));
+                }
+            }
+        }
+        changes
+    }
+}
+
+impl Evaluator for Nnue {
+    fn evaluate(&self, board: &Board) -> i32 {
+        if self.hashes.get(self.ply) != Some(&board.hash) {
+            // not a position the hooks followed: no shortcuts
+            let mut fresh = Nnue::new(self.network.clone());
+            fresh.reset(board);
+            return fresh.evaluate(board);
+        }
+        let (white, black) = self.stack[self.slot(self.ply)].split_at(self.network.hidden);
+        let accumulators = ByColor::new(white, black);
+        let us = board.side_to_move;
+        self.network
+            .output(accumulators[us], accumulators[us.opposite()])
+    }
+
+    fn reset(&mut self, board: &Board) {
+        let range = self.slot(0);
+        if self.stack.len() < range.end {
+            self.stack.resize(range.end, 0);
+        }
+        let (white, black) = self.stack[range].split_at_mut(self.network.hidden);
+        self.network.refresh(board, white, black);
+        self.hashes.clear();
+        self.hashes.push(board.hash);
+        self.ply = 0;
+    }
+
+    fn on_make_move(&mut self, board: &Board, m: Move, undo: &UndoInfo) {
+        let (current, next) = (self.slot(self.ply), self.slot(self.ply + 1));
+        if self.stack.len() < next.end {
+            self.stack.resize(next.end, 0);
+        }
+        self.stack.copy_within(current, next.start);
+        let hidden = self.network.hidden;
+        let (white, black) = self.stack[next].split_at_mut(hidden);
+        for (color, pt, sq, added) in Nnue::changes(board, m, undo).into_iter().flatten() {
+            let network = &self.network;
+            let (w, b) = (
+                network.feature_row(feature(Color::White, color, pt, sq)),
+                network.feature_row(feature(Color::Black, color, pt, sq)),
+            );
+            if added {
+                add(white, w);
+                add(black, b);
+            } else {
+                sub(white, w);
+                sub(black, b);
+            }
+        }
+        self.ply += 1;
+        self.hashes.truncate(self.ply);
+        self.hashes.push(board.hash);
+    }
+
+    fn on_unmake_move(&mut self) {
+        self.ply = self.ply.saturating_sub(1);
+        self.hashes.truncate(self.ply + 1);
+    }
+
+    fn name(&self) -> &str {
+        "nnue"
+    }
+}
@@ -0,0 +1,168 @@
+//! The search's tunable parameters: the pruning margins and depths and the
+//! extensions, kept
+//! together so that they can be set by name, from a UCI option or a config
+//! file, and a tuning run or an experiment can change them without a
+//! rebuild. The defaults are what the engine plays with.
+
+/// Every tunable the alpha-beta search reads. All are whole numbers, in
+/// centipawns, plies or moves.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Params {
+    /// Reverse futility, futility and late move pruning are only done this
+    /// many plies from the leaves, or closer.
+    pub pruning_depth: i32,
+    /// Reverse futility: the static eval has to clear beta by this much per
+    /// ply left for the node to be cut off unsearched.
+    pub reverse_futility_margin: i32,
+    /// Futility: a quiet move is skipped when the static eval plus this
+    /// much per ply left can't reach alpha.
+    pub futility_margin: i32,
+    /// Late move pruning is only done this many plies from the leaves, or
+    /// closer.
+    pub late_move_depth: i32,
+    /// Late move pruning searches `base + per_ply_squared * depth^2` quiet
+    /// moves at a node that's improving, half as many otherwise. The quiet
+    /// moves after the killers come in no particular order, so it has to
+    /// leave plenty of them.
+    pub late_move_base: i32,
+    pub late_move_per_ply_squared: i32,
+    /// Plies added to a recapture on the square of the capture just
+    /// before, so that an exchange is seen through to its end. Off by
+    /// default: it made no measurable difference in self-play, and it
+    /// takes several times the nodes in positions full of exchanges.
+    pub recapture_extension: i32,
+    /// Plies added to a pawn push to the seventh rank, where it's passed
+    /// by definition and a move from promoting.
+    pub pawn_push_extension: i32,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            pruning_depth: 3,
+            reverse_futility_margin: 120,
+            futility_margin: 150,
+            late_move_depth: 2,
+            late_move_base: 8,
+            late_move_per_ply_squared: 4,
+            recapture_extension: 0,
+            pawn_push_extension: 1,
+        }
+    }
+}
+
+/// One parameter: its name, as UCI options and config files have it, and
+/// the range it may be set in.
+pub struct Param {
+    pub name: &'static str,
+    pub min: i32,
+    pub max: i32,
+    get: fn(&Params) -> i32,
+    set: fn(&mut Params, i32),
+}
+
+impl Param {
+    pub fn get(&self, params: &Params) -> i32 {
+        (self.get)(params)
+    }
+}
+
+/// All of them, in the order UCI lists them.
+pub const PARAMS: &[Param] = &[
+    Param {
+        name: "PruningDepth",
+        min: 0,
+        max: 8,
+        get: |p| p.pruning_depth,
+        set: |p, v| p.pruning_depth = v,
+    },
+    Param {
+        name: "ReverseFutilityMargin",
+        min: 0,
+        max: 1000,
+        get: |p| p.reverse_futility_margin,
+        set: |p, v| p.reverse_futility_margin = v,
+    },
+    Param {
+        name: "FutilityMargin",
+        min: 0,
+        max: 1000,
+        get: |p| p.futility_margin,
+        set: |p, v| p.futility_margin = v,
+    },
+    Param {
+        name: "LateMoveDepth",
+        min: 0,
+        max: 8,
+        get: |p| p.late_move_depth,
+        set: |p, v| p.late_move_depth = v,
+    },
+    Param {
+        name: "LateMoveBase",
+        min: 0,
+        max: 64,
+        get: |p| p.late_move_base,
+        set: |p, v| p.late_move_base = v,
+    },
+    Param {
+        name: "LateMovePerPlySquared",
+        min: 0,
+        max: 64,
+        get: |p| p.late_move_per_ply_squared,
+        set: |p, v| p.late_move_per_ply_squared = v,
+    },
+    Param {
+        name: "RecaptureExtension",
+        min: 0,
+        max: 2,
+        get: |p| p.recapture_extension,
+        set: |p, v| p.recapture_extension = v,
+    },
+    Param {
+        name: "PawnPushExtension",
+        min: 0,
+        max: 2,
+        get: |p| p.pawn_push_extension,
+        set: |p, v| p.pawn_push_extension = v,
+    },
+];
+
+/// The parameter called `name`, ignoring case as UCI option names do.
+pub fn find(name: &str) -> Option<&'static Param> {
+    PARAMS.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+}
+
+impl Params {
+    /// Set the parameter called `name` from its text `value`, which has to
+    /// be a whole number in its range.
+    ///
+    /// ```
+    /// use ananke::params::Params;
+    ///
+    /// let mut params = Params::default();
+    /// params.set("futilitymargin", "200").unwrap();
+    /// assert_eq!(params.futility_margin, 200);
+    /// assert!(params.set("FutilityMargin", "-5").is_err());
+    /// assert!(params.set("NoSuchParam", "1").is_err());
+    /// ```
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let param = find(name).ok_or_else(|| format!("Unknown parameter: {}", name))?;
+        let value: i32 = value
+            .parse()
+            .ok()
+            .filter(|v| (param.min..=param.max).contains(v))
+            .ok_or_else(|| {
+                format!(
+                    "Invalid {}: {} (from {} to {})",
+                    param.name, value, param.min, param.max
+                )
+            })?;
+        (param.set)(self, value);
+        Ok(())
+    }
+
+    /// The value of the parameter called `name`.
+    pub fn get(&self, name: &str) -> Option<i32> {
+        find(name).map(|param| param.get(self))
+    }
+}
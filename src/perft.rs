@@ -1,28 +1,168 @@
 use crate::board::Board;
-use crate::movegen::MoveGenerator;
+use crate::game::Game;
+use crate::movegen::{self, MoveGenerator};
+use crate::types::MoveList;
+use std::time::Duration;
 
+/// Count the leaf nodes of the legal move tree to `depth`.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::perft::perft;
+///
+/// assert_eq!(perft(&Board::startpos(), 3), 8902);
+/// ```
 pub fn perft(board: &Board, depth: u8) -> u64 {
+    // one move list per ply, reused across the whole tree
+    let mut lists: Vec<MoveList> = (0..depth).map(|_| MoveList::new()).collect();
+    // and one board, made and unmade in place
+    perft_with(&mut board.clone(), depth, &mut lists)
+}
+
+fn perft_with(board: &mut Board, depth: u8, lists: &mut [MoveList]) -> u64 {
     if depth == 0 {
         return 1;
     }
+    // bulk counting: the last ply only needs how many moves there are
+    if depth == 1 {
+        return board.count_legal_moves() as u64;
+    }
 
     let mut nodes = 0;
-    let generator = MoveGenerator::new(board);
-    let moves = generator.generate_all();
+    let (moves, rest) = lists.split_first_mut().unwrap();
+    movegen::generate_legal_into(board, moves);
 
     for m in moves.iter() {
-        let next_board = board.make_move(*m);
+        let undo = board.make_move_in_place(*m);
+        nodes += perft_with(board, depth - 1, rest);
+        board.unmake_move(*m, undo);
+    }
 
-        let us = board.side_to_move;
-        let king_sq = next_board.get_king_square(us);
+    nodes
+}
 
-        if next_board.is_square_attacked(king_sq, next_board.side_to_move) {
-            continue;
+/// Perft that checks make/unmake as it goes: after every unmake the board
+/// must be exactly what it was before the make, down to the hash, clocks
+/// and occupancy sets. Slower than `perft`, with no bulk counting, but a
+/// state-restoration bug is reported at the first move that shows it.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::perft::perft_checked;
+///
+/// assert_eq!(perft_checked(&Board::startpos(), 3), Ok(8902));
+/// ```
+pub fn perft_checked(board: &Board, depth: u8) -> Result<u64, String> {
+    let mut lists: Vec<MoveList> = (0..depth).map(|_| MoveList::new()).collect();
+    perft_checked_with(&mut board.clone(), depth, &mut lists)
+}
+
+fn perft_checked_with(board: &mut Board, depth: u8, lists: &mut [MoveList]) -> Result<u64, String> {
+    if depth == 0 {
+        return Ok(1);
+    }
+    let mut nodes = 0;
+    let (moves, rest) = lists.split_first_mut().unwrap();
+    movegen::generate_legal_into(board, moves);
+    let before = board.clone();
+
+    for m in moves.iter() {
+        let undo = board.make_move_in_place(*m);
+        nodes += perft_checked_with(board, depth - 1, rest)?;
+        board.unmake_move(*m, undo);
+        if let Some(field) = first_difference(board, &before) {
+            return Err(format!(
+                "unmaking {} in {} left {} changed",
+                m,
+                before.to_fen(),
+                field
+            ));
+        }
+    }
+    Ok(nodes)
+}
+
+/// Perft with both ways of updating a board at once: every move is made in
+/// place and with the copying `make_move`, and the two results must agree
+/// field for field, hash included, at every node. Returns the node count,
+/// the same as `perft`'s, or the first move where they part.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::perft::{perft, perft_cross_checked};
+///
+/// let board = Board::startpos();
+/// assert_eq!(perft_cross_checked(&board, 3), Ok(perft(&board, 3)));
+/// ```
+pub fn perft_cross_checked(board: &Board, depth: u8) -> Result<u64, String> {
+    if depth == 0 {
+        return Ok(1);
+    }
+    let mut nodes = 0;
+    let mut in_place = board.clone();
+    for m in board.legal_moves().iter() {
+        let copied = board.make_move(*m);
+        let undo = in_place.make_move_in_place(*m);
+        if let Some(field) = first_difference(&in_place, &copied) {
+            return Err(format!(
+                "{} in {}: {} differs between make_move_in_place and make_move",
+                m,
+                board.to_fen(),
+                field
+            ));
         }
+        nodes += perft_cross_checked(&copied, depth - 1)?;
+        in_place.unmake_move(*m, undo);
+    }
+    Ok(nodes)
+}
 
-        nodes += perft(&next_board, depth - 1);
+// the first field where two boards differ, by name
+fn first_difference(a: &Board, b: &Board) -> Option<&'static str> {
+    if a.pieces != b.pieces {
+        Some("pieces")
+    } else if a.occupancy != b.occupancy {
+        Some("occupancy")
+    } else if a.all_occupancy != b.all_occupancy {
+        Some("all_occupancy")
+    } else if a.side_to_move != b.side_to_move {
+        Some("side_to_move")
+    } else if a.castling_rights != b.castling_rights {
+        Some("castling_rights")
+    } else if a.en_passant_sq != b.en_passant_sq {
+        Some("en_passant_sq")
+    } else if a.halfmove_clock != b.halfmove_clock {
+        Some("halfmove_clock")
+    } else if a.fullmove_number != b.fullmove_number {
+        Some("fullmove_number")
+    } else if a.material_key != b.material_key {
+        Some("material_key")
+    } else if a.hash != b.hash {
+        Some("hash")
+    } else {
+        None
     }
+}
 
+/// Perft through the `Game` layer: every move goes through `Game::play`, so
+/// repetition history, clocks and automatic endings are updated at each node.
+/// A finished game has no moves, so counts match `perft` unless a draw rule
+/// ends a line early. Panics if the game refuses a legal move.
+pub fn game_perft(game: &Game, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    if game.is_over() {
+        return 0;
+    }
+    let mut nodes = 0;
+    for m in game.board().legal_moves().iter() {
+        let mut next = game.clone();
+        // zero think time, so a clock never flags partway through the tree
+        next.play_timed(*m, Duration::ZERO)
+            .unwrap_or_else(|e| panic!("game refused {} in {}: {}", m, game.board().to_fen(), e));
+        nodes += game_perft(&next, depth - 1);
+    }
     nodes
 }
 
@@ -37,11 +177,8 @@ pub fn perft_driver(board: &Board, depth: u8) {
     for m in moves.iter() {
         let next_board = board.make_move(*m);
 
-        let us = board.side_to_move;
-        let king_sq = next_board.get_king_square(us);
-
         // filter illegal moves at root level
-        if next_board.is_square_attacked(king_sq, next_board.side_to_move) {
+        if next_board.opponent_in_check() {
             continue;
         }
 
@@ -55,3 +192,153 @@ pub fn perft_driver(board: &Board, depth: u8) {
     println!("time: {:.3}s", duration.as_secs_f64());
     println!("nps: {:.0}", total_nodes as f64 / duration.as_secs_f64());
 }
+
+// positions for `ananke bench`: start, kiwipete, and the endgame/promotion
+// positions from the chessprogramming perft suite
+const BENCH_FENS: [&str; 4] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+];
+
+/// Perft every bench position to `depth`, printing time-to-depth and nps
+/// for each depth, then the profiler breakdown (needs `--features profile`).
+pub fn bench(depth: u8) {
+    crate::profile::reset();
+    let start = std::time::Instant::now();
+    let mut total_nodes = 0;
+
+    for fen in BENCH_FENS {
+        println!("\n{}", fen);
+        let board = Board::from_fen(fen).unwrap();
+        for d in 1..=depth {
+            let t = std::time::Instant::now();
+            let nodes = perft(&board, d);
+            let secs = t.elapsed().as_secs_f64();
+            println!(
+                "depth {:>2}  nodes {:>12}  time {:>8.3}s  nps {:>10.0}",
+                d,
+                nodes,
+                secs,
+                nodes as f64 / secs
+            );
+            total_nodes += nodes;
+        }
+    }
+
+    let duration = start.elapsed();
+    println!("\ntotal nodes: {}", total_nodes);
+    println!("time: {:.3}s", duration.as_secs_f64());
+    println!("nps: {:.0}", total_nodes as f64 / duration.as_secs_f64());
+    println!("\n{}", crate::profile::report(duration));
+}
+
+/// One independent slice of a split perft: count `depth` plies from `fen`.
+/// Units travel between machines as single text lines, `<id> <depth> <fen>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkUnit {
+    pub id: usize,
+    pub depth: u8,
+    pub fen: String,
+}
+
+/// Node count a worker reports for one unit, as the line `<id> <nodes>`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WorkResult {
+    pub id: usize,
+    pub nodes: u64,
+}
+
+impl WorkUnit {
+    pub fn to_line(&self) -> String {
+        format!("{} {} {}", self.id, self.depth, self.fen)
+    }
+
+    pub fn from_line(line: &str) -> Result<Self, String> {
+        let mut parts = line.trim().splitn(3, ' ');
+        let (Some(id), Some(depth), Some(fen)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(format!("Invalid work unit: {}", line));
+        };
+        Ok(WorkUnit {
+            id: id
+                .parse()
+                .map_err(|_| format!("Invalid work unit id: {}", id))?,
+            depth: depth
+                .parse()
+                .map_err(|_| format!("Invalid work unit depth: {}", depth))?,
+            fen: fen.to_string(),
+        })
+    }
+
+    /// Do the work. Fails only if the FEN doesn't parse.
+    pub fn run(&self) -> Result<WorkResult, String> {
+        let board = Board::from_fen(&self.fen)?;
+        Ok(WorkResult {
+            id: self.id,
+            nodes: perft(&board, self.depth),
+        })
+    }
+}
+
+impl WorkResult {
+    pub fn to_line(&self) -> String {
+        format!("{} {}", self.id, self.nodes)
+    }
+
+    pub fn from_line(line: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid work result: {}", line);
+        let (id, nodes) = line.trim().split_once(' ').ok_or_else(invalid)?;
+        Ok(WorkResult {
+            id: id.parse().map_err(|_| invalid())?,
+            nodes: nodes.trim().parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Split a perft of `board` to `depth` into one unit per line of play
+/// `split_depth` plies deep (capped at `depth`). Transpositions are not
+/// merged, so the unit counts always add up to `perft(board, depth)`.
+pub fn split(board: &Board, depth: u8, split_depth: u8) -> Vec<WorkUnit> {
+    let split_depth = split_depth.min(depth);
+    let mut units = Vec::new();
+    split_into(board, depth, split_depth, &mut units);
+    units
+}
+
+fn split_into(board: &Board, depth: u8, split_depth: u8, units: &mut Vec<WorkUnit>) {
+    if split_depth == 0 {
+        units.push(WorkUnit {
+            id: units.len(),
+            depth,
+            fen: board.to_fen(),
+        });
+        return;
+    }
+    for m in board.legal_moves().iter() {
+        split_into(&board.make_move(*m), depth - 1, split_depth - 1, units);
+    }
+}
+
+/// Total the results for `units`. Every unit needs exactly one result; a
+/// missing, repeated or unknown id is an error rather than a wrong total.
+pub fn merge(units: &[WorkUnit], results: &[WorkResult]) -> Result<u64, String> {
+    let mut nodes: Vec<Option<u64>> = vec![None; units.len()];
+    let index: std::collections::HashMap<usize, usize> =
+        units.iter().enumerate().map(|(i, u)| (u.id, i)).collect();
+
+    for result in results {
+        let &i = index
+            .get(&result.id)
+            .ok_or_else(|| format!("Result for unknown unit {}", result.id))?;
+        if nodes[i].replace(result.nodes).is_some() {
+            return Err(format!("Duplicate result for unit {}", result.id));
+        }
+    }
+
+    let mut total = 0;
+    for (unit, count) in units.iter().zip(&nodes) {
+        total += count.ok_or_else(|| format!("Missing result for unit {}", unit.id))?;
+    }
+    Ok(total)
+}
@@ -0,0 +1,121 @@
+use crate::board::Board;
+use crate::types::Move;
+
+/// A forced line ending in a repeated position. `moves[cycle_start..]` is the
+/// cycle: playing it returns to the position before `moves[cycle_start]`.
+#[derive(Debug, Clone)]
+pub struct RepetitionLine {
+    pub moves: Vec<Move>,
+    pub cycle_start: usize,
+}
+
+/// Can the side to move force a repetition by giving check on every move,
+/// within `max_plies`? Returns the shortest such line.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::perpetual::find_perpetual;
+///
+/// // two rooks down, but the queen checks from e8 and h5 for ever
+/// let board = Board::from_fen("6k1/6p1/5p2/7Q/8/8/rr6/6K1 w - - 0 1").unwrap();
+/// let line = find_perpetual(&board, 6).unwrap();
+/// let moves: Vec<String> = line.moves.iter().map(|m| m.to_string()).collect();
+/// assert_eq!(moves, ["h5e8", "g8h7", "e8h5", "h7g8"]);
+/// assert_eq!(line.cycle_start, 0);
+/// ```
+pub fn find_perpetual(board: &Board, max_plies: u8) -> Option<RepetitionLine> {
+    find_forced_repetition(board, max_plies, true)
+}
+
+/// Can the side to move force the game into a repeated position within
+/// `max_plies`, whatever the opponent does? With `checks_only` the attacker
+/// may only play checking moves, which keeps the search narrow; without it
+/// every move is tried and only small depths are practical.
+///
+/// Lines where the defender gets mated or stalemated don't count: they are
+/// decided some other way, not by repetition.
+pub fn find_forced_repetition(
+    board: &Board,
+    max_plies: u8,
+    checks_only: bool,
+) -> Option<RepetitionLine> {
+    let mut search = RepetitionSearch {
+        checks_only,
+        path: vec![board.clone()],
+        moves: Vec::new(),
+    };
+    // shortest line first
+    (1..=max_plies).find_map(|depth| search.attacker(board, depth))
+}
+
+struct RepetitionSearch {
+    checks_only: bool,
+    // positions from the root to the current node, for repetition lookups
+    path: Vec<Board>,
+    moves: Vec<Move>,
+}
+
+impl RepetitionSearch {
+    // The attacker needs one move after which the repetition is forced.
+    fn attacker(&mut self, board: &Board, depth: u8) -> Option<RepetitionLine> {
+        if depth == 0 {
+            return None;
+        }
+        for (m, next) in legal_successors(board) {
+            if self.checks_only && !next.in_check() {
+                continue;
+            }
+            if let Some(line) = self.try_move(m, &next, depth, Self::defender) {
+                return Some(line);
+            }
+        }
+        None
+    }
+
+    // Every defender reply must still run into a repetition.
+    fn defender(&mut self, board: &Board, depth: u8) -> Option<RepetitionLine> {
+        let replies = legal_successors(board);
+        if depth == 0 || replies.is_empty() {
+            return None;
+        }
+        let mut forced = None;
+        for (m, next) in replies {
+            let line = self.try_move(m, &next, depth, Self::attacker)?;
+            forced.get_or_insert(line);
+        }
+        forced
+    }
+
+    // Play `m`; a repetition ends the line, otherwise recurse with the other side.
+    fn try_move(
+        &mut self,
+        m: Move,
+        next: &Board,
+        depth: u8,
+        recurse: fn(&mut Self, &Board, u8) -> Option<RepetitionLine>,
+    ) -> Option<RepetitionLine> {
+        self.moves.push(m);
+        let result = match self.path.iter().position(|p| p.is_same_position(next)) {
+            Some(cycle_start) => Some(RepetitionLine {
+                moves: self.moves.clone(),
+                cycle_start,
+            }),
+            None => {
+                self.path.push(next.clone());
+                let line = recurse(self, next, depth - 1);
+                self.path.pop();
+                line
+            }
+        };
+        self.moves.pop();
+        result
+    }
+}
+
+fn legal_successors(board: &Board) -> Vec<(Move, Board)> {
+    board
+        .legal_moves()
+        .iter()
+        .map(|m| (*m, board.make_move(*m)))
+        .collect()
+}
@@ -0,0 +1,645 @@
+use crate::board::{Board, START_FEN};
+use crate::eco;
+use crate::game::Game;
+use crate::san;
+use crate::types::{Color, GameResult, Move};
+use std::fmt;
+use std::io::BufRead;
+
+/// One move of PGN movetext with its annotations and alternatives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnMove {
+    pub mv: Move,
+    /// Numeric annotation glyphs: 1 "!", 2 "?", 3 "!!", 4 "??", 5 "!?",
+    /// 6 "?!", and the rest of the standard table ($14 "+=", ...).
+    pub nags: Vec<u8>,
+    /// Comment written before the move, e.g. at the start of a variation.
+    pub comment_before: Option<String>,
+    pub comment: Option<String>,
+    /// Recursive annotation variations: alternatives to this move, each
+    /// starting from the position before it.
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+impl PgnMove {
+    pub fn new(mv: Move) -> Self {
+        PgnMove {
+            mv,
+            nags: Vec::new(),
+            comment_before: None,
+            comment: None,
+            variations: Vec::new(),
+        }
+    }
+}
+
+/// A single PGN game: tag pairs, the starting position and annotated movetext.
+#[derive(Clone)]
+pub struct Pgn {
+    pub tags: Vec<(String, String)>,
+    pub start: Board,
+    pub moves: Vec<PgnMove>,
+    /// None for an unfinished game ("*").
+    pub result: Option<GameResult>,
+}
+
+impl Pgn {
+    pub fn new(start: Board) -> Self {
+        let mut pgn = Pgn {
+            tags: Vec::new(),
+            start,
+            moves: Vec::new(),
+            result: None,
+        };
+        for (name, unknown) in [
+            ("Event", "?"),
+            ("Site", "?"),
+            ("Date", "????.??.??"),
+            ("Round", "?"),
+            ("White", "?"),
+            ("Black", "?"),
+        ] {
+            pgn.set_tag(name, unknown);
+        }
+        pgn
+    }
+
+    /// The moves and result of a game, with the Seven Tag Roster filled
+    /// with "?" placeholders and ECO/Opening tags when the opening is known.
+    pub fn from_game(game: &Game) -> Self {
+        let mut pgn = Pgn::new(game.start_board().clone());
+        pgn.moves = game.moves().iter().map(|m| PgnMove::new(*m)).collect();
+        pgn.result = game.result();
+        pgn.tag_opening();
+        pgn
+    }
+
+    /// Set the ECO and Opening tags from the main line. Only games from the
+    /// standard starting position are classified.
+    pub fn tag_opening(&mut self) {
+        if self.start.to_fen() != START_FEN {
+            return;
+        }
+        if let Some((code, name)) = eco::classify_opening(&self.mainline()) {
+            self.set_tag("ECO", &code.to_string());
+            self.set_tag("Opening", name);
+        }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Set a tag, replacing any existing value.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// The main line, without variations.
+    pub fn mainline(&self) -> Vec<Move> {
+        self.moves.iter().map(|pm| pm.mv).collect()
+    }
+
+    /// Parse one game. Moves are checked for legality as they are read,
+    /// including those inside variations.
+    ///
+    /// ```
+    /// use ananke::pgn::Pgn;
+    ///
+    /// let pgn = Pgn::parse("[White \"Morphy\"]\n\n1. e4 e5 (1... c5) 2. Nf3 *").unwrap();
+    /// assert_eq!(pgn.tag("White"), Some("Morphy"));
+    /// assert_eq!(pgn.mainline().len(), 3);
+    /// assert_eq!(pgn.moves[1].variations.len(), 1);
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut tokens = tokenize(text)?.into_iter().peekable();
+
+        let mut tags = Vec::new();
+        while let Some(Token::Tag(name, value)) = tokens.peek() {
+            tags.push((name.clone(), value.clone()));
+            tokens.next();
+        }
+
+        let start = match tags.iter().find(|(n, _)| n == "FEN") {
+            Some((_, fen)) => Board::from_fen(fen)?,
+            None => Board::startpos(),
+        };
+        // reading SAN plays each move to see if it checks, which can't be
+        // done from a position where the king can be taken
+        if start.opponent_in_check() {
+            return Err(format!(
+                "FEN tag has the side not to move in check: {}",
+                start.to_fen()
+            ));
+        }
+
+        let mut result = None;
+        let moves = parse_line(&mut tokens, &start, 0, &mut result)?;
+        Ok(Pgn {
+            tags,
+            start,
+            moves,
+            result,
+        })
+    }
+}
+
+/// Games read one at a time from a PGN stream, so files of any size can be
+/// walked with only the current game in memory. A game that fails to parse
+/// comes out as an `Err` naming its number and first line, and reading
+/// carries on with the next one.
+///
+/// ```
+/// use ananke::pgn::PgnReader;
+///
+/// let text = "[Event \"a\"]\n\n1. e4 e5 1-0\n\n[Event \"b\"]\n\n1. e4 Ke7?? 0-1\n\n1. d4 *\n";
+/// let games: Vec<_> = PgnReader::new(text.as_bytes()).collect();
+/// assert_eq!(games.len(), 3);
+/// assert_eq!(games[0].as_ref().unwrap().mainline().len(), 2);
+/// assert!(games[1].as_ref().is_err_and(|e| e.starts_with("game 2 (line 5)")));
+/// assert_eq!(games[2].as_ref().unwrap().mainline().len(), 1);
+/// ```
+pub struct PgnReader<R> {
+    input: R,
+    line: usize,
+    games: usize,
+    // a line read past the end of the previous game
+    pending: Option<String>,
+    failed: bool,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(input: R) -> Self {
+        PgnReader {
+            input,
+            line: 0,
+            games: 0,
+            pending: None,
+            failed: false,
+        }
+    }
+
+    /// Games handed out so far, good or bad.
+    pub fn games_read(&self) -> usize {
+        self.games
+    }
+
+    // bytes rather than read_line, so a stray non-UTF-8 byte only spoils
+    // the game it is in instead of stopping the whole file
+    fn next_line(&mut self) -> std::io::Result<Option<String>> {
+        if let Some(line) = self.pending.take() {
+            return Ok(Some(line));
+        }
+        let mut bytes = Vec::new();
+        if self.input.read_until(b'\n', &mut bytes)? == 0 {
+            return Ok(None);
+        }
+        self.line += 1;
+        let line = String::from_utf8_lossy(&bytes);
+        Ok(Some(line.trim_start_matches('\u{feff}').to_string()))
+    }
+
+    // the text of the next game and the line it starts on, or None at the end
+    fn next_game_text(&mut self) -> std::io::Result<Option<(String, usize)>> {
+        let mut text = String::new();
+        let mut first_line = 0;
+        let mut scan = MovetextScan::default();
+        let mut in_movetext = false;
+
+        while let Some(line) = self.next_line()? {
+            // "%" lines are an escape mechanism, ignored by readers
+            if line.starts_with('%') {
+                continue;
+            }
+            let is_tag = !scan.in_comment && line.trim_start().starts_with('[');
+            if is_tag && in_movetext {
+                // tags of the next game: this one never got a result
+                self.pending = Some(line);
+                break;
+            }
+            if text.is_empty() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                first_line = self.line;
+            }
+            text.push_str(&line);
+            if !is_tag && !line.trim().is_empty() {
+                in_movetext = true;
+                if scan.ends_game(&line) {
+                    break;
+                }
+            }
+        }
+        Ok((!text.is_empty()).then_some((text, first_line)))
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<Pgn, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let (text, first_line) = match self.next_game_text() {
+            Ok(Some(game)) => game,
+            Ok(None) => return None,
+            Err(e) => {
+                // a read error won't go away by trying again
+                self.failed = true;
+                return Some(Err(format!("read error after line {}: {}", self.line, e)));
+            }
+        };
+        self.games += 1;
+        let games = self.games;
+        Some(Pgn::parse(&text).map_err(|e| format!("game {} (line {}): {}", games, first_line, e)))
+    }
+}
+
+// just enough of the movetext grammar to spot the termination marker:
+// results inside comments or variations don't count
+#[derive(Default)]
+struct MovetextScan {
+    in_comment: bool,
+    depth: usize,
+}
+
+impl MovetextScan {
+    // does this line finish the game, i.e. is its last word outside comments
+    // and variations a result?
+    fn ends_game(&mut self, line: &str) -> bool {
+        let mut last = String::new();
+        let mut word = String::new();
+        for c in line.chars() {
+            if self.in_comment {
+                self.in_comment = c != '}';
+                continue;
+            }
+            if !c.is_whitespace() && !"{}();".contains(c) {
+                word.push(c);
+                continue;
+            }
+            if self.depth == 0 && !word.is_empty() {
+                last = std::mem::take(&mut word);
+            }
+            word.clear();
+            match c {
+                '{' => self.in_comment = true,
+                ';' => break,
+                '(' => self.depth += 1,
+                ')' => self.depth = self.depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        if self.depth == 0 && !word.is_empty() {
+            last = word;
+        }
+        ["1-0", "0-1", "1/2-1/2", "*"].contains(&last.as_str())
+    }
+}
+
+fn result_str(result: Option<GameResult>) -> &'static str {
+    match result {
+        Some(GameResult::WhiteWins) => "1-0",
+        Some(GameResult::BlackWins) => "0-1",
+        Some(GameResult::Draw) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+impl fmt::Display for Pgn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let custom_start = self.start.to_fen() != START_FEN;
+        for (name, value) in &self.tags {
+            // written below from the actual position and result
+            if name == "Result" || (custom_start && (name == "FEN" || name == "SetUp")) {
+                continue;
+            }
+            writeln!(f, "[{} \"{}\"]", name, escape(value))?;
+        }
+        writeln!(f, "[Result \"{}\"]", result_str(self.result))?;
+        if custom_start {
+            writeln!(f, "[SetUp \"1\"]")?;
+            writeln!(f, "[FEN \"{}\"]", self.start.to_fen())?;
+        }
+        writeln!(f)?;
+
+        let mut tokens = Vec::new();
+        write_line(&mut tokens, &self.start, &self.moves);
+        tokens.push(result_str(self.result).to_string());
+
+        // wrap at 80 columns; no space just inside parentheses
+        let mut line = String::new();
+        for token in tokens {
+            let glue = line.is_empty() || line.ends_with('(') || token == ")";
+            if !glue && line.len() + 1 + token.len() > 80 {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            } else if !glue {
+                line.push(' ');
+            }
+            line.push_str(&token);
+            // a rest-of-line comment has to end the line
+            if token.starts_with(';') {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            }
+        }
+        if !line.is_empty() {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// brace comments can't contain '}', so those are written as ';' comments
+fn comment_token(comment: &str) -> String {
+    if comment.contains('}') {
+        format!("; {}", comment)
+    } else {
+        format!("{{{}}}", comment)
+    }
+}
+
+// one line of moves being written, and how far through it the writer is
+struct LineWriter<'a> {
+    board: Board,
+    moves: &'a [PgnMove],
+    next: usize,
+    // the variations of `moves[next]` written so far, once the move itself is
+    variations: Option<usize>,
+    // black moves need "N..." after anything that interrupts the move pair
+    need_number: bool,
+}
+
+impl<'a> LineWriter<'a> {
+    fn new(board: Board, moves: &'a [PgnMove]) -> Self {
+        LineWriter {
+            board,
+            moves,
+            next: 0,
+            variations: None,
+            need_number: true,
+        }
+    }
+}
+
+// with a stack of lines rather than recursion, so no nesting depth can
+// overflow the call stack
+fn write_line(tokens: &mut Vec<String>, start: &Board, moves: &[PgnMove]) {
+    let mut stack = vec![LineWriter::new(start.clone(), moves)];
+
+    while let Some(line) = stack.last_mut() {
+        let Some(pm) = line.moves.get(line.next) else {
+            stack.pop();
+            if let Some(parent) = stack.last_mut() {
+                tokens.push(")".to_string());
+                parent.need_number = true;
+            }
+            continue;
+        };
+        match line.variations {
+            Some(i) if i < pm.variations.len() => {
+                line.variations = Some(i + 1);
+                tokens.push("(".to_string());
+                let variation = LineWriter::new(line.board.clone(), &pm.variations[i]);
+                stack.push(variation);
+            }
+            Some(_) => {
+                line.board = line.board.make_move(pm.mv);
+                line.next += 1;
+                line.variations = None;
+            }
+            None => {
+                if let Some(comment) = &pm.comment_before {
+                    tokens.push(comment_token(comment));
+                    line.need_number = true;
+                }
+                if line.board.side_to_move == Color::White {
+                    tokens.push(format!("{}.", line.board.fullmove_number));
+                } else if line.need_number {
+                    tokens.push(format!("{}...", line.board.fullmove_number));
+                }
+                tokens.push(san::to_san(&line.board, pm.mv));
+                for nag in &pm.nags {
+                    tokens.push(format!("${}", nag));
+                }
+                line.need_number = false;
+                if let Some(comment) = &pm.comment {
+                    tokens.push(comment_token(comment));
+                    line.need_number = true;
+                }
+                line.variations = Some(0);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Open,
+    Close,
+    Nag(u8),
+    Result(Option<GameResult>),
+    San(String),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            '[' => {
+                chars.next();
+                let mut inside = String::new();
+                let mut quoted = false;
+                loop {
+                    match chars.next() {
+                        Some('\\') if quoted => inside.extend(chars.next()),
+                        Some('"') => quoted = !quoted,
+                        Some(']') if !quoted => break,
+                        Some(c) => inside.push(c),
+                        None => return Err("Unterminated tag".to_string()),
+                    }
+                }
+                let (name, value) = inside
+                    .trim()
+                    .split_once(char::is_whitespace)
+                    .filter(|(name, _)| name.chars().all(|c| c.is_alphanumeric() || c == '_'))
+                    .ok_or_else(|| format!("Invalid tag: [{}]", inside))?;
+                tokens.push(Token::Tag(name.to_string(), value.trim().to_string()));
+            }
+            '{' => {
+                chars.next();
+                // line breaks are only where the text happened to wrap, and
+                // a comment written back as ';' couldn't keep them anyway
+                let comment: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                tokens.push(Token::Comment(comment.trim().replace(['\r', '\n'], " ")));
+            }
+            ';' => {
+                chars.next();
+                let comment: String = chars.by_ref().take_while(|&c| c != '\n').collect();
+                tokens.push(Token::Comment(comment.trim().replace('\r', " ")));
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '$' => {
+                chars.next();
+                let digits: String =
+                    std::iter::from_fn(|| chars.next_if(char::is_ascii_digit)).collect();
+                let nag = digits
+                    .parse()
+                    .map_err(|_| format!("Invalid NAG: ${}", digits))?;
+                tokens.push(Token::Nag(nag));
+            }
+            // a stray closer would otherwise never be consumed
+            '}' | ']' => return Err(format!("Unexpected '{}'", c)),
+            _ => {
+                let word: String = std::iter::from_fn(|| {
+                    chars.next_if(|c| !c.is_whitespace() && !"{}()[];$".contains(*c))
+                })
+                .collect();
+                push_word(&mut tokens, &word)?;
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+// a bare word: result, move number, or SAN with optional "!?" suffix
+fn push_word(tokens: &mut Vec<Token>, word: &str) -> Result<(), String> {
+    let result = match word {
+        "1-0" => Some(Some(GameResult::WhiteWins)),
+        "0-1" => Some(Some(GameResult::BlackWins)),
+        "1/2-1/2" => Some(Some(GameResult::Draw)),
+        "*" => Some(None),
+        _ => None,
+    };
+    if let Some(result) = result {
+        tokens.push(Token::Result(result));
+        return Ok(());
+    }
+
+    // "12." and "12..." stand alone, but "12.e4" happens too
+    let word = word.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+    if word.is_empty() {
+        return Ok(());
+    }
+
+    let san = word.trim_end_matches(['!', '?']);
+    let suffix = &word[san.len()..];
+    tokens.push(Token::San(san.to_string()));
+    let nag = match suffix {
+        "" => return Ok(()),
+        "!" => 1,
+        "?" => 2,
+        "!!" => 3,
+        "??" => 4,
+        "!?" => 5,
+        "?!" => 6,
+        _ => return Err(format!("Invalid annotation: {}", suffix)),
+    };
+    tokens.push(Token::Nag(nag));
+    Ok(())
+}
+
+// far beyond any real annotation, and shallow enough that the recursion
+// below can't run out of stack
+const MAX_VARIATION_DEPTH: usize = 64;
+
+fn parse_line(
+    tokens: &mut std::iter::Peekable<std::vec::IntoIter<Token>>,
+    start: &Board,
+    depth: usize,
+    result: &mut Option<GameResult>,
+) -> Result<Vec<PgnMove>, String> {
+    let mut moves: Vec<PgnMove> = Vec::new();
+    let mut board = start.clone();
+    let mut before = start.clone();
+    let mut pending_comment = None;
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::San(text) => {
+                let mv = san::from_san(&board, &text)?;
+                let mut pm = PgnMove::new(mv);
+                pm.comment_before = pending_comment.take();
+                moves.push(pm);
+                before = board.clone();
+                board = board.make_move(mv);
+            }
+            Token::Nag(nag) => moves
+                .last_mut()
+                .ok_or("NAG before any move")?
+                .nags
+                .push(nag),
+            Token::Comment(text) => match moves.last_mut() {
+                Some(pm) => append_comment(&mut pm.comment, text),
+                None => append_comment(&mut pending_comment, text),
+            },
+            Token::Open => {
+                if moves.is_empty() {
+                    return Err("Variation before any move".to_string());
+                }
+                if depth >= MAX_VARIATION_DEPTH {
+                    return Err(format!(
+                        "Variations nested deeper than {}",
+                        MAX_VARIATION_DEPTH
+                    ));
+                }
+                let variation = parse_line(tokens, &before, depth + 1, result)?;
+                moves.last_mut().unwrap().variations.push(variation);
+            }
+            Token::Close if depth > 0 => return Ok(moves),
+            Token::Close => return Err("Unmatched ')'".to_string()),
+            Token::Result(r) if depth == 0 => {
+                *result = r;
+                return Ok(moves);
+            }
+            Token::Result(_) => return Err("Result inside a variation".to_string()),
+            Token::Tag(..) => return Err("Tag inside movetext".to_string()),
+        }
+    }
+
+    if depth > 0 {
+        return Err("Unterminated variation".to_string());
+    }
+    Ok(moves)
+}
+
+// comments in a row run together, a space apart; an empty one adds nothing
+fn append_comment(slot: &mut Option<String>, text: String) {
+    match slot {
+        Some(comment) if !text.is_empty() => {
+            if !comment.is_empty() {
+                comment.push(' ');
+            }
+            comment.push_str(&text);
+        }
+        Some(_) => {}
+        None => *slot = Some(text),
+    }
+}
@@ -0,0 +1,38 @@
+use crate::board::Board;
+use crate::rng::Rng;
+use crate::types::Move;
+
+/// Play up to `plies` random legal moves from `start`. Captures and
+/// promotions are picked whenever the coin says so, which pushes games into
+/// material imbalances and promotion races that uniform playouts rarely
+/// reach. Stops early at mate or stalemate.
+pub fn random_playout(start: &Board, rng: &mut Rng, plies: usize) -> Board {
+    let mut board = start.clone();
+    for _ in 0..plies {
+        let moves = board.legal_moves();
+        if moves.is_empty() {
+            break;
+        }
+        let forcing: Vec<Move> = moves
+            .iter()
+            .copied()
+            .filter(|m| m.is_capture() || m.is_promotion())
+            .collect();
+        let m = if !forcing.is_empty() && rng.below(4) == 0 {
+            forcing[rng.below(forcing.len() as u32) as usize]
+        } else {
+            moves.as_slice()[rng.below(moves.len() as u32) as usize]
+        };
+        board = board.make_move(m);
+    }
+    board
+}
+
+/// A random position reachable from the starting position, between 0 and
+/// `max_plies` moves deep.
+pub fn random_position(rng: &mut Rng, max_plies: usize) -> Board {
+    let plies = rng.below(max_plies as u32 + 1) as usize;
+    let board = random_playout(&Board::startpos(), rng, plies);
+    debug_assert!(board.is_reachable_sanity(), "{}", board.to_fen());
+    board
+}
@@ -0,0 +1,187 @@
+//! Several engines analysing a queue of positions side by side, for batch
+//! and server work. Each engine has a thread, hash table and game of its
+//! own, for as long as the pool lasts. The positions are handed out in
+//! the order given, each to the first engine free to take one.
+
+use crate::board::Board;
+use crate::engine::{DEFAULT_DEPTH, Engine};
+use crate::search::{Limits, SearchResult};
+use crate::timeman;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// One position to analyse, how far, and for how many lines.
+#[derive(Clone)]
+pub struct Job {
+    pub board: Board,
+    pub limits: Limits,
+    /// The best this many moves, as MultiPV; 0 and 1 are the same.
+    pub lines: usize,
+}
+
+impl Job {
+    /// The best line of `board`, within `limits`.
+    pub fn new(board: Board, limits: Limits) -> Self {
+        Job {
+            board,
+            limits,
+            lines: 1,
+        }
+    }
+}
+
+/// The limits for analysing to `depth`, for `movetime` or for `nodes`,
+/// whichever comes first. With none of them it's `DEFAULT_DEPTH`; with a
+/// time or node limit and no depth, as deep as the limit allows.
+pub fn analysis_limits(
+    depth: Option<u8>,
+    movetime: Option<Duration>,
+    nodes: Option<u64>,
+) -> Limits {
+    let limited = movetime.is_some() || nodes.is_some();
+    Limits {
+        nodes,
+        time: movetime.map(|t| timeman::fixed_with_overhead(t, Duration::ZERO)),
+        ..Limits::depth(depth.unwrap_or(if limited { u8::MAX } else { DEFAULT_DEPTH }))
+    }
+}
+
+/// What came of a job: its lines, best first, or why there were none.
+pub type Analysis = Result<Vec<SearchResult>, String>;
+
+/// How a batch is going, as each of its positions is finished.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolProgress<'a> {
+    /// The finished position's place in the batch, from 0.
+    pub index: usize,
+    pub result: &'a Analysis,
+    /// Positions finished so far, this one included, and in all.
+    pub done: usize,
+    pub total: usize,
+    /// Nodes searched for the batch so far.
+    pub nodes: u64,
+}
+
+// a job, where it came in the batch, and where its result goes
+type Queued = (Job, usize, Sender<(usize, Analysis)>);
+
+pub struct EnginePool {
+    jobs: Option<Sender<Queued>>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl EnginePool {
+    /// `engines` new engines, at least one.
+    pub fn new(engines: usize) -> Self {
+        Self::with(engines, Engine::new)
+    }
+
+    /// `engines` engines as `make` builds them, each on its own thread.
+    pub fn with(engines: usize, make: impl Fn() -> Engine + Send + Sync + 'static) -> Self {
+        let (jobs, queue) = mpsc::channel::<Queued>();
+        // whichever engine is free takes the next job, so the queue is shared
+        let queue = Arc::new(Mutex::new(queue));
+        let make = Arc::new(make);
+        let threads = (0..engines.max(1))
+            .map(|_| {
+                let (queue, make) = (queue.clone(), make.clone());
+                thread::spawn(move || serve(make(), &queue))
+            })
+            .collect();
+        EnginePool {
+            jobs: Some(jobs),
+            threads,
+        }
+    }
+
+    pub fn engines(&self) -> usize {
+        self.threads.len()
+    }
+
+    /// Analyse every job, telling `progress` of each as it's finished, and
+    /// return the results in the jobs' order. A job fails if its position
+    /// has the side not to move in check. A position with no legal moves
+    /// has no lines.
+    ///
+    /// ```
+    /// use ananke::board::Board;
+    /// use ananke::pool::{EnginePool, Job};
+    /// use ananke::search::Limits;
+    ///
+    /// let pool = EnginePool::new(2);
+    /// let jobs = ["6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "k7/8/8/8/8/8/1R6/7K b - - 0 1"]
+    ///     .map(|fen| Job::new(Board::from_fen(fen).unwrap(), Limits::depth(3)));
+    /// let mut done = Vec::new();
+    /// let results = pool.analyse(jobs.to_vec(), &mut |p| done.push(p.done));
+    /// assert_eq!(done, [1, 2]);
+    /// let best = |i: usize| results[i].as_ref().unwrap()[0].best_move.unwrap().to_string();
+    /// assert_eq!((best(0), best(1)), ("a1a8".to_string(), "a8a7".to_string()));
+    /// ```
+    pub fn analyse(&self, jobs: Vec<Job>, progress: &mut dyn FnMut(PoolProgress)) -> Vec<Analysis> {
+        let total = jobs.len();
+        let (sent, finished) = mpsc::channel();
+        let queue = self.jobs.as_ref().expect("the pool is running");
+        for (index, job) in jobs.into_iter().enumerate() {
+            queue
+                .send((job, index, sent.clone()))
+                .expect("the pool's engines are running");
+        }
+        drop(sent);
+        let mut results: Vec<Option<Analysis>> = vec![None; total];
+        let mut nodes = 0;
+        for (done, (index, result)) in finished.iter().enumerate() {
+            // a line's nodes are the total so far, so the last has them all
+            if let Some(last) = result.as_ref().ok().and_then(|lines| lines.last()) {
+                nodes += last.nodes;
+            }
+            progress(PoolProgress {
+                index,
+                result: &result,
+                done: done + 1,
+                total,
+                nodes,
+            });
+            results[index] = Some(result);
+        }
+        results
+            .into_iter()
+            .map(|r| r.expect("an engine stopped without answering"))
+            .collect()
+    }
+}
+
+impl Drop for EnginePool {
+    fn drop(&mut self) {
+        // with the queue closed each engine finishes its job and stops
+        self.jobs = None;
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+// one engine's thread: jobs until the queue is closed
+fn serve(mut engine: Engine, queue: &Mutex<Receiver<Queued>>) {
+    loop {
+        let next = queue.lock().unwrap().recv();
+        let Ok((job, index, results)) = next else {
+            return;
+        };
+        let result = engine.set_position(job.board, "").map(|_| {
+            if job.lines > 1 {
+                engine.think_multipv(job.limits, job.lines, &mut |_, _| {})
+            } else {
+                let result = engine.think(job.limits);
+                result
+                    .best_move
+                    .is_some()
+                    .then_some(result)
+                    .into_iter()
+                    .collect()
+            }
+        });
+        let _ = results.send((index, result));
+    }
+}
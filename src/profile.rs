@@ -0,0 +1,151 @@
+//! Lightweight section profiler for bench runs. With the `profile` feature,
+//! `scope()` guards record exclusive ("self") time per section: while a nested
+//! section runs, its parent's clock is paused. Without the feature the guards
+//! are zero-sized and compile away.
+
+use std::time::Duration;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Section {
+    MoveGen,
+    MakeMove,
+    Attacks,
+    LegalCount,
+}
+
+impl Section {
+    pub const ALL: [Section; 4] = [
+        Section::MoveGen,
+        Section::MakeMove,
+        Section::Attacks,
+        Section::LegalCount,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Section::MoveGen => "movegen",
+            Section::MakeMove => "make_move",
+            Section::Attacks => "attack checks",
+            Section::LegalCount => "legal counting",
+        }
+    }
+}
+
+#[cfg(feature = "profile")]
+mod imp {
+    use super::Section;
+    use std::cell::RefCell;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::Instant;
+
+    pub static NANOS: [AtomicU64; 4] = [const { AtomicU64::new(0) }; 4];
+    pub static CALLS: [AtomicU64; 4] = [const { AtomicU64::new(0) }; 4];
+
+    struct Stack {
+        sections: Vec<Section>,
+        since: Instant,
+    }
+
+    thread_local! {
+        static STACK: RefCell<Stack> = RefCell::new(Stack {
+            sections: Vec::new(),
+            since: Instant::now(),
+        });
+    }
+
+    // charge the time since the last switch to whatever section is on top
+    fn switch(stack: &mut Stack) {
+        let now = Instant::now();
+        if let Some(top) = stack.sections.last() {
+            let nanos = now.duration_since(stack.since).as_nanos() as u64;
+            NANOS[*top as usize].fetch_add(nanos, Ordering::Relaxed);
+        }
+        stack.since = now;
+    }
+
+    pub fn enter(section: Section) {
+        CALLS[section as usize].fetch_add(1, Ordering::Relaxed);
+        STACK.with(|s| {
+            let mut stack = s.borrow_mut();
+            switch(&mut stack);
+            stack.sections.push(section);
+        });
+    }
+
+    pub fn exit() {
+        STACK.with(|s| {
+            let mut stack = s.borrow_mut();
+            switch(&mut stack);
+            stack.sections.pop();
+        });
+    }
+}
+
+/// Guard returned by `scope`; the section ends when it is dropped.
+#[must_use]
+pub struct Scope(());
+
+#[cfg(feature = "profile")]
+impl Drop for Scope {
+    fn drop(&mut self) {
+        imp::exit();
+    }
+}
+
+/// Start timing `section` until the returned guard goes out of scope.
+#[inline(always)]
+pub fn scope(_section: Section) -> Scope {
+    #[cfg(feature = "profile")]
+    imp::enter(_section);
+    Scope(())
+}
+
+/// Clear all counters, e.g. before a bench run.
+pub fn reset() {
+    #[cfg(feature = "profile")]
+    for i in 0..Section::ALL.len() {
+        imp::NANOS[i].store(0, std::sync::atomic::Ordering::Relaxed);
+        imp::CALLS[i].store(0, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// Percentage breakdown of `wall` time across sections. Time not spent in
+/// any section (the perft loop itself, bookkeeping) is reported as "other".
+pub fn report(wall: Duration) -> String {
+    #[cfg(feature = "profile")]
+    {
+        use std::fmt::Write;
+        use std::sync::atomic::Ordering;
+
+        let wall_ns = wall.as_nanos().max(1) as f64;
+        let mut out = String::new();
+        let mut accounted = 0u64;
+        for section in Section::ALL {
+            let nanos = imp::NANOS[section as usize].load(Ordering::Relaxed);
+            let calls = imp::CALLS[section as usize].load(Ordering::Relaxed);
+            accounted += nanos;
+            let _ = writeln!(
+                out,
+                "{:<16} {:>6.1}%  {:>8.3}s  {:>12} calls",
+                section.name(),
+                100.0 * nanos as f64 / wall_ns,
+                nanos as f64 / 1e9,
+                calls
+            );
+        }
+        let other = wall.as_nanos().saturating_sub(accounted as u128) as f64;
+        let _ = writeln!(
+            out,
+            "{:<16} {:>6.1}%  {:>8.3}s",
+            "other",
+            100.0 * other / wall_ns,
+            other / 1e9
+        );
+        out
+    }
+    #[cfg(not(feature = "profile"))]
+    {
+        let _ = wall;
+        "profiling disabled, rebuild with --features profile\n".to_string()
+    }
+}
@@ -0,0 +1,181 @@
+use crate::bitboard::Square;
+use crate::board::Board;
+use crate::types::{Color, Move, PieceType};
+use std::fmt::Write;
+
+/// How pieces are drawn on the board.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PieceSet {
+    /// Unicode chess glyphs (♔♕♖...), needs a font with chess symbols.
+    Unicode,
+    /// Plain FEN letters (K, q, ...), renders anywhere.
+    Letters,
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub square_size: u32,
+    pub light_square: &'static str,
+    pub dark_square: &'static str,
+    pub piece_set: PieceSet,
+    /// Draw the board from Black's side.
+    pub flipped: bool,
+    /// Highlight the from/to squares of this move.
+    pub last_move: Option<Move>,
+    /// Highlight the king of the side to move when it is in check.
+    pub highlight_check: bool,
+    pub coordinates: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            square_size: 45,
+            light_square: "#f0d9b5",
+            dark_square: "#b58863",
+            piece_set: PieceSet::Unicode,
+            flipped: false,
+            last_move: None,
+            highlight_check: true,
+            coordinates: true,
+        }
+    }
+}
+
+const LAST_MOVE_COLOR: &str = "#cdd26a";
+const CHECK_COLOR: &str = "#e8534a";
+
+/// Render a position as a standalone SVG document.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::render::{PieceSet, RenderOptions, to_svg};
+///
+/// let options = RenderOptions {
+///     piece_set: PieceSet::Letters,
+///     ..RenderOptions::default()
+/// };
+/// let svg = to_svg(&Board::startpos(), &options);
+/// assert!(svg.starts_with("<svg"));
+/// assert!(svg.trim_end().ends_with("</svg>"));
+/// ```
+pub fn to_svg(board: &Board, options: &RenderOptions) -> String {
+    let size = options.square_size;
+    let board_px = size * 8;
+    let mut svg = String::new();
+
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{0}" viewBox="0 0 {0} {0}">"#,
+        board_px
+    );
+
+    let check_sq = if options.highlight_check && board.in_check() {
+        Some(board.get_king_square(board.side_to_move))
+    } else {
+        None
+    };
+
+    for index in 0..64u8 {
+        let sq = Square::new(index);
+        let (x, y) = square_origin(sq, options);
+
+        let mut fill = if (sq.rank() + sq.file()).is_multiple_of(2) {
+            options.dark_square
+        } else {
+            options.light_square
+        };
+        if let Some(m) = options.last_move
+            && (m.from() == sq || m.to() == sq)
+        {
+            fill = LAST_MOVE_COLOR;
+        }
+        if check_sq == Some(sq) {
+            fill = CHECK_COLOR;
+        }
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{size}" height="{size}" fill="{}"/>"#,
+            x, y, fill
+        );
+
+        if let Some((color, pt)) = board.piece_at(sq) {
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}" font-size="{}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                x + size / 2,
+                y + size / 2,
+                size * 4 / 5,
+                glyph(color, pt, options.piece_set)
+            );
+        }
+    }
+
+    if options.coordinates {
+        let font = size / 5;
+        for i in 0..8u8 {
+            let file = if options.flipped { 7 - i } else { i };
+            let rank = if options.flipped { i } else { 7 - i };
+            let _ = writeln!(
+                svg,
+                r##"<text x="{}" y="{}" font-size="{font}" fill="#555555">{}</text>"##,
+                i as u32 * size + size - font,
+                board_px - 2,
+                (b'a' + file) as char
+            );
+            let _ = writeln!(
+                svg,
+                r##"<text x="2" y="{}" font-size="{font}" fill="#555555">{}</text>"##,
+                i as u32 * size + font,
+                rank + 1
+            );
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// top-left pixel of a square, honoring board orientation
+fn square_origin(sq: Square, options: &RenderOptions) -> (u32, u32) {
+    let (col, row) = if options.flipped {
+        (7 - sq.file(), sq.rank())
+    } else {
+        (sq.file(), 7 - sq.rank())
+    };
+    (
+        col as u32 * options.square_size,
+        row as u32 * options.square_size,
+    )
+}
+
+fn glyph(color: Color, pt: PieceType, set: PieceSet) -> char {
+    let white = color == Color::White;
+    match set {
+        PieceSet::Unicode => match (pt, white) {
+            (PieceType::King, true) => '♔',
+            (PieceType::Queen, true) => '♕',
+            (PieceType::Rook, true) => '♖',
+            (PieceType::Bishop, true) => '♗',
+            (PieceType::Knight, true) => '♘',
+            (PieceType::Pawn, true) => '♙',
+            (PieceType::King, false) => '♚',
+            (PieceType::Queen, false) => '♛',
+            (PieceType::Rook, false) => '♜',
+            (PieceType::Bishop, false) => '♝',
+            (PieceType::Knight, false) => '♞',
+            (PieceType::Pawn, false) => '♟',
+        },
+        PieceSet::Letters => {
+            let c = match pt {
+                PieceType::Pawn => 'p',
+                PieceType::Knight => 'n',
+                PieceType::Bishop => 'b',
+                PieceType::Rook => 'r',
+                PieceType::Queen => 'q',
+                PieceType::King => 'k',
+            };
+            if white { c.to_ascii_uppercase() } else { c }
+        }
+    }
+}
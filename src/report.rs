@@ -0,0 +1,155 @@
+use crate::board::{Board, START_FEN};
+use crate::eco;
+use crate::metrics;
+use crate::san;
+use crate::types::{Color, Move};
+use std::fmt::Write;
+
+/// Engine verdict on one played move. Evals are centipawns from White's
+/// point of view, before and after the move.
+#[derive(Debug, Copy, Clone)]
+pub struct MoveAnalysis {
+    pub played: Move,
+    pub best: Move,
+    pub eval_before: i32,
+    pub eval_after: i32,
+}
+
+impl MoveAnalysis {
+    /// Centipawns the mover gave away compared to the position before the move.
+    pub fn loss(&self, mover: Color) -> i32 {
+        let swing = self.eval_before - self.eval_after;
+        let loss = if mover == Color::White { swing } else { -swing };
+        loss.max(0)
+    }
+}
+
+/// Losses at or above these thresholds are called out in the report.
+pub const INACCURACY: i32 = 50;
+pub const MISTAKE: i32 = 100;
+pub const BLUNDER: i32 = 300;
+
+// evals beyond this are drawn as the top/bottom of the graph
+const GRAPH_CLAMP: i32 = 500;
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Eval graph as a one-line sparkline, one character per move.
+///
+/// ```
+/// use ananke::report::sparkline;
+///
+/// // level, then White pulls ahead, then Black swings it right round
+/// assert_eq!(sparkline(&[0, 250, 900, -900]), "▅▆█▁");
+/// ```
+pub fn sparkline(evals: &[i32]) -> String {
+    evals
+        .iter()
+        .map(|&cp| {
+            let clamped = cp.clamp(-GRAPH_CLAMP, GRAPH_CLAMP) + GRAPH_CLAMP;
+            let idx = (clamped * (SPARKS.len() as i32 - 1) + GRAPH_CLAMP) / (2 * GRAPH_CLAMP);
+            SPARKS[idx as usize]
+        })
+        .collect()
+}
+
+/// Markdown summary of an analysed game starting from `start`: eval graph, a
+/// list of inaccuracies, mistakes and blunders with the engine's preferred move,
+/// and accuracy per player.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::report::{MoveAnalysis, analysis_report};
+///
+/// let start = Board::startpos();
+/// let e4 = start.parse_uci_move("e2e4").unwrap();
+/// let after = start.make_move(e4);
+/// let f6 = after.parse_uci_move("f7f6").unwrap();
+/// let e5 = after.parse_uci_move("e7e5").unwrap();
+/// let analysis = [
+///     MoveAnalysis { played: e4, best: e4, eval_before: 20, eval_after: 30 },
+///     MoveAnalysis { played: f6, best: e5, eval_before: 30, eval_after: 150 },
+/// ];
+/// let report = analysis_report(&start, &analysis);
+/// assert!(report.contains("1... f6 (mistake, -120 cp), best was e5"));
+/// ```
+pub fn analysis_report(start: &Board, analysis: &[MoveAnalysis]) -> String {
+    let mut out = String::new();
+    let evals: Vec<i32> = analysis.iter().map(|a| a.eval_after).collect();
+
+    if start.to_fen() == START_FEN {
+        let moves: Vec<Move> = analysis.iter().map(|a| a.played).collect();
+        if let Some((code, name)) = eco::classify_opening(&moves) {
+            let _ = writeln!(out, "**Opening:** {} {}\n", code, name);
+        }
+    }
+
+    let _ = writeln!(out, "## Evaluation\n");
+    let _ = writeln!(out, "`{}`\n", sparkline(&evals));
+
+    let _ = writeln!(out, "## Errors\n");
+    let mut board = start.clone();
+    // count plies as if White moved first, so move numbers line up
+    let first_ply = if start.side_to_move == Color::Black {
+        1
+    } else {
+        0
+    };
+    let mut errors = 0;
+    for (ply_count, a) in (first_ply..).zip(analysis) {
+        let mover = board.side_to_move;
+        let loss = a.loss(mover);
+        if loss >= INACCURACY {
+            let label = if loss >= BLUNDER {
+                "blunder"
+            } else if loss >= MISTAKE {
+                "mistake"
+            } else {
+                "inaccuracy"
+            };
+            let number = ply_count / 2 + 1;
+            let dots = if mover == Color::White { "." } else { "..." };
+            let _ = writeln!(
+                out,
+                "- {}{} {} ({}, -{} cp), best was {}",
+                number,
+                dots,
+                san::to_san(&board, a.played),
+                label,
+                loss,
+                san::to_san(&board, a.best)
+            );
+            errors += 1;
+        }
+        board = board.make_move(a.played);
+    }
+    if errors == 0 {
+        let _ = writeln!(out, "No errors found.");
+    }
+
+    let _ = writeln!(out, "\n## Accuracy\n");
+    for color in [Color::White, Color::Black] {
+        let evals = player_evals(start, analysis, color);
+        let _ = writeln!(
+            out,
+            "- {:?}: {:.1}% accuracy, {:.0} average centipawn loss",
+            color,
+            metrics::game_accuracy(&evals),
+            metrics::average_centipawn_loss(&evals)
+        );
+    }
+    out
+}
+
+// (before, after) pairs for one player's moves, flipped to that player's view
+fn player_evals(start: &Board, analysis: &[MoveAnalysis], color: Color) -> Vec<(i32, i32)> {
+    let mut mover = start.side_to_move;
+    let mut evals = Vec::new();
+    for a in analysis {
+        if mover == color {
+            let sign = if color == Color::White { 1 } else { -1 };
+            evals.push((sign * a.eval_before, sign * a.eval_after));
+        }
+        mover = mover.opposite();
+    }
+    evals
+}
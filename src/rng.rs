@@ -0,0 +1,51 @@
+/// Seed used for magic finding and anything else that doesn't ask for one.
+pub const DEFAULT_SEED: u32 = 1804289383;
+
+/// Xorshift32 generator. Cheap and fully deterministic, so anything random
+/// (magics, hash keys, playouts) can be reproduced from its seed.
+#[derive(Debug, Clone)]
+pub struct Rng(u32);
+
+impl Default for Rng {
+    fn default() -> Self {
+        Rng::new(DEFAULT_SEED)
+    }
+}
+
+impl Rng {
+    pub fn new(seed: u32) -> Self {
+        // xorshift never leaves an all-zero state
+        Rng(if seed == 0 { DEFAULT_SEED } else { seed })
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let n1 = (self.next_u32() as u64) & 0xFFFF;
+        let n2 = (self.next_u32() as u64) & 0xFFFF;
+        let n3 = (self.next_u32() as u64) & 0xFFFF;
+        let n4 = (self.next_u32() as u64) & 0xFFFF;
+        n1 | (n2 << 16) | (n3 << 32) | (n4 << 48)
+    }
+
+    /// Random number with few bits set (good magic candidates).
+    pub fn sparse_u64(&mut self) -> u64 {
+        self.next_u64() & self.next_u64() & self.next_u64()
+    }
+
+    /// Uniform-ish value in 0..n. n must be non-zero.
+    pub fn below(&mut self, n: u32) -> u32 {
+        ((self.next_u32() as u64 * n as u64) >> 32) as u32
+    }
+
+    /// Independent generator seeded from this one, so subsystems can each
+    /// own a stream while everything still derives from one seed.
+    pub fn fork(&mut self) -> Rng {
+        Rng::new(self.next_u32())
+    }
+}
@@ -0,0 +1,155 @@
+use crate::board::Board;
+use crate::types::{Move, MoveFlag, PieceType};
+
+/// Write a legal move in Standard Algebraic Notation: "Nbd7", "exd5", "e8=Q+",
+/// "O-O", "Qh4#".
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::san;
+///
+/// let board = Board::startpos();
+/// let m = board.parse_uci_move("g1f3").unwrap();
+/// assert_eq!(san::to_san(&board, m), "Nf3");
+/// ```
+pub fn to_san(board: &Board, m: Move) -> String {
+    let mut san = String::new();
+
+    match m.flag() {
+        MoveFlag::KingCastle => san.push_str("O-O"),
+        MoveFlag::QueenCastle => san.push_str("O-O-O"),
+        _ => {
+            let piece_type = board
+                .get_piece_type_at(m.from(), board.side_to_move)
+                .expect("SAN for a move with no piece");
+
+            if piece_type == PieceType::Pawn {
+                // pawn captures name the file they came from
+                if m.is_capture() {
+                    san.push((b'a' + m.from().file()) as char);
+                    san.push('x');
+                }
+                san.push_str(&m.to().to_string());
+                if let Some(promo) = m.promotion_piece() {
+                    san.push('=');
+                    san.push(piece_letter(promo));
+                }
+            } else {
+                san.push(piece_letter(piece_type));
+                san.push_str(&disambiguation(board, m, piece_type));
+                if m.is_capture() {
+                    san.push('x');
+                }
+                san.push_str(&m.to().to_string());
+            }
+        }
+    }
+
+    let next = board.make_move(m);
+    if next.in_check() {
+        san.push(if next.count_legal_moves() > 0 {
+            '+'
+        } else {
+            '#'
+        });
+    }
+    san
+}
+
+/// Write a line of moves played one after another from `board` in SAN.
+/// Every move must be legal where it's played.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::san;
+///
+/// let board = Board::startpos();
+/// let line: Vec<_> = ["e2e4", "e7e5", "g1f3"]
+///     .iter()
+///     .scan(board.clone(), |b, text| {
+///         let m = b.parse_uci_move(text).unwrap();
+///         *b = b.make_move(m);
+///         Some(m)
+///     })
+///     .collect();
+/// assert_eq!(san::line_to_san(&board, &line), ["e4", "e5", "Nf3"]);
+/// ```
+pub fn line_to_san(board: &Board, line: &[Move]) -> Vec<String> {
+    let mut board = board.clone();
+    line.iter()
+        .map(|&m| {
+            let san = to_san(&board, m);
+            board = board.make_move(m);
+            san
+        })
+        .collect()
+}
+
+/// Read a move in SAN. Check marks, annotation suffixes ("!?"), "0-0"
+/// castling and a missing "=" before the promotion piece are accepted.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::san;
+///
+/// let board = Board::startpos();
+/// let m = san::from_san(&board, "e4!").unwrap();
+/// assert_eq!(m.to_string(), "e2e4");
+/// assert!(san::from_san(&board, "e5").is_err());
+/// ```
+pub fn from_san(board: &Board, text: &str) -> Result<Move, String> {
+    let wanted = normalize(text);
+    board
+        .legal_moves()
+        .iter()
+        .copied()
+        .find(|m| normalize(&to_san(board, *m)) == wanted)
+        .ok_or_else(|| format!("Illegal or unknown SAN move: {}", text))
+}
+
+fn normalize(san: &str) -> String {
+    san.trim_end_matches(['+', '#', '!', '?'])
+        .replace('0', "O")
+        .replace('=', "")
+}
+
+// Extra file/rank needed when another piece of the same type can also
+// legally reach the target square.
+fn disambiguation(board: &Board, m: Move, piece_type: PieceType) -> String {
+    let us = board.side_to_move;
+    let rivals: Vec<Move> = board
+        .legal_moves()
+        .iter()
+        .copied()
+        .filter(|other| {
+            other.to() == m.to()
+                && other.from() != m.from()
+                && board.get_piece_type_at(other.from(), us) == Some(piece_type)
+        })
+        .collect();
+
+    let from = m.from();
+    let file = ((b'a' + from.file()) as char).to_string();
+    let rank = (from.rank() + 1).to_string();
+
+    if rivals.is_empty() {
+        String::new()
+    } else if rivals.iter().all(|r| r.from().file() != from.file()) {
+        file
+    } else if rivals.iter().all(|r| r.from().rank() != from.rank()) {
+        rank
+    } else {
+        file + &rank
+    }
+}
+
+fn piece_letter(piece_type: PieceType) -> char {
+    match piece_type {
+        PieceType::Pawn => 'P',
+        PieceType::Knight => 'N',
+        PieceType::Bishop => 'B',
+        PieceType::Rook => 'R',
+        PieceType::Queen => 'Q',
+        PieceType::King => 'K',
+    }
+}
@@ -0,0 +1,1071 @@
+//! Negamax alpha-beta search with iterative deepening, and a captures-only
+//! quiescence search at the leaves so that the eval isn't taken in the
+//! middle of an exchange. It's fail-soft: a node that fails high or low
+//! returns the best score it actually saw, beyond the window, rather than
+//! the window's edge, which makes for tighter bounds in the table.
+//!
+//! In the last few plies above the quiescence search, positions whose
+//! static eval is far above beta are cut off without a search (reverse
+//! futility), and quiet moves that can't plausibly raise alpha, or that
+//! come late in the ordering, are skipped (futility and late move pruning).
+//! All three go by whether the side to move's static eval is improving on
+//! what it was two plies before: an improving position is cut off above
+//! beta more readily, and has fewer of its quiet moves skipped.
+//!
+//! A few moves are searched a ply deeper than the rest, as set in `params`:
+//! pawn pushes to the seventh rank and, when turned on, recaptures. The
+//! `stats` counters say how often they fire.
+//!
+//! The engine doesn't call it directly but goes through the `Searcher`
+//! trait, so other algorithms (the tree search in `mcts`, or anything
+//! experimental) can be swapped in at runtime, with the same limits and
+//! progress reports.
+
+use crate::bitboard::Bitboard;
+use crate::board::{Board, UndoInfo};
+use crate::eval::{Evaluator, Hce};
+use crate::movegen::MoveGenerator;
+use crate::movepick::{MovePicker, order_score};
+use crate::params::Params;
+use crate::stats::SearchStats;
+use crate::timeman::{NodesClock, TimeBudget, TimeSource, WallClock};
+use crate::tree::{Outcome, SearchTree};
+use crate::tt::{Bound, TranspositionTable, TtEntry};
+use crate::types::{Color, Move, MoveList, PieceType};
+use crate::values;
+use std::cmp::Reverse;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Larger than any score a search can return.
+pub const INFINITY: i32 = 32_000;
+/// Score for delivering mate right now; mate in n plies scores `MATE - n`.
+pub const MATE: i32 = 31_000;
+const MAX_PLY: i32 = 256;
+// the most plies a move can be extended by, as the params allow
+const MAX_EXTENSION: i32 = 2;
+// delta pruning's margin, widening from the middlegame to the endgame, where
+// king activity and passed pawns swing the eval further than a capture
+const DELTA_MARGIN_MIDGAME: i32 = 200;
+const DELTA_MARGIN_ENDGAME: i32 = 500;
+// the static eval of a node in check, which has none
+const NO_EVAL: i32 = i32::MIN;
+// root moves are reported as they're started once the search has run this
+// long, and the current line, when asked for, at most this often
+const CURRENT_MOVE_AFTER: Duration = Duration::from_secs(1);
+const CURRENT_LINE_EVERY: Duration = Duration::from_secs(1);
+
+/// How far above what a capture wins the quiescence search still looks,
+/// tapered by game phase from 200 centipawns to 500 in a bare endgame.
+pub fn delta_margin(board: &Board) -> i32 {
+    let phase = values::phase(board);
+    (DELTA_MARGIN_MIDGAME * phase + DELTA_MARGIN_ENDGAME * (values::MAX_PHASE - phase))
+        / values::MAX_PHASE
+}
+
+/// Delta pruning: can quiescence pass over `m` without playing it, because
+/// even taking the piece (and promoting) for free, plus `delta_margin`,
+/// leaves the side to move short of `alpha`?
+pub fn is_futile(board: &Board, m: Move, stand_pat: i32, alpha: i32) -> bool {
+    let mut gain = 0;
+    if m.is_capture() {
+        gain += board
+            .get_piece_type_at(m.to(), board.side_to_move.opposite())
+            .map_or(values::PAWN, values::piece_value);
+    }
+    if let Some(pt) = m.promotion_piece() {
+        gain += values::promotion_gain(pt);
+    }
+    stand_pat + gain + delta_margin(board) < alpha
+}
+
+/// Is `score` a forced mate, for either side?
+pub fn is_mate_score(score: i32) -> bool {
+    score.abs() >= MATE - MAX_PLY
+}
+
+/// For a mate score, the moves to the mate, negative when the side to
+/// move is the one getting mated, as UCI reports it.
+pub fn mate_in_moves(score: i32) -> Option<i32> {
+    let plies = MATE - score.abs();
+    is_mate_score(score).then(|| score.signum() * (plies + 1) / 2)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    /// None only when the side to move has no legal moves.
+    pub best_move: Option<Move>,
+    /// The line the search expects, starting with `best_move`. Searchers
+    /// that don't track one give just the move, or nothing without one.
+    pub pv: Vec<Move>,
+    /// Centipawns from the side to move's point of view.
+    pub score: i32,
+    /// Deepest iteration that finished, 0 if none did.
+    pub depth: u8,
+    pub nodes: u64,
+    /// Every root move and the effort that went into it, best first, for
+    /// searchers that keep them; empty for the others.
+    pub root_moves: Vec<RootMove>,
+    /// What the search's heuristics did along the way.
+    pub stats: SearchStats,
+}
+
+/// What a searcher tells its `info` callback while it runs.
+#[derive(Debug, Clone, Copy)]
+pub enum Progress<'a> {
+    /// A better idea of the best move, as when an iteration finishes.
+    Result(&'a SearchResult),
+    /// The root move being started on, the `number`th of the iteration to
+    /// `depth`, counting from 1. Only once the search has run for a second,
+    /// so that short searches don't flood the GUI.
+    CurrMove { mv: Move, number: usize, depth: u8 },
+    /// The line from the root being searched right now, about once a
+    /// second, when `Limits::current_line` asks for it.
+    CurrLine(&'a [Move]),
+}
+
+/// One root move, with what the search has found out about it so far.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootMove {
+    pub mv: Move,
+    /// Nodes searched below it, over every iteration.
+    pub nodes: u64,
+    /// Its score in the last iteration that searched it, `-INFINITY` before
+    /// any did. Only an upper bound, as `bound` says, for a move that
+    /// turned out no better than one searched before it.
+    pub score: i32,
+    pub bound: Bound,
+    /// The line it leads to, when its score is exact; otherwise just it.
+    pub pv: Vec<Move>,
+}
+
+impl RootMove {
+    pub fn new(mv: Move) -> Self {
+        RootMove {
+            mv,
+            nodes: 0,
+            score: -INFINITY,
+            bound: Bound::Upper,
+            pv: vec![mv],
+        }
+    }
+}
+
+/// Search `board` to `depth` plies and return the best move found.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::search::{search, MATE};
+///
+/// // back rank mate
+/// let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+/// let result = search(&board, 2);
+/// assert_eq!(result.best_move.unwrap().to_string(), "a1a8");
+/// assert_eq!(result.score, MATE - 1);
+/// ```
+pub fn search(board: &Board, depth: u8) -> SearchResult {
+    search_with(board, Limits::depth(depth))
+}
+
+/// How far a search may go. It deepens one ply at a time up to `depth`, and
+/// stops early once `nodes` have been searched or the time budget is spent:
+/// no new iteration starts once half the optimum time is gone, and the
+/// search is cut off at the maximum, or as soon as `stop` is set.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    pub depth: u8,
+    pub nodes: Option<u64>,
+    pub time: Option<TimeBudget>,
+    /// Draws score `-contempt` for the side to move at the root, and
+    /// `contempt` for the other side.
+    pub contempt: i32,
+    /// Measure time in nodes rather than on the wall clock: this many
+    /// nodes count as a millisecond. Both `time` and the elapsed time are
+    /// then virtual.
+    pub nodes_time: Option<u64>,
+    /// Set from another thread to end the search early, as UCI's `stop`
+    /// does. The result is the same as running out of time there.
+    pub stop: Option<Arc<AtomicBool>>,
+    /// Reproducible mode: `time` is ignored, so that only the depth and
+    /// node limits end the search, and the same position and limits give
+    /// the same result every time, on any machine. A `stop` still ends it.
+    pub deterministic: bool,
+    /// Root moves to leave out, as if they weren't legal: the best of the
+    /// rest is the second-best plan, say, once the best is excluded. With
+    /// every legal move excluded there's nothing to search, and the result
+    /// has no best move and a score of `-INFINITY`.
+    pub excluded: Vec<Move>,
+    /// Report the line being searched as it goes, as UCI_ShowCurrLine
+    /// does, for searchers that have one.
+    pub current_line: bool,
+}
+
+impl Limits {
+    pub fn depth(depth: u8) -> Self {
+        Limits {
+            depth,
+            nodes: None,
+            time: None,
+            contempt: 0,
+            nodes_time: None,
+            stop: None,
+            deterministic: false,
+            excluded: Vec::new(),
+            current_line: false,
+        }
+    }
+
+    /// Reproducible: as deep as `depth` and `nodes` allow, and nothing else.
+    pub fn deterministic(depth: u8, nodes: Option<u64>) -> Self {
+        Limits {
+            nodes,
+            deterministic: true,
+            ..Limits::depth(depth)
+        }
+    }
+
+    /// The same limits, with `moves` left out at the root as well.
+    ///
+    /// ```
+    /// use ananke::board::Board;
+    /// use ananke::search::{Limits, search_with};
+    ///
+    /// // the rook mates on a8; what else is there?
+    /// let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    /// let mate = search_with(&board, Limits::depth(2)).best_move.unwrap();
+    /// assert_eq!(mate.to_string(), "a1a8");
+    /// let second = search_with(&board, Limits::depth(2).excluding(&[mate]));
+    /// assert_ne!(second.best_move, Some(mate));
+    /// assert!(second.root_moves.iter().all(|r| r.mv != mate));
+    /// ```
+    pub fn excluding(mut self, moves: &[Move]) -> Self {
+        self.excluded.extend_from_slice(moves);
+        self
+    }
+
+    /// Is `m` one of the root moves this search leaves out?
+    pub fn is_excluded(&self, m: Move) -> bool {
+        self.excluded.contains(&m)
+    }
+
+    /// The time the search may take, or None when it isn't timed, which
+    /// includes deterministic mode.
+    pub fn time_budget(&self) -> Option<TimeBudget> {
+        self.time.filter(|_| !self.deterministic)
+    }
+
+    /// As deep as `nodes` allows.
+    pub fn nodes(nodes: u64) -> Self {
+        Limits {
+            nodes: Some(nodes),
+            ..Limits::depth(u8::MAX)
+        }
+    }
+
+    /// As deep as `time` allows.
+    pub fn time(time: TimeBudget) -> Self {
+        Limits {
+            time: Some(time),
+            ..Limits::depth(u8::MAX)
+        }
+    }
+
+    /// The clock a search starting now measures `time` on.
+    pub fn clock(&self) -> Box<dyn TimeSource> {
+        match self.nodes_time {
+            Some(nodes_per_ms) => Box::new(NodesClock { nodes_per_ms }),
+            None => Box::new(WallClock::start()),
+        }
+    }
+}
+
+/// A search algorithm. It gets the position, the limits and the evaluation
+/// to score positions with (calling its hooks as `Evaluator` describes),
+/// hands `info` a result whenever it has a better idea of the best move,
+/// along with whatever other `Progress` it keeps track of, and returns its
+/// final answer. Reports and result alike are from the side to move's point
+/// of view.
+pub trait Searcher {
+    fn search(
+        &mut self,
+        board: &Board,
+        limits: Limits,
+        evaluator: &mut dyn Evaluator,
+        info: &mut dyn FnMut(Progress),
+    ) -> SearchResult;
+
+    /// Short name for logs and the UCI `info string`.
+    fn name(&self) -> &str;
+
+    /// Resize the hash table to `mb` megabytes, for searchers that keep
+    /// one. Returns the size now in use, 0 for the ones that don't.
+    fn set_hash_size(&mut self, _mb: usize) -> usize {
+        0
+    }
+
+    /// Forget whatever earlier searches left behind, for a new game.
+    fn clear(&mut self) {}
+
+    /// Search with `params` from now on, for searchers that have any.
+    fn set_params(&mut self, _params: &Params) {}
+}
+
+/// The alpha-beta search in this module, as a `Searcher`. It reports each
+/// iteration as it finishes, and the current move and line as described
+/// for `Progress`; it keeps its transposition table from one
+/// search to the next until it's cleared.
+#[derive(Default)]
+pub struct AlphaBeta {
+    tt: TranspositionTable,
+    params: Params,
+}
+
+impl AlphaBeta {
+    /// With a table of `tt::DEFAULT_HASH_MB`, and the default parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn tt(&self) -> &TranspositionTable {
+        &self.tt
+    }
+
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+}
+
+impl Searcher for AlphaBeta {
+    fn search(
+        &mut self,
+        board: &Board,
+        limits: Limits,
+        evaluator: &mut dyn Evaluator,
+        info: &mut dyn FnMut(Progress),
+    ) -> SearchResult {
+        iterative_deepening(
+            board,
+            limits,
+            evaluator,
+            &mut self.tt,
+            self.params,
+            info,
+            None,
+        )
+    }
+
+    fn name(&self) -> &str {
+        "alphabeta"
+    }
+
+    fn set_hash_size(&mut self, mb: usize) -> usize {
+        self.tt.resize(mb);
+        self.tt.size_mb()
+    }
+
+    fn clear(&mut self) {
+        self.tt.clear();
+    }
+
+    fn set_params(&mut self, params: &Params) {
+        self.params = *params;
+    }
+}
+
+/// Iterative deepening within `limits`. The result comes from the deepest
+/// iteration that finished; if the budget runs out before even depth 1 is
+/// done, the best move so far (or simply the first one) is still returned,
+/// so there is a move whenever the position has one.
+pub fn search_with(board: &Board, limits: Limits) -> SearchResult {
+    search_with_evaluator(board, limits, &mut Hce::default())
+}
+
+/// `search_with`, scoring positions with `evaluator` instead of the
+/// built-in evaluation. Each call starts with an empty table.
+pub fn search_with_evaluator(
+    board: &Board,
+    limits: Limits,
+    evaluator: &mut dyn Evaluator,
+) -> SearchResult {
+    AlphaBeta::new().search(board, limits, evaluator, &mut |_| {})
+}
+
+/// `search_with`, keeping the tree of the last iteration that finished
+/// down to `max_ply` plies from the root, for debugging. Nothing below
+/// that is kept, and the quiescence search isn't either.
+pub fn search_tree(board: &Board, limits: Limits, max_ply: usize) -> (SearchResult, SearchTree) {
+    let mut tree = SearchTree::new(max_ply);
+    let mut searcher = AlphaBeta::new();
+    let result = iterative_deepening(
+        board,
+        limits,
+        &mut Hce::default(),
+        &mut searcher.tt,
+        searcher.params,
+        &mut |_| {},
+        Some(&mut tree),
+    );
+    (result, tree)
+}
+
+fn iterative_deepening(
+    board: &Board,
+    limits: Limits,
+    evaluator: &mut dyn Evaluator,
+    tt: &mut TranspositionTable,
+    params: Params,
+    info: &mut dyn FnMut(Progress),
+    tree: Option<&mut SearchTree>,
+) -> SearchResult {
+    tt.new_search();
+    let mut search = Search {
+        nodes: 0,
+        node_limit: limits.nodes.unwrap_or(u64::MAX),
+        time_limit: limits.time_budget().map(|t| t.maximum),
+        clock: limits.clock(),
+        stop: limits.stop.clone(),
+        stopped: false,
+        root_side: board.side_to_move,
+        contempt: limits.contempt,
+        evaluator,
+        tt,
+        params,
+        root_depth: 0,
+        stats: SearchStats::default(),
+        killers: [[None; 2]; MAX_PLY as usize],
+        static_evals: [NO_EVAL; MAX_PLY as usize],
+        pv: (0..MAX_PLY)
+            .map(|_| Vec::with_capacity(MAX_PLY as usize))
+            .collect(),
+        line: Vec::with_capacity(MAX_PLY as usize),
+        next_line_report: limits.current_line.then_some(CURRENT_LINE_EVERY),
+        info,
+        tree,
+    };
+    search.evaluator.reset(board);
+    let mut legal: Vec<Move> = ordered_moves(board)
+        .iter()
+        .copied()
+        .filter(|m| !board.make_move(*m).opponent_in_check())
+        .collect();
+    let any_legal = !legal.is_empty();
+    legal.retain(|&m| !limits.is_excluded(m));
+    // an earlier search's best move here goes first from the start: this
+    // position's own from the last time it was searched, or what the last
+    // search expected once the opponent had replied
+    if let Some(m) = search
+        .tt
+        .probe(board.hash)
+        .and_then(|entry| entry.best_move)
+        && let Some(i) = legal.iter().position(|&r| r == m)
+    {
+        legal[..=i].rotate_right(1);
+    }
+    let Some(&first) = legal.first() else {
+        return SearchResult {
+            best_move: None,
+            pv: Vec::new(),
+            score: if any_legal {
+                -INFINITY
+            } else {
+                search.no_moves_score(board, 0)
+            },
+            depth: 0,
+            nodes: 0,
+            root_moves: Vec::new(),
+            stats: SearchStats::default(),
+        };
+    };
+
+    let mut best = (first, search.evaluator.evaluate(board));
+    if !board.in_check() {
+        search.static_evals[0] = best.1;
+    }
+    let mut best_pv = vec![first];
+    let mut completed = 0;
+    let mut root_moves: Vec<RootMove> = legal.into_iter().map(RootMove::new).collect();
+    // as they were when the last iteration finished
+    let mut best_root_moves = root_moves.clone();
+    // iterations in a row that have ended on the same best move
+    let mut unchanged = 0;
+    // the search makes and unmakes its moves on this copy
+    let mut scratch = board.clone();
+    for depth in 1..=limits.depth.max(1) {
+        let (m, score) = search.root(&mut scratch, &mut root_moves, depth);
+        if search.stopped {
+            // a partial iteration only counts if nothing finished before it,
+            // and only if it got through at least one move
+            if depth == 1 && score > -INFINITY {
+                best = (m, score);
+                best_pv.clone_from(&search.pv[0]);
+                best_root_moves = root_moves;
+            } else if let Some(tree) = search.tree.as_deref_mut() {
+                tree.abandon();
+            }
+            break;
+        }
+        unchanged = if m == best.0 { unchanged + 1 } else { 0 };
+        best = (m, score);
+        best_pv.clone_from(&search.pv[0]);
+        completed = depth;
+        // with moves left out, this isn't the position's own score
+        if limits.excluded.is_empty() {
+            search.store(board, Some(m), score, depth, 0, Bound::Exact);
+        }
+        // search the last best move first next time, then the others by
+        // the effort they took: the ones that were hardest to refute are
+        // the likeliest to turn out best
+        let i = root_moves.iter().position(|r| r.mv == m).unwrap();
+        root_moves[..=i].rotate_right(1);
+        root_moves[1..].sort_by_key(|r| Reverse(r.nodes));
+        best_root_moves.clone_from(&root_moves);
+        (search.info)(Progress::Result(&SearchResult {
+            best_move: Some(m),
+            pv: best_pv.clone(),
+            score,
+            depth,
+            nodes: search.nodes,
+            root_moves: best_root_moves.clone(),
+            stats: search.stats,
+        }));
+        // a forced mate won't get any shorter by searching deeper
+        if is_mate_score(score) {
+            break;
+        }
+        if let Some(t) = limits.time_budget() {
+            let elapsed = search.clock.elapsed(search.nodes);
+            // the next iteration takes several times longer than this one,
+            // so it would likely be cut off anyway
+            if elapsed >= t.optimum / 2 {
+                break;
+            }
+            // an easy move: settled for a while, and the others refuted so
+            // quickly that it took nearly all the effort
+            if unchanged >= EASY_MOVE_ITERATIONS
+                && is_easy_move(&root_moves)
+                && elapsed >= t.optimum / EASY_MOVE_TIME_DIVISOR
+            {
+                break;
+            }
+        }
+    }
+
+    SearchResult {
+        best_move: Some(best.0),
+        pv: best_pv,
+        score: best.1,
+        depth: completed,
+        nodes: search.nodes,
+        root_moves: best_root_moves,
+        stats: search.stats,
+    }
+}
+
+// an easy move has been best for this many iterations in a row, and taken
+// this share of the root's nodes; it's played once the optimum time over
+// the divisor is gone
+const EASY_MOVE_ITERATIONS: u32 = 4;
+const EASY_MOVE_PERCENT: u64 = 90;
+const EASY_MOVE_TIME_DIVISOR: u32 = 8;
+
+// the first move, the best, took nearly all the nodes
+fn is_easy_move(root_moves: &[RootMove]) -> bool {
+    let total: u64 = root_moves.iter().map(|r| r.nodes).sum();
+    root_moves[0].nodes * 100 >= total * EASY_MOVE_PERCENT
+}
+
+struct Search<'a> {
+    nodes: u64,
+    node_limit: u64,
+    time_limit: Option<Duration>,
+    clock: Box<dyn TimeSource>,
+    stop: Option<Arc<AtomicBool>>,
+    stopped: bool,
+    root_side: Color,
+    contempt: i32,
+    evaluator: &'a mut dyn Evaluator,
+    tt: &'a mut TranspositionTable,
+    params: Params,
+    // the depth of the iteration being searched
+    root_depth: u8,
+    stats: SearchStats,
+    // quiet moves that last caused a cutoff at each ply
+    killers: [[Option<Move>; 2]; MAX_PLY as usize],
+    // static eval of the node at each ply on the current line, NO_EVAL in
+    // check, to tell whether the side to move is improving
+    static_evals: [i32; MAX_PLY as usize],
+    // triangular PV table: row `ply` is the best line found so far from
+    // the node being searched at that ply, built from the row below it
+    pv: Vec<Vec<Move>>,
+    // the moves from the root to the node being searched
+    line: Vec<Move>,
+    // when to report `line` next, if it's reported at all
+    next_line_report: Option<Duration>,
+    info: &'a mut dyn FnMut(Progress),
+    // the nodes near the root, when they're being kept
+    tree: Option<&'a mut SearchTree>,
+}
+
+impl Search<'_> {
+    // make and unmake, keeping the evaluator in step
+    fn make(&mut self, board: &mut Board, m: Move) -> UndoInfo {
+        let undo = board.make_move_in_place(m);
+        self.evaluator.on_make_move(board, m, &undo);
+        self.line.push(m);
+        undo
+    }
+
+    fn unmake(&mut self, board: &mut Board, m: Move, undo: UndoInfo) {
+        board.unmake_move(m, undo);
+        self.evaluator.on_unmake_move();
+        self.line.pop();
+    }
+
+    // best of the (legal, ordered) root moves at `depth`, adding up the
+    // nodes each one takes and keeping its score
+    fn root(&mut self, board: &mut Board, moves: &mut [RootMove], depth: u8) -> (Move, i32) {
+        self.root_depth = depth;
+        if let Some(tree) = self.tree.as_deref_mut() {
+            tree.start(depth);
+        }
+        let mut best = (moves[0].mv, -INFINITY);
+        for (i, root_move) in moves.iter_mut().enumerate() {
+            let m = root_move.mv;
+            if self.clock.elapsed(self.nodes) >= CURRENT_MOVE_AFTER {
+                (self.info)(Progress::CurrMove {
+                    mv: m,
+                    number: i + 1,
+                    depth,
+                });
+            }
+            let before = self.nodes;
+            let undo = self.make(board, m);
+            let score = -self.negamax(board, depth - 1, 1, -INFINITY, -best.1);
+            self.unmake(board, m, undo);
+            root_move.nodes += self.nodes - before;
+            if self.stopped {
+                break;
+            }
+            root_move.score = score;
+            if score > best.1 {
+                best = (m, score);
+                self.update_pv(0, m);
+                root_move.bound = Bound::Exact;
+                root_move.pv.clone_from(&self.pv[0]);
+            } else {
+                root_move.bound = Bound::Upper;
+                root_move.pv.truncate(1);
+            }
+        }
+        if let Some(tree) = self.tree.as_deref_mut() {
+            tree.leave(0, best.1);
+        }
+        best
+    }
+
+    // `m` is the new best move at `ply`: the line is it and then the best
+    // line found below it
+    fn update_pv(&mut self, ply: i32, m: Move) {
+        let (above, below) = self.pv.split_at_mut(ply as usize + 1);
+        let line = &mut above[ply as usize];
+        line.clear();
+        line.push(m);
+        line.extend_from_slice(&below[0]);
+    }
+
+    // out of nodes or time: unwind, and let the caller throw away the scores.
+    // The clock is only read every 1024 nodes, which is also when the
+    // current line is reported, if it's due
+    fn out_of_budget(&mut self) -> bool {
+        if self.nodes >= self.node_limit {
+            self.stopped = true;
+        }
+        if self.nodes.is_multiple_of(1024) {
+            let elapsed = self.clock.elapsed(self.nodes);
+            if let Some(next) = self.next_line_report
+                && elapsed >= next
+            {
+                self.next_line_report = Some(elapsed + CURRENT_LINE_EVERY);
+                (self.info)(Progress::CurrLine(&self.line));
+            }
+            let out_of_time = self.time_limit.is_some_and(|limit| elapsed >= limit);
+            let told_to = self
+                .stop
+                .as_ref()
+                .is_some_and(|s| s.load(Ordering::Relaxed));
+            if out_of_time || told_to {
+                self.stopped = true;
+            }
+        }
+        self.stopped
+    }
+
+    fn negamax(&mut self, board: &mut Board, depth: u8, ply: i32, alpha: i32, beta: i32) -> i32 {
+        let Some(tree) = self.tree.as_deref_mut() else {
+            return self.node(board, depth, ply, alpha, beta);
+        };
+        tree.enter(ply as usize, self.line.last().copied(), depth, alpha, beta);
+        let score = self.node(board, depth, ply, alpha, beta);
+        if let Some(tree) = self.tree.as_deref_mut() {
+            tree.leave(ply as usize, score);
+        }
+        score
+    }
+
+    // when the tree is being kept, what became of the node at `ply`
+    fn note(&mut self, ply: i32, outcome: Outcome) {
+        if let Some(tree) = self.tree.as_deref_mut() {
+            tree.note(ply as usize, outcome);
+        }
+    }
+
+    fn node(&mut self, board: &mut Board, depth: u8, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+        // the line below here, if any, is only for the node before
+        self.pv[ply as usize].clear();
+        if depth == 0 {
+            self.note(ply, Outcome::Quiescence);
+            return self.quiesce(board, ply, alpha, beta);
+        }
+        if self.out_of_budget() {
+            return 0;
+        }
+        self.nodes += 1;
+        // mate on the hundredth ply still wins
+        if board.halfmove_clock >= 100 {
+            if board.in_check() && board.legal_moves().is_empty() {
+                self.note(ply, Outcome::NoMoves);
+                return -MATE + ply;
+            }
+            self.note(ply, Outcome::Draw);
+            return self.draw_score(board);
+        }
+
+        // a result from the table that settles this node without a search:
+        // deep enough, and outside the window, so the line through here
+        // doesn't matter. One inside the window is searched again for its PV
+        let entry = self.tt.probe(board.hash);
+        if let Some(entry) = entry
+            && entry.depth >= depth
+        {
+            let score = score_from_tt(entry.score, ply);
+            let settled = match entry.bound {
+                Bound::Exact => score >= beta || score <= alpha,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if settled {
+                self.note(ply, Outcome::Table);
+                return score;
+            }
+        }
+
+        let in_check = board.in_check();
+        let eval = if in_check {
+            NO_EVAL
+        } else {
+            self.evaluator.evaluate(board)
+        };
+        self.static_evals[ply as usize] = eval;
+        let improving = self.is_improving(ply);
+        // pruning is for positions with an eval to go by, and scores that
+        // aren't mates, which a margin says nothing about
+        let params = &self.params;
+        let depth_left = depth as i32;
+        let can_prune = depth_left <= params.pruning_depth && !in_check && !is_mate_score(beta);
+        let margin_plies = depth_left - improving as i32;
+
+        // reverse futility: so far above beta that a move is sure to keep it
+        // there, unless some tactic below the horizon says otherwise
+        if can_prune && eval - params.reverse_futility_margin * margin_plies >= beta {
+            self.note(ply, Outcome::ReverseFutility);
+            return eval;
+        }
+        let futile =
+            can_prune && eval + params.futility_margin * (depth_left + improving as i32) <= alpha;
+        let late_move_count = if depth_left <= params.late_move_depth {
+            late_move_count(params, depth_left, improving)
+        } else {
+            i32::MAX
+        };
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        let mut any_legal = false;
+        let mut quiets_searched = 0;
+        let tt_move = entry.and_then(|entry| entry.best_move);
+        let mut picker = MovePicker::new(board, tt_move, self.killers[ply as usize]);
+        while let Some(m) = picker.next(board) {
+            let quiet = !m.is_capture() && !m.is_promotion();
+            let undo = self.make(board, m);
+            if board.opponent_in_check() {
+                self.unmake(board, m, undo);
+                continue;
+            }
+            any_legal = true;
+            let extension = self.extension(board, m, depth, ply);
+            // skip a quiet move that doesn't give check and isn't extended,
+            // once a move that doesn't lose to mate has been found: when it
+            // can't bring the eval up to alpha, or when enough quiet moves
+            // have gone before
+            if can_prune
+                && quiet
+                && extension == 0
+                && !board.in_check()
+                && best_score > -(MATE - MAX_PLY)
+                && (futile || quiets_searched >= late_move_count)
+            {
+                if let Some(tree) = self.tree.as_deref_mut() {
+                    let child = ply as usize + 1;
+                    tree.enter(child, Some(m), depth - 1, -beta, -alpha);
+                    tree.note(child, Outcome::Pruned);
+                }
+                self.unmake(board, m, undo);
+                continue;
+            }
+            if quiet {
+                quiets_searched += 1;
+            }
+            let score = -self.negamax(board, depth - 1 + extension, ply + 1, -beta, -alpha);
+            self.unmake(board, m, undo);
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                if quiet {
+                    self.store_killer(ply, m);
+                }
+                self.store(board, Some(m), score, depth, ply, Bound::Lower);
+                return score;
+            }
+            if score > best_score {
+                best_score = score;
+                if score > alpha {
+                    alpha = score;
+                    best_move = Some(m);
+                    self.update_pv(ply, m);
+                }
+            }
+        }
+
+        if !any_legal {
+            self.note(ply, Outcome::NoMoves);
+            let score = self.no_moves_score(board, ply);
+            self.store(board, None, score, depth, ply, Bound::Exact);
+            return score;
+        }
+        let bound = if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.store(board, best_move, best_score, depth, ply, bound);
+        best_score
+    }
+
+    // plies to search `m`, just made at a node `depth` from the leaves and
+    // `ply` from the root, beyond the one less than the node's depth: for a
+    // recapture on the square of the capture before it, and for a pawn push
+    // to the seventh rank. Only up to twice the iteration's depth from the
+    // root, so a long line of them can't run on, and never past the last
+    // ply there's room for
+    fn extension(&mut self, board: &Board, m: Move, depth: u8, ply: i32) -> u8 {
+        if ply >= 2 * self.root_depth as i32 || ply + depth as i32 + MAX_EXTENSION >= MAX_PLY {
+            return 0;
+        }
+        // the line ends with `m`, so the move before it is one back
+        let previous = self.line.len().checked_sub(2).map(|i| self.line[i]);
+        if self.params.recapture_extension > 0
+            && m.is_capture()
+            && previous.is_some_and(|p| p.is_capture() && p.to() == m.to())
+        {
+            self.stats.recapture_extensions += 1;
+            return self.params.recapture_extension as u8;
+        }
+        let us = board.side_to_move.opposite();
+        let seventh = if us == Color::White { 6 } else { 1 };
+        if self.params.pawn_push_extension > 0
+            && !m.is_promotion()
+            && m.to().rank() == seventh
+            && board.piece_at(m.to()) == Some((us, PieceType::Pawn))
+        {
+            self.stats.pawn_push_extensions += 1;
+            return self.params.pawn_push_extension as u8;
+        }
+        0
+    }
+
+    // is the side to move's eval better than two plies ago? Taken to be
+    // when there's nothing to compare with, two plies ago being in check or
+    // before the root. Never in check, though nothing is pruned there
+    fn is_improving(&self, ply: i32) -> bool {
+        let eval = self.static_evals[ply as usize];
+        let before = if ply >= 2 {
+            self.static_evals[ply as usize - 2]
+        } else {
+            NO_EVAL
+        };
+        eval != NO_EVAL && (before == NO_EVAL || eval > before)
+    }
+
+    fn store(
+        &mut self,
+        board: &Board,
+        m: Option<Move>,
+        score: i32,
+        depth: u8,
+        ply: i32,
+        bound: Bound,
+    ) {
+        self.tt.store(
+            board.hash,
+            TtEntry {
+                best_move: m,
+                score: score_to_tt(score, ply),
+                depth,
+                bound,
+            },
+        );
+    }
+
+    // only captures and promotions, until the position is quiet. The side to
+    // move can always "stand pat" on the static eval instead of taking,
+    // except in check, where every evasion is searched, and in stalemate,
+    // where there is nothing to stand on: a capture that leaves the other
+    // side no moves is a draw, not the material it wins. Counting moves at
+    // every node would cost a full generation, so stalemate is only looked
+    // for once the side to move is down to king and pawns; walling in
+    // pieces as well is rare enough to leave to the main search.
+    fn quiesce(&mut self, board: &mut Board, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+        if self.out_of_budget() {
+            return 0;
+        }
+        self.nodes += 1;
+        let in_check = board.in_check();
+        let mut stand_pat = -INFINITY;
+        if !in_check {
+            if only_king_and_pawns(board) && board.count_legal_moves() == 0 {
+                return self.draw_score(board);
+            }
+            stand_pat = self.evaluator.evaluate(board);
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+        }
+        let mut best_score = stand_pat;
+        // losing captures never come out of the quiescence picker
+        let mut picker = if in_check {
+            MovePicker::new(board, None, [None; 2])
+        } else {
+            MovePicker::quiescence()
+        };
+
+        let mut any_legal = false;
+        while let Some(m) = picker.next(board) {
+            if !in_check && is_futile(board, m, stand_pat, alpha) {
+                continue;
+            }
+            let undo = self.make(board, m);
+            if board.opponent_in_check() {
+                self.unmake(board, m, undo);
+                continue;
+            }
+            any_legal = true;
+            let score = -self.quiesce(board, ply + 1, -beta, -alpha);
+            self.unmake(board, m, undo);
+            if score >= beta {
+                return score;
+            }
+            best_score = best_score.max(score);
+            alpha = alpha.max(score);
+        }
+        if in_check && !any_legal {
+            return -MATE + ply;
+        }
+        best_score
+    }
+
+    fn store_killer(&mut self, ply: i32, m: Move) {
+        let killers = &mut self.killers[ply as usize];
+        if killers[0] != Some(m) {
+            killers[1] = killers[0];
+            killers[0] = Some(m);
+        }
+    }
+
+    // checkmated or stalemated: mates found sooner score higher for the winner
+    fn no_moves_score(&self, board: &Board, ply: i32) -> i32 {
+        if board.in_check() {
+            -MATE + ply
+        } else {
+            self.draw_score(board)
+        }
+    }
+
+    // a draw, as the side to move in `board` sees it
+    fn draw_score(&self, board: &Board) -> i32 {
+        if board.side_to_move == self.root_side {
+            -self.contempt
+        } else {
+            self.contempt
+        }
+    }
+}
+
+// mate scores count plies from the root in the search, but from the
+// position itself in the table, where they may be found again at another
+// ply or in a later search
+fn score_to_tt(score: i32, ply: i32) -> i32 {
+    if score >= MATE - MAX_PLY {
+        score + ply
+    } else if score <= -(MATE - MAX_PLY) {
+        score - ply
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: i32) -> i32 {
+    if score >= MATE - MAX_PLY {
+        score - ply
+    } else if score <= -(MATE - MAX_PLY) {
+        score + ply
+    } else {
+        score
+    }
+}
+
+// late move pruning: how many quiet moves are searched at `depth` before
+// the rest are skipped, half as many when the eval isn't improving
+fn late_move_count(params: &Params, depth: i32, improving: bool) -> i32 {
+    (params.late_move_base + params.late_move_per_ply_squared * depth * depth)
+        / (2 - improving as i32)
+}
+
+// the side to move has nothing but its king and pawns, the material where
+// a stalemate can come up in quiescence
+fn only_king_and_pawns(board: &Board) -> bool {
+    let us = board.side_to_move;
+    board.occupancy[us] == board.pieces(us, PieceType::King) | board.pieces(us, PieceType::Pawn)
+}
+
+// the root's moves, all generated and sorted up front as they're searched
+// over and over: captures and promotions first, most valuable victim then
+// least valuable attacker; quiet moves after them. In check only the
+// evasions are tried
+fn ordered_moves(board: &Board) -> MoveList {
+    let checkers = board.checkers();
+    let mut moves = if checkers == Bitboard::EMPTY {
+        MoveGenerator::new(board).generate_all()
+    } else {
+        MoveGenerator::new(board).generate_evasions(checkers)
+    };
+    // a stable sort, so that moves which score the same stay in generation
+    // order and ties are always broken the same way
+    moves
+        .as_mut_slice()
+        .sort_by_key(|m| -order_score(board, *m));
+    moves
+}
@@ -0,0 +1,102 @@
+use crate::bitboard::{Bitboard, Square};
+use crate::board::Board;
+use crate::types::{Color, Move, MoveFlag, PieceType};
+use crate::values;
+
+/// Static exchange evaluation: the material balance (from the mover's point of
+/// view) of the capture sequence started by `m` on its target square, assuming
+/// both sides always recapture with their least valuable attacker and may stop
+/// whenever continuing would lose material.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::see::see;
+///
+/// // the knight on e5 is defended by a pawn
+/// let board = Board::from_fen("4k3/8/3p4/4n3/3P4/8/8/4K3 w - - 0 1").unwrap();
+/// // pawn takes knight, pawn takes pawn: a knight for a pawn
+/// assert_eq!(see(&board, board.parse_uci_move("d4e5").unwrap()), 220);
+/// // a knight for a pawn the other way round
+/// let board = Board::from_fen("4k3/8/4p3/3p4/8/2N5/8/4K3 w - - 0 1").unwrap();
+/// assert_eq!(see(&board, board.parse_uci_move("c3d5").unwrap()), -220);
+/// ```
+pub fn see(board: &Board, m: Move) -> i32 {
+    let from = m.from();
+    let to = m.to();
+    let us = board.side_to_move;
+
+    let mover = board
+        .get_piece_type_at(from, us)
+        .expect("SEE on a move with no piece");
+
+    let mut occupancy = board.all_occupancy;
+    // one entry per capture; a (lenient FEN) board can hold far more than
+    // the 32 pieces a game starts with, so leave room for every square
+    let mut gain = [0i32; 64];
+
+    // what the first capture wins
+    gain[0] = if m.flag() == MoveFlag::EnPassant {
+        let cap_sq = if us == Color::White {
+            to as u8 - 8
+        } else {
+            to as u8 + 8
+        };
+        occupancy.clear_bit(Square::new(cap_sq));
+        values::PAWN
+    } else {
+        board
+            .get_piece_type_at(to, us.opposite())
+            .map_or(0, values::piece_value)
+    };
+
+    // the piece standing on the target square after the first capture
+    let mut on_square = values::piece_value(mover);
+    if let Some(promo) = m.promotion_piece() {
+        gain[0] += values::promotion_gain(promo);
+        on_square = values::piece_value(promo);
+    }
+
+    occupancy.clear_bit(from);
+    let mut side = us.opposite();
+    let mut depth = 0;
+
+    loop {
+        depth += 1;
+        // speculative: what `side` nets if it recaptures here. No cutting
+        // the exchange short when that is negative: the recapture can still
+        // lose less than standing pat, and the exact value matters to
+        // `see_ge`, not just its sign
+        gain[depth] = on_square - gain[depth - 1];
+
+        let attackers = board.attackers_to(to, occupancy) & occupancy;
+        let Some((sq, pt)) = least_valuable_attacker(board, attackers, side) else {
+            break;
+        };
+
+        occupancy.clear_bit(sq);
+        on_square = values::piece_value(pt);
+        side = side.opposite();
+    }
+
+    // negamax the gains back to the root
+    while depth > 1 {
+        depth -= 1;
+        gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+    }
+    gain[0]
+}
+
+/// Does `m` win at least `threshold` material according to SEE?
+pub fn see_ge(board: &Board, m: Move, threshold: i32) -> bool {
+    see(board, m) >= threshold
+}
+
+fn least_valuable_attacker(
+    board: &Board,
+    attackers: Bitboard,
+    side: Color,
+) -> Option<(Square, PieceType)> {
+    let pieces = &board.pieces[side];
+    // ALL runs cheapest first
+    PieceType::iter().find_map(|pt| (attackers & pieces[pt]).lsb_index().map(|sq| (sq, pt)))
+}
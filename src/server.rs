@@ -0,0 +1,160 @@
+//! Analysis over HTTP, for web apps that want it done server-side. One
+//! route: POST /analyse with a JSON object, answered with the analysis as
+//! `json::analysis` writes it. Each connection is served on its own
+//! thread and carries one request; the searches themselves share an
+//! `EnginePool`, so no more run at once than it has engines.
+//!
+//! The request: `fen` (required), and optionally `depth`, `movetime` in
+//! milliseconds, `nodes` and `multipv`, the limits as
+//! `pool::analysis_limits` takes them.
+
+use crate::board::Board;
+use crate::json::{self, Value};
+use crate::pool::{EnginePool, Job, analysis_limits};
+use crate::uci::MAX_MULTIPV;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+
+/// The longest `movetime` a request can have.
+pub const MAX_MOVETIME: Duration = Duration::from_secs(60);
+// requests are a FEN and a few numbers; anything much bigger isn't one
+const MAX_BODY: usize = 64 * 1024;
+
+/// Serve connections from `listener` until it fails.
+pub fn serve(listener: TcpListener, pool: &EnginePool) -> io::Result<()> {
+    thread::scope(|scope| {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            scope.spawn(move || {
+                let reader = BufReader::new(&stream);
+                // a client that goes away takes its answer with it
+                let _ = handle(reader, &stream, pool);
+            });
+        }
+        Ok(())
+    })
+}
+
+/// Read one request from `input` and answer it on `out`.
+pub fn handle(mut input: impl BufRead, mut out: impl Write, pool: &EnginePool) -> io::Result<()> {
+    let (status, body) = match read_request(&mut input) {
+        Ok((method, path, body)) => respond(&method, &path, &body, pool),
+        Err(e) => (400, error(&e)),
+    };
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        _ => "Method Not Allowed",
+    };
+    write!(
+        out,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    )?;
+    out.flush()
+}
+
+/// The status and JSON body answering `method` on `path` with `body`.
+///
+/// ```
+/// use ananke::pool::EnginePool;
+/// use ananke::server::respond;
+///
+/// let pool = EnginePool::new(1);
+/// let request = r#"{"fen": "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "depth": 3}"#;
+/// let (status, body) = respond("POST", "/analyse", request, &pool);
+/// assert_eq!(status, 200);
+/// assert!(body.contains(r#""bestmove":"a1a8""#));
+/// assert_eq!(respond("GET", "/analyse", "", &pool).0, 405);
+/// ```
+pub fn respond(method: &str, path: &str, body: &str, pool: &EnginePool) -> (u16, String) {
+    if path != "/analyse" && path != "/analyze" {
+        return (404, error(&format!("no such route: {}", path)));
+    }
+    if method != "POST" {
+        return (405, error("POST a JSON object to analyse"));
+    }
+    let (fen, job) = match parse_job(body) {
+        Ok(parsed) => parsed,
+        Err(e) => return (400, error(&e)),
+    };
+    let result = pool.analyse(vec![job], &mut |_| {}).remove(0);
+    match result {
+        Ok(lines) => (200, json::analysis(&fen, &lines)),
+        Err(e) => (400, error(&e)),
+    }
+}
+
+fn error(message: &str) -> String {
+    format!("{{\"error\":{}}}", json::string(message))
+}
+
+// the FEN as given, and the job it asks for
+fn parse_job(body: &str) -> Result<(String, Job), String> {
+    let (mut fen, mut depth, mut movetime, mut nodes, mut lines) = (None, None, None, None, 1);
+    for (name, value) in json::parse_object(body)? {
+        let number = |value: &Value| {
+            value
+                .as_u64()
+                .ok_or_else(|| format!("{} must be a whole number", name))
+        };
+        match name.as_str() {
+            "fen" => {
+                let text = value.as_str().ok_or("fen must be a string")?;
+                fen = Some(text.to_string());
+            }
+            "depth" => depth = Some(number(&value)?.clamp(1, u8::MAX as u64) as u8),
+            "movetime" => {
+                let ms = Duration::from_millis(number(&value)?);
+                movetime = Some(ms.min(MAX_MOVETIME));
+            }
+            "nodes" => nodes = Some(number(&value)?),
+            "multipv" => lines = (number(&value)? as usize).clamp(1, MAX_MULTIPV),
+            _ => return Err(format!("unknown field: {}", name)),
+        }
+    }
+    let fen = fen.ok_or("fen is missing")?;
+    let board = Board::from_fen(&fen)?;
+    let mut job = Job::new(board, analysis_limits(depth, movetime, nodes));
+    job.lines = lines;
+    Ok((fen, job))
+}
+
+// "<method> <path> HTTP/1.x", headers, and a body of Content-Length bytes
+fn read_request(input: &mut impl BufRead) -> Result<(String, String, String), String> {
+    let mut line = String::new();
+    input.read_line(&mut line).map_err(|e| e.to_string())?;
+    let mut words = line.split_whitespace();
+    let (Some(method), Some(path)) = (words.next(), words.next()) else {
+        return Err("bad request line".to_string());
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+    let mut length = 0;
+    loop {
+        line.clear();
+        input.read_line(&mut line).map_err(|e| e.to_string())?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length")
+        {
+            length = value.trim().parse().map_err(|_| "bad Content-Length")?;
+        }
+    }
+    if length > MAX_BODY {
+        return Err("request too large".to_string());
+    }
+    let mut body = vec![0; length];
+    input.read_exact(&mut body).map_err(|e| e.to_string())?;
+    let body = String::from_utf8(body).map_err(|_| "body is not UTF-8")?;
+    Ok((method, path, body))
+}
@@ -0,0 +1,159 @@
+//! Shutting down cleanly when told to by the operating system: SIGINT or
+//! SIGTERM, or the console closing on Windows. A tournament manager that
+//! gives up on an engine sends one of these, and an engine that dies on
+//! the spot can leave a move unanswered and output cut off. Instead the
+//! signal is turned into a `quit` on the engine's input: UCI stops the
+//! search, answers it with its bestmove and returns; xboard finishes the
+//! move it's thinking on first.
+
+use std::io::{self, BufRead, Read};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+// how often the input is checked for a signal having come in
+const POLL: Duration = Duration::from_millis(10);
+
+/// Catch the shutdown signals from now on. Where there are none to catch
+/// this does nothing.
+pub fn install() {
+    os::install();
+}
+
+/// Whether a shutdown signal has come in.
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::Relaxed)
+}
+
+/// Act as if a shutdown signal had come in.
+pub fn request() {
+    REQUESTED.store(true, Ordering::Relaxed);
+}
+
+/// The lines of `input`, until it ends or a shutdown signal comes in,
+/// and then a `quit`. A signal that came in before is answered at once.
+/// `input` is read on a thread of its own, which is left waiting on it
+/// if the signal comes first.
+pub fn quit_on_signal(input: impl BufRead + Send + 'static) -> impl BufRead + Send {
+    let (lines, received) = mpsc::channel();
+    let ended = Arc::new(AtomicBool::new(false));
+    let reader = {
+        let (lines, ended) = (lines.clone(), ended.clone());
+        move || {
+            for line in input.split(b'\n') {
+                let Ok(mut line) = line else {
+                    break;
+                };
+                line.push(b'\n');
+                if lines.send(line).is_err() {
+                    return;
+                }
+            }
+            ended.store(true, Ordering::Relaxed);
+        }
+    };
+    thread::spawn(reader);
+    thread::spawn(move || {
+        while !ended.load(Ordering::Relaxed) {
+            if requested() {
+                let _ = lines.send(b"quit\n".to_vec());
+                return;
+            }
+            thread::sleep(POLL);
+        }
+    });
+    Lines {
+        received,
+        line: Vec::new(),
+        read: 0,
+    }
+}
+
+// lines from the channel, as one stream; it ends when every sender is gone
+struct Lines {
+    received: Receiver<Vec<u8>>,
+    line: Vec<u8>,
+    read: usize,
+}
+
+impl Read for Lines {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.fill_buf()?.read(buf)?;
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for Lines {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.read == self.line.len() {
+            self.line = self.received.recv().unwrap_or_default();
+            self.read = 0;
+        }
+        Ok(&self.line[self.read..])
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.read = (self.read + n).min(self.line.len());
+    }
+}
+
+#[cfg(unix)]
+mod os {
+    use std::ffi::c_int;
+
+    const SIGINT: c_int = 2;
+    const SIGTERM: c_int = 15;
+
+    unsafe extern "C" {
+        fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+    }
+
+    // storing to an atomic is all a signal handler can safely do
+    extern "C" fn on_signal(_: c_int) {
+        super::request();
+    }
+
+    pub fn install() {
+        for signum in [SIGINT, SIGTERM] {
+            // SAFETY: the handler is a plain function that's async-signal-safe
+            unsafe { signal(signum, on_signal) };
+        }
+    }
+}
+
+#[cfg(windows)]
+mod os {
+    use std::time::Duration;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn SetConsoleCtrlHandler(
+            handler: Option<unsafe extern "system" fn(u32) -> i32>,
+            add: i32,
+        ) -> i32;
+    }
+
+    // Ctrl-C, Ctrl-Break and the console closing alike. Windows ends the
+    // process once a close is handled, so the engine is given a moment to
+    // answer first
+    unsafe extern "system" fn on_event(_: u32) -> i32 {
+        super::request();
+        std::thread::sleep(Duration::from_millis(500));
+        1
+    }
+
+    pub fn install() {
+        // SAFETY: the handler only sets a flag and sleeps
+        unsafe { SetConsoleCtrlHandler(Some(on_event), 1) };
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod os {
+    pub fn install() {}
+}
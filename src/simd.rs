@@ -0,0 +1,151 @@
+//! Vector versions of the network's hot loops: adding and taking away
+//! accumulator rows, and the clipped dot product of the output layer.
+//! AVX2 is used when the CPU has it, found out at run time, so one binary
+//! runs on any x86-64; anywhere else, or without it, the plain loops in
+//! `scalar` do the work. Both give exactly the same numbers.
+
+/// `acc += row`, lane by lane, wrapping.
+pub fn add(acc: &mut [i16], row: &[i16]) {
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        // SAFETY: the CPU has AVX2
+        return unsafe { avx2::add(acc, row) };
+    }
+    scalar::add(acc, row)
+}
+
+/// `acc -= row`, lane by lane, wrapping.
+pub fn sub(acc: &mut [i16], row: &[i16]) {
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() {
+        // SAFETY: the CPU has AVX2
+        return unsafe { avx2::sub(acc, row) };
+    }
+    scalar::sub(acc, row)
+}
+
+/// The sum of each of `acc`, clamped to 0..=`max`, times its weight.
+///
+/// ```
+/// use ananke::simd;
+///
+/// let acc = [-5, 10, 300, 7];
+/// let weights = [100, 2, 3, -1];
+/// // -5 counts as 0 and 300 as 255
+/// assert_eq!(simd::clipped_dot(&acc, &weights, 255), 20 + 765 - 7);
+/// ```
+pub fn clipped_dot(acc: &[i16], weights: &[i16], max: i16) -> i64 {
+    #[cfg(target_arch = "x86_64")]
+    if has_avx2() && (0..=avx2::MAX_CLIP).contains(&max) {
+        // SAFETY: the CPU has AVX2
+        return unsafe { avx2::clipped_dot(acc, weights, max) };
+    }
+    scalar::clipped_dot(acc, weights, max)
+}
+
+/// Whether the vector paths are the ones in use.
+pub fn has_avx2() -> bool {
+    #[cfg(target_arch = "x86_64")]
+    {
+        std::arch::is_x86_feature_detected!("avx2")
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        false
+    }
+}
+
+/// The plain loops: the fallback, and what the vector paths must agree
+/// with.
+pub mod scalar {
+    pub fn add(acc: &mut [i16], row: &[i16]) {
+        for (a, &w) in acc.iter_mut().zip(row) {
+            *a = a.wrapping_add(w);
+        }
+    }
+
+    pub fn sub(acc: &mut [i16], row: &[i16]) {
+        for (a, &w) in acc.iter_mut().zip(row) {
+            *a = a.wrapping_sub(w);
+        }
+    }
+
+    pub fn clipped_dot(acc: &[i16], weights: &[i16], max: i16) -> i64 {
+        acc.iter()
+            .zip(weights)
+            .map(|(&a, &w)| a.clamp(0, max.max(0)) as i64 * w as i64)
+            .sum()
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    // 16 lanes of i16 to a register
+    const LANES: usize = 16;
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn add(acc: &mut [i16], row: &[i16]) {
+        let n = acc.len().min(row.len());
+        let whole = n / LANES * LANES;
+        for i in (0..whole).step_by(LANES) {
+            // SAFETY: i + LANES <= n, inside both slices; loads and stores
+            // are unaligned
+            unsafe {
+                let a = _mm256_loadu_si256(acc.as_ptr().add(i).cast());
+                let w = _mm256_loadu_si256(row.as_ptr().add(i).cast());
+                _mm256_storeu_si256(acc.as_mut_ptr().add(i).cast(), _mm256_add_epi16(a, w));
+            }
+        }
+        super::scalar::add(&mut acc[whole..n], &row[whole..n]);
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn sub(acc: &mut [i16], row: &[i16]) {
+        let n = acc.len().min(row.len());
+        let whole = n / LANES * LANES;
+        for i in (0..whole).step_by(LANES) {
+            // SAFETY: as for add
+            unsafe {
+                let a = _mm256_loadu_si256(acc.as_ptr().add(i).cast());
+                let w = _mm256_loadu_si256(row.as_ptr().add(i).cast());
+                _mm256_storeu_si256(acc.as_mut_ptr().add(i).cast(), _mm256_sub_epi16(a, w));
+            }
+        }
+        super::scalar::sub(&mut acc[whole..n], &row[whole..n]);
+    }
+
+    // the highest clamp the dot product takes: madd sums two products in
+    // an i32, and two of this times -32768 is as far as one reaches
+    pub const MAX_CLIP: i16 = i16::MAX / 2;
+
+    // `max` is in 0..=MAX_CLIP
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn clipped_dot(acc: &[i16], weights: &[i16], max: i16) -> i64 {
+        let n = acc.len().min(weights.len());
+        let whole = n / LANES * LANES;
+        let (zero, top) = (_mm256_setzero_si256(), _mm256_set1_epi16(max));
+        let mut sum = _mm256_setzero_si256();
+        for i in (0..whole).step_by(LANES) {
+            // SAFETY: i + LANES <= n, inside both slices
+            let (a, w) = unsafe {
+                (
+                    _mm256_loadu_si256(acc.as_ptr().add(i).cast()),
+                    _mm256_loadu_si256(weights.as_ptr().add(i).cast()),
+                )
+            };
+            let clamped = _mm256_min_epi16(_mm256_max_epi16(a, zero), top);
+            // eight i32s, each two neighbouring products, widened to i64
+            let pairs = _mm256_madd_epi16(clamped, w);
+            let low = _mm256_cvtepi32_epi64(_mm256_castsi256_si128(pairs));
+            let high = _mm256_cvtepi32_epi64(_mm256_extracti128_si256(pairs, 1));
+            sum = _mm256_add_epi64(sum, _mm256_add_epi64(low, high));
+        }
+        let mut lanes = [0i64; 4];
+        // SAFETY: four i64s are one register's worth
+        unsafe { _mm256_storeu_si256(lanes.as_mut_ptr().cast(), sum) };
+        lanes.iter().sum::<i64>()
+            + super::scalar::clipped_dot(&acc[whole..n], &weights[whole..n], max)
+    }
+}
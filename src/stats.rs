@@ -0,0 +1,36 @@
+//! Counters the search keeps as it goes, to measure what its heuristics
+//! actually do on real positions: how often each extension fires, so far.
+//! They come back with every `SearchResult`, and UCI's SearchStats option
+//! prints them after each search, along with the engine's count of how
+//! often it guessed the opponent's reply.
+
+/// What happened during one search. Searchers that don't count something
+/// leave it at 0.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Recaptures on the square of the capture just before, searched a
+    /// ply deeper.
+    pub recapture_extensions: u64,
+    /// Pawn pushes to the seventh rank, searched a ply deeper.
+    pub pawn_push_extensions: u64,
+}
+
+/// How often the reply a search expected, the second move of its line,
+/// is the one the next search finds played, over a game. A hit means the
+/// hash table already holds that search's work on the position, its best
+/// move there included, so the next search gets through its early depths
+/// on that work.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Predictions {
+    /// Searches that came after one that expected a reply.
+    pub made: u64,
+    /// Of those, the ones that found the reply played.
+    pub hits: u64,
+}
+
+impl Predictions {
+    /// Hits per prediction, or None before there are any.
+    pub fn hit_rate(&self) -> Option<f64> {
+        (self.made > 0).then(|| self.hits as f64 / self.made as f64)
+    }
+}
@@ -0,0 +1,165 @@
+//! Playing strength limited by nodes searched per move instead of time, so a
+//! given setting plays the same on a phone as on a workstation, and the same
+//! from one run to the next.
+//!
+//! The Elo ladder below is from self-play: each budget was played against
+//! half of it with `ananke calibrate 2n n 100` (100 pairs of games, the
+//! default seed), and the measured gaps were chained. Each gap is only good
+//! to about +-35 Elo at that many games, and the bottom rung is pinned at
+//! `MIN_ELO` by assumption, so treat absolute numbers as rough. Rerun it
+//! after anything that changes the search.
+
+use crate::adjudication::{AdjudicationRules, Adjudicator, ConcessionRules, Concessions};
+use crate::board::Board;
+use crate::game::Game;
+use crate::playout;
+use crate::rng::Rng;
+use crate::search::{self, Limits};
+use crate::types::{ByColor, Color, GameResult};
+
+// (elo, nodes per move), gaps chained upwards from 16 nodes
+const LADDER: [(u32, u64); 13] = [
+    (800, 16),
+    (960, 32),
+    (1054, 64),
+    (1152, 128),
+    (1222, 256),
+    (1294, 512),
+    (1359, 1024),
+    (1422, 2048),
+    (1518, 4096),
+    (1556, 8192),
+    (1605, 16384),
+    (1661, 32768),
+    (1687, 65536),
+];
+
+pub const MIN_ELO: u32 = LADDER[0].0;
+pub const MAX_ELO: u32 = LADDER[LADDER.len() - 1].0;
+
+/// Nodes per move for an Elo target, interpolated between ladder rungs on
+/// a log scale (strength grows with each doubling of nodes, not each node).
+/// Targets outside `MIN_ELO..=MAX_ELO` are clamped.
+pub fn nodes_for_elo(elo: u32) -> u64 {
+    let elo = elo.clamp(MIN_ELO, MAX_ELO);
+    let i = LADDER
+        .windows(2)
+        .position(|w| elo <= w[1].0)
+        .unwrap_or(LADDER.len() - 2);
+    let ((elo_lo, nodes_lo), (elo_hi, nodes_hi)) = (LADDER[i], LADDER[i + 1]);
+    let t = (elo - elo_lo) as f64 / (elo_hi - elo_lo) as f64;
+    let log = (nodes_lo as f64).log2() * (1.0 - t) + (nodes_hi as f64).log2() * t;
+    log.exp2().round() as u64
+}
+
+/// Wins, draws and losses from the first player's point of view.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchScore {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Fraction of the points won, 0.0 to 1.0.
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games().max(1) as f64
+    }
+
+    /// Elo difference implied by the score, clamped short of the infinities
+    /// at 0% and 100%.
+    pub fn elo_difference(&self) -> f64 {
+        let p = self.score().clamp(0.01, 0.99);
+        400.0 * (p / (1.0 - p)).log10()
+    }
+}
+
+/// Play `pairs` pairs of games between a `nodes_a` and a `nodes_b` budget.
+/// Each pair starts from the same short random opening with colors swapped,
+/// so lopsided openings cancel out.
+pub fn play_match(nodes_a: u64, nodes_b: u64, pairs: u32, seed: u32) -> MatchScore {
+    play_match_conceding(nodes_a, nodes_b, pairs, seed, ConcessionRules::default())
+}
+
+/// `play_match`, with each side also resigning, offering draws and
+/// accepting them as `rules` say, from its own scores, the way ananke does
+/// in a game under those options.
+pub fn play_match_conceding(
+    nodes_a: u64,
+    nodes_b: u64,
+    pairs: u32,
+    seed: u32,
+    rules: ConcessionRules,
+) -> MatchScore {
+    let mut rng = Rng::new(seed);
+    let mut score = MatchScore::default();
+    for _ in 0..pairs {
+        let opening = playout::random_playout(&Board::startpos(), &mut rng, 6);
+        if opening.legal_moves().is_empty() {
+            continue;
+        }
+        for a_is_white in [true, false] {
+            let (white, black) = if a_is_white {
+                (nodes_a, nodes_b)
+            } else {
+                (nodes_b, nodes_a)
+            };
+            match (play_game(&opening, white, black, rules), a_is_white) {
+                (GameResult::Draw, _) => score.draws += 1,
+                (GameResult::WhiteWins, true) | (GameResult::BlackWins, false) => score.wins += 1,
+                _ => score.losses += 1,
+            }
+        }
+    }
+    score
+}
+
+// one game between fixed node budgets; long or dead-level games are
+// adjudicated so a match doesn't spend most of its time in them
+fn play_game(
+    start: &Board,
+    white_nodes: u64,
+    black_nodes: u64,
+    rules: ConcessionRules,
+) -> GameResult {
+    let mut game = Game::from_board(start.clone());
+    let mut adjudicator = Adjudicator::new(AdjudicationRules::default());
+    let mut concessions = ByColor::new(Concessions::new(rules), Concessions::new(rules));
+    while !game.is_over() && game.moves().len() < 400 {
+        let mover = game.board().side_to_move;
+        let nodes = if mover == Color::White {
+            white_nodes
+        } else {
+            black_nodes
+        };
+        let result = search::search_with(game.board(), Limits::nodes(nodes));
+        let m = result
+            .best_move
+            .expect("search found no move in a live game");
+        let concessions = &mut concessions[mover];
+        if concessions.record(result.score) {
+            game.resign(mover).expect("the game is still on");
+            break;
+        }
+        // the opponent's offer stands until this move is played
+        if game.draw_offer() == Some(mover.opposite()) && concessions.accept_draw() {
+            game.accept_draw(mover).expect("the offer is on the table");
+            break;
+        }
+        if concessions.offer_draw() {
+            game.offer_draw(mover).expect("the game is still on");
+        }
+        game.play(m).expect("search chose an illegal move");
+        if let Some(result) = adjudicator.record(mover, result.score) {
+            return result;
+        }
+        if game.can_claim_draw().is_some() {
+            return GameResult::Draw;
+        }
+    }
+    game.result().unwrap_or(GameResult::Draw)
+}
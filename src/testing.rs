@@ -0,0 +1,248 @@
+//! Deliberately simple reference implementations for differential testing.
+//! Everything here walks the board square by square with no bitboard tricks,
+//! so it is slow but easy to check by eye. Not for engine use.
+
+use crate::bitboard::Square;
+use crate::board::Board;
+use crate::types::{Color, Move, MoveFlag, PieceType};
+
+const KNIGHT_STEPS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const KING_STEPS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+
+// the square `df` files and `dr` ranks away, if still on the board
+fn offset(sq: Square, df: i8, dr: i8) -> Option<Square> {
+    let file = sq.file() as i8 + df;
+    let rank = sq.rank() as i8 + dr;
+    ((0..8).contains(&file) && (0..8).contains(&rank)).then(|| Square::new((rank * 8 + file) as u8))
+}
+
+/// Is `sq` attacked by any piece of `by`? Looks outward from the square for
+/// each kind of attacker.
+pub fn reference_is_attacked(board: &Board, sq: Square, by: Color) -> bool {
+    let is = |target: Option<Square>, pieces: &[PieceType]| {
+        target
+            .and_then(|t| board.piece_at(t))
+            .is_some_and(|(color, pt)| color == by && pieces.contains(&pt))
+    };
+
+    // a pawn of `by` attacks from one rank behind, seen from its side
+    let pawn_rank = if by == Color::White { -1 } else { 1 };
+    if is(offset(sq, -1, pawn_rank), &[PieceType::Pawn])
+        || is(offset(sq, 1, pawn_rank), &[PieceType::Pawn])
+    {
+        return true;
+    }
+    if KNIGHT_STEPS
+        .iter()
+        .any(|&(df, dr)| is(offset(sq, df, dr), &[PieceType::Knight]))
+    {
+        return true;
+    }
+    if KING_STEPS
+        .iter()
+        .any(|&(df, dr)| is(offset(sq, df, dr), &[PieceType::King]))
+    {
+        return true;
+    }
+
+    let slides = [
+        (ROOK_DIRECTIONS, PieceType::Rook),
+        (BISHOP_DIRECTIONS, PieceType::Bishop),
+    ];
+    for (directions, slider) in slides {
+        for (df, dr) in directions {
+            let mut current = offset(sq, df, dr);
+            while let Some(target) = current {
+                if board.piece_at(target).is_some() {
+                    if is(Some(target), &[slider, PieceType::Queen]) {
+                        return true;
+                    }
+                    break;
+                }
+                current = offset(target, df, dr);
+            }
+        }
+    }
+    false
+}
+
+/// Every legal move, found by trying each piece's moves square by square
+/// and keeping those that don't leave the king attacked.
+pub fn reference_legal_moves(board: &Board) -> Vec<Move> {
+    let us = board.side_to_move;
+    reference_pseudo_moves(board)
+        .into_iter()
+        .filter(|m| {
+            let next = board.make_move(*m);
+            let king = (0..64)
+                .map(Square::new)
+                .find(|&sq| next.piece_at(sq) == Some((us, PieceType::King)))
+                .expect("no king");
+            !reference_is_attacked(&next, king, us.opposite())
+        })
+        .collect()
+}
+
+fn reference_pseudo_moves(board: &Board) -> Vec<Move> {
+    let us = board.side_to_move;
+    let mut moves = Vec::new();
+
+    for from in (0..64).map(Square::new) {
+        let Some((color, piece)) = board.piece_at(from) else {
+            continue;
+        };
+        if color != us {
+            continue;
+        }
+        match piece {
+            PieceType::Pawn => pawn_moves(board, from, &mut moves),
+            PieceType::Knight => step_moves(board, from, &KNIGHT_STEPS, &mut moves),
+            PieceType::King => {
+                step_moves(board, from, &KING_STEPS, &mut moves);
+                castling_moves(board, from, &mut moves);
+            }
+            PieceType::Bishop => slide_moves(board, from, &BISHOP_DIRECTIONS, &mut moves),
+            PieceType::Rook => slide_moves(board, from, &ROOK_DIRECTIONS, &mut moves),
+            PieceType::Queen => {
+                slide_moves(board, from, &BISHOP_DIRECTIONS, &mut moves);
+                slide_moves(board, from, &ROOK_DIRECTIONS, &mut moves);
+            }
+        }
+    }
+    moves
+}
+
+// quiet or capture onto `to`, or nothing if our own piece stands there
+fn push_to(board: &Board, from: Square, to: Square, moves: &mut Vec<Move>) -> bool {
+    match board.piece_at(to) {
+        None => {
+            moves.push(Move::new(from, to, MoveFlag::Quiet));
+            true
+        }
+        Some((color, _)) if color != board.side_to_move => {
+            moves.push(Move::new(from, to, MoveFlag::Capture));
+            false
+        }
+        Some(_) => false,
+    }
+}
+
+fn step_moves(board: &Board, from: Square, steps: &[(i8, i8)], moves: &mut Vec<Move>) {
+    for &(df, dr) in steps {
+        if let Some(to) = offset(from, df, dr) {
+            push_to(board, from, to, moves);
+        }
+    }
+}
+
+fn slide_moves(board: &Board, from: Square, directions: &[(i8, i8)], moves: &mut Vec<Move>) {
+    for &(df, dr) in directions {
+        let mut current = offset(from, df, dr);
+        // keep going while the squares are empty
+        while let Some(to) = current {
+            if !push_to(board, from, to, moves) {
+                break;
+            }
+            current = offset(to, df, dr);
+        }
+    }
+}
+
+fn pawn_moves(board: &Board, from: Square, moves: &mut Vec<Move>) {
+    let us = board.side_to_move;
+    let (up, start_rank, last_rank) = if us == Color::White {
+        (1, 1, 7)
+    } else {
+        (-1, 6, 0)
+    };
+    let promote = |to: Square, capture: bool, moves: &mut Vec<Move>| {
+        let flags = if capture {
+            MoveFlag::PROMOTION_CAPTURES
+        } else {
+            MoveFlag::PROMOTIONS
+        };
+        for flag in flags {
+            moves.push(Move::new(from, to, flag));
+        }
+    };
+
+    if let Some(one) = offset(from, 0, up)
+        && board.piece_at(one).is_none()
+    {
+        if one.rank() == last_rank {
+            promote(one, false, moves);
+        } else {
+            moves.push(Move::new(from, one, MoveFlag::Quiet));
+        }
+        if from.rank() == start_rank
+            && let Some(two) = offset(from, 0, 2 * up)
+            && board.piece_at(two).is_none()
+        {
+            moves.push(Move::new(from, two, MoveFlag::DoublePawnPush));
+        }
+    }
+
+    for df in [-1, 1] {
+        let Some(to) = offset(from, df, up) else {
+            continue;
+        };
+        match board.piece_at(to) {
+            Some((color, _)) if color != us => {
+                if to.rank() == last_rank {
+                    promote(to, true, moves);
+                } else {
+                    moves.push(Move::new(from, to, MoveFlag::Capture));
+                }
+            }
+            None if board.en_passant_sq == Some(to) => {
+                moves.push(Move::new(from, to, MoveFlag::EnPassant));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn castling_moves(board: &Board, from: Square, moves: &mut Vec<Move>) {
+    let us = board.side_to_move;
+    let them = us.opposite();
+    let rank = if us == Color::White { 0 } else { 7 };
+    let sq = |file: u8| Square::new(rank * 8 + file);
+    if from != sq(4) {
+        return;
+    }
+    let empty = |files: &[u8]| files.iter().all(|&f| board.piece_at(sq(f)).is_none());
+    let safe = |files: &[u8]| {
+        files
+            .iter()
+            .all(|&f| !reference_is_attacked(board, sq(f), them))
+    };
+    let rook_home = |file: u8| board.piece_at(sq(file)) == Some((us, PieceType::Rook));
+
+    let rights = board.castling_rights;
+    if rights.can_castle_kingside(us) && rook_home(7) && empty(&[5, 6]) && safe(&[4, 5, 6]) {
+        moves.push(Move::new(from, sq(6), MoveFlag::KingCastle));
+    }
+    if rights.can_castle_queenside(us) && rook_home(0) && empty(&[1, 2, 3]) && safe(&[4, 3, 2]) {
+        moves.push(Move::new(from, sq(2), MoveFlag::QueenCastle));
+    }
+}
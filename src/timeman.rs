@@ -0,0 +1,160 @@
+//! Time management: how long to think on a move, given both clocks.
+//!
+//! The base allotment spreads our time over the moves still to play: until
+//! the next time control with `moves_to_go`, or an assumed horizon in sudden
+//! death. That is then scaled by how our clock compares with the opponent's:
+//! far ahead we can afford to think longer, far behind we save time, since
+//! losing on time is losing.
+//!
+//! A search reads its time from a `TimeSource`: normally the wall clock,
+//! or with `nodestime` a virtual clock that counts searched nodes, so that
+//! timed games come out the same on any machine and under any load.
+
+use std::time::{Duration, Instant};
+
+/// Kept back on every move for GUI and OS latency, unless set otherwise.
+pub const MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+/// The most the front ends let the overhead be set to.
+pub const MAX_MOVE_OVERHEAD: Duration = Duration::from_millis(5000);
+/// The least a budget allows, even when the overhead eats the whole clock:
+/// without it the search stops inside its first iteration and plays
+/// whatever it tried first.
+pub const MIN_THINK_TIME: Duration = Duration::from_millis(10);
+// with the clock nearly gone, the floor drops to this fraction of it
+const MIN_THINK_SHARE: u32 = 10;
+// how many more moves a sudden death game is assumed to last
+const SUDDEN_DEATH_HORIZON: u32 = 30;
+// never plan on more than this share of what is left, and outside the last
+// move before a control never go past it either
+const MAX_SHARE: f64 = 0.5;
+// at the last move before a control the clock is about to be refilled,
+// so nearly all of it can go, minus a safety margin
+const LAST_MOVE_SHARE: f64 = 0.9;
+
+/// Both clocks as the GUI reports them, from our side of the board.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct ClockState {
+    pub time: Duration,
+    pub increment: Duration,
+    /// Zero if the GUI didn't send the opponent's clock.
+    pub opponent_time: Duration,
+    pub opponent_increment: Duration,
+    /// Moves until the next time control; None (or 0) for sudden death.
+    pub moves_to_go: Option<u32>,
+}
+
+/// Time for one move: aim for `optimum`, never go past `maximum`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimeBudget {
+    pub optimum: Duration,
+    pub maximum: Duration,
+}
+
+/// Decide how long to think on the next move.
+pub fn allocate(clock: &ClockState) -> TimeBudget {
+    allocate_with_overhead(clock, MOVE_OVERHEAD)
+}
+
+/// `allocate`, keeping `overhead` back on every move instead of
+/// `MOVE_OVERHEAD`: more for a slow GUI or a server across the network.
+/// The budget is never below `MIN_THINK_TIME`, or a tenth of the clock if
+/// that is less.
+pub fn allocate_with_overhead(clock: &ClockState, overhead: Duration) -> TimeBudget {
+    let usable = clock.time.saturating_sub(overhead).as_secs_f64();
+    let increment = clock.increment.as_secs_f64();
+    let moves_to_go = clock.moves_to_go.filter(|&n| n > 0);
+    let horizon = moves_to_go.unwrap_or(SUDDEN_DEATH_HORIZON) as f64;
+
+    // hard cap first: whatever else happens, this much is left afterwards
+    let maximum = match moves_to_go {
+        Some(1) => usable * LAST_MOVE_SHARE,
+        _ => usable * MAX_SHARE,
+    };
+
+    // the increment comes back after every move, so it is spent in full,
+    // except for a sliver kept in case the clock is nearly empty. Even on
+    // the last move before a control only half is planned for; the rest is
+    // there for the search to overrun into if it has to.
+    let base = usable / horizon + increment * 0.9;
+    let optimum = (base * clock_ratio_factor(clock, horizon)).min(usable * MAX_SHARE);
+
+    let floor = MIN_THINK_TIME.min(clock.time / MIN_THINK_SHARE);
+    TimeBudget {
+        optimum: Duration::from_secs_f64(optimum.max(0.0)).max(floor),
+        maximum: Duration::from_secs_f64(maximum.max(0.0)).max(floor),
+    }
+}
+
+/// A fixed time per move, as with UCI's `movetime`, less `overhead` so
+/// that the move still reaches the GUI inside the time. Never below
+/// `MIN_THINK_TIME`, unless the move time itself is shorter.
+pub fn fixed_with_overhead(move_time: Duration, overhead: Duration) -> TimeBudget {
+    let time = move_time
+        .saturating_sub(overhead)
+        .max(MIN_THINK_TIME.min(move_time));
+    TimeBudget {
+        optimum: time,
+        maximum: time,
+    }
+}
+
+// > 1 when our clock is well ahead of the opponent's, < 1 when behind. Each
+// side's time is counted together with the increments it will get over the
+// horizon, and the effect is damped (square root, clamped) so that a lead
+// makes us somewhat bolder, not reckless.
+fn clock_ratio_factor(clock: &ClockState, horizon: f64) -> f64 {
+    if clock.opponent_time.is_zero() {
+        return 1.0;
+    }
+    let ours = clock.time.as_secs_f64() + horizon * clock.increment.as_secs_f64();
+    let theirs =
+        clock.opponent_time.as_secs_f64() + horizon * clock.opponent_increment.as_secs_f64();
+    (ours / theirs).sqrt().clamp(0.7, 1.4)
+}
+
+/// Where a search reads how much time it has used.
+pub trait TimeSource {
+    /// Time used since the search started, `nodes` nodes into it.
+    fn elapsed(&self, nodes: u64) -> Duration;
+}
+
+/// Real time since `start`.
+#[derive(Debug, Copy, Clone)]
+pub struct WallClock {
+    pub start: Instant,
+}
+
+impl WallClock {
+    pub fn start() -> Self {
+        WallClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl TimeSource for WallClock {
+    fn elapsed(&self, _nodes: u64) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// The `nodestime` convention: every `nodes_per_ms` nodes count as one
+/// millisecond, however long they really took.
+///
+/// ```
+/// use ananke::timeman::{NodesClock, TimeSource};
+/// use std::time::Duration;
+///
+/// let clock = NodesClock { nodes_per_ms: 500 };
+/// assert_eq!(clock.elapsed(1_250_000), Duration::from_millis(2500));
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NodesClock {
+    pub nodes_per_ms: u64,
+}
+
+impl TimeSource for NodesClock {
+    fn elapsed(&self, nodes: u64) -> Duration {
+        Duration::from_millis(nodes / self.nodes_per_ms.max(1))
+    }
+}
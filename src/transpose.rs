@@ -0,0 +1,72 @@
+use crate::board::Board;
+use crate::types::Move;
+
+/// Two lines reaching the same position: after `ply_a` moves of the first
+/// and `ply_b` moves of the second.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Transposition {
+    pub ply_a: usize,
+    pub ply_b: usize,
+}
+
+/// Find where two move sequences from `start` first transpose: the earliest
+/// point in `b` whose position also occurs in `a` by a different move order.
+/// Shared prefixes don't count. Moves must be legal.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::transpose::{Transposition, find_transposition};
+/// use ananke::types::Move;
+///
+/// let start = Board::startpos();
+/// let line = |text: &str| -> Vec<Move> {
+///     let mut board = start.clone();
+///     text.split(' ')
+///         .map(|m| {
+///             let m = board.parse_uci_move(m).unwrap();
+///             board = board.make_move(m);
+///             m
+///         })
+///         .collect()
+/// };
+/// // 1.d4 Nf6 2.c4 e6 and 1.c4 e6 2.d4 Nf6 meet after four plies
+/// let a = line("d2d4 g8f6 c2c4 e7e6");
+/// let b = line("c2c4 e7e6 d2d4 g8f6");
+/// assert_eq!(
+///     find_transposition(&start, &a, &b),
+///     Some(Transposition { ply_a: 4, ply_b: 4 })
+/// );
+/// ```
+pub fn find_transposition(start: &Board, a: &[Move], b: &[Move]) -> Option<Transposition> {
+    let line_a = positions(start, a);
+    let line_b = positions(start, b);
+
+    for (ply_b, board_b) in line_b.iter().enumerate().skip(1) {
+        for (ply_a, board_a) in line_a.iter().enumerate().skip(1) {
+            if a[..ply_a] != b[..ply_b] && board_a.is_same_position(board_b) {
+                return Some(Transposition { ply_a, ply_b });
+            }
+        }
+    }
+    None
+}
+
+/// Do both sequences end in the same position?
+pub fn same_final_position(start: &Board, a: &[Move], b: &[Move]) -> bool {
+    let end = |moves: &[Move]| {
+        moves
+            .iter()
+            .fold(start.clone(), |board, m| board.make_move(*m))
+    };
+    end(a).is_same_position(&end(b))
+}
+
+// the start position followed by the position after each move
+fn positions(start: &Board, moves: &[Move]) -> Vec<Board> {
+    let mut boards = vec![start.clone()];
+    for m in moves {
+        let next = boards.last().unwrap().make_move(*m);
+        boards.push(next);
+    }
+    boards
+}
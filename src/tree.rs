@@ -0,0 +1,241 @@
+//! A record of the alpha-beta search tree, for working out offline why a
+//! search decided as it did. `search::search_tree` keeps every node of the
+//! last iteration that finished, down to a few plies from the root, with
+//! its window, its score, and what became of it: searched, settled by the
+//! hash table, cut off by reverse futility, skipped by futility or late
+//! move pruning, and so on. It writes out as JSON, or as a graphviz graph
+//! to draw.
+
+use crate::json;
+use crate::search::INFINITY;
+use crate::tt::Bound;
+use crate::types::Move;
+use std::fmt::Write;
+
+/// What became of a node.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Its moves were searched.
+    Searched,
+    /// A hash table entry settled it without a search.
+    Table,
+    /// Its static eval was so far above beta that it was cut off there.
+    ReverseFutility,
+    /// Skipped before it was searched, by futility or late move pruning;
+    /// it has no score.
+    Pruned,
+    /// At the end of the depth: its score is the quiescence search's.
+    Quiescence,
+    /// A draw by the fifty-move rule.
+    Draw,
+    /// Checkmate or stalemate.
+    NoMoves,
+}
+
+impl Outcome {
+    pub fn name(self) -> &'static str {
+        match self {
+            Outcome::Searched => "searched",
+            Outcome::Table => "table",
+            Outcome::ReverseFutility => "reverse-futility",
+            Outcome::Pruned => "pruned",
+            Outcome::Quiescence => "quiescence",
+            Outcome::Draw => "draw",
+            Outcome::NoMoves => "no-moves",
+        }
+    }
+}
+
+/// One node, from the point of view of its side to move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+    /// Where its parent is in `SearchTree::nodes`; None at the root.
+    pub parent: Option<usize>,
+    /// The move that led to it; None at the root.
+    pub mv: Option<Move>,
+    pub ply: usize,
+    /// Plies left to search from it.
+    pub depth: u8,
+    /// The window it was searched with.
+    pub alpha: i32,
+    pub beta: i32,
+    /// What it returned, and how that compares with its true score. None
+    /// for a pruned node.
+    pub score: Option<(i32, Bound)>,
+    pub outcome: Outcome,
+}
+
+/// The nodes of one iteration, each after its parent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchTree {
+    /// How many plies from the root are kept.
+    pub max_ply: usize,
+    /// The iteration's depth.
+    pub depth: u8,
+    pub nodes: Vec<TreeNode>,
+    // the node at each ply of the line being searched
+    path: Vec<usize>,
+    // the last iteration's nodes, while the next is under way
+    previous: (u8, Vec<TreeNode>),
+}
+
+impl SearchTree {
+    /// A tree to keep nodes up to `max_ply` plies from the root.
+    pub fn new(max_ply: usize) -> Self {
+        SearchTree {
+            max_ply,
+            depth: 0,
+            nodes: Vec::new(),
+            path: vec![0; max_ply + 1],
+            previous: (0, Vec::new()),
+        }
+    }
+
+    // a new iteration, to `depth`
+    pub(crate) fn start(&mut self, depth: u8) {
+        self.previous = (self.depth, std::mem::take(&mut self.nodes));
+        self.depth = depth;
+        self.enter(0, None, depth, -INFINITY, INFINITY);
+    }
+
+    // the iteration was cut off: back to the last one
+    pub(crate) fn abandon(&mut self) {
+        (self.depth, self.nodes) = std::mem::take(&mut self.previous);
+    }
+
+    pub(crate) fn enter(&mut self, ply: usize, mv: Option<Move>, depth: u8, alpha: i32, beta: i32) {
+        if ply > self.max_ply {
+            return;
+        }
+        self.path[ply] = self.nodes.len();
+        self.nodes.push(TreeNode {
+            parent: ply.checked_sub(1).map(|above| self.path[above]),
+            mv,
+            ply,
+            depth,
+            alpha,
+            beta,
+            score: None,
+            outcome: Outcome::Searched,
+        });
+    }
+
+    // what became of the node being searched at `ply`
+    pub(crate) fn note(&mut self, ply: usize, outcome: Outcome) {
+        if ply <= self.max_ply {
+            self.nodes[self.path[ply]].outcome = outcome;
+        }
+    }
+
+    pub(crate) fn leave(&mut self, ply: usize, score: i32) {
+        if ply > self.max_ply {
+            return;
+        }
+        let node = &mut self.nodes[self.path[ply]];
+        let bound = if score <= node.alpha {
+            Bound::Upper
+        } else if score >= node.beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        node.score = Some((score, bound));
+    }
+
+    /// The tree as one JSON object: the depth, the plies kept, and the
+    /// nodes in order, each naming its parent by place.
+    ///
+    /// ```
+    /// use ananke::board::Board;
+    /// use ananke::search::{Limits, search_tree};
+    ///
+    /// let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    /// let (result, tree) = search_tree(&board, Limits::depth(2), 1);
+    /// assert_eq!(result.best_move.unwrap().to_string(), "a1a8");
+    /// let json = tree.to_json();
+    /// assert!(json.starts_with(r#"{"depth":1,"maxply":1,"nodes":[{"id":0,"parent":null"#));
+    /// assert!(json.contains(r#""move":"a1a8","ply":1"#));
+    /// ```
+    pub fn to_json(&self) -> String {
+        let mut out = format!(
+            "{{\"depth\":{},\"maxply\":{},\"nodes\":[",
+            self.depth, self.max_ply
+        );
+        for (id, node) in self.nodes.iter().enumerate() {
+            let (score, bound) = match node.score {
+                Some((score, bound)) => (score.to_string(), json::string(bound_name(bound))),
+                None => ("null".to_string(), "null".to_string()),
+            };
+            let _ = write!(
+                out,
+                "{}{{\"id\":{},\"parent\":{},\"move\":{},\"ply\":{},\"depth\":{},\"alpha\":{},\
+                 \"beta\":{},\"score\":{},\"bound\":{},\"outcome\":{}}}",
+                if id == 0 { "" } else { "," },
+                id,
+                node.parent.map_or("null".to_string(), |p| p.to_string()),
+                node.mv
+                    .map_or("null".to_string(), |m| json::string(&m.to_string())),
+                node.ply,
+                node.depth,
+                node.alpha,
+                node.beta,
+                score,
+                bound,
+                json::string(node.outcome.name()),
+            );
+        }
+        out.push_str("]}");
+        out
+    }
+
+    /// The tree as a graphviz digraph: a box per node with its move, depth,
+    /// window and score, pruned nodes dashed and the others shaded by what
+    /// became of them.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph search {\n  node [shape=box, fontname=monospace];\n");
+        for (id, node) in self.nodes.iter().enumerate() {
+            let window = |score: i32| match score {
+                INFINITY => "inf".to_string(),
+                s if s == -INFINITY => "-inf".to_string(),
+                s => s.to_string(),
+            };
+            let score = match node.score {
+                Some((score, bound)) => format!("{} {}", score, bound_name(bound)),
+                None => "-".to_string(),
+            };
+            let style = match node.outcome {
+                Outcome::Searched => "",
+                Outcome::Pruned => ", style=dashed, color=gray",
+                Outcome::Table => ", style=filled, fillcolor=lightblue",
+                Outcome::ReverseFutility => ", style=filled, fillcolor=orange",
+                Outcome::Quiescence => ", style=filled, fillcolor=lightyellow",
+                Outcome::Draw | Outcome::NoMoves => ", style=filled, fillcolor=lightgray",
+            };
+            let _ = writeln!(
+                out,
+                "  n{} [label=\"{}\\nd{} [{}, {}]\\n{}\\n{}\"{}];",
+                id,
+                node.mv.map_or("root".to_string(), |m| m.to_string()),
+                node.depth,
+                window(node.alpha),
+                window(node.beta),
+                score,
+                node.outcome.name(),
+                style
+            );
+            if let Some(parent) = node.parent {
+                let _ = writeln!(out, "  n{} -> n{};", parent, id);
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+fn bound_name(bound: Bound) -> &'static str {
+    match bound {
+        Bound::Exact => "exact",
+        Bound::Lower => "lower",
+        Bound::Upper => "upper",
+    }
+}
@@ -0,0 +1,170 @@
+//! The transposition table: what earlier visits to a position found out
+//! about it (a score, how deep that score was searched, and the best move),
+//! keyed by the Zobrist hash, so the search can skip positions it has
+//! already searched deeply enough, and try the best move first otherwise.
+//!
+//! The table belongs to the alpha-beta searcher and lives as long as it
+//! does: entries carry over from one search to the next within a game, and
+//! are cleared for a new one. Its size is set in megabytes, and changed on
+//! the fly with UCI's `Hash` option.
+
+use crate::types::Move;
+
+/// Size of a new table, and of UCI's `Hash` option by default, in MB.
+pub const DEFAULT_HASH_MB: usize = 16;
+/// The largest `Hash` the UCI option allows.
+pub const MAX_HASH_MB: usize = 4096;
+
+/// How the stored score relates to the position's true score.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Bound {
+    /// Exactly the score: it fell inside the window.
+    Exact,
+    /// At least the score: it failed high, cutting off.
+    Lower,
+    /// At most the score: every move failed low.
+    Upper,
+}
+
+/// One position's worth of search results.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TtEntry {
+    pub best_move: Option<Move>,
+    /// From the side to move's point of view. Mate scores are counted from
+    /// this position, not the root; the search converts them both ways.
+    pub score: i32,
+    /// Remaining depth the score was searched to.
+    pub depth: u8,
+    pub bound: Bound,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Slot {
+    // the full hash, so that two positions sharing a slot aren't confused
+    key: u64,
+    entry: TtEntry,
+    // the search that stored it, so older entries go first
+    generation: u8,
+}
+
+/// A fixed number of slots, one entry each, indexed by the hash.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::tt::{Bound, TranspositionTable, TtEntry};
+///
+/// let board = Board::startpos();
+/// let mut tt = TranspositionTable::new(1);
+/// let entry = TtEntry { best_move: None, score: 20, depth: 5, bound: Bound::Exact };
+/// tt.store(board.hash, entry);
+/// assert_eq!(tt.probe(board.hash), Some(entry));
+///
+/// tt.resize(2);
+/// assert_eq!(tt.size_mb(), 2);
+/// assert_eq!(tt.probe(board.hash), Some(entry));
+/// tt.clear();
+/// assert_eq!(tt.probe(board.hash), None);
+/// ```
+pub struct TranspositionTable {
+    slots: Vec<Option<Slot>>,
+    size_mb: usize,
+    generation: u8,
+}
+
+impl TranspositionTable {
+    /// A table taking `mb` megabytes, at least one.
+    pub fn new(mb: usize) -> Self {
+        let mb = mb.max(1);
+        TranspositionTable {
+            slots: vec![None; slots_in(mb)],
+            size_mb: mb,
+            generation: 0,
+        }
+    }
+
+    /// Reallocate to `mb` megabytes, keeping what fits: entries are moved
+    /// over, and where two land in the same slot the deeper one stays.
+    pub fn resize(&mut self, mb: usize) {
+        let mut resized = TranspositionTable::new(mb);
+        resized.generation = self.generation;
+        for slot in self.slots.iter().flatten() {
+            let index = resized.index(slot.key);
+            let keep = match resized.slots[index] {
+                Some(old) => slot.entry.depth > old.entry.depth,
+                None => true,
+            };
+            if keep {
+                resized.slots[index] = Some(*slot);
+            }
+        }
+        *self = resized;
+    }
+
+    pub fn size_mb(&self) -> usize {
+        self.size_mb
+    }
+
+    /// Forget everything, as for a new game.
+    pub fn clear(&mut self) {
+        self.slots.fill(None);
+        self.generation = 0;
+    }
+
+    /// Mark the start of a new search. Entries from earlier ones are still
+    /// found, but give way to new ones however deep they were searched.
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    pub fn probe(&self, hash: u64) -> Option<TtEntry> {
+        self.slots[self.index(hash)]
+            .filter(|slot| slot.key == hash)
+            .map(|slot| slot.entry)
+    }
+
+    /// Store `entry` unless the slot holds something more useful: a deeper
+    /// result for another position, from this same search. A result for the
+    /// same position without a best move keeps the move already there.
+    pub fn store(&mut self, hash: u64, mut entry: TtEntry) {
+        let index = self.index(hash);
+        if let Some(old) = self.slots[index] {
+            if old.key == hash {
+                entry.best_move = entry.best_move.or(old.entry.best_move);
+            } else if old.generation == self.generation && old.entry.depth > entry.depth {
+                return;
+            }
+        }
+        self.slots[index] = Some(Slot {
+            key: hash,
+            entry,
+            generation: self.generation,
+        });
+    }
+
+    /// How full the table is with this search's entries, in permille, from
+    /// the first thousand slots, as UCI's `hashfull` reports it.
+    pub fn hashfull(&self) -> u32 {
+        let sample = &self.slots[..self.slots.len().min(1000)];
+        let used = sample
+            .iter()
+            .flatten()
+            .filter(|slot| slot.generation == self.generation)
+            .count();
+        (used * 1000 / sample.len()) as u32
+    }
+
+    // the hash scaled onto the slots, so any number of them can be used
+    fn index(&self, hash: u64) -> usize {
+        ((hash as u128 * self.slots.len() as u128) >> 64) as usize
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_MB)
+    }
+}
+
+fn slots_in(mb: usize) -> usize {
+    (mb << 20) / std::mem::size_of::<Option<Slot>>()
+}
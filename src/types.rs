@@ -1,5 +1,7 @@
 use crate::bitboard::Square;
 use std::fmt;
+use std::mem::MaybeUninit;
+use std::ops::{Index, IndexMut};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Color {
@@ -16,6 +18,54 @@ impl Color {
     }
 }
 
+/// One `T` per color, indexed by `Color`, so per-side data needs no
+/// `if color == Color::White` branch.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ByColor<T>(pub [T; 2]);
+
+impl<T> ByColor<T> {
+    pub const fn new(white: T, black: T) -> Self {
+        ByColor([white, black])
+    }
+
+    /// White's entry, then black's.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.0.iter()
+    }
+}
+
+impl<T> Index<Color> for ByColor<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, color: Color) -> &T {
+        &self.0[color as usize]
+    }
+}
+
+impl<T> IndexMut<Color> for ByColor<T> {
+    #[inline]
+    fn index_mut(&mut self, color: Color) -> &mut T {
+        &mut self.0[color as usize]
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl GameResult {
+    pub fn win_for(color: Color) -> Self {
+        match color {
+            Color::White => GameResult::WhiteWins,
+            Color::Black => GameResult::BlackWins,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum PieceType {
     Pawn,
@@ -26,38 +76,130 @@ pub enum PieceType {
     King,
 }
 
+impl PieceType {
+    /// Every piece type in index order, pawn to king. Also cheapest first.
+    pub const ALL: [PieceType; 6] = [
+        PieceType::Pawn,
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+        PieceType::King,
+    ];
+
+    pub fn iter() -> impl Iterator<Item = PieceType> {
+        Self::ALL.into_iter()
+    }
+
+    /// The piece type stored at `index` in per-piece arrays. Panics if
+    /// `index` is 6 or more.
+    #[inline]
+    pub fn from_index(index: usize) -> PieceType {
+        Self::ALL[index]
+    }
+
+    /// Position in per-piece arrays such as `ByPiece`.
+    #[inline]
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// One `T` per piece type, indexed by `PieceType`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ByPiece<T>(pub [T; 6]);
+
+impl<T> ByPiece<T> {
+    /// Entries in `PieceType::ALL` order.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.0.iter()
+    }
+}
+
+impl<T> Index<PieceType> for ByPiece<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, pt: PieceType) -> &T {
+        &self.0[pt.index()]
+    }
+}
+
+impl<T> IndexMut<PieceType> for ByPiece<T> {
+    #[inline]
+    fn index_mut(&mut self, pt: PieceType) -> &mut T {
+        &mut self.0[pt.index()]
+    }
+}
+
+/// What kind of move a `Move` is, stored in its top 4 bits. Bit 2 marks
+/// captures and bit 3 promotions; the low two bits of a promotion pick the
+/// piece (knight, bishop, rook, queen).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[repr(u16)]
+pub enum MoveFlag {
+    #[default]
+    Quiet = 0b0000,
+    DoublePawnPush = 0b0001,
+    KingCastle = 0b0010,
+    QueenCastle = 0b0011,
+    Capture = 0b0100,
+    EnPassant = 0b0101,
+    KnightPromo = 0b1000,
+    BishopPromo = 0b1001,
+    RookPromo = 0b1010,
+    QueenPromo = 0b1011,
+    KnightPromoCapture = 0b1100,
+    BishopPromoCapture = 0b1101,
+    RookPromoCapture = 0b1110,
+    QueenPromoCapture = 0b1111,
+}
+
+impl MoveFlag {
+    /// Quiet promotions, knight to queen.
+    pub const PROMOTIONS: [MoveFlag; 4] = [
+        MoveFlag::KnightPromo,
+        MoveFlag::BishopPromo,
+        MoveFlag::RookPromo,
+        MoveFlag::QueenPromo,
+    ];
+    /// Capturing promotions, knight to queen.
+    pub const PROMOTION_CAPTURES: [MoveFlag; 4] = [
+        MoveFlag::KnightPromoCapture,
+        MoveFlag::BishopPromoCapture,
+        MoveFlag::RookPromoCapture,
+        MoveFlag::QueenPromoCapture,
+    ];
+
+    /// The piece a pawn promotes to, if this is a promotion.
+    pub fn promotion_piece(self) -> Option<PieceType> {
+        match self {
+            MoveFlag::KnightPromo | MoveFlag::KnightPromoCapture => Some(PieceType::Knight),
+            MoveFlag::BishopPromo | MoveFlag::BishopPromoCapture => Some(PieceType::Bishop),
+            MoveFlag::RookPromo | MoveFlag::RookPromoCapture => Some(PieceType::Rook),
+            MoveFlag::QueenPromo | MoveFlag::QueenPromoCapture => Some(PieceType::Queen),
+            MoveFlag::Quiet
+            | MoveFlag::DoublePawnPush
+            | MoveFlag::KingCastle
+            | MoveFlag::QueenCastle
+            | MoveFlag::Capture
+            | MoveFlag::EnPassant => None,
+        }
+    }
+}
+
 /// A compact chess move stored in 16 bits.
 /// Layout: [4 flag bits][6 from square][6 to square]
-#[derive(Copy, Clone, PartialEq, Eq, Default)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default)]
 pub struct Move(u16);
 
 impl Move {
-    // Move type flags stored in the top 4 bits
-    pub const QUIET: u16 = 0b0000;
-    pub const DOUBLE_PAWN_PUSH: u16 = 0b0001;
-    pub const K_CASTLE: u16 = 0b0010;
-    pub const Q_CASTLE: u16 = 0b0011;
-    pub const CAPTURE: u16 = 0b0100;
-    pub const EP_CAPTURE: u16 = 0b0101;
-
-    // Promotions (knight, bishop, rook, queen)
-    pub const N_PROMO: u16 = 0b1000;
-    pub const B_PROMO: u16 = 0b1001;
-    pub const R_PROMO: u16 = 0b1010;
-    pub const Q_PROMO: u16 = 0b1011;
-
-    // Captures with promotion
-    pub const N_PROMO_CAP: u16 = 0b1100;
-    pub const B_PROMO_CAP: u16 = 0b1101;
-    pub const R_PROMO_CAP: u16 = 0b1110;
-    pub const Q_PROMO_CAP: u16 = 0b1111;
-
-    /// Pack from, to, and flags into 16 bits.
-    #[inline]
-    pub fn new(from: Square, to: Square, flag: u16) -> Self {
+    /// Pack from, to, and flag into 16 bits.
+    #[inline]
+    pub fn new(from: Square, to: Square, flag: MoveFlag) -> Self {
         let from_bits = (from as u16) << 6;
         let to_bits = to as u16;
-        Move(flag << 12 | from_bits | to_bits)
+        Move((flag as u16) << 12 | from_bits | to_bits)
     }
 
     /// Extract the source square.
@@ -74,8 +216,26 @@ impl Move {
 
     /// Get the move type flag.
     #[inline]
-    pub fn flag(&self) -> u16 {
-        self.0 >> 12
+    pub fn flag(&self) -> MoveFlag {
+        match self.0 >> 12 {
+            0b0000 => MoveFlag::Quiet,
+            0b0001 => MoveFlag::DoublePawnPush,
+            0b0010 => MoveFlag::KingCastle,
+            0b0011 => MoveFlag::QueenCastle,
+            0b0100 => MoveFlag::Capture,
+            0b0101 => MoveFlag::EnPassant,
+            0b1000 => MoveFlag::KnightPromo,
+            0b1001 => MoveFlag::BishopPromo,
+            0b1010 => MoveFlag::RookPromo,
+            0b1011 => MoveFlag::QueenPromo,
+            0b1100 => MoveFlag::KnightPromoCapture,
+            0b1101 => MoveFlag::BishopPromoCapture,
+            0b1110 => MoveFlag::RookPromoCapture,
+            0b1111 => MoveFlag::QueenPromoCapture,
+            // the top bits only ever come from a MoveFlag in new(), or are
+            // zero from default()
+            bits => unreachable!("invalid move flag {:#06b}", bits),
+        }
     }
 
     /// Is this a capture?
@@ -89,51 +249,127 @@ impl Move {
     pub fn is_promotion(&self) -> bool {
         (self.0 & 0b1000_0000_0000_0000) != 0
     }
+
+    /// The piece a pawn promotes to, if this is a promotion.
+    pub fn promotion_piece(&self) -> Option<PieceType> {
+        self.flag().promotion_piece()
+    }
 }
 
 impl fmt::Debug for Move {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}{:?}", self.from(), self.to())?;
-        match self.flag() {
-            Self::N_PROMO | Self::N_PROMO_CAP => write!(f, "n"),
-            Self::B_PROMO | Self::B_PROMO_CAP => write!(f, "b"),
-            Self::R_PROMO | Self::R_PROMO_CAP => write!(f, "r"),
-            Self::Q_PROMO | Self::Q_PROMO_CAP => write!(f, "q"),
-            _ => Ok(()),
+        match self.promotion_piece() {
+            Some(PieceType::Knight) => write!(f, "n"),
+            Some(PieceType::Bishop) => write!(f, "b"),
+            Some(PieceType::Rook) => write!(f, "r"),
+            Some(_) => write!(f, "q"),
+            None => Ok(()),
+        }
+    }
+}
+
+// UCI long algebraic notation: e2e4, e7e8q
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.from(), self.to())?;
+        match self.promotion_piece() {
+            Some(PieceType::Knight) => write!(f, "n"),
+            Some(PieceType::Bishop) => write!(f, "b"),
+            Some(PieceType::Rook) => write!(f, "r"),
+            Some(_) => write!(f, "q"),
+            None => Ok(()),
         }
     }
 }
 
 /// A stack-allocated move list. Much faster than Vec for perft.
+/// Slots past `count` are left uninitialized, so creating one is free.
 pub struct MoveList {
-    pub moves: [Move; 256],
-    pub count: usize,
+    moves: [MaybeUninit<Move>; 256],
+    count: usize,
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MoveList {
     pub fn new() -> Self {
         MoveList {
-            moves: [Move::default(); 256],
+            moves: [const { MaybeUninit::uninit() }; 256],
             count: 0,
         }
     }
 
     #[inline]
     pub fn push(&mut self, m: Move) {
-        self.moves[self.count] = m;
+        self.moves[self.count].write(m);
         self.count += 1;
     }
 
+    /// Drop all moves, keeping the storage for reuse.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.count = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// The filled moves.
+    #[inline]
+    pub fn as_slice(&self) -> &[Move] {
+        // SAFETY: push() initializes slots in order, so the first `count`
+        // are initialized, and MaybeUninit<Move> has Move's layout
+        unsafe { std::slice::from_raw_parts(self.moves.as_ptr().cast::<Move>(), self.count) }
+    }
+
+    /// The filled moves, e.g. for sorting in place.
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [Move] {
+        // SAFETY: as for as_slice
+        unsafe {
+            std::slice::from_raw_parts_mut(self.moves.as_mut_ptr().cast::<Move>(), self.count)
+        }
+    }
+
     /// Iterate over only the filled moves
     #[inline]
     pub fn iter(&self) -> std::slice::Iter<'_, Move> {
-        self.moves[0..self.count].iter()
+        self.as_slice().iter()
+    }
+}
+
+// Space-separated UCI moves, e.g. for logging a generated list
+impl fmt::Display for MoveList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, m) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", m)?;
+        }
+        Ok(())
     }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct CastlingRights(pub u8);
 
+impl Default for CastlingRights {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // Castling rights bit layout:
 // bit 0 = white kingside (K)
 // bit 1 = white queenside (Q)
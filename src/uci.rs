@@ -0,0 +1,836 @@
+//! UCI front end: reads commands line by line and answers on `out`. Plugs the
+//! engine into GUIs such as Arena, Cute Chess and Banksia.
+
+use crate::adjudication::{MAX_CONCESSION_SCORE, MAX_RESIGN_MOVES};
+use crate::board::{Board, START_FEN};
+use crate::build_info;
+use crate::config;
+use crate::contempt::MAX_CONTEMPT;
+use crate::engine::{DEFAULT_DEPTH, Engine};
+use crate::eval::Hce;
+use crate::mcts::Mcts;
+use crate::nnue::{Network, Nnue};
+use crate::params::{self, PARAMS};
+use crate::search::{self, AlphaBeta, INFINITY, Limits, Progress, RootMove, SearchResult};
+use crate::strength;
+use crate::timeman::{self, ClockState};
+use crate::tt::{Bound, MAX_HASH_MB};
+use crate::types::{ByColor, Color};
+use crate::variety::{MAX_VARIETY_MARGIN, MAX_VARIETY_MOVES};
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const MAX_NODES_TIME: u64 = 100_000;
+/// The most lines MultiPV can ask for.
+pub const MAX_MULTIPV: usize = 64;
+
+/// State kept between UCI commands.
+///
+/// ```
+/// use ananke::uci::Uci;
+///
+/// let mut out = Vec::new();
+/// let script = "position startpos moves f2f3 e7e5 g2g4\ngo depth 2\n";
+/// Uci::new().run(script.as_bytes(), &mut out).unwrap();
+/// let out = String::from_utf8(out).unwrap();
+/// assert!(out.contains("score mate 1"));
+/// assert!(out.ends_with("bestmove d8h4\n"));
+/// ```
+pub struct Uci {
+    engine: Engine,
+    /// UCI_LimitStrength and UCI_Elo; together they set the node budget.
+    limit_strength: bool,
+    elo: u32,
+    /// The rating from UCI_Opponent, when the GUI knows it.
+    opponent_elo: Option<u32>,
+    /// nodestime: when set, this many nodes are a millisecond and the
+    /// clocks in `go` are virtual.
+    nodes_time: Option<u64>,
+    /// Move Overhead: time kept back on every move, off the clocks in `go`.
+    move_overhead: Duration,
+    /// RootMoveStats: after each search, a line on every root move with
+    /// the nodes it took and its score.
+    root_move_stats: bool,
+    /// SearchStats: after each search, a line with its counters and one
+    /// with how often the engine has guessed the reply this game.
+    search_stats: bool,
+    /// UCI_ShowCurrLine: report the line being searched as it goes.
+    show_current_line: bool,
+    /// MultiPV: how many of the best lines to search and report.
+    multipv: usize,
+    /// UCI_AnalyseMode: the GUI is analysing rather than playing, so no
+    /// contempt, no clock, no variety, and no resigning or draw offers.
+    analyse_mode: bool,
+    /// Set by a `stop` after the `go` being handled, when running under
+    /// `run`; None when lines are handed to `handle` one by one.
+    stop: Option<Arc<AtomicBool>>,
+}
+
+impl Default for Uci {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Uci {
+    pub fn new() -> Self {
+        Uci {
+            engine: Engine::new(),
+            limit_strength: false,
+            elo: 1500,
+            opponent_elo: None,
+            nodes_time: None,
+            move_overhead: timeman::MOVE_OVERHEAD,
+            root_move_stats: false,
+            search_stats: false,
+            show_current_line: false,
+            multipv: 1,
+            analyse_mode: false,
+            stop: None,
+        }
+    }
+
+    /// The position set by the last `position` command.
+    pub fn board(&self) -> &Board {
+        self.engine.board()
+    }
+
+    /// Run until `quit` or end of input. Input is read on its own thread,
+    /// so that a `stop` (or `quit`, or the end of input) can end the search
+    /// started by the `go` before it, and an `isready` is answered while
+    /// that search is still running.
+    pub fn run(&mut self, input: impl BufRead + Send, out: impl Write + Send) -> io::Result<()> {
+        let out = Mutex::new(out);
+        thread::scope(|scope| {
+            let (lines, received) = mpsc::channel();
+            let shared = &out;
+            scope.spawn(move || read_commands(input, lines, shared));
+            let mut out = Lines {
+                out: &out,
+                pending: Vec::new(),
+            };
+            for message in received {
+                let (line, stop) = message?;
+                self.stop = stop.clone();
+                let more = self.handle(&line, &mut out)?;
+                // the search is over, so isready goes back in the queue
+                if let Some(stop) = stop {
+                    stop.store(true, Ordering::Relaxed);
+                }
+                out.flush()?;
+                if !more {
+                    break;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Handle one command line. Returns false on `quit`.
+    pub fn handle(&mut self, line: &str, out: &mut impl Write) -> io::Result<bool> {
+        let mut words = line.split_whitespace();
+        let Some(command) = words.next() else {
+            return Ok(true);
+        };
+        let rest = line.trim_start()[command.len()..].trim();
+
+        match command {
+            "uci" => {
+                writeln!(out, "{}", build_info::uci_id())?;
+                writeln!(
+                    out,
+                    "option name UCI_LimitStrength type check default {}",
+                    self.limit_strength
+                )?;
+                writeln!(
+                    out,
+                    "option name UCI_Elo type spin default {} min {} max {}",
+                    self.elo,
+                    strength::MIN_ELO,
+                    strength::MAX_ELO
+                )?;
+                let contempt = self.engine.contempt();
+                writeln!(
+                    out,
+                    "option name Contempt type spin default {} min {} max {}",
+                    contempt.base, -MAX_CONTEMPT, MAX_CONTEMPT
+                )?;
+                writeln!(
+                    out,
+                    "option name DynamicContempt type check default {}",
+                    contempt.dynamic
+                )?;
+                writeln!(
+                    out,
+                    "option name UCI_AnalyseMode type check default {}",
+                    self.analyse_mode
+                )?;
+                writeln!(
+                    out,
+                    "option name MultiPV type spin default {} min 1 max {}",
+                    self.multipv, MAX_MULTIPV
+                )?;
+                let variety = self.engine.variety();
+                writeln!(
+                    out,
+                    "option name Variety type spin default {} min 0 max {}",
+                    variety.margin, MAX_VARIETY_MARGIN
+                )?;
+                writeln!(
+                    out,
+                    "option name VarietyMoves type spin default {} min 1 max {}",
+                    variety.moves, MAX_VARIETY_MOVES
+                )?;
+                writeln!(
+                    out,
+                    "option name VarietySeed type spin default {} min 0 max {}",
+                    variety.seed,
+                    u32::MAX
+                )?;
+                writeln!(out, "option name UCI_Opponent type string default <empty>")?;
+                writeln!(out, "option name EvalFile type string default <empty>")?;
+                writeln!(
+                    out,
+                    "option name Search type combo default {} var alphabeta var mcts",
+                    self.engine.searcher().name()
+                )?;
+                writeln!(
+                    out,
+                    "option name nodestime type spin default 0 min 0 max {}",
+                    MAX_NODES_TIME
+                )?;
+                writeln!(
+                    out,
+                    "option name Move Overhead type spin default {} min 0 max {}",
+                    self.move_overhead.as_millis(),
+                    timeman::MAX_MOVE_OVERHEAD.as_millis()
+                )?;
+                writeln!(
+                    out,
+                    "option name Hash type spin default {} min 1 max {}",
+                    self.engine.hash_size(),
+                    MAX_HASH_MB
+                )?;
+                writeln!(
+                    out,
+                    "option name Deterministic type check default {}",
+                    self.engine.deterministic()
+                )?;
+                writeln!(
+                    out,
+                    "option name RootMoveStats type check default {}",
+                    self.root_move_stats
+                )?;
+                writeln!(
+                    out,
+                    "option name SearchStats type check default {}",
+                    self.search_stats
+                )?;
+                writeln!(
+                    out,
+                    "option name UCI_ShowCurrLine type check default {}",
+                    self.show_current_line
+                )?;
+                let rules = self.engine.concession_rules();
+                writeln!(
+                    out,
+                    "option name ResignScore type spin default {} min 0 max {}",
+                    rules.resign_score.unwrap_or(0),
+                    MAX_CONCESSION_SCORE
+                )?;
+                writeln!(
+                    out,
+                    "option name ResignMoves type spin default {} min 1 max {}",
+                    rules.resign_moves, MAX_RESIGN_MOVES
+                )?;
+                writeln!(
+                    out,
+                    "option name AcceptDraws type check default {}",
+                    rules.accept_draws
+                )?;
+                writeln!(
+                    out,
+                    "option name AcceptDrawScore type spin default {} min {} max {}",
+                    rules.accept_draw_score, -MAX_CONCESSION_SCORE, MAX_CONCESSION_SCORE
+                )?;
+                writeln!(out, "option name ConfigFile type string default <empty>")?;
+                let current = self.engine.params();
+                for param in PARAMS {
+                    writeln!(
+                        out,
+                        "option name {} type spin default {} min {} max {}",
+                        param.name,
+                        param.get(current),
+                        param.min,
+                        param.max
+                    )?;
+                }
+                writeln!(out, "uciok")?;
+            }
+            "isready" => writeln!(out, "readyok")?,
+            "setoption" => match self.set_option(rest) {
+                Ok(Some(message)) | Err(message) => writeln!(out, "info string {}", message)?,
+                Ok(None) => {}
+            },
+            "ucinewgame" => self.engine.new_game(),
+            "position" => {
+                if let Err(e) = self.set_position(rest) {
+                    writeln!(out, "info string {}", e)?;
+                }
+            }
+            "go" => self.go(rest, out)?,
+            // the input thread already stopped the search it was meant for
+            "stop" => {}
+            "quit" => return Ok(false),
+            _ => writeln!(out, "info string unknown command: {}", command)?,
+        }
+        Ok(true)
+    }
+
+    // depth, nodes, movetime and the clocks are honoured, and mate N looks
+    // N moves deep. Under `run`, infinite searches until stop and only then
+    // answers; otherwise it, like a go with no limits at all, runs to the
+    // default depth. With nodestime set, movetime and the clocks are in
+    // virtual milliseconds; with Deterministic set, or in UCI_AnalyseMode,
+    // they are ignored. Each finished iteration gets an info line, and so,
+    // once the search has run for a second, does each root move as it's
+    // started. With MultiPV above 1 each line gets its own info lines,
+    // numbered, at every depth.
+    fn go(&mut self, args: &str, out: &mut impl Write) -> io::Result<()> {
+        let (mut depth, mut nodes, mut movetime, mut mate) = (None, None, None, None);
+        let (mut times, mut increments) = (ByColor::<Duration>::default(), ByColor::default());
+        let (mut moves_to_go, mut infinite) = (None, false);
+        let mut words = args.split_whitespace();
+        while let Some(word) = words.next() {
+            let mut number = || words.next().and_then(|n| n.parse::<u64>().ok());
+            match word {
+                "depth" => depth = number().map(|d| d.min(u8::MAX as u64) as u8),
+                "nodes" => nodes = number(),
+                "movetime" => movetime = number().map(Duration::from_millis),
+                "mate" => mate = number().filter(|&n| n > 0),
+                "wtime" => times[Color::White] = Duration::from_millis(number().unwrap_or(0)),
+                "btime" => times[Color::Black] = Duration::from_millis(number().unwrap_or(0)),
+                "winc" => increments[Color::White] = Duration::from_millis(number().unwrap_or(0)),
+                "binc" => increments[Color::Black] = Duration::from_millis(number().unwrap_or(0)),
+                "movestogo" => moves_to_go = number().map(|n| n as u32),
+                "infinite" => infinite = self.stop.is_some(),
+                _ => {}
+            }
+        }
+        // mate in n moves is 2n - 1 plies away
+        if let Some(moves) = mate {
+            let plies = moves
+                .saturating_mul(2)
+                .saturating_sub(1)
+                .min(u8::MAX as u64) as u8;
+            depth = Some(depth.map_or(plies, |d| d.min(plies)));
+        }
+
+        let us = self.board().side_to_move;
+        let time = if let Some(movetime) = movetime {
+            Some(timeman::fixed_with_overhead(movetime, self.move_overhead))
+        } else if !times[us].is_zero() {
+            Some(timeman::allocate_with_overhead(
+                &ClockState {
+                    time: times[us],
+                    increment: increments[us],
+                    opponent_time: times[us.opposite()],
+                    opponent_increment: increments[us.opposite()],
+                    moves_to_go,
+                },
+                self.move_overhead,
+            ))
+        } else {
+            None
+        };
+        // a deterministic search doesn't look at the clock at all, and
+        // analysis goes on until it's told to stop
+        let time = time.filter(|_| !self.engine.deterministic() && !self.analyse_mode);
+        let limited =
+            infinite || nodes.is_some() || time.is_some() || self.engine.node_budget().is_some();
+        let limits = Limits {
+            depth: depth.unwrap_or(if limited { u8::MAX } else { DEFAULT_DEPTH }),
+            nodes,
+            time,
+            // the engine fills it in
+            contempt: 0,
+            nodes_time: self.nodes_time,
+            stop: self.stop.clone(),
+            // the engine fills it in
+            deterministic: false,
+            excluded: Vec::new(),
+            current_line: self.show_current_line,
+        };
+
+        let board = self.board().clone();
+        let mut written = Ok(());
+        if self.multipv > 1 {
+            let lines = self
+                .engine
+                .think_multipv(limits, self.multipv, &mut |line, result| {
+                    if written.is_ok() {
+                        written =
+                            write_info(out, &board, result, Some(line)).and_then(|_| out.flush());
+                    }
+                });
+            written?;
+            self.wait_for_stop(infinite);
+            return match lines.first().and_then(|best| best.best_move) {
+                Some(m) => writeln!(out, "bestmove {}", m),
+                None => writeln!(out, "bestmove 0000"),
+            };
+        }
+        let mut last = None;
+        let result = self.engine.think_with_info(limits, &mut |progress| {
+            if written.is_ok() {
+                written = write_progress(out, &board, progress).and_then(|_| out.flush());
+            }
+            if let Progress::Result(report) = progress {
+                last = Some(report.clone());
+            }
+        });
+        written?;
+        // the final answer can come from a partial iteration, or from no
+        // search at all when there are no moves
+        if last.as_ref() != Some(&result) {
+            write_info(out, &board, &result, None)?;
+        }
+        if self.root_move_stats {
+            write_root_moves(out, &result.root_moves)?;
+        }
+        if self.search_stats {
+            writeln!(
+                out,
+                "info string stats recaptureextensions {} pawnpushextensions {}",
+                result.stats.recapture_extensions, result.stats.pawn_push_extensions
+            )?;
+            let predictions = self.engine.predictions();
+            writeln!(
+                out,
+                "info string predictions {} hits {}",
+                predictions.made, predictions.hits
+            )?;
+        }
+        // UCI has no way to resign or offer a draw, so the GUI is only told
+        // what ResignScore and AcceptDraws would have the engine do
+        if result.best_move.is_some() && !self.analyse_mode {
+            if self.engine.should_resign() {
+                writeln!(out, "info string resign")?;
+            } else if self.engine.offer_draw() {
+                writeln!(out, "info string offer draw")?;
+            }
+        }
+        self.wait_for_stop(infinite);
+        match result.best_move {
+            Some(m) => writeln!(out, "bestmove {}", m),
+            None => writeln!(out, "bestmove 0000"),
+        }
+    }
+
+    // infinite never answers before it's told to stop
+    fn wait_for_stop(&self, infinite: bool) {
+        if infinite && let Some(stop) = &self.stop {
+            while !stop.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+
+    /// Set every option and search parameter in the config file at `path`,
+    /// as `setoption` would, in order. Stops at the first that can't be
+    /// set, with an error naming its line.
+    pub fn load_config(&mut self, path: &str) -> Result<usize, String> {
+        let entries = config::load(path)?;
+        for entry in &entries {
+            if entry.name.eq_ignore_ascii_case("ConfigFile") {
+                return Err(format!(
+                    "{}:{}: a config file can't load another",
+                    path, entry.line
+                ));
+            }
+            self.apply_option(&entry.name, &entry.value)
+                .map_err(|e| format!("{}:{}: {}", path, entry.line, e))?;
+        }
+        Ok(entries.len())
+    }
+
+    // "name <name> [value <value>]"; option names are case-insensitive.
+    // Some options have something to tell the GUI once they're set
+    fn set_option(&mut self, args: &str) -> Result<Option<String>, String> {
+        let args = args.strip_prefix("name").unwrap_or(args).trim();
+        let (name, value) = match args.split_once(" value ") {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => (args, ""),
+        };
+        self.apply_option(name, value)
+    }
+
+    fn apply_option(&mut self, name: &str, value: &str) -> Result<Option<String>, String> {
+        let mut contempt = self.engine.contempt();
+        let mut rules = self.engine.concession_rules();
+        let mut variety = self.engine.variety();
+        let mut message = None;
+        match name.to_ascii_lowercase().as_str() {
+            "uci_limitstrength" => {
+                self.limit_strength = value
+                    .parse()
+                    .map_err(|_| format!("Invalid UCI_LimitStrength: {}", value))?;
+            }
+            "uci_elo" => {
+                self.elo = value
+                    .parse()
+                    .map_err(|_| format!("Invalid UCI_Elo: {}", value))?;
+            }
+            "contempt" => {
+                let base: i32 = value
+                    .parse()
+                    .map_err(|_| format!("Invalid Contempt: {}", value))?;
+                contempt.base = base.clamp(-MAX_CONTEMPT, MAX_CONTEMPT);
+            }
+            "dynamiccontempt" => {
+                contempt.dynamic = value
+                    .parse()
+                    .map_err(|_| format!("Invalid DynamicContempt: {}", value))?;
+            }
+            "uci_analysemode" => {
+                self.analyse_mode = value
+                    .parse()
+                    .map_err(|_| format!("Invalid UCI_AnalyseMode: {}", value))?;
+                contempt.enabled = !self.analyse_mode;
+                variety.enabled = !self.analyse_mode;
+            }
+            "multipv" => {
+                let lines: usize = value
+                    .parse()
+                    .map_err(|_| format!("Invalid MultiPV: {}", value))?;
+                self.multipv = lines.clamp(1, MAX_MULTIPV);
+            }
+            // 0 always plays the best move
+            "variety" => {
+                let margin: i32 = value
+                    .parse()
+                    .map_err(|_| format!("Invalid Variety: {}", value))?;
+                variety.margin = margin.clamp(0, MAX_VARIETY_MARGIN);
+            }
+            "varietymoves" => {
+                let moves: u32 = value
+                    .parse()
+                    .map_err(|_| format!("Invalid VarietyMoves: {}", value))?;
+                variety.moves = moves.clamp(1, MAX_VARIETY_MOVES);
+            }
+            "varietyseed" => {
+                variety.seed = value
+                    .parse()
+                    .map_err(|_| format!("Invalid VarietySeed: {}", value))?;
+            }
+            // "<title> <elo> <computer|human> <name>", with "none" for
+            // whatever isn't known
+            "uci_opponent" => {
+                self.opponent_elo = value
+                    .split_whitespace()
+                    .nth(1)
+                    .and_then(|elo| elo.parse().ok())
+            }
+            // a network file for NNUE; empty goes back to the built-in eval
+            "evalfile" => {
+                if value.is_empty() || value == "<empty>" {
+                    self.engine.set_evaluator(Box::new(Hce::default()));
+                } else {
+                    let network = Network::load(value)?;
+                    self.engine
+                        .set_evaluator(Box::new(Nnue::new(Arc::new(network))));
+                }
+            }
+            "search" => match value.to_ascii_lowercase().as_str() {
+                "alphabeta" => self.engine.set_searcher(Box::new(AlphaBeta::new())),
+                "mcts" => self.engine.set_searcher(Box::new(Mcts::new())),
+                _ => return Err(format!("Unknown search: {}", value)),
+            },
+            // 0 turns it off
+            "nodestime" => {
+                let nodes: u64 = value
+                    .parse()
+                    .map_err(|_| format!("Invalid nodestime: {}", value))?;
+                self.nodes_time = (nodes > 0).then_some(nodes.min(MAX_NODES_TIME));
+            }
+            "rootmovestats" => {
+                self.root_move_stats = value
+                    .parse()
+                    .map_err(|_| format!("Invalid RootMoveStats: {}", value))?;
+            }
+            "searchstats" => {
+                self.search_stats = value
+                    .parse()
+                    .map_err(|_| format!("Invalid SearchStats: {}", value))?;
+            }
+            "uci_showcurrline" => {
+                self.show_current_line = value
+                    .parse()
+                    .map_err(|_| format!("Invalid UCI_ShowCurrLine: {}", value))?;
+            }
+            "deterministic" => {
+                let deterministic = value
+                    .parse()
+                    .map_err(|_| format!("Invalid Deterministic: {}", value))?;
+                self.engine.set_deterministic(deterministic);
+            }
+            // only ever handled between searches, so the table is idle
+            "hash" => {
+                let mb: usize = value
+                    .parse()
+                    .map_err(|_| format!("Invalid Hash: {}", value))?;
+                let mb = mb.clamp(1, MAX_HASH_MB);
+                message = Some(match self.engine.set_hash_size(mb) {
+                    0 => format!("Hash {} MB, for the alphabeta search", mb),
+                    size => format!("Hash {} MB", size),
+                });
+            }
+            "move overhead" => {
+                let ms: u64 = value
+                    .parse()
+                    .map_err(|_| format!("Invalid Move Overhead: {}", value))?;
+                self.move_overhead = Duration::from_millis(ms).min(timeman::MAX_MOVE_OVERHEAD);
+            }
+            // 0 never resigns
+            "resignscore" => {
+                let score: i32 = value
+                    .parse()
+                    .map_err(|_| format!("Invalid ResignScore: {}", value))?;
+                let score = score.clamp(0, MAX_CONCESSION_SCORE);
+                rules.resign_score = (score > 0).then_some(score);
+            }
+            "resignmoves" => {
+                let moves: u32 = value
+                    .parse()
+                    .map_err(|_| format!("Invalid ResignMoves: {}", value))?;
+                rules.resign_moves = moves.clamp(1, MAX_RESIGN_MOVES);
+            }
+            "acceptdraws" => {
+                rules.accept_draws = value
+                    .parse()
+                    .map_err(|_| format!("Invalid AcceptDraws: {}", value))?;
+            }
+            "acceptdrawscore" => {
+                let score: i32 = value
+                    .parse()
+                    .map_err(|_| format!("Invalid AcceptDrawScore: {}", value))?;
+                rules.accept_draw_score = score.clamp(-MAX_CONCESSION_SCORE, MAX_CONCESSION_SCORE);
+            }
+            "configfile" => {
+                if !value.is_empty() && value != "<empty>" {
+                    let count = self.load_config(value)?;
+                    message = Some(format!("ConfigFile {}: {} settings", value, count));
+                }
+            }
+            _ if params::find(name).is_some() => self.engine.set_param(name, value)?,
+            _ => return Err(format!("Unknown option: {}", name)),
+        }
+        let budget = self
+            .limit_strength
+            .then(|| strength::nodes_for_elo(self.elo));
+        self.engine.set_node_budget(budget);
+        // at full strength our rating is the top of the UCI_Elo range
+        let ours = if self.limit_strength {
+            self.elo
+        } else {
+            strength::MAX_ELO
+        };
+        contempt.ratings = self.opponent_elo.map(|theirs| (ours, theirs));
+        self.engine.set_contempt(contempt);
+        self.engine.set_concession_rules(rules);
+        self.engine.set_variety(variety);
+        Ok(message)
+    }
+
+    // "startpos [moves ...]" or "fen <fen> [moves ...]"; the position is
+    // only replaced if everything parses
+    fn set_position(&mut self, args: &str) -> Result<(), String> {
+        let (setup, moves) = match args.split_once("moves") {
+            Some((setup, moves)) => (setup.trim(), moves),
+            None => (args.trim(), ""),
+        };
+        let board = if setup == "startpos" {
+            Board::from_fen(START_FEN)?
+        } else if let Some(fen) = setup.strip_prefix("fen") {
+            Board::from_fen(fen.trim())?
+        } else {
+            return Err(format!("Invalid position command: {}", args));
+        };
+        self.engine.set_position(board, moves)
+    }
+}
+
+// "cp <centipawns>", or "mate <moves>" for a forced mate
+fn uci_score(score: i32) -> String {
+    match search::mate_in_moves(score) {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", score),
+    }
+}
+
+// with MultiPV, `multipv` is the line's number, from 1 for the best
+fn write_info(
+    out: &mut impl Write,
+    board: &Board,
+    result: &SearchResult,
+    multipv: Option<usize>,
+) -> io::Result<()> {
+    write!(out, "info depth {}", result.depth)?;
+    if let Some(line) = multipv {
+        write!(out, " multipv {}", line)?;
+    }
+    write!(
+        out,
+        " score {} nodes {}",
+        uci_score(result.score),
+        result.nodes
+    )?;
+    // each move checked against the position it is played in, so a line
+    // that has gone wrong is cut off rather than sent on to the GUI
+    let mut board = board.clone();
+    for (i, m) in result.pv.iter().enumerate() {
+        let Ok(m) = board.parse_uci_move(&m.to_string()) else {
+            break;
+        };
+        write!(out, "{}{}", if i == 0 { " pv " } else { " " }, m)?;
+        board = board.make_move(m);
+    }
+    writeln!(out)
+}
+
+// a result as write_info has it; the root move being searched, once the
+// search has gone on for a while; or the line being searched, for
+// UCI_ShowCurrLine
+fn write_progress(out: &mut impl Write, board: &Board, progress: Progress) -> io::Result<()> {
+    match progress {
+        Progress::Result(result) => write_info(out, board, result, None),
+        Progress::CurrMove { mv, number, depth } => writeln!(
+            out,
+            "info depth {} currmove {} currmovenumber {}",
+            depth, mv, number
+        ),
+        Progress::CurrLine(line) => {
+            write!(out, "info currline")?;
+            for m in line {
+                write!(out, " {}", m)?;
+            }
+            writeln!(out)
+        }
+    }
+}
+
+// one "info string rootmove" line per root move, best first; a score that
+// only bounds the move from above says so, as UCI's "upperbound" does
+fn write_root_moves(out: &mut impl Write, root_moves: &[RootMove]) -> io::Result<()> {
+    for root_move in root_moves.iter().filter(|r| r.score > -INFINITY) {
+        write!(
+            out,
+            "info string rootmove {} nodes {} score {}{} pv",
+            root_move.mv,
+            root_move.nodes,
+            uci_score(root_move.score),
+            if root_move.bound == Bound::Upper {
+                " upperbound"
+            } else {
+                ""
+            }
+        )?;
+        for m in &root_move.pv {
+            write!(out, " {}", m)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+// the output of `run`, shared with the input thread, which answers isready
+// itself during a search. Written a whole line at a time, so a readyok
+// can't land in the middle of an info line
+struct Lines<'a, W> {
+    out: &'a Mutex<W>,
+    pending: Vec<u8>,
+}
+
+impl<W: Write> Write for Lines<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        if let Some(end) = self.pending.iter().rposition(|&b| b == b'\n') {
+            self.out.lock().unwrap().write_all(&self.pending[..=end])?;
+            self.pending.drain(..=end);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut out = self.out.lock().unwrap();
+        out.write_all(&self.pending)?;
+        self.pending.clear();
+        out.flush()
+    }
+}
+
+// the input thread: hands each line to `run` along with, for a go, the
+// flag the next stop or quit sets, and `run` sets once the go has answered.
+// Until then isready is answered here, since `run` is busy searching. The
+// end of input stops a go infinite too, which would otherwise never answer;
+// other searches run to their limits as if the input were still open
+fn read_commands(
+    input: impl BufRead,
+    lines: mpsc::Sender<io::Result<(String, Option<Arc<AtomicBool>>)>>,
+    out: &Mutex<impl Write>,
+) {
+    let mut searching: Option<(Arc<AtomicBool>, bool)> = None;
+    for line in input.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                let _ = lines.send(Err(e));
+                break;
+            }
+        };
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or("");
+        let mut stop = None;
+        match command {
+            "go" => {
+                // a go before the last one has answered ends that search,
+                // which would otherwise wait for a stop meant for this one
+                if let Some((flag, _)) = searching.take() {
+                    flag.store(true, Ordering::Relaxed);
+                }
+                let flag = Arc::new(AtomicBool::new(false));
+                searching = Some((flag.clone(), words.any(|w| w == "infinite")));
+                stop = Some(flag);
+            }
+            "stop" | "quit" => {
+                if let Some((flag, _)) = searching.take() {
+                    flag.store(true, Ordering::Relaxed);
+                }
+            }
+            "isready"
+                if searching
+                    .as_ref()
+                    .is_some_and(|(flag, _)| !flag.load(Ordering::Relaxed)) =>
+            {
+                let mut out = out.lock().unwrap();
+                if let Err(e) = writeln!(out, "readyok").and_then(|_| out.flush()) {
+                    let _ = lines.send(Err(e));
+                    return;
+                }
+                continue;
+            }
+            _ => {}
+        }
+        let quit = command == "quit";
+        if lines.send(Ok((line, stop))).is_err() || quit {
+            return;
+        }
+    }
+    if let Some((flag, true)) = searching {
+        flag.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,55 @@
+//! Shared piece values and game-phase weights. Anything that needs to price
+//! material (SEE, move ordering, pruning margins, eval, adjudication) takes its
+//! numbers from here so they can't drift apart.
+
+use crate::board::Board;
+use crate::types::{Color, PieceType};
+
+pub const PAWN: i32 = 100;
+pub const KNIGHT: i32 = 320;
+pub const BISHOP: i32 = 330;
+pub const ROOK: i32 = 500;
+pub const QUEEN: i32 = 900;
+/// Never actually captured; priced high so that "capturing" with the king
+/// last in an exchange never looks free.
+pub const KING: i32 = 20000;
+
+/// Centipawn values indexed by PieceType.
+pub const PIECE_VALUES: [i32; 6] = [PAWN, KNIGHT, BISHOP, ROOK, QUEEN, KING];
+
+/// How much each piece counts towards the middlegame, indexed by PieceType.
+/// The full starting set adds up to `MAX_PHASE`.
+pub const PHASE_WEIGHTS: [i32; 6] = [0, 1, 1, 2, 4, 0];
+pub const MAX_PHASE: i32 = 24;
+
+#[inline]
+pub fn piece_value(pt: PieceType) -> i32 {
+    PIECE_VALUES[pt.index()]
+}
+
+/// What promoting a pawn to `pt` adds to the material balance.
+#[inline]
+pub fn promotion_gain(pt: PieceType) -> i32 {
+    piece_value(pt) - PAWN
+}
+
+/// Material of `color` in centipawns, not counting the king.
+pub fn material(board: &Board, color: Color) -> i32 {
+    PieceType::iter()
+        .filter(|&pt| pt != PieceType::King)
+        .map(|pt| board.pieces(color, pt).count() as i32 * piece_value(pt))
+        .sum()
+}
+
+/// Game phase from `MAX_PHASE` (all pieces on) down to 0 (pawns and kings
+/// only). Capped, so extra promoted queens don't push it past the top.
+pub fn phase(board: &Board) -> i32 {
+    let total: i32 = PieceType::iter()
+        .map(|pt| {
+            let count =
+                board.pieces(Color::White, pt).count() + board.pieces(Color::Black, pt).count();
+            count as i32 * PHASE_WEIGHTS[pt.index()]
+        })
+        .sum();
+    total.min(MAX_PHASE)
+}
@@ -0,0 +1,92 @@
+//! Variety in the opening: for the first few moves of a game the engine
+//! plays any move that scores nearly as well as its best, picked at
+//! random, so that games from the same start, self-play above all, don't
+//! all follow one line. The picks come from a seeded generator, so a run
+//! of games can still be played again move for move. The scores to pick
+//! by come from a MultiPV search: a plain one only bounds the moves that
+//! aren't best.
+
+use crate::board::Board;
+use crate::rng::{DEFAULT_SEED, Rng};
+use crate::search::{SearchResult, is_mate_score};
+
+/// The widest margin the Variety option takes, in centipawns.
+pub const MAX_VARIETY_MARGIN: i32 = 200;
+pub const MAX_VARIETY_MOVES: u32 = 40;
+/// How many of the best moves are searched to pick among.
+pub const VARIETY_LINES: usize = 4;
+
+/// When and how far to stray from the best move. Set from the Variety,
+/// VarietyMoves and VarietySeed options.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct VarietyRules {
+    /// Off for analysis, where the answer is the best move and no other.
+    pub enabled: bool,
+    /// Moves within this many centipawns of the best may be played; 0
+    /// plays the best as always.
+    pub margin: i32,
+    /// Up to this move number of the game.
+    pub moves: u32,
+    pub seed: u32,
+}
+
+impl Default for VarietyRules {
+    fn default() -> Self {
+        VarietyRules {
+            enabled: true,
+            margin: 0,
+            moves: 8,
+            seed: DEFAULT_SEED,
+        }
+    }
+}
+
+impl VarietyRules {
+    /// Whether to pick among the best moves in `board`: early enough in
+    /// the game, with more than one move to pick.
+    pub fn applies(&self, board: &Board) -> bool {
+        self.enabled
+            && self.margin > 0
+            && board.fullmove_number as u32 <= self.moves
+            && board.count_legal_moves() > 1
+    }
+}
+
+/// The generator for picks from `seed`. Seeds that differ only in their
+/// low bits, as the small ones people choose do, start xorshift off on
+/// numbers that differ only in theirs, and the picks go by the high bits.
+/// Multiplying by 2^32 over the golden ratio spreads them out first.
+pub fn rng(seed: u32) -> Rng {
+    Rng::new(seed.wrapping_mul(0x9E37_79B9))
+}
+
+/// One of `lines`, best first, whose score is within `margin` of the best,
+/// all of them as likely; the best itself when it's a mate, either way.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::engine::Engine;
+/// use ananke::rng::Rng;
+/// use ananke::search::Limits;
+/// use ananke::variety::pick;
+///
+/// let mut engine = Engine::new();
+/// engine.set_position(Board::startpos(), "").unwrap();
+/// let lines = engine.think_multipv(Limits::depth(3), 4, &mut |_, _| {});
+/// let mut rng = Rng::new(1);
+/// let picked = pick(&lines, 30, &mut rng).unwrap();
+/// assert!(picked.score >= lines[0].score - 30);
+/// // no margin, no choice
+/// assert_eq!(pick(&lines, 0, &mut rng), lines.first());
+/// ```
+pub fn pick<'a>(lines: &'a [SearchResult], margin: i32, rng: &mut Rng) -> Option<&'a SearchResult> {
+    let best = lines.first()?;
+    if is_mate_score(best.score) {
+        return Some(best);
+    }
+    let close = lines
+        .iter()
+        .take_while(|line| line.score >= best.score - margin)
+        .count();
+    Some(&lines[rng.below(close as u32) as usize])
+}
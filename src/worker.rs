@@ -0,0 +1,167 @@
+//! An engine on a thread of its own, for programs that embed ananke and
+//! can't block while it thinks. The thread lives as long as the `Worker`
+//! and owns the engine outright; commands reach it over a channel, in
+//! order, and what it finds comes back over another. `Uci::run` has the
+//! same shape with the text protocol in front, so it doesn't use this.
+
+use crate::board::Board;
+use crate::engine::Engine;
+use crate::search::{Limits, Progress, SearchResult};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+/// What the worker can be told to do.
+#[derive(Clone)]
+pub enum Command {
+    /// As `Engine::set_position`.
+    SetPosition {
+        board: Board,
+        moves: String,
+    },
+    /// Search the position. The worker keeps the stop flag, so any in
+    /// `limits` is replaced.
+    Go(Limits),
+    /// End the search under way, if there is one.
+    Stop,
+    NewGame,
+    /// End the search under way and the thread with it.
+    Quit,
+}
+
+/// What the worker has to say.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// A better idea of the best move, as the search goes on.
+    Info(SearchResult),
+    /// The search is over, and this is its answer.
+    BestMove(SearchResult),
+    /// A command that couldn't be carried out.
+    Error(String),
+}
+
+/// The handle on the thread. Dropping it quits the thread and waits for it.
+///
+/// ```
+/// use ananke::board::Board;
+/// use ananke::search::Limits;
+/// use ananke::worker::{Command, Event, Worker};
+///
+/// let mut worker = Worker::new();
+/// worker.send(Command::SetPosition {
+///     board: Board::startpos(),
+///     moves: "f2f3 e7e5 g2g4".to_string(),
+/// });
+/// worker.send(Command::Go(Limits::depth(2)));
+/// let best = loop {
+///     if let Event::BestMove(result) = worker.recv().unwrap() {
+///         break result;
+///     }
+/// };
+/// assert_eq!(best.best_move.unwrap().to_string(), "d8h4");
+/// ```
+pub struct Worker {
+    commands: Sender<Command>,
+    events: Receiver<Event>,
+    /// The flag of the last search started, which `Stop` sets.
+    stop: Option<Arc<AtomicBool>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Default for Worker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Worker {
+    /// A worker around a new engine.
+    pub fn new() -> Self {
+        Self::with(Engine::new)
+    }
+
+    /// A worker around the engine `make` builds, on the worker's thread,
+    /// since an engine can't be sent between threads.
+    pub fn with(make: impl FnOnce() -> Engine + Send + 'static) -> Self {
+        let (commands, received) = mpsc::channel();
+        let (sent, events) = mpsc::channel();
+        let thread = thread::spawn(move || serve(make(), received, sent));
+        Worker {
+            commands,
+            events,
+            stop: None,
+            thread: Some(thread),
+        }
+    }
+
+    /// Hand the worker `command`. Stop and quit take effect at once, even
+    /// mid-search; everything else waits its turn. Once the thread has
+    /// quit, commands go nowhere.
+    pub fn send(&mut self, mut command: Command) {
+        match &mut command {
+            // a new search ends the one before it, as a stop would
+            Command::Go(limits) => {
+                self.stop_search();
+                let flag = Arc::new(AtomicBool::new(false));
+                limits.stop = Some(flag.clone());
+                self.stop = Some(flag);
+            }
+            Command::Stop | Command::Quit => self.stop_search(),
+            _ => {}
+        }
+        let _ = self.commands.send(command);
+    }
+
+    /// The next event, waiting for it. None once the thread has quit and
+    /// every event has been taken.
+    pub fn recv(&self) -> Option<Event> {
+        self.events.recv().ok()
+    }
+
+    /// The next event, if there is one already.
+    pub fn try_recv(&self) -> Option<Event> {
+        self.events.try_recv().ok()
+    }
+
+    fn stop_search(&mut self) {
+        if let Some(flag) = self.stop.take() {
+            flag.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.send(Command::Quit);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// the worker's thread: commands in order until quit, or until the handle
+// is gone. Events nobody is listening for are dropped
+fn serve(mut engine: Engine, commands: Receiver<Command>, events: Sender<Event>) {
+    for command in commands {
+        match command {
+            Command::SetPosition { board, moves } => {
+                if let Err(e) = engine.set_position(board, &moves) {
+                    let _ = events.send(Event::Error(e));
+                }
+            }
+            Command::Go(limits) => {
+                let result = engine.think_with_info(limits, &mut |progress| {
+                    if let Progress::Result(result) = progress {
+                        let _ = events.send(Event::Info(result.clone()));
+                    }
+                });
+                let _ = events.send(Event::BestMove(result));
+            }
+            // the handle has set the flag already
+            Command::Stop => {}
+            Command::NewGame => engine.new_game(),
+            Command::Quit => break,
+        }
+    }
+}
@@ -0,0 +1,399 @@
+//! XBoard/CECP front end, for older GUIs and ICS clients. Speaks protocol
+//! version 2 and shares the engine core with the UCI front end.
+
+use crate::adjudication::{MAX_CONCESSION_SCORE, MAX_RESIGN_MOVES};
+use crate::board::Board;
+use crate::build_info;
+use crate::config;
+use crate::contempt::Contempt;
+use crate::engine::{DEFAULT_DEPTH, Engine};
+use crate::params::{self, PARAMS};
+use crate::search::Limits;
+use crate::timeman::{self, ClockState};
+use crate::tt::MAX_HASH_MB;
+use crate::types::{Color, GameResult};
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+/// State kept between xboard commands.
+pub struct Xboard {
+    engine: Engine,
+    /// The side the engine plays, or None in force mode, where it only
+    /// records the moves it is sent.
+    engine_side: Option<Color>,
+    /// Search depth, set with `sd`. Without it the clock decides, or
+    /// `DEFAULT_DEPTH` if there's no clock either.
+    depth: Option<u8>,
+    /// The time control, set with `level`.
+    level: Option<Level>,
+    /// Fixed time per move, set with `st`.
+    move_time: Option<Duration>,
+    /// Our clock and the opponent's, set with `time` and `otim`.
+    time: Option<Duration>,
+    opponent_time: Duration,
+    /// Kept back on every move, set with `option Move Overhead=<ms>`.
+    move_overhead: Duration,
+}
+
+// "level MPS BASE INC"
+#[derive(Debug, Copy, Clone, Default)]
+struct Level {
+    // moves per control, 0 for sudden death
+    moves: u32,
+    base: Duration,
+    increment: Duration,
+}
+
+impl Default for Xboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Xboard {
+    pub fn new() -> Self {
+        Xboard {
+            engine: Engine::new(),
+            engine_side: Some(Color::Black),
+            depth: None,
+            level: None,
+            move_time: None,
+            time: None,
+            opponent_time: Duration::ZERO,
+            move_overhead: timeman::MOVE_OVERHEAD,
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        self.engine.board()
+    }
+
+    /// Run until `quit` or end of input.
+    pub fn run(&mut self, input: impl BufRead, mut out: impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            if !self.handle(&line?, &mut out)? {
+                break;
+            }
+            out.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Handle one command line. Returns false on `quit`.
+    pub fn handle(&mut self, line: &str, out: &mut impl Write) -> io::Result<bool> {
+        let mut words = line.split_whitespace();
+        let Some(command) = words.next() else {
+            return Ok(true);
+        };
+        let rest = line.trim_start()[command.len()..].trim();
+
+        match command {
+            "xboard" => {}
+            "protover" => {
+                write!(
+                    out,
+                    "feature myname=\"{} {}\" usermove=1 setboard=1 ping=1 colors=0 sigint=0 \
+                     sigterm=0 memory=1 option=\"Move Overhead -spin {} 0 {}\"",
+                    build_info::NAME,
+                    build_info::VERSION,
+                    self.move_overhead.as_millis(),
+                    timeman::MAX_MOVE_OVERHEAD.as_millis()
+                )?;
+                let rules = self.engine.concession_rules();
+                write!(
+                    out,
+                    " option=\"ResignScore -spin {} 0 {}\" option=\"ResignMoves -spin {} 1 {}\" \
+                     option=\"AcceptDraws -check {}\" option=\"AcceptDrawScore -spin {} {} {}\"",
+                    rules.resign_score.unwrap_or(0),
+                    MAX_CONCESSION_SCORE,
+                    rules.resign_moves,
+                    MAX_RESIGN_MOVES,
+                    rules.accept_draws as u8,
+                    rules.accept_draw_score,
+                    -MAX_CONCESSION_SCORE,
+                    MAX_CONCESSION_SCORE
+                )?;
+                let current = self.engine.params();
+                for param in PARAMS {
+                    write!(
+                        out,
+                        " option=\"{} -spin {} {} {}\"",
+                        param.name,
+                        param.get(current),
+                        param.min,
+                        param.max
+                    )?;
+                }
+                writeln!(out, " done=1")?;
+            }
+            "new" => {
+                self.engine.new_game();
+                self.engine_side = Some(Color::Black);
+                self.depth = None;
+            }
+            "force" => self.engine_side = None,
+            "go" => {
+                self.engine_side = Some(self.board().side_to_move);
+                self.reply(out)?;
+            }
+            "usermove" => self.user_move(rest, out)?,
+            // the game is over as far as the GUI is concerned: stop playing
+            "result" => self.engine_side = None,
+            // the opponent offers a draw, which offering one back accepts
+            "draw" => {
+                if let Some(us) = self.engine_side
+                    && self.engine.answer_draw_offer(us)
+                {
+                    self.engine_side = None;
+                    writeln!(out, "offer draw")?;
+                }
+            }
+            "setboard" => {
+                if let Err(e) =
+                    Board::from_fen(rest).and_then(|board| self.engine.set_position(board, ""))
+                {
+                    writeln!(out, "tellusererror Illegal position: {}", e)?;
+                }
+            }
+            "ping" => writeln!(out, "pong {}", rest)?,
+            "sd" => match rest.parse() {
+                Ok(depth) => self.depth = Some(depth),
+                Err(_) => writeln!(out, "Error (bad depth): {}", rest)?,
+            },
+            "level" => match parse_level(rest) {
+                Some(level) => {
+                    self.level = Some(level);
+                    self.move_time = None;
+                }
+                None => writeln!(out, "Error (bad level): {}", rest)?,
+            },
+            "st" => match parse_seconds(rest) {
+                Some(move_time) => {
+                    self.move_time = Some(move_time);
+                    self.level = None;
+                }
+                None => writeln!(out, "Error (bad time): {}", rest)?,
+            },
+            // both clocks come in centiseconds; anything past u64::MAX
+            // milliseconds is as good as forever
+            "time" => match rest.parse::<u64>() {
+                Ok(cs) => self.time = Some(Duration::from_millis(cs.saturating_mul(10))),
+                Err(_) => writeln!(out, "Error (bad time): {}", rest)?,
+            },
+            "otim" => match rest.parse::<u64>() {
+                Ok(cs) => self.opponent_time = Duration::from_millis(cs.saturating_mul(10)),
+                Err(_) => writeln!(out, "Error (bad time): {}", rest)?,
+            },
+            "rating" => self.set_ratings(rest),
+            // the megabytes the engine may use, all of which go to the table
+            "memory" => match rest.parse::<usize>() {
+                Ok(mb) => {
+                    self.engine.set_hash_size(mb.clamp(1, MAX_HASH_MB));
+                }
+                Err(_) => writeln!(out, "Error (bad memory): {}", rest)?,
+            },
+            "option" => {
+                if let Err(e) = self.set_option(rest) {
+                    writeln!(out, "Error (bad option): {}", e)?;
+                }
+            }
+            "quit" => return Ok(false),
+            // accepted but not used yet
+            "accepted" | "rejected" | "random" | "post" | "nopost" | "hard" | "easy"
+            | "computer" | "name" => {}
+            // without usermove=1 moves arrive bare
+            _ if self.board().parse_uci_move(command).is_ok() => self.user_move(command, out)?,
+            _ => writeln!(out, "Error (unknown command): {}", command)?,
+        }
+        Ok(true)
+    }
+
+    fn user_move(&mut self, text: &str, out: &mut impl Write) -> io::Result<()> {
+        if self.engine.play_uci(text).is_err() {
+            return writeln!(out, "Illegal move: {}", text);
+        }
+        self.report_result(out)?;
+        if self.engine_side == Some(self.board().side_to_move) {
+            self.reply(out)?;
+        }
+        Ok(())
+    }
+
+    // "rating <ours> <theirs>", from ICS clients; contempt follows the gap.
+    // A rating of 0 means unrated, which tells us nothing
+    fn set_ratings(&mut self, args: &str) {
+        let mut ratings = args.split_whitespace().map(|r| r.parse::<u32>().ok());
+        let ratings = match (ratings.next().flatten(), ratings.next().flatten()) {
+            (Some(ours), Some(theirs)) if ours > 0 && theirs > 0 => Some((ours, theirs)),
+            _ => None,
+        };
+        let contempt = self.engine.contempt();
+        self.engine.set_contempt(Contempt {
+            ratings,
+            ..contempt
+        });
+    }
+
+    /// Set the options and search parameters in the config file at
+    /// `path`, as `option NAME=VALUE` would, in order. Stops at the first
+    /// that can't be set, with an error naming its line.
+    pub fn load_config(&mut self, path: &str) -> Result<usize, String> {
+        let entries = config::load(path)?;
+        for entry in &entries {
+            self.set_option(&format!("{}={}", entry.name, entry.value))
+                .map_err(|e| format!("{}:{}: bad option {}", path, entry.line, e))?;
+        }
+        Ok(entries.len())
+    }
+
+    // "NAME=VALUE", for the options announced in the features
+    fn set_option(&mut self, args: &str) -> Result<(), String> {
+        let (name, value) = args.split_once('=').ok_or_else(|| args.to_string())?;
+        let (name, value) = (name.trim(), value.trim());
+        match name {
+            "Move Overhead" => {
+                let ms: u64 = value.parse().map_err(|_| args.to_string())?;
+                self.move_overhead = Duration::from_millis(ms).min(timeman::MAX_MOVE_OVERHEAD);
+                Ok(())
+            }
+            "ResignScore" | "ResignMoves" | "AcceptDraws" | "AcceptDrawScore" => {
+                let mut rules = self.engine.concession_rules();
+                let number = || value.parse::<i32>().map_err(|_| args.to_string());
+                match name {
+                    // 0 never resigns
+                    "ResignScore" => {
+                        let score = number()?.clamp(0, MAX_CONCESSION_SCORE);
+                        rules.resign_score = (score > 0).then_some(score);
+                    }
+                    "ResignMoves" => {
+                        rules.resign_moves = number()?.clamp(1, MAX_RESIGN_MOVES as i32) as u32
+                    }
+                    // 1 or 0 from the GUI, true or false from a config file
+                    "AcceptDraws" => {
+                        rules.accept_draws = match value {
+                            "1" | "true" => true,
+                            "0" | "false" => false,
+                            _ => return Err(args.to_string()),
+                        }
+                    }
+                    _ => {
+                        rules.accept_draw_score =
+                            number()?.clamp(-MAX_CONCESSION_SCORE, MAX_CONCESSION_SCORE)
+                    }
+                }
+                self.engine.set_concession_rules(rules);
+                Ok(())
+            }
+            _ if params::find(name).is_some() => self
+                .engine
+                .set_param(name, value)
+                .map_err(|_| args.to_string()),
+            _ => Err(args.to_string()),
+        }
+    }
+
+    // think and play a move for the side to move, if the game isn't over,
+    // or resign instead; with a draw offer first, when the concession
+    // rules call for one
+    fn reply(&mut self, out: &mut impl Write) -> io::Result<()> {
+        if self.engine.game().is_over() {
+            return Ok(());
+        }
+        let Some(m) = self.engine.think(self.limits()).best_move else {
+            return Ok(());
+        };
+        if self.engine.should_resign() {
+            self.engine.resign().expect("the game is still on");
+            self.engine_side = None;
+            return writeln!(out, "resign");
+        }
+        // an offer goes with the move it's made before
+        if self.engine.offer_draw() {
+            writeln!(out, "offer draw")?;
+        }
+        let text = m.to_string();
+        self.engine
+            .play_uci(&text)
+            .expect("engine chose an illegal move");
+        writeln!(out, "move {}", text)?;
+        self.report_result(out)
+    }
+
+    /// What the next search is limited by. `st` is a fixed time per move;
+    /// otherwise the time manager splits our clock (from `time`, or the
+    /// level's base time before the GUI sends one) over the moves left in
+    /// the control, as it does for UCI's `wtime` and `btime`. Either way
+    /// the move overhead is kept back.
+    pub fn limits(&self) -> Limits {
+        let time = if let Some(move_time) = self.move_time {
+            Some(timeman::fixed_with_overhead(move_time, self.move_overhead))
+        } else {
+            let level = self.level.unwrap_or_default();
+            let time = self.time.or(self.level.map(|level| level.base));
+            time.map(|time| {
+                // counted from the start of the game, controls repeating
+                let played = self.board().fullmove_number.saturating_sub(1) as u32;
+                let moves_to_go = (level.moves > 0).then(|| level.moves - played % level.moves);
+                timeman::allocate_with_overhead(
+                    &ClockState {
+                        time,
+                        increment: level.increment,
+                        opponent_time: self.opponent_time,
+                        opponent_increment: level.increment,
+                        moves_to_go,
+                    },
+                    self.move_overhead,
+                )
+            })
+        };
+        Limits {
+            time,
+            ..Limits::depth(self.depth.unwrap_or(if time.is_some() {
+                u8::MAX
+            } else {
+                DEFAULT_DEPTH
+            }))
+        }
+    }
+
+    // xboard leaves game-end detection to the engine as well
+    fn report_result(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let game = self.engine.game();
+        let (Some(result), Some(termination)) = (game.result(), game.termination()) else {
+            return Ok(());
+        };
+        let score = match result {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+        };
+        self.engine_side = None;
+        writeln!(out, "{} {{{:?}}}", score, termination)
+    }
+}
+
+// "level MPS BASE INC": moves per control, base time as minutes or
+// minutes:seconds, and the increment in seconds
+fn parse_level(args: &str) -> Option<Level> {
+    let mut words = args.split_whitespace();
+    let moves = words.next()?.parse().ok()?;
+    let base = words.next()?;
+    let (minutes, seconds) = base.split_once(':').unwrap_or((base, "0"));
+    let base = minutes
+        .parse::<u64>()
+        .ok()?
+        .checked_mul(60)?
+        .checked_add(seconds.parse::<u64>().ok()?)?;
+    let increment = parse_seconds(words.next()?)?;
+    Some(Level {
+        moves,
+        base: Duration::from_secs(base),
+        increment,
+    })
+}
+
+// a number of seconds, possibly fractional; None for anything negative,
+// infinite or NaN, or too long for a Duration
+fn parse_seconds(text: &str) -> Option<Duration> {
+    Duration::try_from_secs_f64(text.parse().ok()?).ok()
+}
@@ -0,0 +1,108 @@
+//! Zobrist hashing: one random key per (piece, square), castling rights
+//! combination, en passant file and side to move, XORed together. Boards
+//! keep their hash up to date in `make_move` by XORing the changed keys in
+//! and out, so it costs a handful of XORs per move instead of a rescan.
+
+use crate::bitboard::Square;
+use crate::board::Board;
+use crate::types::{ByColor, ByPiece, CastlingRights, Color, PieceType};
+use std::sync::OnceLock;
+
+// fixed so hashes are the same from run to run (and in saved test data)
+const SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+// SplitMix64. Not `rng::Rng`: xorshift is linear, so every key it makes is
+// a XOR of the same 32 state bits, and with hundreds of keys many sets of
+// them cancel out, giving real positions the same hash.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+struct Keys {
+    pieces: ByColor<ByPiece<[u64; 64]>>,
+    castling: [u64; 16],
+    en_passant: [u64; 8],
+    side: u64,
+}
+
+fn keys() -> &'static Keys {
+    static KEYS: OnceLock<Keys> = OnceLock::new();
+    KEYS.get_or_init(|| {
+        let mut rng = SplitMix64(SEED);
+        let mut keys = Keys {
+            pieces: ByColor([ByPiece([[0; 64]; 6]); 2]),
+            castling: [0; 16],
+            en_passant: [0; 8],
+            side: 0,
+        };
+        for color in [Color::White, Color::Black] {
+            for pt in PieceType::iter() {
+                for key in keys.pieces[color][pt].iter_mut() {
+                    *key = rng.next_u64();
+                }
+            }
+        }
+        // no rights hashes to zero, like an empty board
+        for key in keys.castling[1..].iter_mut() {
+            *key = rng.next_u64();
+        }
+        for key in keys.en_passant.iter_mut() {
+            *key = rng.next_u64();
+        }
+        keys.side = rng.next_u64();
+        keys
+    })
+}
+
+/// Key for a piece standing on a square.
+#[inline]
+pub fn piece_key(color: Color, pt: PieceType, sq: Square) -> u64 {
+    keys().pieces[color][pt][sq as usize]
+}
+
+/// Key for a full set of castling rights.
+#[inline]
+pub fn castling_key(rights: CastlingRights) -> u64 {
+    keys().castling[rights.0 as usize]
+}
+
+/// Key for an en passant square; only the file matters.
+#[inline]
+pub fn en_passant_key(sq: Square) -> u64 {
+    keys().en_passant[sq.file() as usize]
+}
+
+/// XORed in when black is to move.
+#[inline]
+pub fn side_key() -> u64 {
+    keys().side
+}
+
+/// Hash `board` from scratch. `Board::hash` should always equal this.
+pub fn hash(board: &Board) -> u64 {
+    let mut hash = 0;
+    for color in [Color::White, Color::Black] {
+        for pt in PieceType::iter() {
+            let mut bb = board.pieces(color, pt);
+            while let Some(sq) = bb.pop_lsb() {
+                hash ^= piece_key(color, pt, sq);
+            }
+        }
+    }
+    hash ^= castling_key(board.castling_rights);
+    if let Some(sq) = board.en_passant_sq {
+        hash ^= en_passant_key(sq);
+    }
+    if board.side_to_move == Color::Black {
+        hash ^= side_key();
+    }
+    hash
+}
@@ -0,0 +1,65 @@
+//! The node loop (move generation, make_move, legality checks) must not touch
+//! the heap. A counting global allocator catches any regression; it only
+//! counts while `COUNTING` is set, so test harness noise is ignored.
+
+use ananke::board::Board;
+use ananke::perft::perft;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+struct CountingAllocator;
+
+static COUNTING: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if COUNTING.load(Ordering::Relaxed) {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// heap allocations made while running `f`
+fn allocations_during(f: impl FnOnce()) -> usize {
+    ALLOCATIONS.store(0, Ordering::Relaxed);
+    COUNTING.store(true, Ordering::Relaxed);
+    f();
+    COUNTING.store(false, Ordering::Relaxed);
+    ALLOCATIONS.load(Ordering::Relaxed)
+}
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+// one test only: the counter is global, so parallel tests would disturb it
+#[test]
+fn node_loop_does_not_allocate() {
+    let board = Board::from_fen(KIWIPETE).unwrap();
+    // warm up lazily built tables (magics, profiler stacks) outside the count
+    assert_eq!(perft(&board, 3), 97862);
+
+    let mut nodes = 0;
+    let allocations = allocations_during(|| nodes = perft(&board, 4));
+    assert_eq!(nodes, 4085603);
+    // just the per-ply move list buffer
+    assert!(allocations <= 1, "perft allocated {} times", allocations);
+
+    let allocations = allocations_during(|| {
+        for m in board.legal_moves().iter() {
+            let next = board.make_move(*m);
+            std::hint::black_box(next.count_legal_moves());
+            std::hint::black_box(next.in_check());
+            std::hint::black_box(next.legal_moves().len());
+        }
+        std::hint::black_box(board.parse_uci_move("e1g1").ok());
+    });
+    assert_eq!(allocations, 0);
+}
@@ -0,0 +1,161 @@
+//! Analysis: MultiPV in the engine and over UCI, and UCI_AnalyseMode,
+//! which takes the clock, contempt and concessions out of the search.
+
+use ananke::board::Board;
+use ananke::engine::Engine;
+use ananke::search::Limits;
+use ananke::uci::Uci;
+use std::collections::HashSet;
+
+// Black's back rank is weak: a1a8 mates, and the rest don't
+const BACK_RANK: &str = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+
+fn uci(script: &str) -> String {
+    let mut out = Vec::new();
+    Uci::new().run(script.as_bytes(), &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn multipv_lines_are_distinct_and_ordered() {
+    let mut engine = Engine::new();
+    engine.set_deterministic(true);
+    engine.set_position(Board::startpos(), "").unwrap();
+    let mut reported = Vec::new();
+    let lines = engine.think_multipv(Limits::depth(4), 4, &mut |line, result| {
+        reported.push((line, result.depth));
+    });
+    assert_eq!(lines.len(), 4);
+    let moves: HashSet<_> = lines.iter().map(|l| l.best_move.unwrap()).collect();
+    assert_eq!(moves.len(), 4);
+    for pair in lines.windows(2) {
+        assert!(pair[0].score >= pair[1].score);
+    }
+    for line in &lines {
+        assert_eq!(line.depth, 4);
+        assert_eq!(line.pv[0], line.best_move.unwrap());
+    }
+    // every line at every depth, in order
+    let expected: Vec<_> = (1..=4).flat_map(|d| (1..=4).map(move |l| (l, d))).collect();
+    assert_eq!(reported, expected);
+}
+
+#[test]
+fn multipv_has_no_more_lines_than_moves() {
+    // the rook leaves the king only a7
+    let mut engine = Engine::new();
+    engine
+        .set_position(
+            Board::from_fen("k7/8/8/8/8/8/1R6/7K b - - 0 1").unwrap(),
+            "",
+        )
+        .unwrap();
+    let lines = engine.think_multipv(Limits::depth(3), 5, &mut |_, _| {});
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].best_move.unwrap().to_string(), "a8a7");
+    // single line MultiPV is the ordinary search
+    let mut single = Engine::new();
+    single.set_deterministic(true);
+    single
+        .set_position(Board::from_fen(BACK_RANK).unwrap(), "")
+        .unwrap();
+    let lines = single.think_multipv(Limits::depth(3), 1, &mut |_, _| {});
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].best_move.unwrap().to_string(), "a1a8");
+}
+
+#[test]
+fn multipv_holds_to_the_node_limit() {
+    let mut engine = Engine::new();
+    engine.set_position(Board::startpos(), "").unwrap();
+    let lines = engine.think_multipv(
+        Limits {
+            nodes: Some(5_000),
+            ..Limits::depth(u8::MAX)
+        },
+        3,
+        &mut |_, _| {},
+    );
+    assert_eq!(lines.len(), 3);
+    // the last line found carries the total; a search can overshoot its
+    // own limit by a little before it notices
+    let total = lines.iter().map(|l| l.nodes).max().unwrap();
+    assert!(total < 6_000, "{}", total);
+}
+
+#[test]
+fn uci_reports_each_line() {
+    let out = uci(&format!(
+        "uci\nsetoption name MultiPV value 3\nposition fen {}\ngo depth 3\n",
+        BACK_RANK
+    ));
+    assert!(out.contains("option name MultiPV type spin default 1 min 1 max 64\n"));
+    // the last three lines are the last depth's; the mate is found at
+    // depth 1 and isn't searched any deeper
+    let info: Vec<_> = out.lines().filter(|l| l.starts_with("info ")).collect();
+    let last_depth = &info[info.len() - 3..];
+    assert!(last_depth[0].starts_with("info depth 1 multipv 1 score mate 1 "));
+    for (i, line) in last_depth.iter().enumerate().skip(1) {
+        assert!(line.starts_with(&format!("info depth 3 multipv {} score ", i + 1)));
+        assert!(line.contains(" pv "), "{}", line);
+    }
+    assert!(out.ends_with("bestmove a1a8\n"));
+}
+
+#[test]
+fn analyse_mode_ignores_the_clock() {
+    // a millisecond on the clock would stop the search long before depth
+    // 6; analysing, only the depth counts
+    let go = "position startpos\ngo depth 6 wtime 1 btime 1\n";
+    let reached = |out: &str| {
+        out.lines()
+            .rfind(|l| l.starts_with("info depth "))
+            .unwrap()
+            .split_whitespace()
+            .nth(2)
+            .unwrap()
+            .to_string()
+    };
+    assert_ne!(reached(&uci(go)), "6");
+    let analysing = uci(&format!(
+        "setoption name UCI_AnalyseMode value true\n{}",
+        go
+    ));
+    assert_eq!(reached(&analysing), "6");
+}
+
+#[test]
+fn analyse_mode_neither_resigns_nor_offers_draws() {
+    let lost = "4k3/8/8/8/8/8/8/QQ2K3 b - - 0 1";
+    let script = format!(
+        "setoption name ResignScore value 1000\nsetoption name ResignMoves value 1\n\
+         setoption name AcceptDraws value true\n\
+         setoption name UCI_AnalyseMode value true\nposition fen {}\ngo depth 3\n",
+        lost
+    );
+    assert!(!uci(&script).contains("info string"));
+}
+
+#[test]
+fn multipv_lines_are_sorted_at_every_depth() {
+    // the lines are searched one after another, and a later one can come
+    // out ahead; the report has them best first all the same
+    let mut engine = Engine::new();
+    engine.set_deterministic(true);
+    // after 1. e4 e5 2. Nf3 Nc6, where the third line searched at depth 3
+    // beats the second
+    engine
+        .set_position(Board::startpos(), "e2e4 e7e5 g1f3 b8c6")
+        .unwrap();
+    let mut depths: Vec<Vec<i32>> = Vec::new();
+    engine.think_multipv(Limits::depth(4), 3, &mut |line, result| {
+        if line == 1 {
+            depths.push(Vec::new());
+        }
+        depths.last_mut().unwrap().push(result.score);
+    });
+    assert_eq!(depths.len(), 4);
+    for scores in depths {
+        assert!(scores.windows(2).all(|w| w[0] >= w[1]), "{:?}", scores);
+    }
+}
@@ -0,0 +1,85 @@
+//! The async search handle: awaited on a bare executor, cancelled
+//! mid-search, and refusing illegal positions up front.
+
+use ananke::async_search::{search_async, search_async_with};
+use ananke::board::Board;
+use ananke::engine::Engine;
+use ananke::search::Limits;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+// the smallest executor there is: park until woken
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> (F::Output, usize) {
+    let waker = Arc::new(Unpark(thread::current())).into();
+    let mut context = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    let mut polls = 0;
+    loop {
+        polls += 1;
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return (output, polls);
+        }
+        thread::park();
+    }
+}
+
+const BACK_RANK: &str = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+
+#[test]
+fn the_handle_can_be_awaited() {
+    let board = Board::from_fen(BACK_RANK).unwrap();
+    let handle = search_async(board, "", Limits::depth(6)).unwrap();
+    let (result, polls) = block_on(handle);
+    assert_eq!(result.best_move.unwrap().to_string(), "a1a8");
+    // woken when it was done, not polled in a loop
+    assert!(polls <= 3, "{}", polls);
+}
+
+#[test]
+fn cancel_ends_the_search() {
+    let handle = search_async(Board::startpos(), "e2e4", Limits::depth(u8::MAX)).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    assert!(!handle.is_finished());
+    let start = Instant::now();
+    handle.cancel();
+    let result = handle.wait();
+    assert!(start.elapsed() < Duration::from_secs(1));
+    // Black's move, the best found before the stop
+    assert!(result.best_move.is_some());
+    assert!(result.depth < u8::MAX);
+}
+
+#[test]
+fn the_engine_can_be_set_up_first() {
+    let make = || {
+        let mut engine = Engine::new();
+        engine.set_deterministic(true);
+        engine
+    };
+    let first = search_async_with(make, Board::startpos(), "", Limits::depth(4))
+        .unwrap()
+        .wait();
+    let second = search_async_with(make, Board::startpos(), "", Limits::depth(4))
+        .unwrap()
+        .wait();
+    assert_eq!(first, second);
+}
+
+#[test]
+fn illegal_positions_are_refused() {
+    assert!(search_async(Board::startpos(), "e2e5", Limits::depth(1)).is_err());
+    let checked = Board::from_fen("R3k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    assert!(search_async(checked, "", Limits::depth(1)).is_err());
+}
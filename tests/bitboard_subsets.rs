@@ -0,0 +1,38 @@
+//! `Bitboard::subsets` against brute-force enumeration.
+
+use ananke::bitboard::Bitboard;
+use std::collections::HashSet;
+
+// every x in 0..=mask with no bits outside the mask, in increasing order
+fn brute_force_subsets(mask: u64) -> Vec<u64> {
+    (0..=mask).filter(|x| x & !mask == 0).collect()
+}
+
+#[test]
+fn subsets_match_brute_force() {
+    for mask in [0, 1, 0b1011, 0x8001, 0x0F0F, 0x7E, 0x81_8181, 0xFFFF] {
+        let subsets: Vec<u64> = Bitboard::new(mask).subsets().map(|bb| bb.0).collect();
+        assert_eq!(subsets, brute_force_subsets(mask), "mask {:#x}", mask);
+    }
+}
+
+#[test]
+fn subsets_of_a_rook_mask_are_distinct_and_complete() {
+    // d4 rook blocker mask: the d-file and 4th rank without edges or d4
+    let mask = 0x0008_0808_7608_0800u64;
+    let subsets: Vec<u64> = Bitboard::new(mask).subsets().map(|bb| bb.0).collect();
+    assert_eq!(subsets.len(), 1 << mask.count_ones());
+    assert!(subsets.iter().all(|s| s & !mask == 0));
+    assert_eq!(subsets.iter().collect::<HashSet<_>>().len(), subsets.len());
+    assert_eq!(subsets.first(), Some(&0));
+    assert_eq!(subsets.last(), Some(&mask));
+}
+
+#[test]
+fn subsets_of_high_and_full_sets() {
+    let top = Bitboard::new(1 << 63).subsets().map(|bb| bb.0);
+    assert_eq!(top.collect::<Vec<_>>(), vec![0, 1 << 63]);
+    // the full board would be 2^64 subsets; just check it starts and steps right
+    let mut full = Bitboard::UNIVERSE.subsets().map(|bb| bb.0);
+    assert_eq!(full.by_ref().take(4).collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+}
@@ -0,0 +1,176 @@
+//! Resigning and draw offers by ananke's own scores: the engine core, the
+//! options that set the thresholds in both front ends, xboard's `draw`,
+//! `resign` and `offer draw`, and matches played with them.
+
+use ananke::adjudication::ConcessionRules;
+use ananke::board::Board;
+use ananke::engine::Engine;
+use ananke::game::Termination;
+use ananke::search::Limits;
+use ananke::strength::play_match_conceding;
+use ananke::types::{Color, GameResult};
+use ananke::uci::Uci;
+use ananke::xboard::Xboard;
+
+// black to move, two queens down, and the reverse
+const LOST: &str = "4k3/8/8/8/8/8/8/QQ2K3 b - - 0 1";
+const WON: &str = "4K3/8/8/8/8/8/8/qq2k3 b - - 0 1";
+
+fn xboard(script: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    Xboard::new().run(script.as_bytes(), &mut out).unwrap();
+    String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn the_engine_resigns_by_its_rules() {
+    let mut engine = Engine::new();
+    engine
+        .set_position(Board::from_fen(LOST).unwrap(), "")
+        .unwrap();
+    engine.think(Limits::depth(3));
+    assert!(!engine.should_resign(), "no rules, no resigning");
+
+    engine.set_concession_rules(ConcessionRules {
+        resign_score: Some(1000),
+        resign_moves: 2,
+        ..ConcessionRules::default()
+    });
+    engine.think(Limits::depth(3));
+    assert!(!engine.should_resign(), "one move isn't enough");
+    engine.think(Limits::depth(3));
+    assert!(engine.should_resign());
+    engine.resign().unwrap();
+    assert_eq!(engine.game().result(), Some(GameResult::WhiteWins));
+    assert_eq!(engine.game().termination(), Some(Termination::Resignation));
+
+    // a new game starts the count over, under the same rules
+    engine.new_game();
+    assert!(!engine.should_resign());
+    assert_eq!(engine.concession_rules().resign_score, Some(1000));
+}
+
+#[test]
+fn the_engine_takes_a_draw_only_when_it_should() {
+    let rules = ConcessionRules {
+        accept_draws: true,
+        ..ConcessionRules::default()
+    };
+    let mut engine = Engine::new();
+    engine.set_concession_rules(rules);
+    engine
+        .set_position(Board::from_fen(WON).unwrap(), "")
+        .unwrap();
+    engine.think(Limits::depth(3));
+    assert!(!engine.offer_draw());
+    assert!(!engine.answer_draw_offer(Color::Black));
+    assert!(!engine.game().is_over());
+
+    engine
+        .set_position(Board::from_fen(LOST).unwrap(), "")
+        .unwrap();
+    engine.think(Limits::depth(3));
+    assert!(engine.offer_draw());
+    assert_eq!(engine.game().draw_offer(), Some(Color::Black));
+    assert!(engine.answer_draw_offer(Color::Black));
+    assert_eq!(engine.game().result(), Some(GameResult::Draw));
+    assert_eq!(engine.game().termination(), Some(Termination::Agreement));
+}
+
+#[test]
+fn uci_options_set_the_rules() {
+    let mut uci = Uci::new();
+    let mut out = Vec::new();
+    uci.handle("uci", &mut out).unwrap();
+    let options = String::from_utf8(out).unwrap();
+    for option in [
+        "option name ResignScore type spin default 0 min 0 max 10000\n",
+        "option name ResignMoves type spin default 5 min 1 max 100\n",
+        "option name AcceptDraws type check default false\n",
+        "option name AcceptDrawScore type spin default 0 min -10000 max 10000\n",
+    ] {
+        assert!(options.contains(option), "{}", option);
+    }
+
+    // UCI can't resign, but the GUI hears that the engine would
+    let script = format!(
+        "setoption name ResignScore value 1000\nsetoption name ResignMoves value 1\n\
+         position fen {}\ngo depth 3\n",
+        LOST
+    );
+    let mut out = Vec::new();
+    Uci::new().run(script.as_bytes(), &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let resign = out.find("info string resign\n").unwrap();
+    assert!(resign < out.find("bestmove").unwrap());
+
+    let mut out = Vec::new();
+    let script = format!("position fen {}\ngo depth 3\n", LOST);
+    Uci::new().run(script.as_bytes(), &mut out).unwrap();
+    assert!(!String::from_utf8(out).unwrap().contains("info string"));
+}
+
+#[test]
+fn xboard_resigns() {
+    let out = xboard("xboard\nprotover 2\n");
+    assert!(out[0].contains(
+        " option=\"ResignScore -spin 0 0 10000\" option=\"ResignMoves -spin 5 1 100\" \
+         option=\"AcceptDraws -check 0\" option=\"AcceptDrawScore -spin 0 -10000 10000\""
+    ));
+
+    let out = xboard(&format!(
+        "new\nforce\nsetboard {}\noption ResignScore=1000\noption ResignMoves=1\ngo\n",
+        LOST
+    ));
+    assert_eq!(out, ["resign"]);
+    // without the option it plays on
+    let out = xboard(&format!("new\nforce\nsetboard {}\ngo\n", LOST));
+    assert!(out[0].starts_with("move "));
+}
+
+#[test]
+fn xboard_offers_and_accepts_draws() {
+    // losing, it offers a draw with its move, and takes one when offered
+    let out = xboard(&format!(
+        "new\nforce\nsetboard {}\noption AcceptDraws=1\ngo\ndraw\n",
+        LOST
+    ));
+    assert_eq!(out.len(), 3);
+    assert_eq!(out[0], "offer draw");
+    assert!(out[1].starts_with("move "));
+    assert_eq!(out[2], "offer draw");
+
+    // winning, it does neither
+    let out = xboard(&format!(
+        "new\nforce\nsetboard {}\noption AcceptDraws=1\ngo\ndraw\n",
+        WON
+    ));
+    assert_eq!(out.len(), 1);
+    assert!(out[0].starts_with("move "));
+
+    let out = xboard("option AcceptDraws=maybe\noption ResignScore=lots\n");
+    assert_eq!(
+        out,
+        [
+            "Error (bad option): AcceptDraws=maybe",
+            "Error (bad option): ResignScore=lots"
+        ]
+    );
+}
+
+#[test]
+fn matches_end_by_agreement() {
+    // both sides take any draw, so every game ends on the second move
+    let rules = ConcessionRules {
+        accept_draws: true,
+        accept_draw_score: 10_000,
+        ..ConcessionRules::default()
+    };
+    let score = play_match_conceding(64, 64, 2, 1, rules);
+    assert_eq!(score.games(), 4);
+    assert_eq!(score.draws, 4);
+}
@@ -0,0 +1,159 @@
+//! Config files: the TOML subset they're written in, and loading one into
+//! the UCI and xboard front ends, with `ConfigFile` or `--config`.
+
+use ananke::config::parse;
+use ananke::params::{PARAMS, Params};
+use ananke::uci::Uci;
+use ananke::xboard::Xboard;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// a config file of its own for each test, so they can run side by side
+fn write_config(name: &str, text: &str) -> String {
+    let path = std::env::temp_dir().join(format!("ananke-{}-{}.toml", name, std::process::id()));
+    std::fs::write(&path, text).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+const TUNING: &str = "\
+# a tuning run
+Hash = 8
+\"Move Overhead\" = 25   # ms
+
+[params]
+FutilityMargin = 180
+LateMoveBase = +6
+";
+
+#[test]
+fn the_subset_of_toml() {
+    let entries = parse(TUNING).unwrap();
+    let settings: Vec<(&str, &str, usize)> = entries
+        .iter()
+        .map(|e| (e.name.as_str(), e.value.as_str(), e.line))
+        .collect();
+    assert_eq!(
+        settings,
+        [
+            ("Hash", "8", 2),
+            ("Move Overhead", "25", 3),
+            ("FutilityMargin", "180", 6),
+            ("LateMoveBase", "+6", 7)
+        ]
+    );
+    let entries =
+        parse("EvalFile = \"nets/a \\\"b\\\".nnue\" # quoted\nDeterministic = true").unwrap();
+    assert_eq!(entries[0].value, "nets/a \"b\".nnue");
+    assert_eq!(entries[1].value, "true");
+
+    for (text, error) in [
+        ("Hash = 8\nHash 8", "2: expected name = value"),
+        ("= 8", "1: expected name = value"),
+        ("Hash = lots", "1: bad value: lots"),
+        ("Hash = 8 16", "1: unexpected: 16"),
+        ("EvalFile = \"open", "1: unterminated string"),
+        ("[params", "1: bad section header"),
+    ] {
+        assert_eq!(parse(text).unwrap_err(), error, "{}", text);
+    }
+}
+
+#[test]
+fn every_param_is_a_uci_option() {
+    let mut uci = Uci::new();
+    let mut out = Vec::new();
+    uci.handle("uci", &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let defaults = Params::default();
+    for param in PARAMS {
+        let option = format!(
+            "option name {} type spin default {} min {} max {}\n",
+            param.name,
+            param.get(&defaults),
+            param.min,
+            param.max
+        );
+        assert!(out.contains(&option), "{}", option);
+    }
+}
+
+#[test]
+fn config_file_option_sets_everything_in_it() {
+    let path = write_config("uci", TUNING);
+    let mut uci = Uci::new();
+    let mut out = Vec::new();
+    uci.handle(
+        &format!("setoption name ConfigFile value {}", path),
+        &mut out,
+    )
+    .unwrap();
+    uci.handle("uci", &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with(&format!("info string ConfigFile {}: 4 settings\n", path)));
+    assert!(out.contains("option name Hash type spin default 8 "));
+    assert!(out.contains("option name Move Overhead type spin default 25 "));
+    assert!(out.contains("option name FutilityMargin type spin default 180 "));
+    assert!(out.contains("option name LateMoveBase type spin default 6 "));
+}
+
+#[test]
+fn a_bad_setting_names_its_line() {
+    let path = write_config("bad", "Hash = 8\n\nFutilityMargin = 5000\n");
+    let mut uci = Uci::new();
+    assert_eq!(
+        uci.load_config(&path).unwrap_err(),
+        format!("{}:3: Invalid FutilityMargin: 5000 (from 0 to 1000)", path)
+    );
+    let path = write_config("nested", "ConfigFile = \"other.toml\"\n");
+    assert!(
+        uci.load_config(&path)
+            .unwrap_err()
+            .ends_with(":1: a config file can't load another")
+    );
+    assert!(uci.load_config("/nonexistent/ananke.toml").is_err());
+}
+
+#[test]
+fn xboard_takes_a_config_file_too() {
+    let path = write_config("xboard", "\"Move Overhead\" = 40\nFutilityMargin = 90\n");
+    let mut xboard = Xboard::new();
+    assert_eq!(xboard.load_config(&path), Ok(2));
+    let mut out = Vec::new();
+    xboard.handle("protover 2", &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("option=\"Move Overhead -spin 40 0 "));
+    assert!(out.contains("option=\"FutilityMargin -spin 90 0 1000\""));
+
+    let path = write_config("xboard-bad", "Hash = 8\n");
+    assert_eq!(
+        Xboard::new().load_config(&path).unwrap_err(),
+        format!("{}:1: bad option Hash=8", path)
+    );
+}
+
+#[test]
+fn config_flag_applies_before_the_first_command() {
+    let path = write_config("flag", "FutilityMargin = 175\n");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ananke"))
+        .args(["--config", &path])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"uci\nquit\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("option name FutilityMargin type spin default 175 "));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_ananke"))
+        .args(["--config", "/nonexistent/ananke.toml"])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
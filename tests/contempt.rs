@@ -0,0 +1,180 @@
+//! Contempt: the value from ratings and trend, draws scored by it in search,
+//! the UCI options that set it, and adjudication reading drawn scores as
+//! level again.
+
+use ananke::adjudication::{AdjudicationRules, Adjudicator, ConcessionRules, Concessions};
+use ananke::board::Board;
+use ananke::contempt::{Contempt, MAX_CONTEMPT};
+use ananke::search::{Limits, search_with};
+use ananke::types::{Color, GameResult};
+use ananke::uci::Uci;
+
+// every quiet white move runs out the fifty-move clock; only Rxa6 plays on,
+// into a level rook ending
+const CLOCK_RUNNING_OUT: &str = "4k2r/8/p7/8/R7/8/8/4K3 w - - 99 80";
+
+fn search(fen: &str, depth: u8, contempt: i32) -> (String, i32) {
+    let board = Board::from_fen(fen).unwrap();
+    let result = search_with(
+        &board,
+        Limits {
+            contempt,
+            ..Limits::depth(depth)
+        },
+    );
+    (result.best_move.unwrap().to_string(), result.score)
+}
+
+#[test]
+fn value_adds_up_and_stays_in_range() {
+    let base = Contempt::default();
+    assert_eq!(base.value(&[500, 600]), base.base);
+    // the weaker side welcomes draws
+    let weaker = Contempt {
+        ratings: Some((1800, 2200)),
+        ..base
+    };
+    assert_eq!(weaker.value(&[]), base.base - 40);
+    // huge gaps, long winning streaks and mates are all capped
+    let everything = Contempt {
+        base: 80,
+        ratings: Some((3000, 1000)),
+        dynamic: true,
+        ..base
+    };
+    assert_eq!(everything.value(&[30_000; 10]), MAX_CONTEMPT);
+    let losing = Contempt {
+        base: 0,
+        dynamic: true,
+        ..base
+    };
+    assert_eq!(losing.value(&[-400, -400, -400, -400]), -50);
+    // only the latest moves count
+    assert_eq!(losing.value(&[-900, -900, 0, 0, 0, 0]), 0);
+}
+
+#[test]
+fn analysis_mode_ignores_everything_else() {
+    let analysis = Contempt {
+        enabled: false,
+        base: 50,
+        ratings: Some((2800, 1200)),
+        dynamic: true,
+    };
+    assert_eq!(analysis.value(&[300, 300]), 0);
+}
+
+#[test]
+fn draws_score_minus_contempt_for_the_root_side() {
+    for contempt in [-30, 0, 30] {
+        // king and rook against king: nothing mates in time, so it's a draw
+        let (_, score) = search("4k3/8/8/8/8/8/8/R3K3 w - - 99 80", 2, contempt);
+        assert_eq!(score, -contempt);
+        // the same from black's side
+        let (_, score) = search("r3k3/8/8/8/8/8/8/4K3 b - - 99 80", 2, contempt);
+        assert_eq!(score, -contempt);
+    }
+}
+
+#[test]
+fn contempt_decides_between_a_draw_and_playing_on() {
+    let (best, _) = search(CLOCK_RUNNING_OUT, 3, MAX_CONTEMPT);
+    assert_eq!(best, "a4a6");
+    let (best, score) = search(CLOCK_RUNNING_OUT, 3, -MAX_CONTEMPT);
+    assert_ne!(best, "a4a6");
+    assert_eq!(score, MAX_CONTEMPT);
+}
+
+fn uci_score(script: &str) -> String {
+    let mut uci = Uci::new();
+    let mut out = Vec::new();
+    uci.run(script.as_bytes(), &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    // the last report is the final answer
+    let info = out.lines().rfind(|l| l.starts_with("info depth")).unwrap();
+    let score = info.split(" score ").nth(1).unwrap();
+    score.split(" nodes").next().unwrap().to_string()
+}
+
+#[test]
+fn uci_options_set_contempt() {
+    let go = "position fen 4k3/8/8/8/8/8/8/R3K3 w - - 99 80\ngo depth 2\n";
+    assert_eq!(uci_score(go), "cp -10");
+    let script = format!("setoption name Contempt value 25\n{}", go);
+    assert_eq!(uci_score(&script), "cp -25");
+    let script = format!("setoption name UCI_AnalyseMode value true\n{}", go);
+    assert_eq!(uci_score(&script), "cp 0");
+
+    // at full strength against a 1000: the rating part is at its cap
+    let script = format!(
+        "setoption name UCI_Opponent value none 1000 human someone\n{}",
+        go
+    );
+    assert_eq!(uci_score(&script), "cp -60");
+    // playing as a 1500 ourselves it goes away again
+    let script = format!(
+        "setoption name UCI_Opponent value none 1500 human someone\n\
+         setoption name UCI_LimitStrength value true\n\
+         setoption name UCI_Elo value 1500\n{}",
+        go
+    );
+    assert_eq!(uci_score(&script), "cp -10");
+    // no rating known
+    let script = format!(
+        "setoption name UCI_Opponent value none none computer x\n{}",
+        go
+    );
+    assert_eq!(uci_score(&script), "cp -10");
+}
+
+#[test]
+fn uci_lists_the_options() {
+    let mut out = Vec::new();
+    Uci::new().run("uci\n".as_bytes(), &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    for option in [
+        "Contempt",
+        "DynamicContempt",
+        "UCI_AnalyseMode",
+        "UCI_Opponent",
+    ] {
+        let prefix = format!("option name {} type", option);
+        assert!(out.lines().any(|l| l.starts_with(&prefix)), "{}", option);
+    }
+}
+
+#[test]
+fn adjudication_reads_contempt_draws_as_level() {
+    let rules = AdjudicationRules {
+        draw_move_number: 0,
+        draw_moves: 2,
+        ..AdjudicationRules::default()
+    };
+    // both engines see the dead draw, each through its own contempt
+    let play = |adjudicator: &mut Adjudicator| {
+        let mut result = None;
+        for _ in 0..4 {
+            result = result.or(adjudicator.record(Color::White, -30));
+            result = result.or(adjudicator.record(Color::Black, 20));
+        }
+        result
+    };
+    assert_eq!(play(&mut Adjudicator::new(rules)), None);
+    let mut adjudicator = Adjudicator::new(rules);
+    adjudicator.set_contempt(Color::White, 30);
+    adjudicator.set_contempt(Color::Black, -20);
+    assert_eq!(play(&mut adjudicator), Some(GameResult::Draw));
+
+    let rules = ConcessionRules {
+        accept_draws: true,
+        ..ConcessionRules::default()
+    };
+    // a negative contempt scores draws above zero: still a draw
+    let mut concessions = Concessions::new(rules);
+    concessions.set_contempt(-20);
+    concessions.record(20);
+    assert!(concessions.accept_draw());
+    let mut concessions = Concessions::new(rules);
+    concessions.record(20);
+    assert!(!concessions.accept_draw());
+}
@@ -0,0 +1,119 @@
+//! Corpus dedup and filtering over small FEN/EPD and PGN inputs.
+
+use ananke::corpus::{CorpusStats, Filter, filter_lines, positions_from_pgn};
+
+fn run(input: &str, filter: &Filter) -> (Vec<String>, CorpusStats) {
+    let mut out = Vec::new();
+    let stats = filter_lines(input.as_bytes(), &mut out, filter).unwrap();
+    let lines = String::from_utf8(out).unwrap();
+    (lines.lines().map(str::to_string).collect(), stats)
+}
+
+#[test]
+fn duplicates_go_by_position_not_text() {
+    let input = "\
+rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 [0.5]
+# the same position with other counters and another label
+rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 12 30 [1.0]
+rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - bm e4;
+# but not with the other side to move
+rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1 [0.5]
+
+not a fen at all
+";
+    let (lines, stats) = run(input, &Filter::default());
+    assert_eq!(
+        lines,
+        [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 [0.5]",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1 [0.5]",
+        ]
+    );
+    assert_eq!(
+        stats,
+        CorpusStats {
+            read: 5,
+            invalid: 1,
+            duplicates: 2,
+            filtered: 0,
+            written: 2,
+        }
+    );
+}
+
+#[test]
+fn filters_on_check_pieces_and_eval() {
+    let input = "\
+4k3/8/8/8/8/8/8/4K2R b K - 0 1
+4k3/4R3/8/8/8/8/8/4K3 b - - 0 1
+4k3/pppp4/8/8/8/8/PPPP4/4K3 w - - 0 1
+4k3/8/8/8/8/8/8/QQQQK3 b - - 0 1
+";
+    let filter = Filter {
+        skip_in_check: true,
+        ..Filter::default()
+    };
+    let (lines, _) = run(input, &filter);
+    assert_eq!(lines.len(), 3);
+    assert!(!lines.iter().any(|l| l.starts_with("4k3/4R3")));
+
+    let filter = Filter {
+        pieces: Some(2..=4),
+        ..Filter::default()
+    };
+    let (lines, stats) = run(input, &filter);
+    assert_eq!(lines.len(), 2);
+    assert_eq!(stats.filtered, 2);
+
+    // balanced positions only: black is lost in the first and last, the
+    // rook in the second just gets taken
+    let filter = Filter {
+        eval: Some(-200..=200),
+        eval_depth: 2,
+        ..Filter::default()
+    };
+    let (lines, _) = run(input, &filter);
+    assert_eq!(
+        lines,
+        [
+            "4k3/4R3/8/8/8/8/8/4K3 b - - 0 1",
+            "4k3/pppp4/8/8/8/8/PPPP4/4K3 w - - 0 1"
+        ]
+    );
+}
+
+#[test]
+fn pgn_positions_are_labelled_with_the_result() {
+    let input = "\
+[Result \"0-1\"]
+
+1. f3 e5 2. g4 Qh4# 0-1
+
+1. e4 Ke7 1-0
+
+[Result \"1/2-1/2\"]
+
+1. f3 e6 1/2-1/2
+
+1. e4 f6 2. d4 1-0
+
+1. d4 *
+";
+    let mut out = Vec::new();
+    let stats = positions_from_pgn(input.as_bytes(), &mut out, &Filter::default()).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+
+    // four from the first game, none from the illegal second, none from the
+    // third (the start and 1. f3 were seen already), two from the fourth
+    // and none from the unfinished last one
+    assert_eq!(lines.len(), 6);
+    assert_eq!(
+        lines[0],
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 [0.0]"
+    );
+    assert!(lines[..4].iter().all(|l| l.ends_with(" [0.0]")));
+    assert!(lines[4..].iter().all(|l| l.ends_with(" [1.0]")));
+    assert_eq!(stats.duplicates, 3);
+    assert_eq!(stats.invalid, 1);
+}
@@ -0,0 +1,66 @@
+//! Deterministic mode: the same position and limits give the same search,
+//! whatever the clock says and whatever was searched before.
+
+use ananke::board::Board;
+use ananke::search::{Limits, search_with};
+use ananke::timeman::TimeBudget;
+use ananke::uci::Uci;
+use std::time::Duration;
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+#[test]
+fn same_limits_same_result() {
+    let board = Board::from_fen(KIWIPETE).unwrap();
+    let limits = Limits::deterministic(u8::MAX, Some(20_000));
+    let first = search_with(&board, limits.clone());
+    assert_eq!(first.nodes, 20_000);
+    assert_eq!(search_with(&board, limits.clone()), first);
+
+    // a budget far too short to finish anything makes no difference
+    let instant = Duration::from_nanos(1);
+    let timed = Limits {
+        time: Some(TimeBudget {
+            optimum: instant,
+            maximum: instant,
+        }),
+        ..limits
+    };
+    assert_eq!(search_with(&board, timed), first);
+}
+
+fn session(script: &str) -> String {
+    let mut out = Vec::new();
+    Uci::new().run(script.as_bytes(), &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn uci_option_ignores_clocks_and_history() {
+    assert!(session("uci\n").contains("option name Deterministic type check default false"));
+
+    let go = format!(
+        "setoption name Deterministic value true\n\
+         position fen {}\n\
+         go wtime 1 btime 1 nodes 5000\n",
+        KIWIPETE
+    );
+    let fresh = session(&go);
+    assert!(fresh.contains(" nodes 5000 "), "{}", fresh);
+    // dynamic contempt, which would otherwise follow these earlier scores
+    let after_a_game = session(&format!(
+        "setoption name DynamicContempt value true\n\
+         position startpos moves e2e4\n\
+         go depth 3\n\
+         position startpos moves e2e4 e7e5 g1f3\n\
+         go depth 3\n\
+         {}",
+        go
+    ));
+    assert!(
+        after_a_game.ends_with(&fresh),
+        "{}\n{}",
+        after_a_game,
+        fresh
+    );
+}
@@ -0,0 +1,25 @@
+//! Pawn double pushes for both colors, counted by perft against the
+//! reference numbers.
+
+use ananke::board::Board;
+use ananke::magic;
+use ananke::perft::perft;
+
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const AFTER_E4: &str = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+
+#[test]
+fn double_pushes_match_perft() {
+    magic::initialize();
+    for (fen, depth, nodes) in [
+        // 16 pawn moves, half of them double pushes, and 4 knight moves
+        (START, 1, 20),
+        (START, 3, 8902),
+        (AFTER_E4, 1, 20),
+        // e2 is blocked outright and f4 is taken, so f3 is the only pawn move
+        ("4k3/8/8/8/5n2/4N3/4PP2/4K3 w - - 0 1", 1, 12),
+    ] {
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(perft(&board, depth), nodes, "{} depth {}", fen, depth);
+    }
+}
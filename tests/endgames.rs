@@ -0,0 +1,102 @@
+//! The endgame specialists: which material gets one, mating a bare king
+//! with the mop-up, and the rook and pawn ending's two textbook positions.
+
+use ananke::board::Board;
+use ananke::endgame::Endgame;
+use ananke::engine::Engine;
+use ananke::eval::evaluate;
+use ananke::game::Termination;
+use ananke::material::MaterialKey;
+use ananke::search::Limits;
+use ananke::types::Color;
+
+fn endgame(fen: &str) -> Option<Endgame> {
+    Endgame::for_material(MaterialKey::of(&Board::from_fen(fen).unwrap()))
+}
+
+#[test]
+fn material_picks_the_specialist() {
+    use Endgame::*;
+    for (fen, expected) in [
+        (
+            "8/8/8/4k3/8/8/8/3QK3 w - - 0 1",
+            Some(MopUp {
+                strong: Color::White,
+            }),
+        ),
+        (
+            "8/8/8/4k3/8/8/8/2NRK3 b - - 0 1",
+            Some(MopUp {
+                strong: Color::White,
+            }),
+        ),
+        (
+            "3rk3/8/8/8/8/8/8/4K3 w - - 0 1",
+            Some(MopUp {
+                strong: Color::Black,
+            }),
+        ),
+        ("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1", None),
+        (
+            "3rk3/8/8/8/8/2P5/8/3RK3 w - - 0 1",
+            Some(RookAndPawn {
+                strong: Color::White,
+            }),
+        ),
+        (
+            "3rk3/6p1/8/8/8/8/8/3RK3 w - - 0 1",
+            Some(RookAndPawn {
+                strong: Color::Black,
+            }),
+        ),
+        ("3rk3/6p1/8/8/8/2P5/8/3RK3 w - - 0 1", None),
+        ("3rk3/8/8/8/8/2P5/2P5/3RK3 w - - 0 1", None),
+    ] {
+        assert_eq!(endgame(fen), expected, "{}", fen);
+    }
+}
+
+#[test]
+fn the_bare_king_belongs_on_the_edge() {
+    // White to move either way: higher is better for White
+    let centre = evaluate(&Board::from_fen("8/8/8/4k3/8/8/8/R3K3 w - - 0 1").unwrap());
+    let edge = evaluate(&Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap());
+    let close = evaluate(&Board::from_fen("4k3/8/4K3/8/8/8/8/R7 w - - 0 1").unwrap());
+    assert!(
+        centre < edge && edge < close,
+        "{} {} {}",
+        centre,
+        edge,
+        close
+    );
+}
+
+#[test]
+fn a_rook_mates_the_bare_king() {
+    // a shallow search can't see the mate from the middle of the board; the
+    // mop-up score has to lead it there
+    let mut engine = Engine::new();
+    engine
+        .set_position(
+            Board::from_fen("8/8/8/3k4/8/8/8/R3K3 w - - 0 1").unwrap(),
+            "",
+        )
+        .unwrap();
+    while !engine.game().is_over() && engine.game().moves().len() < 100 {
+        let m = engine.think(Limits::depth(4)).best_move.unwrap();
+        engine.play_uci(&m.to_string()).unwrap();
+    }
+    assert_eq!(engine.game().termination(), Some(Termination::Checkmate));
+}
+
+#[test]
+fn rook_and_pawn_textbook_positions() {
+    // White to move throughout, a pawn up; only where the kings stand differs
+    let score = |fen: &str| evaluate(&Board::from_fen(fen).unwrap());
+    let philidor = score("3k4/8/r7/3PK3/8/8/8/1R6 w - - 0 1");
+    let open = score("8/8/r7/3PK3/8/8/8/1R4k1 w - - 0 1");
+    assert!(philidor < open / 2, "{} {}", philidor, open);
+    let lucena = score("3K4/1k1P4/8/8/8/8/7r/4R3 w - - 0 1");
+    let defended = score("2kK4/3P4/8/8/8/8/7r/4R3 w - - 0 1");
+    assert!(lucena > defended + 100, "{} {}", lucena, defended);
+}
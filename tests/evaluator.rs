@@ -0,0 +1,270 @@
+//! Pluggable evaluation: the search runs with any `Evaluator`, calls its
+//! hooks in pairs, and the NNUE accumulators it keeps up move by move match
+//! a refresh from scratch.
+
+use ananke::board::{Board, UndoInfo};
+use ananke::eval::{self, Evaluator, Hce};
+use ananke::nnue::{Network, Nnue};
+use ananke::playout::random_playout;
+use ananke::rng::Rng;
+use ananke::search::{Limits, is_mate_score, search_with, search_with_evaluator};
+use ananke::types::Move;
+use ananke::uci::Uci;
+use std::sync::Arc;
+
+fn random_network(hidden: usize, seed: u32) -> Network {
+    let mut rng = Rng::new(seed);
+    let mut weights = |n: usize, range: u32| -> Vec<i16> {
+        (0..n)
+            .map(|_| rng.below(2 * range + 1) as i16 - range as i16)
+            .collect()
+    };
+    Network {
+        hidden,
+        feature_weights: weights(768 * hidden, 60),
+        feature_biases: weights(hidden, 60),
+        output_weights: weights(2 * hidden, 60),
+        output_bias: 100,
+    }
+}
+
+// the same network, evaluated from scratch at every node
+struct Stateless(Arc<Network>);
+
+impl Evaluator for Stateless {
+    fn evaluate(&self, board: &Board) -> i32 {
+        let mut nnue = Nnue::new(self.0.clone());
+        nnue.reset(board);
+        nnue.evaluate(board)
+    }
+
+    fn name(&self) -> &str {
+        "stateless"
+    }
+}
+
+#[test]
+fn network_files_round_trip() {
+    let network = random_network(8, 1);
+    let bytes = network.to_bytes();
+    assert_eq!(Network::from_bytes(&bytes).unwrap(), network);
+
+    assert!(Network::from_bytes(b"NOPE\x08\0\0\0").is_err());
+    assert!(Network::from_bytes(&bytes[..bytes.len() - 2]).is_err());
+    assert!(Network::load("/no/such/network.bin").is_err());
+}
+
+#[test]
+fn the_output_layer_doesnt_overflow() {
+    // the largest network a file can hold, every hidden unit saturated and
+    // every output weight at the limit
+    let hidden = 4096;
+    for w in [i16::MAX, i16::MIN] {
+        let network = Network {
+            hidden,
+            feature_weights: vec![0; 768 * hidden],
+            feature_biases: vec![255; hidden],
+            output_weights: vec![w; 2 * hidden],
+            output_bias: w,
+        };
+        let mut nnue = Nnue::new(Arc::new(network));
+        let board = Board::startpos();
+        nnue.reset(&board);
+        let score = nnue.evaluate(&board);
+        assert_eq!(score.signum(), w.signum() as i32);
+        assert!(!is_mate_score(score), "{}", score);
+    }
+}
+
+#[test]
+fn accumulators_follow_the_moves() {
+    let network = Arc::new(random_network(16, 2));
+    let mut rng = Rng::new(3);
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "1n2k3/P7/8/8/8/8/7p/4K1N1 w - - 0 1",
+        "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+    ] {
+        let start = Board::from_fen(fen).unwrap();
+        let mut nnue = Nnue::new(network.clone());
+        let mut board = start.clone();
+        nnue.reset(&board);
+        // down a random line, checking every move from each position on it
+        let mut played: Vec<(Move, UndoInfo)> = Vec::new();
+        for _ in 0..40 {
+            for m in board.legal_moves().iter() {
+                let undo = board.make_move_in_place(*m);
+                nnue.on_make_move(&board, *m, &undo);
+                assert_eq!(
+                    nnue.evaluate(&board),
+                    Stateless(network.clone()).evaluate(&board),
+                    "{} after {}",
+                    fen,
+                    m
+                );
+                board.unmake_move(*m, undo);
+                nnue.on_unmake_move();
+            }
+            let next = random_playout(&board, &mut rng, 1);
+            let Some(&m) = board
+                .legal_moves()
+                .iter()
+                .find(|m| board.make_move(**m).hash == next.hash)
+            else {
+                break;
+            };
+            let undo = board.make_move_in_place(m);
+            nnue.on_make_move(&board, m, &undo);
+            played.push((m, undo));
+        }
+        // and all the way back
+        while let Some((m, undo)) = played.pop() {
+            board.unmake_move(m, undo);
+            nnue.on_unmake_move();
+        }
+        assert_eq!(
+            nnue.evaluate(&board),
+            Stateless(network.clone()).evaluate(&start)
+        );
+    }
+}
+
+#[test]
+fn incremental_search_matches_from_scratch() {
+    let network = Arc::new(random_network(16, 4));
+    // en passant and castling both available. A random network gives the
+    // quiescence search nothing to settle on, so a busy middlegame is out
+    let board = Board::from_fen("r3k2r/pp3ppp/8/3pP3/8/2N5/PP3PPP/R3K2R w KQkq d6 0 1").unwrap();
+    let incremental =
+        search_with_evaluator(&board, Limits::depth(4), &mut Nnue::new(network.clone()));
+    let scratch = search_with_evaluator(&board, Limits::depth(4), &mut Stateless(network));
+    assert_eq!(incremental.best_move, scratch.best_move);
+    assert_eq!(incremental.score, scratch.score);
+    assert_eq!(incremental.nodes, scratch.nodes);
+}
+
+// follows the hooks and checks they pair up
+#[derive(Default)]
+struct Counting {
+    depth: usize,
+    max_depth: usize,
+    evaluations: std::cell::Cell<u64>,
+}
+
+impl Evaluator for Counting {
+    fn evaluate(&self, board: &Board) -> i32 {
+        self.evaluations.set(self.evaluations.get() + 1);
+        eval::evaluate(board)
+    }
+
+    fn reset(&mut self, _board: &Board) {
+        self.depth = 0;
+    }
+
+    fn on_make_move(&mut self, _board: &Board, _m: Move, _undo: &UndoInfo) {
+        self.depth += 1;
+        self.max_depth = self.max_depth.max(self.depth);
+    }
+
+    fn on_unmake_move(&mut self) {
+        self.depth = self.depth.checked_sub(1).expect("unmake without make");
+    }
+
+    fn name(&self) -> &str {
+        "counting"
+    }
+}
+
+#[test]
+fn search_uses_any_evaluator() {
+    let board = Board::startpos();
+    let mut counting = Counting::default();
+    let result = search_with_evaluator(&board, Limits::depth(3), &mut counting);
+    assert_eq!(counting.depth, 0);
+    assert!(counting.max_depth >= 3);
+    assert!(counting.evaluations.get() > 0);
+    // an evaluator that only counts as the built-in one does searches alike
+    assert_eq!(result, search_with(&board, Limits::depth(3)));
+    assert_eq!(
+        search_with_evaluator(&board, Limits::depth(3), &mut Hce::default()),
+        result
+    );
+}
+
+#[test]
+fn hce_looks_material_up_in_its_table() {
+    // the table's entries give what a fresh count would, position after
+    // position as the material changes
+    let hce = Hce::default();
+    let mut rng = Rng::new(5);
+    for _ in 0..20 {
+        let mut board = Board::startpos();
+        for _ in 0..150 {
+            assert_eq!(
+                hce.evaluate(&board),
+                eval::evaluate(&board),
+                "{}",
+                board.to_fen()
+            );
+            if board.count_legal_moves() == 0 {
+                break;
+            }
+            board = random_playout(&board, &mut rng, 1);
+        }
+    }
+
+    // with the pieces off, a central king is worth more
+    let corner = Board::from_fen("7k/8/8/8/8/8/P7/K7 w - - 0 1").unwrap();
+    let centre = Board::from_fen("7k/8/8/8/3K4/8/P7/8 w - - 0 1").unwrap();
+    assert!(hce.evaluate(&centre) > hce.evaluate(&corner));
+    // and with them on, it isn't
+    let corner = Board::from_fen("rnbqkbnr/8/8/8/8/8/K7/RNBQ1BNR w - - 0 1").unwrap();
+    let centre = Board::from_fen("rnbqkbnr/8/8/8/3K4/8/8/RNBQ1BNR w - - 0 1").unwrap();
+    assert_eq!(hce.evaluate(&centre), hce.evaluate(&corner));
+}
+
+#[test]
+fn hce_counts_the_material_imbalance() {
+    use ananke::material::MaterialEntry;
+
+    // bishop and knight against the pair, minors on the same squares
+    let hce = Hce::default();
+    let pair = Board::from_fen("4k3/pppppppp/8/8/8/8/PPPPPPPP/2B1KB2 w - - 0 1").unwrap();
+    let no_pair = Board::from_fen("4k3/pppppppp/8/8/8/8/PPPPPPPP/2B1KN2 w - - 0 1").unwrap();
+    let imbalance = |board: &Board| MaterialEntry::compute(board.material_key).imbalance;
+    assert!(imbalance(&pair) > imbalance(&no_pair));
+    // the knight is priced 10 below the bishop; the rest is the imbalance
+    assert_eq!(
+        hce.evaluate(&pair) - hce.evaluate(&no_pair),
+        10 + imbalance(&pair) - imbalance(&no_pair)
+    );
+    // and it counts from the side to move's point of view
+    assert_eq!(hce.evaluate(&pair.color_flipped()), hce.evaluate(&pair));
+}
+
+fn uci(script: &str) -> String {
+    let mut out = Vec::new();
+    Uci::new().run(script.as_bytes(), &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn uci_eval_file() {
+    let out = uci("setoption name EvalFile value /no/such/network.bin\n");
+    assert!(
+        out.starts_with("info string /no/such/network.bin"),
+        "{}",
+        out
+    );
+
+    let path = std::env::temp_dir().join(format!("ananke-test-{}.nnue", std::process::id()));
+    std::fs::write(&path, random_network(8, 5).to_bytes()).unwrap();
+    let script = format!(
+        "setoption name EvalFile value {}\nposition startpos\ngo depth 2\n",
+        path.display()
+    );
+    let out = uci(&script);
+    std::fs::remove_file(&path).unwrap();
+    assert!(!out.contains("info string"), "{}", out);
+    assert!(out.contains("bestmove"));
+}
@@ -0,0 +1,57 @@
+//! The check evasion generator on the checks it has to treat differently:
+//! sliders that can be blocked, contact and knight checks that can't,
+//! double check, and en passant taking the checker.
+
+use ananke::board::Board;
+use ananke::movegen::MoveGenerator;
+
+fn evasions(fen: &str) -> Vec<String> {
+    let board = Board::from_fen(fen).unwrap();
+    let mut moves: Vec<String> = MoveGenerator::new(&board)
+        .generate_evasions(board.checkers())
+        .iter()
+        .map(|m| m.to_string())
+        .collect();
+    moves.sort();
+    moves
+}
+
+#[test]
+fn sliders_can_be_blocked_or_taken() {
+    let moves = evasions("4k3/Q3r3/8/8/8/2B2N2/8/4K3 w - - 0 1");
+    assert_eq!(
+        moves,
+        [
+            "a7e3", "a7e7", "c3e5", "e1d1", "e1d2", "e1f1", "e1f2", "f3e5"
+        ]
+    );
+}
+
+#[test]
+fn knight_checks_cant_be_blocked() {
+    // only taking the knight or moving the king
+    let moves = evasions("4k3/8/8/8/8/3n4/8/R3K2R w KQ - 0 1");
+    assert_eq!(moves, ["e1d1", "e1d2", "e1e2", "e1f1"]);
+}
+
+#[test]
+fn double_check_moves_the_king() {
+    let moves = evasions("4k3/8/8/8/8/5n2/8/R2rK3 w - - 0 1");
+    assert_eq!(moves, ["e1d1", "e1e2", "e1f2"]);
+}
+
+#[test]
+fn en_passant_can_take_the_checker() {
+    // the pawn that just double-pushed gives check
+    let moves = evasions("8/8/8/3pP3/4K3/8/8/7k w - d6 0 1");
+    assert!(moves.contains(&"e5d6".to_string()));
+    // but not when something else checks
+    let moves = evasions("8/8/8/3pP3/8/8/8/r3K2k w - d6 0 1");
+    assert!(!moves.contains(&"e5d6".to_string()));
+}
+
+#[test]
+fn the_king_doesnt_step_back_along_the_line() {
+    let moves = evasions("4k3/8/8/8/8/8/8/r3K3 w - - 0 1");
+    assert_eq!(moves, ["e1d2", "e1e2", "e1f2"]);
+}
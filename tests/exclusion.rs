@@ -0,0 +1,81 @@
+//! Searching with root moves left out: the best of the rest, for both
+//! searchers, and nothing at all once every move is gone.
+
+use ananke::board::Board;
+use ananke::engine::Engine;
+use ananke::eval::Hce;
+use ananke::mcts::Mcts;
+use ananke::search::{AlphaBeta, INFINITY, Limits, MATE, Searcher, search_with};
+use ananke::types::Move;
+
+const BACK_RANK: &str = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+
+fn moves(board: &Board, uci: &[&str]) -> Vec<Move> {
+    uci.iter()
+        .map(|m| board.parse_uci_move(m).unwrap())
+        .collect()
+}
+
+#[test]
+fn the_best_move_left_out_gives_the_second_best() {
+    let board = Board::from_fen(BACK_RANK).unwrap();
+    let mate = moves(&board, &["a1a8"]);
+    let second = search_with(&board, Limits::depth(3).excluding(&mate));
+    assert_ne!(second.best_move, Some(mate[0]));
+    assert!(second.score < MATE - 10, "{}", second.score);
+    assert_eq!(second.root_moves.len(), board.legal_moves().len() - 1);
+    assert!(second.root_moves.iter().all(|r| r.mv != mate[0]));
+}
+
+#[test]
+fn only_the_moves_left_in_are_searched() {
+    // three king moves left out of its five, plus every rook move
+    let board = Board::from_fen(BACK_RANK).unwrap();
+    let kept = moves(&board, &["g1f2", "g1h2"]);
+    let excluded: Vec<Move> = board
+        .legal_moves()
+        .iter()
+        .copied()
+        .filter(|m| !kept.contains(m))
+        .collect();
+    let limits = Limits::depth(3).excluding(&excluded);
+    let result = search_with(&board, limits.clone());
+    assert!(kept.contains(&result.best_move.unwrap()));
+    assert_eq!(result.root_moves.len(), 2);
+
+    let result = Mcts::new().search(
+        &board,
+        Limits::nodes(500).excluding(&excluded),
+        &mut Hce::default(),
+        &mut |_| {},
+    );
+    assert!(kept.contains(&result.best_move.unwrap()));
+}
+
+#[test]
+fn everything_excluded_is_no_move() {
+    let board = Board::from_fen(BACK_RANK).unwrap();
+    let all: Vec<Move> = board.legal_moves().iter().copied().collect();
+    let limits = Limits::depth(3).excluding(&all);
+    for searcher in [&mut AlphaBeta::new() as &mut dyn Searcher, &mut Mcts::new()] {
+        let result = searcher.search(&board, limits.clone(), &mut Hce::default(), &mut |_| {});
+        assert_eq!(result.best_move, None, "{}", searcher.name());
+        assert_eq!(result.score, -INFINITY);
+    }
+}
+
+#[test]
+fn an_excluded_search_leaves_the_table_alone() {
+    // the position's own result goes in the table, the partial one doesn't:
+    // searching the whole position again afterwards still finds the mate
+    // straight from the first move tried
+    let board = Board::from_fen(BACK_RANK).unwrap();
+    let mate = moves(&board, &["a1a8"]);
+    let mut engine = Engine::new();
+    engine.set_position(board, "").unwrap();
+    engine.think(Limits::depth(3));
+    let second = engine.think(Limits::depth(3).excluding(&mate));
+    assert_ne!(second.best_move, Some(mate[0]));
+    let again = engine.think(Limits::nodes(1));
+    assert_eq!(again.best_move, Some(mate[0]));
+}
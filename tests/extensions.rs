@@ -0,0 +1,75 @@
+//! Recapture and pawn push extensions: when they fire, the params that
+//! turn them off, and the counters that measure them.
+
+use ananke::board::Board;
+use ananke::engine::Engine;
+use ananke::search::Limits;
+use ananke::uci::Uci;
+
+// a knight takes on d5 and the c-pawn takes back
+const EXCHANGE: &str = "4k3/8/2p5/3p4/8/4N3/8/4K3 w - - 0 1";
+// a pawn a push away from the seventh, for either side
+const PAWNS: &str = "4k3/8/1P6/8/8/6p1/8/4K3 w - - 0 1";
+
+fn stats_with(fen: &str, name: &str, value: &str) -> ananke::stats::SearchStats {
+    let mut engine = Engine::new();
+    engine.set_deterministic(true);
+    engine
+        .set_position(Board::from_fen(fen).unwrap(), "")
+        .unwrap();
+    engine.set_param(name, value).unwrap();
+    engine.think(Limits::depth(4)).stats
+}
+
+#[test]
+fn recaptures_are_extended() {
+    let stats = stats_with(EXCHANGE, "RecaptureExtension", "1");
+    assert!(stats.recapture_extensions > 0, "{:?}", stats);
+    let stats = stats_with(EXCHANGE, "RecaptureExtension", "0");
+    assert_eq!(stats.recapture_extensions, 0);
+}
+
+#[test]
+fn pushes_to_the_seventh_are_extended() {
+    let stats = stats_with(PAWNS, "PawnPushExtension", "1");
+    assert!(stats.pawn_push_extensions > 0, "{:?}", stats);
+    assert_eq!(stats.recapture_extensions, 0);
+    let stats = stats_with(PAWNS, "PawnPushExtension", "0");
+    assert_eq!(stats.pawn_push_extensions, 0);
+}
+
+#[test]
+fn extensions_search_deeper() {
+    // the exchange is seen to its end, which takes more nodes
+    let mut engine = Engine::new();
+    engine.set_deterministic(true);
+    engine
+        .set_position(Board::from_fen(EXCHANGE).unwrap(), "")
+        .unwrap();
+    let plain = engine.think(Limits::depth(4));
+    engine.set_param("RecaptureExtension", "1").unwrap();
+    let extended = engine.think(Limits::depth(4));
+    assert!(
+        extended.nodes > plain.nodes,
+        "{} {}",
+        extended.nodes,
+        plain.nodes
+    );
+}
+
+#[test]
+fn uci_reports_the_counters() {
+    let script = format!(
+        "setoption name SearchStats value true\nposition fen {}\ngo depth 4\n",
+        PAWNS
+    );
+    let mut out = Vec::new();
+    Uci::new().run(script.as_bytes(), &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let line = out
+        .lines()
+        .find(|l| l.starts_with("info string stats "))
+        .unwrap();
+    assert!(line.starts_with("info string stats recaptureextensions 0 pawnpushextensions "));
+    assert!(!line.ends_with(" 0"), "{}", line);
+}
@@ -0,0 +1,79 @@
+//! FEN parsing: what the lenient parser quietly repairs and never panics
+//! on, and what strict mode rejects instead.
+
+use ananke::board::Board;
+use ananke::uci::Uci;
+
+#[test]
+fn en_passant_on_the_edge_ranks_is_dropped() {
+    for fen in [
+        "4k3/8/8/8/8/8/8/4K3 w - a1 0 1",
+        "4k3/8/8/8/8/8/8/4K3 w - h8 0 1",
+        "4k3/8/8/8/8/8/8/4K3 b - a1 0 1",
+        "4k3/8/8/8/8/8/8/4K3 b - h8 0 1",
+    ] {
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(board.en_passant_sq, None, "{}", fen);
+    }
+    // a real one stays
+    let board = Board::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+    assert_eq!(board.en_passant_sq.unwrap().to_string(), "d6");
+}
+
+#[test]
+fn strict_mode_rejects_rights_the_pieces_dont_back() {
+    for fen in [
+        // no rooks at home
+        "4k3/8/8/8/8/8/8/4K3 w KQkq - 0 1",
+        "r3k3/8/8/8/8/8/8/4K3 w k - 0 1",
+        // no pawn that just double-pushed
+        "4k3/8/8/8/8/8/8/4K3 w - e6 0 1",
+        "4k3/8/8/8/8/8/8/4K3 b - e3 0 1",
+        // on the wrong rank for the side to move
+        "4k3/8/8/3pP3/8/8/8/4K3 b - d6 0 1",
+    ] {
+        assert!(Board::from_fen_strict(fen).is_err(), "{}", fen);
+        // the lenient parser drops them instead
+        let board = Board::from_fen(fen).unwrap();
+        assert!(!board.castling_rights.has_any() && board.en_passant_sq.is_none());
+    }
+    for fen in [
+        "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+        "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+    ] {
+        let strict = Board::from_fen_strict(fen).unwrap();
+        assert_eq!(strict.to_fen(), fen);
+        assert_eq!(strict.hash, Board::from_fen(fen).unwrap().hash);
+    }
+}
+
+#[test]
+fn only_strict_mode_rejects_a_king_left_in_check() {
+    // black to move, with white's king attacked by the queen
+    let fen = "4k3/8/8/8/8/8/4q3/4K3 b - - 0 1";
+    let board = Board::from_fen(fen).unwrap();
+    assert!(board.opponent_in_check());
+    assert!(!board.is_reachable_sanity());
+    assert!(Board::from_fen_strict(fen).is_err());
+
+    // the engine won't play on from it
+    let mut out = Vec::new();
+    let script = format!("position fen {}\ngo depth 1\n", fen);
+    Uci::new().run(script.as_bytes(), &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with("info string Illegal position"), "{}", out);
+}
+
+#[test]
+fn the_largest_fullmove_number_saturates() {
+    let fen = "4k3/8/8/8/8/8/8/4K3 b - - 0 65535";
+    let board = Board::from_fen(fen).unwrap();
+    let m = board.parse_uci_move("e8d8").unwrap();
+    assert_eq!(board.make_move(m).fullmove_number, u16::MAX);
+
+    // and unmaking puts back exactly what was there
+    let mut in_place = board.clone();
+    let undo = in_place.make_move_in_place(m);
+    in_place.unmake_move(m, undo);
+    assert_eq!(in_place.to_fen(), fen);
+}
@@ -0,0 +1,133 @@
+//! Game-level rules that sit on top of the board: the clock, resignation
+//! and draw offers.
+
+use ananke::clock::{ClockMode, TimeControl};
+use ananke::game::{Game, Termination};
+use ananke::types::{Color, GameResult};
+use std::time::Duration;
+
+fn secs(s: f64) -> Duration {
+    Duration::from_secs_f64(s)
+}
+
+fn timed(fen: &str, control: TimeControl) -> Game {
+    let mut game = Game::from_fen(fen).unwrap();
+    game.set_clock(control);
+    game
+}
+
+#[test]
+fn flag_fall_on_a_timed_move() {
+    let control = TimeControl::fischer(secs(10.0), secs(1.0));
+    let mut game = timed("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", control);
+    game.play_timed(game.board().parse_uci_move("e2e4").unwrap(), secs(4.0))
+        .unwrap();
+    assert_eq!(game.clock().unwrap().remaining(Color::White), secs(7.0));
+
+    // Black's move came too late: it isn't played, and Black loses
+    let e8d8 = game.board().parse_uci_move("e8d8").unwrap();
+    assert!(game.play_timed(e8d8, secs(10.5)).is_err());
+    assert_eq!(game.moves().len(), 1);
+    assert_eq!(game.result(), Some(GameResult::WhiteWins));
+    assert_eq!(game.termination(), Some(Termination::Timeout));
+    assert!(game.play_timed(e8d8, secs(0.0)).is_err());
+}
+
+#[test]
+fn flag_fall_while_thinking() {
+    let control = TimeControl {
+        base: Duration::from_millis(1),
+        bonus: Duration::ZERO,
+        mode: ClockMode::Increment,
+    };
+    let mut game = timed("4k3/4p3/8/8/8/8/4P3/4K3 w - - 0 1", control);
+    std::thread::sleep(Duration::from_millis(10));
+    assert!(game.check_flag());
+    assert_eq!(game.result(), Some(GameResult::BlackWins));
+    assert_eq!(game.termination(), Some(Termination::Timeout));
+    // already decided
+    assert!(!game.check_flag());
+
+    // no clock, no flag
+    let mut game = Game::new();
+    assert!(!game.check_flag());
+    assert!(!game.is_over());
+}
+
+#[test]
+fn flag_fall_against_a_lone_minor_is_a_draw() {
+    // Black flags; White's pieces decide it
+    for (fen, result) in [
+        ("4k3/4p3/8/8/8/8/8/4KN2 b - - 0 1", GameResult::Draw),
+        ("4k3/4p3/8/8/8/8/8/2B1K3 b - - 0 1", GameResult::Draw),
+        // bishops all on one color can't mate either
+        ("4k3/4p3/8/8/8/8/8/2B1KB2 b - - 0 1", GameResult::WhiteWins),
+        ("4k3/4p3/8/8/8/8/3B4/2B1K3 b - - 0 1", GameResult::Draw),
+        ("4k3/4p3/8/8/8/8/8/1N2KN2 b - - 0 1", GameResult::WhiteWins),
+        ("4k3/4p3/8/8/8/8/7P/4K3 b - - 0 1", GameResult::WhiteWins),
+    ] {
+        let mut game = timed(fen, TimeControl::fischer(secs(5.0), secs(0.0)));
+        let e7e5 = game.board().parse_uci_move("e7e5").unwrap();
+        assert!(game.play_timed(e7e5, secs(6.0)).is_err());
+        assert_eq!(game.result(), Some(result), "{}", fen);
+        assert_eq!(game.termination(), Some(Termination::Timeout));
+    }
+}
+
+#[test]
+fn flag_fall_against_a_bare_king_is_a_draw() {
+    // White has only the king left to mate with
+    let mut game = timed(
+        "4k3/4p3/8/8/8/8/8/4K3 b - - 0 1",
+        TimeControl::fischer(secs(5.0), secs(0.0)),
+    );
+    let e7e5 = game.board().parse_uci_move("e7e5").unwrap();
+    assert!(game.play_timed(e7e5, secs(6.0)).is_err());
+    assert_eq!(game.result(), Some(GameResult::Draw));
+    assert_eq!(game.termination(), Some(Termination::Timeout));
+}
+
+#[test]
+fn resignation() {
+    let mut game = Game::new();
+    game.play_uci("e2e4").unwrap();
+    // either side can resign, on move or not
+    game.resign(Color::White).unwrap();
+    assert_eq!(game.result(), Some(GameResult::BlackWins));
+    assert_eq!(game.termination(), Some(Termination::Resignation));
+    assert!(game.resign(Color::Black).is_err());
+    assert!(game.play_uci("e7e5").is_err());
+}
+
+#[test]
+fn draw_offers() {
+    let mut game = Game::new();
+    game.offer_draw(Color::White).unwrap();
+    assert_eq!(game.draw_offer(), Some(Color::White));
+    // nobody accepts their own offer
+    assert!(game.accept_draw(Color::White).is_err());
+    game.accept_draw(Color::Black).unwrap();
+    assert_eq!(game.result(), Some(GameResult::Draw));
+    assert_eq!(game.termination(), Some(Termination::Agreement));
+    assert_eq!(game.draw_offer(), None);
+    assert!(game.offer_draw(Color::Black).is_err());
+}
+
+#[test]
+fn moving_declines_a_draw_offer() {
+    let mut game = Game::new();
+    game.play_uci("e2e4").unwrap();
+    game.offer_draw(Color::White).unwrap();
+    game.play_uci("e7e5").unwrap();
+    assert_eq!(game.draw_offer(), None);
+    assert!(game.accept_draw(Color::Black).is_err());
+    assert!(!game.is_over());
+
+    // offering with the move: the offer stands through the offerer's own
+    // move, until the opponent replies
+    game.offer_draw(Color::White).unwrap();
+    game.play_uci("g1f3").unwrap();
+    assert_eq!(game.draw_offer(), Some(Color::White));
+    game.accept_draw(Color::Black).unwrap();
+    assert_eq!(game.termination(), Some(Termination::Agreement));
+}
@@ -0,0 +1,96 @@
+//! Walk the `Game` layer the way perft walks boards, to make sure history,
+//! clocks and draw bookkeeping never change which moves are legal.
+
+use ananke::board::Board;
+use ananke::clock::TimeControl;
+use ananke::game::{Game, Termination};
+use ananke::perft::{game_perft, perft};
+use ananke::rng::Rng;
+use std::time::Duration;
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+#[test]
+fn game_perft_matches_board_perft() {
+    for (fen, depth) in [(ananke::board::START_FEN, 4), (KIWIPETE, 3)] {
+        let board = Board::from_fen(fen).unwrap();
+        let mut game = Game::from_board(board.clone());
+        game.set_clock(TimeControl::fischer(
+            Duration::from_secs(60),
+            Duration::from_secs(1),
+        ));
+        assert_eq!(game_perft(&game, depth), perft(&board, depth), "{}", fen);
+    }
+}
+
+// long random games in a rook ending, where repetitions and the 75-move rule
+// actually happen, checked ply by ply
+#[test]
+fn long_random_games_keep_bookkeeping_consistent() {
+    let mut rng = Rng::new(5);
+    let mut endings = Vec::new();
+    for _ in 0..40 {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        while !game.is_over() {
+            let board = game.board().clone();
+            let moves = board.legal_moves();
+            assert_eq!(game_perft(&game, 1), moves.len() as u64);
+            if game.repetition_count() >= 3 {
+                assert!(game.can_claim_draw().is_some());
+            }
+
+            let m = moves.as_slice()[rng.below(moves.len() as u32) as usize];
+            game.play(m).unwrap();
+            assert_eq!(game.board().to_fen(), board.make_move(m).to_fen());
+        }
+
+        let board = game.board();
+        let expected = match game.termination().unwrap() {
+            Termination::Checkmate => board.in_check() && board.count_legal_moves() == 0,
+            Termination::Stalemate => !board.in_check() && board.count_legal_moves() == 0,
+            Termination::FivefoldRepetition => game.repetition_count() >= 5,
+            Termination::SeventyFiveMoveRule => board.halfmove_clock >= 150,
+            Termination::DeadPosition => board.has_insufficient_material(),
+            other => panic!("unexpected ending {:?}", other),
+        };
+        assert!(expected, "{:?} in {}", game.termination(), board.to_fen());
+        assert!(
+            game.play(
+                board
+                    .legal_moves()
+                    .as_slice()
+                    .first()
+                    .copied()
+                    .unwrap_or_default()
+            )
+            .is_err()
+        );
+        endings.push(game.termination().unwrap());
+    }
+    // the seed should reach more than one kind of ending
+    assert!(endings.iter().any(|e| *e != endings[0]), "{:?}", endings);
+}
+
+#[test]
+fn replaying_a_move_list_keeps_the_history() {
+    let moves = "e2e4 c7c5 g1f3 d7d6 d2d4 c5d4 f3d4 g8f6";
+    let mut game = Game::new();
+    game.apply_uci_moves(moves).unwrap();
+    let mut board = Board::startpos();
+    board.apply_uci_moves(moves).unwrap();
+    assert_eq!(game.board().to_fen(), board.to_fen());
+    assert_eq!(game.moves().len(), 8);
+    assert_eq!(game.start_board().to_fen(), ananke::board::START_FEN);
+
+    // a bad move anywhere leaves the game as it was, and says where
+    let err = game.apply_uci_moves("b1c3 e7e5 a1a8").unwrap_err();
+    assert!(err.ends_with("(move 3)"), "{}", err);
+    assert_eq!(game.moves().len(), 8);
+
+    // and moves past the end of the game are refused
+    let mut game = Game::new();
+    assert!(game.apply_uci_moves("f2f3 e7e5 g2g4 d8h4 a2a3").is_err());
+    assert!(game.moves().is_empty());
+    game.apply_uci_moves("f2f3 e7e5 g2g4 d8h4").unwrap();
+    assert_eq!(game.termination(), Some(Termination::Checkmate));
+}
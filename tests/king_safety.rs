@@ -0,0 +1,65 @@
+//! The castling terms in the eval: the flag `make_move` keeps for a side
+//! that has castled, and what having castled, or still being able to, is
+//! worth as the pieces come off.
+
+use ananke::board::Board;
+use ananke::eval::evaluate;
+use ananke::types::Color;
+
+// both sides free to castle either way, and nothing else to tell them apart
+const OPEN: &str = "r3k2r/ppp2ppp/2nqbn2/3pp3/3PP3/2NQBN2/PPP2PPP/R3K2R w KQkq - 0 1";
+
+fn play(board: &Board, moves: &[&str]) -> Board {
+    moves.iter().fold(board.clone(), |board, m| {
+        board.make_move(board.parse_uci_move(m).unwrap())
+    })
+}
+
+#[test]
+fn castling_sets_the_flag() {
+    let board = Board::from_fen(OPEN).unwrap();
+    assert_eq!(board.castled.0, [false, false]);
+    let castled = play(&board, &["e1g1", "e8c8"]);
+    assert_eq!(castled.castled.0, [true, true]);
+    // a king move loses the rights without castling
+    assert_eq!(play(&board, &["e1f1"]).castled.0, [false, false]);
+
+    let mut scratch = board.clone();
+    let m = board.parse_uci_move("e1c1").unwrap();
+    let undo = scratch.make_move_in_place(m);
+    assert!(scratch.castled[Color::White]);
+    scratch.unmake_move(m, undo);
+    assert!(!scratch.castled[Color::White]);
+}
+
+#[test]
+fn castling_is_worth_more_than_walking_off() {
+    // Black to move after each, so lower is better for White
+    let board = Board::from_fen(OPEN).unwrap();
+    let castled = evaluate(&play(&board, &["e1g1"]));
+    let walked = evaluate(&play(&board, &["e1f1"]));
+    assert!(castled < walked, "{} {}", castled, walked);
+    // keeping the rights is worth something, but less than using them
+    let waited = evaluate(&play(&board, &["a2a3"]));
+    assert!(
+        walked > waited && waited > castled,
+        "{} {} {}",
+        walked,
+        waited,
+        castled
+    );
+}
+
+#[test]
+fn the_terms_fade_in_the_endgame() {
+    let endgame = Board::from_fen("6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1").unwrap();
+    let mut castled = endgame.clone();
+    castled.castled[Color::White] = true;
+    assert_eq!(evaluate(&castled), evaluate(&endgame));
+
+    let middlegame =
+        Board::from_fen("r4rk1/ppp2ppp/2nqbn2/3pp3/3PP3/2NQBN2/PPP2PPP/R4RK1 w - - 0 1").unwrap();
+    let mut castled = middlegame.clone();
+    castled.castled[Color::White] = true;
+    assert!(evaluate(&castled) > evaluate(&middlegame));
+}
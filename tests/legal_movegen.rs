@@ -0,0 +1,108 @@
+//! The legal generator on the positions masks get wrong most easily: en
+//! passant pins and checks, double check, pinned pieces and castling
+//! around attacks. Each is checked against the reference generator and
+//! for the moves that must or mustn't be there.
+
+use ananke::board::Board;
+use ananke::movegen::MoveGenerator;
+use ananke::testing::reference_legal_moves;
+
+fn legal(fen: &str) -> Vec<String> {
+    let board = Board::from_fen(fen).unwrap();
+    let mut actual: Vec<String> = MoveGenerator::new(&board)
+        .generate_legal()
+        .iter()
+        .map(|m| m.to_string())
+        .collect();
+    let mut expected: Vec<String> = reference_legal_moves(&board)
+        .iter()
+        .map(|m| m.to_string())
+        .collect();
+    actual.sort();
+    expected.sort();
+    assert_eq!(actual, expected, "{}", fen);
+    actual
+}
+
+fn has(moves: &[String], m: &str) -> bool {
+    moves.iter().any(|x| x == m)
+}
+
+#[test]
+fn en_passant_pinned_along_the_rank() {
+    // both pawns leave the fifth rank and the rook sees the king
+    let moves = legal("8/8/8/K2pP2r/8/8/8/4k3 w - d6 0 1");
+    assert!(!has(&moves, "e5d6"));
+    // with another piece still on the rank it's fine
+    let moves = legal("8/8/8/K1NpP2r/8/8/8/4k3 w - d6 0 1");
+    assert!(has(&moves, "e5d6"));
+    // black to move, on the fourth rank
+    let moves = legal("8/8/8/8/R2Pp2k/8/8/4K3 b - d3 0 1");
+    assert!(!has(&moves, "e4d3"));
+    let moves = legal("8/8/4k3/8/R2Pp2K/8/8/8 b - d3 0 1");
+    assert!(has(&moves, "e4d3"));
+}
+
+#[test]
+fn en_passant_pinned_on_a_diagonal() {
+    // the capturing pawn is pinned: off the diagonal is illegal, along it
+    // is fine
+    let moves = legal("7k/5b2/8/2pP4/8/1K6/8/8 w - c6 0 1");
+    assert!(!has(&moves, "d5c6"));
+    let moves = legal("7k/5b2/8/3Pp3/8/1K6/8/8 w - e6 0 1");
+    assert!(has(&moves, "d5e6"));
+    // the pawn taken was the one blocking the diagonal
+    let moves = legal("b6k/8/8/3pP3/8/5K2/8/8 w - d6 0 1");
+    assert!(!has(&moves, "e5d6"));
+}
+
+#[test]
+fn en_passant_and_check() {
+    // the double-pushed pawn gives check: taking it en passant answers it
+    let moves = legal("8/8/8/3pP3/4K3/8/8/7k w - d6 0 1");
+    assert!(has(&moves, "e5d6"));
+    // a check from something else isn't answered by it
+    let moves = legal("8/8/8/3pP3/8/8/8/r3K2k w - d6 0 1");
+    assert!(!has(&moves, "e5d6"));
+    // unless the pawn lands in the way
+    let moves = legal("8/8/r6K/3pP3/8/8/8/k7 w - d6 0 1");
+    assert!(has(&moves, "e5d6"));
+}
+
+#[test]
+fn double_check_leaves_only_king_moves() {
+    let board = Board::from_fen("4k3/8/8/8/8/5n2/8/R2rK3 w - - 0 1").unwrap();
+    let king = board.get_king_square(board.side_to_move);
+    let moves = MoveGenerator::new(&board).generate_legal();
+    assert!(!moves.is_empty());
+    assert!(moves.iter().all(|m| m.from() == king));
+    legal("4k3/8/8/8/8/5n2/8/R2rK3 w - - 0 1");
+}
+
+#[test]
+fn pinned_pieces_stay_on_the_pin() {
+    // the rook may slide along the file or take the pinner, nothing else
+    let moves = legal("4r2k/8/8/8/8/8/4R3/4K3 w - - 0 1");
+    let rook: Vec<&String> = moves.iter().filter(|m| m.starts_with("e2")).collect();
+    assert_eq!(rook.len(), 6);
+    // a pinned knight can't move at all
+    let moves = legal("4k3/8/8/8/1b6/8/3N4/4K3 w - - 0 1");
+    assert!(!moves.iter().any(|m| m.starts_with("d2")));
+    // a pinned pawn may take its pinner, and promote doing it
+    let moves = legal("3q3k/4P3/5K2/8/8/8/8/8 w - - 0 1");
+    assert!(has(&moves, "e7d8q"));
+    assert!(!has(&moves, "e7e8q"));
+}
+
+#[test]
+fn castling_around_attacks() {
+    // f1 attacked: no kingside, queenside fine
+    let moves = legal("r3k2r/8/8/8/8/8/5r2/R3K2R w KQkq - 0 1");
+    assert!(!has(&moves, "e1g1") && has(&moves, "e1c1"));
+    // in check: neither
+    let moves = legal("r3k2r/8/8/8/8/8/4r3/R3K2R w KQkq - 0 1");
+    assert!(!has(&moves, "e1g1") && !has(&moves, "e1c1"));
+    // b1 attacked doesn't matter, only the king's path does
+    let moves = legal("r3k2r/8/8/8/8/8/1r6/R3K2R w KQkq - 0 1");
+    assert!(has(&moves, "e1c1"));
+}
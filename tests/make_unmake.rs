@@ -0,0 +1,82 @@
+//! In-place make/unmake against copy-make: making a move in place gives the
+//! same board as `make_move`, and unmaking it gives back the original,
+//! over random games and the special moves.
+
+use ananke::board::Board;
+use ananke::movegen::MoveGenerator;
+use ananke::playout::random_playout;
+use ananke::rng::Rng;
+
+fn assert_same(a: &Board, b: &Board, context: &str) {
+    assert_eq!(a.to_fen(), b.to_fen(), "{}", context);
+    assert_eq!(a.pieces, b.pieces, "{}", context);
+    assert_eq!(a.occupancy, b.occupancy, "{}", context);
+    assert_eq!(a.all_occupancy, b.all_occupancy, "{}", context);
+    assert_eq!(a.material_key, b.material_key, "{}", context);
+    assert_eq!(a.hash, b.hash, "{}", context);
+    assert_eq!(a.castled, b.castled, "{}", context);
+}
+
+// every pseudo-legal move, so moves that leave the king in check (which
+// search and perft make before rejecting) are covered too
+fn check_all_moves(board: &Board) {
+    let mut scratch = board.clone();
+    for m in MoveGenerator::new(board).generate_all().iter() {
+        let context = format!("{} {:?}", board.to_fen(), m);
+        let undo = scratch.make_move_in_place(*m);
+        assert_same(&scratch, &board.make_move(*m), &context);
+        scratch.unmake_move(*m, undo);
+        assert_same(&scratch, board, &context);
+    }
+}
+
+#[test]
+fn unmake_restores_random_positions() {
+    let mut rng = Rng::new(23);
+    for _ in 0..50 {
+        let mut board = Board::startpos();
+        for _ in 0..150 {
+            check_all_moves(&board);
+            if board.count_legal_moves() == 0 {
+                break;
+            }
+            board = random_playout(&board, &mut rng, 1);
+        }
+    }
+}
+
+#[test]
+fn unmake_restores_special_moves() {
+    for fen in [
+        // castling both ways, and rook captures that take rights away
+        "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+        "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+        // en passant
+        "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+        // promotions, with and without capture
+        "1n2k3/P7/8/8/8/8/7p/4K1N1 w - - 0 1",
+        "1n2k3/P7/8/8/8/8/7p/4K1N1 b - - 0 1",
+        // clocks at their edges
+        "4k3/8/8/8/8/8/8/R3K3 w Q - 99 200",
+    ] {
+        check_all_moves(&Board::from_fen(fen).unwrap());
+    }
+}
+
+#[test]
+fn a_game_unwinds_to_the_start() {
+    let start = Board::startpos();
+    let mut board = start.clone();
+    let mut copied = start.clone();
+    let mut played = Vec::new();
+    for uci in "e2e4 d7d5 e4d5 d8d5 g1f3 d5e4 f1e2 c8g4 e1g1 e4e2 d1e2 g4f3 e2f3".split(' ') {
+        let m = board.parse_uci_move(uci).unwrap();
+        played.push((m, board.make_move_in_place(m)));
+        copied = copied.make_move(m);
+    }
+    assert_same(&board, &copied, "played");
+    while let Some((m, undo)) = played.pop() {
+        board.unmake_move(m, undo);
+    }
+    assert_same(&board, &start, "unwound");
+}
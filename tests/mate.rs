@@ -0,0 +1,92 @@
+//! The mate finder on puzzles longer than one move: the line it returns has
+//! to be legal, end in checkmate, and follow the defender's best resistance.
+//! Also the `solve` command's answer when there is nothing to find.
+
+use ananke::board::Board;
+use ananke::mate::find_mate;
+use ananke::san;
+use ananke::types::Move;
+use std::process::Command;
+
+// play the line out, checking every move is legal, and return the final board
+fn play(board: &Board, line: &[Move]) -> Board {
+    let mut board = board.clone();
+    for &m in line {
+        assert!(board.is_legal(m), "{} is illegal", m);
+        board = board.make_move(m);
+    }
+    board
+}
+
+fn assert_mated(board: &Board) {
+    assert!(board.in_check());
+    assert!(board.legal_moves().is_empty());
+}
+
+fn solve(fen: &str, moves: u8) -> Option<Vec<String>> {
+    let board = Board::from_fen(fen).unwrap();
+    let line = find_mate(&board, moves)?;
+    assert_mated(&play(&board, &line));
+    Some(san::line_to_san(&board, &line))
+}
+
+#[test]
+fn mate_in_two_with_king_and_rook() {
+    let line = solve("k7/8/2K5/8/8/8/8/7R w - - 0 1", 3).unwrap();
+    assert_eq!(line, ["Kb6", "Kb8", "Rh8#"]);
+}
+
+#[test]
+fn mate_in_three_is_not_reported_as_shorter() {
+    let fen = "2k5/8/8/2K5/8/8/8/7R w - - 0 1";
+    assert_eq!(solve(fen, 2), None);
+    let line = solve(fen, 3).unwrap();
+    assert_eq!(line, ["Kc6", "Kb8", "Ra1", "Kc8", "Ra8#"]);
+}
+
+#[test]
+fn the_line_follows_the_longest_defence() {
+    // after Kb6 the king can step into Rh8# at once, or hold out a move
+    // longer with Kc8; the line has to show the second
+    let fen = "1k6/8/8/2K5/8/8/8/7R w - - 0 1";
+    let board = Board::from_fen(fen).unwrap();
+    let after = board.make_move(board.parse_uci_move("c5b6").unwrap());
+    let corner = after.make_move(after.parse_uci_move("b8a8").unwrap());
+    assert_eq!(find_mate(&corner, 1).map(|line| line.len()), Some(1));
+
+    let line = solve(fen, 4).unwrap();
+    assert_eq!(line, ["Kb6", "Kc8", "Rd1", "Kb8", "Rd8#"]);
+}
+
+#[test]
+fn stalemate_is_not_mate() {
+    // b6 leaves Black without a move, but not in check
+    let fen = "k7/2K5/8/1P6/8/8/8/8 w - - 0 1";
+    assert_eq!(solve(fen, 1), None);
+    let board = Board::from_fen(fen).unwrap();
+    let stalemate = play(&board, &[board.parse_uci_move("b5b6").unwrap()]);
+    assert!(stalemate.legal_moves().is_empty());
+    assert!(!stalemate.in_check());
+    assert_eq!(find_mate(&stalemate, 3), None);
+}
+
+#[test]
+fn solve_command_reports_no_mate() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ananke"))
+        .args(["solve", "k7/2K5/8/1P6/8/8/8/8 w - - 0 1", "2"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().last(), Some("no mate <= 2"));
+}
+
+#[test]
+fn solve_command_prints_the_line() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ananke"))
+        .args(["solve", "k7/8/2K5/8/8/8/8/7R w - - 0 1", "2"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().last(), Some("mate in 2: Kb6 Kb8 Rh8#"));
+}
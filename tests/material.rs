@@ -0,0 +1,80 @@
+//! The incrementally updated material key against a recount, over random
+//! games with plenty of captures and promotions.
+
+use ananke::board::Board;
+use ananke::material::{MaterialKey, MaterialTable};
+use ananke::playout::random_playout;
+use ananke::rng::Rng;
+use ananke::values;
+
+#[test]
+fn material_key_matches_recount() {
+    let mut rng = Rng::new(11);
+    let mut table = MaterialTable::default();
+    for _ in 0..100 {
+        let mut board = Board::startpos();
+        for _ in 0..200 {
+            assert_eq!(
+                board.material_key,
+                MaterialKey::of(&board),
+                "{}",
+                board.to_fen()
+            );
+            let entry = table.probe(&board);
+            assert_eq!(entry.phase, values::phase(&board), "{}", board.to_fen());
+            assert_eq!(
+                board.color_flipped().material_key,
+                MaterialKey::of(&board.color_flipped())
+            );
+            if board.count_legal_moves() == 0 {
+                break;
+            }
+            board = random_playout(&board, &mut rng, 1);
+        }
+    }
+}
+
+#[test]
+fn material_key_counts_past_fifteen() {
+    use ananke::types::{Color, PieceType};
+
+    // twenty knights, which from_fen lets through
+    let board = Board::from_fen("nNNNNNNN/NNNNNNNN/NNNN4/8/8/8/8/k6K w - - 0 1").unwrap();
+    let key = board.material_key;
+    assert_eq!(key, MaterialKey::of(&board));
+    assert_eq!(key.count(Color::White, PieceType::Knight), 19);
+    assert_eq!(key.count(Color::White, PieceType::Bishop), 0);
+    assert_eq!(key.count(Color::Black, PieceType::Knight), 1);
+    assert_eq!(key.count(Color::Black, PieceType::Pawn), 0);
+
+    let after = board.make_move(board.parse_uci_move("b6a8").unwrap());
+    assert_eq!(after.material_key, MaterialKey::of(&after));
+    assert_eq!(after.material_key.count(Color::Black, PieceType::Knight), 0);
+}
+
+#[test]
+fn imbalance_is_antisymmetric() {
+    let board = Board::from_fen("4k3/pppp4/8/8/8/8/PPPPPP2/2BBK1N1 w - - 0 1").unwrap();
+    let mut table = MaterialTable::default();
+    let entry = table.probe(&board);
+    assert!(entry.imbalance > 0);
+    assert_eq!(
+        table.probe(&board.color_flipped()).imbalance,
+        -entry.imbalance
+    );
+}
+
+#[test]
+fn imbalance_rewards_the_bishop_pair() {
+    let mut table = MaterialTable::default();
+    let pair = Board::from_fen("4k3/pppppppp/8/8/8/8/PPPPPPPP/2B1KB2 w - - 0 1").unwrap();
+    let bishop_knight = Board::from_fen("4k3/pppppppp/8/8/8/8/PPPPPPPP/2B1KN2 w - - 0 1").unwrap();
+    let no_pair = table.probe(&bishop_knight).imbalance;
+    assert!(table.probe(&pair).imbalance > no_pair);
+}
+
+#[test]
+fn balanced_material_has_no_imbalance() {
+    let mut table = MaterialTable::default();
+    assert_eq!(table.probe(&Board::startpos()).imbalance, 0);
+}
@@ -0,0 +1,179 @@
+//! The staged move picker: every pseudo-legal move exactly once whatever
+//! table move and killers it's handed, in stage order, and the pseudo-legal
+//! check it leans on for moves brought in from other positions.
+
+use ananke::board::Board;
+use ananke::movegen::{MoveGenerator, generate_noisy_into, generate_quiets_into, is_pseudo_legal};
+use ananke::movepick::MovePicker;
+use ananke::playout::random_position;
+use ananke::rng::Rng;
+use ananke::see::{see, see_ge};
+use ananke::types::{Move, MoveList};
+use std::collections::HashSet;
+
+const FENS: [&str; 5] = [
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+    "1n2k3/P7/8/8/8/8/7p/4K1N1 w - - 0 1",
+    "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+    "4k3/8/8/8/8/3n4/8/R3K2R w KQ - 0 1",
+];
+
+// the fixed positions and a few hundred random ones
+fn positions() -> Vec<Board> {
+    let mut rng = Rng::new(7);
+    let mut boards: Vec<Board> = FENS.iter().map(|f| Board::from_fen(f).unwrap()).collect();
+    boards.extend((0..300).map(|_| random_position(&mut rng, 80)));
+    boards
+}
+
+// what the picker should hand out: the evasions in check, otherwise all
+fn expected(board: &Board) -> MoveList {
+    let checkers = board.checkers();
+    if checkers.count() > 0 {
+        MoveGenerator::new(board).generate_evasions(checkers)
+    } else {
+        MoveGenerator::new(board).generate_all()
+    }
+}
+
+fn drain(board: &Board, mut picker: MovePicker) -> Vec<Move> {
+    let mut moves = Vec::new();
+    while let Some(m) = picker.next(board) {
+        moves.push(m);
+    }
+    moves
+}
+
+fn uci(board: &Board, text: &str) -> Move {
+    board.parse_uci_move(text).unwrap()
+}
+
+#[test]
+fn every_move_exactly_once() {
+    let boards = positions();
+    // table moves and killers from this position and from others
+    let pool: Vec<Move> = boards
+        .iter()
+        .flat_map(|b| expected(b).as_slice().to_vec())
+        .collect();
+    let mut rng = Rng::new(8);
+    let mut pick = || Some(pool[rng.below(pool.len() as u32) as usize]);
+    for board in &boards {
+        let own = expected(board);
+        let mut hints = [pick(), pick(), pick()];
+        if !own.is_empty() {
+            hints[0] = Some(own.as_slice()[0]);
+            hints[2] = own.as_slice().last().copied();
+        }
+        let moves = drain(
+            board,
+            MovePicker::new(board, hints[0], [hints[1], hints[2]]),
+        );
+        let unique: HashSet<Move> = moves.iter().copied().collect();
+        assert_eq!(unique.len(), moves.len(), "{}", board.to_fen());
+        assert_eq!(unique, own.iter().copied().collect(), "{}", board.to_fen());
+    }
+}
+
+#[test]
+fn noisy_and_quiet_split_the_moves() {
+    let (mut noisy, mut quiets) = (MoveList::new(), MoveList::new());
+    for board in positions() {
+        generate_noisy_into(&board, &mut noisy);
+        generate_quiets_into(&board, &mut quiets);
+        assert!(noisy.iter().all(|m| m.is_capture() || m.is_promotion()));
+        assert!(quiets.iter().all(|m| !m.is_capture() && !m.is_promotion()));
+        let all = MoveGenerator::new(&board).generate_all();
+        let split: HashSet<Move> = noisy.iter().chain(quiets.iter()).copied().collect();
+        assert_eq!(split.len(), all.len(), "{}", board.to_fen());
+        assert_eq!(split, all.iter().copied().collect(), "{}", board.to_fen());
+
+        // quiescence gets the noisy moves that don't lose material, and
+        // nothing else
+        let picked: HashSet<Move> = drain(&board, MovePicker::quiescence())
+            .into_iter()
+            .collect();
+        let good = noisy
+            .iter()
+            .filter(|m| !m.is_capture() || see(&board, **m) >= 0);
+        assert_eq!(picked, good.copied().collect(), "{}", board.to_fen());
+    }
+}
+
+#[test]
+fn stages_come_in_order() {
+    // Nxh5 wins a pawn, Qxb5 loses the queen to c6
+    let board = Board::from_fen("k7/8/2p5/1p5p/8/1Q4N1/8/K7 w - - 0 1").unwrap();
+    let tt = uci(&board, "b3b4");
+    let killers = [Some(uci(&board, "a1b1")), Some(uci(&board, "g3e4"))];
+    let moves: Vec<String> = drain(&board, MovePicker::new(&board, Some(tt), killers))
+        .iter()
+        .map(|m| m.to_string())
+        .collect();
+    assert_eq!(moves[..4], ["b3b4", "g3h5", "a1b1", "g3e4"]);
+    assert_eq!(moves.last().unwrap(), "b3b5");
+    assert_eq!(moves.len(), expected(&board).len());
+
+    // killers that can't be played here are passed over
+    let stale = [Some(uci(&board, "g3h5")), Some(Move::default())];
+    let moves = drain(&board, MovePicker::new(&board, None, stale));
+    assert_eq!(moves[0].to_string(), "g3h5");
+    assert!(moves[1].to_string() != "g3h5" && !moves[1].is_capture());
+    assert_eq!(moves.len(), expected(&board).len());
+
+    // and quiescence never gets to the losing capture
+    let moves: Vec<String> = drain(&board, MovePicker::quiescence())
+        .iter()
+        .map(|m| m.to_string())
+        .collect();
+    assert_eq!(moves, ["g3h5"]);
+}
+
+#[test]
+fn pseudo_legal_matches_the_generator() {
+    let boards = positions();
+    let pool: HashSet<Move> = boards
+        .iter()
+        .flat_map(|b| expected(b).as_slice().to_vec())
+        .collect();
+    for board in &boards {
+        let all: HashSet<Move> = MoveGenerator::new(board)
+            .generate_all()
+            .iter()
+            .copied()
+            .collect();
+        for m in &pool {
+            assert_eq!(
+                is_pseudo_legal(board, *m),
+                all.contains(m),
+                "{} in {}",
+                m,
+                board.to_fen()
+            );
+        }
+    }
+}
+
+#[test]
+fn see_on_a_crowded_square() {
+    // queens and knights of both colours on every square that sees d5: an
+    // exchange far longer than a game's 32 pieces allow
+    let fen = "q2q2Q1/1QnQnq2/1NqqQN2/QqQqQqQq/1NQQqN2/1qnqnQ2/Q2Q2q1/K1kq3Q w - - 0 1";
+    let board = Board::from_fen(fen).unwrap();
+    let nxd5 = uci(&board, "b4d5");
+    assert_eq!(see(&board, nxd5), 900);
+    let moves = drain(&board, MovePicker::new(&board, None, [None; 2]));
+    assert_eq!(moves.len(), expected(&board).len());
+}
+
+#[test]
+fn see_counts_a_recapture_that_only_loses_less() {
+    // pawn takes a pawn-defended knight: after dxe5 Black is still down,
+    // but by a knight for a pawn, not a whole knight
+    let board = Board::from_fen("4k3/8/3p4/4n3/3P4/8/8/4K3 w - - 0 1").unwrap();
+    let dxe5 = uci(&board, "d4e5");
+    assert_eq!(see(&board, dxe5), 220);
+    assert!(see_ge(&board, dxe5, 220));
+    assert!(!see_ge(&board, dxe5, 221));
+}
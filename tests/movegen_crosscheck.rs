@@ -0,0 +1,91 @@
+//! Cross-check the mask-based legal move generator and counter against the
+//! naive make-the-move-and-look-for-check reference, over random reachable
+//! positions.
+
+use ananke::board::Board;
+use ananke::legal::Legality;
+use ananke::movegen::MoveGenerator;
+use ananke::playout::{random_playout, random_position};
+use ananke::rng::Rng;
+use ananke::types::Move;
+
+const GAMES: usize = 100;
+const PLIES: usize = 150;
+
+fn make_and_test_moves(board: &Board) -> Vec<Move> {
+    let us = board.side_to_move;
+    MoveGenerator::new(board)
+        .generate_all()
+        .iter()
+        .copied()
+        .filter(|m| {
+            let next = board.make_move(*m);
+            !next.is_square_attacked(next.get_king_square(us), us.opposite())
+        })
+        .collect()
+}
+
+fn check(board: &Board) {
+    let mut expected = make_and_test_moves(board);
+    let mut actual = board.legal_moves().as_slice().to_vec();
+    expected.sort_by_key(|m| m.to_string());
+    actual.sort_by_key(|m| m.to_string());
+
+    assert_eq!(actual, expected, "legal moves differ in {}", board.to_fen());
+
+    // and the pseudo-legal list filtered by the masks agrees
+    let legality = Legality::new(board);
+    let mut filtered: Vec<Move> = MoveGenerator::new(board)
+        .generate_all()
+        .iter()
+        .copied()
+        .filter(|m| legality.allows(board, *m))
+        .collect();
+    filtered.sort_by_key(|m| m.to_string());
+    assert_eq!(filtered, expected, "masks disagree in {}", board.to_fen());
+
+    // in check, the evasions are pseudo-legal moves and leave out no legal one
+    let checkers = board.checkers();
+    if checkers.count() > 0 {
+        let pseudo = MoveGenerator::new(board).generate_all();
+        let evasions = MoveGenerator::new(board).generate_evasions(checkers);
+        assert!(evasions.iter().all(|m| pseudo.iter().any(|p| p == m)));
+        let mut legal: Vec<Move> = evasions
+            .iter()
+            .copied()
+            .filter(|m| legality.allows(board, *m))
+            .collect();
+        legal.sort_by_key(|m| m.to_string());
+        assert_eq!(legal, expected, "evasions differ in {}", board.to_fen());
+    }
+
+    assert_eq!(
+        board.count_legal_moves(),
+        expected.len(),
+        "legal move count differs in {}",
+        board.to_fen()
+    );
+}
+
+#[test]
+fn legal_moves_match_reference() {
+    // every position along each random game, up to 15k positions
+    let mut rng = Rng::new(0x5EED);
+    for _ in 0..GAMES {
+        let mut board = Board::startpos();
+        for _ in 0..PLIES {
+            check(&board);
+            if board.count_legal_moves() == 0 {
+                break;
+            }
+            board = random_playout(&board, &mut rng, 1);
+        }
+    }
+}
+
+#[test]
+fn random_positions_are_reproducible() {
+    let a = random_position(&mut Rng::new(42), 80);
+    let b = random_position(&mut Rng::new(42), 80);
+    assert_eq!(a.to_fen(), b.to_fen());
+}
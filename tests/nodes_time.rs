@@ -0,0 +1,99 @@
+//! nodestime: with time counted in nodes, timed searches and whole timed
+//! games come out the same every run.
+
+use ananke::board::Board;
+use ananke::clock::TimeControl;
+use ananke::game::Game;
+use ananke::search::{Limits, SearchResult, search_with};
+use ananke::timeman::{ClockState, NodesClock, TimeBudget, TimeSource, allocate};
+use ananke::types::Color;
+use ananke::uci::Uci;
+use std::time::Duration;
+
+fn timed(board: &Board, millis: u64, nodes_per_ms: u64) -> SearchResult {
+    let budget = Duration::from_millis(millis);
+    search_with(
+        board,
+        Limits {
+            nodes_time: Some(nodes_per_ms),
+            ..Limits::time(TimeBudget {
+                optimum: budget,
+                maximum: budget,
+            })
+        },
+    )
+}
+
+#[test]
+fn virtual_time_stops_on_a_node_count() {
+    let board = Board::startpos();
+    let a = timed(&board, 20, 100);
+    let b = timed(&board, 20, 100);
+    assert_eq!(
+        (a.best_move, a.score, a.depth, a.nodes),
+        (b.best_move, b.score, b.depth, b.nodes)
+    );
+    // 2000 nodes' worth, overrun by at most the stretch between clock reads
+    assert!(a.nodes >= 2000 && a.nodes < 2000 + 1024, "{}", a.nodes);
+}
+
+// a 2+0.05 game between two copies of the engine on virtual time; returns
+// the moves and both clocks at the end
+fn play(nodes_per_ms: u64) -> (String, Duration, Duration) {
+    let control = TimeControl::fischer(Duration::from_secs(2), Duration::from_millis(50));
+    let clock = NodesClock { nodes_per_ms };
+    let mut game = Game::new();
+    game.set_clock(control);
+    while !game.is_over() && game.moves().len() < 40 {
+        let us = game.board().side_to_move;
+        let remaining = |c| game.clock().unwrap().remaining(c);
+        let budget = allocate(&ClockState {
+            time: remaining(us),
+            increment: control.bonus,
+            opponent_time: remaining(us.opposite()),
+            opponent_increment: control.bonus,
+            moves_to_go: None,
+        });
+        let result = search_with(
+            game.board(),
+            Limits {
+                nodes_time: Some(nodes_per_ms),
+                ..Limits::time(budget)
+            },
+        );
+        let m = result.best_move.unwrap();
+        game.play_timed(m, clock.elapsed(result.nodes)).unwrap();
+    }
+    let moves: Vec<String> = game.moves().iter().map(|m| m.to_string()).collect();
+    let clock = game.clock().unwrap();
+    (
+        moves.join(" "),
+        clock.remaining(Color::White),
+        clock.remaining(Color::Black),
+    )
+}
+
+#[test]
+fn timed_games_repeat_exactly() {
+    let first = play(20);
+    assert_eq!(first, play(20));
+    // and the clocks did run
+    assert!(first.1 < Duration::from_secs(2) && first.2 < Duration::from_secs(2));
+}
+
+fn uci(script: &str) -> String {
+    let mut out = Vec::new();
+    Uci::new().run(script.as_bytes(), &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn uci_nodestime_option() {
+    assert!(uci("uci\n").contains("option name nodestime type spin"));
+    let script = "setoption name nodestime value 50\n\
+                  position startpos moves e2e4\n\
+                  go wtime 1000 btime 1000 winc 10 binc 10\n";
+    let out = uci(script);
+    assert!(out.contains("bestmove"));
+    assert_eq!(out, uci(script));
+}
@@ -0,0 +1,55 @@
+//! Perft with make/unmake checked at every node: the counts are the usual
+//! ones, the board comes back exactly as it was after each unmake, and
+//! making in place gives the same board as copy-make.
+
+use ananke::board::{Board, START_FEN};
+use ananke::perft::{perft, perft_checked, perft_cross_checked};
+use std::process::Command;
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+
+#[test]
+fn checked_counts_match_perft() {
+    for (fen, depth, nodes) in [
+        (START_FEN, 4, 197281),
+        (KIWIPETE, 3, 97862),
+        (POSITION_3, 4, 43238),
+        (POSITION_4, 3, 9467),
+        (POSITION_5, 3, 62379),
+    ] {
+        let board = Board::from_fen(fen).unwrap();
+        assert_eq!(perft_checked(&board, depth), Ok(nodes), "{}", fen);
+        assert_eq!(perft_cross_checked(&board, depth), Ok(nodes), "{}", fen);
+        assert_eq!(perft(&board, depth), nodes, "{}", fen);
+    }
+}
+
+#[test]
+fn clocks_and_move_number_come_back() {
+    // a capture resets the halfmove clock and Black's move bumps the
+    // fullmove number, both of which unmake has to put back
+    let board = Board::from_fen("4k3/8/8/3p4/4P3/8/8/4K3 b - - 37 65535").unwrap();
+    assert!(perft_checked(&board, 3).is_ok());
+    assert!(perft_cross_checked(&board, 3).is_ok());
+}
+
+#[test]
+fn perft_check_command() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ananke"))
+        .args(["perft-check", "3", KIWIPETE])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines[lines.len() - 2..],
+        [
+            "nodes 97862, every unmake restored the board",
+            "nodes 97862, in-place and copy-make agree",
+        ]
+    );
+}
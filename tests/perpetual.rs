@@ -0,0 +1,53 @@
+//! Forced repetition search: a queen that checks for ever, and a king that
+//! finds a way out of the checks.
+
+use ananke::board::Board;
+use ananke::perpetual::{RepetitionLine, find_forced_repetition, find_perpetual};
+
+const PERPETUAL: &str = "6k1/6p1/5p2/7Q/8/8/rr6/6K1 w - - 0 1";
+// the same position without the g7 pawn: the king runs to g7 and beyond
+const ESCAPE: &str = "6k1/8/5p2/7Q/8/8/rr6/6K1 w - - 0 1";
+
+// play the line and check its cycle really comes back round
+fn assert_repeats(board: &Board, line: &RepetitionLine) {
+    let mut positions = vec![board.clone()];
+    for &m in &line.moves {
+        let last = positions.last().unwrap();
+        assert!(last.is_legal(m), "{} is illegal", m);
+        positions.push(last.make_move(m));
+    }
+    let end = positions.last().unwrap();
+    assert!(positions[line.cycle_start].is_same_position(end));
+}
+
+fn uci(line: &RepetitionLine) -> Vec<String> {
+    line.moves.iter().map(|m| m.to_string()).collect()
+}
+
+#[test]
+fn queen_checks_for_ever() {
+    let board = Board::from_fen(PERPETUAL).unwrap();
+    let line = find_perpetual(&board, 8).unwrap();
+    assert_eq!(uci(&line), ["h5e8", "g8h7", "e8h5", "h7g8"]);
+    assert_eq!(line.cycle_start, 0);
+    assert_repeats(&board, &line);
+}
+
+#[test]
+fn too_few_plies_finds_nothing() {
+    let board = Board::from_fen(PERPETUAL).unwrap();
+    assert!(find_perpetual(&board, 3).is_none());
+}
+
+#[test]
+fn defender_escapes_the_checks() {
+    let board = Board::from_fen(ESCAPE).unwrap();
+    assert!(find_perpetual(&board, 9).is_none());
+}
+
+#[test]
+fn quiet_moves_can_force_a_repetition_too() {
+    let board = Board::from_fen(PERPETUAL).unwrap();
+    let line = find_forced_repetition(&board, 4, false).unwrap();
+    assert_repeats(&board, &line);
+}
@@ -0,0 +1,241 @@
+//! Reading multi-game PGN streams: game boundaries, skipping bad games, and
+//! not needing the whole file up front.
+
+use ananke::board::Board;
+use ananke::game::Game;
+use ananke::pgn::{Pgn, PgnMove, PgnReader};
+use ananke::rng::Rng;
+use std::io::{self, BufReader, Read};
+
+fn read_all(text: &[u8]) -> Vec<Result<Pgn, String>> {
+    PgnReader::new(text).collect()
+}
+
+fn mainline_len(game: &Result<Pgn, String>) -> usize {
+    game.as_ref().map(|pgn| pgn.mainline().len()).unwrap()
+}
+
+#[test]
+fn written_games_read_back() {
+    let mut rng = Rng::new(5);
+    let mut text = String::new();
+    let mut lines = Vec::new();
+    for _ in 0..30 {
+        let mut game = Game::new();
+        for _ in 0..rng.below(80) {
+            let moves = game.board().legal_moves();
+            if game.is_over() || moves.is_empty() {
+                break;
+            }
+            game.play(moves.as_slice()[rng.below(moves.len() as u32) as usize])
+                .unwrap();
+        }
+        lines.push(game.moves().to_vec());
+        text.push_str(&Pgn::from_game(&game).to_string());
+        text.push('\n');
+    }
+
+    let games = read_all(text.as_bytes());
+    assert_eq!(games.len(), lines.len());
+    for (game, line) in games.iter().zip(&lines) {
+        assert_eq!(&game.as_ref().unwrap().mainline(), line);
+    }
+}
+
+#[test]
+fn bad_games_are_reported_and_skipped() {
+    let text = "\
+[Event \"one\"]
+
+1. e4 e5 2. Nf3 1-0
+
+[Event \"two\"]
+
+1. e4 e5 2. Ke3 0-1
+
+[Event \"three\"]
+[FEN \"not a fen\"]
+
+1. e4 *
+
+[Event \"four\"]
+
+1. d4 d5 1/2-1/2
+";
+    let games = read_all(text.as_bytes());
+    assert_eq!(games.len(), 4);
+    assert_eq!(mainline_len(&games[0]), 3);
+    let error = games[1].as_ref().err().unwrap();
+    assert!(error.starts_with("game 2 (line 5): "), "{}", error);
+    let error = games[2].as_ref().err().unwrap();
+    assert!(error.starts_with("game 3 (line 9): "), "{}", error);
+    assert_eq!(mainline_len(&games[3]), 2);
+}
+
+#[test]
+fn boundaries_without_results_or_tags() {
+    // a game cut off before its result, then tagless games one after another
+    let text = "\
+[Event \"cut off\"]
+
+1. e4 e5 2. Nf3
+
+[Event \"next\"]
+
+1. d4 *
+1. c4 e5 1-0
+
+1. Nf3
+d5 0-1
+";
+    let games = read_all(text.as_bytes());
+    let lens: Vec<usize> = games.iter().map(mainline_len).collect();
+    assert_eq!(lens, [3, 1, 2, 2]);
+    assert_eq!(games[0].as_ref().unwrap().result, None);
+    assert_eq!(games[1].as_ref().unwrap().tag("Event"), Some("next"));
+}
+
+#[test]
+fn results_in_comments_dont_end_the_game() {
+    let text = "\
+1. e4 {white could resign here: 1-0
+[not a tag] still a comment} e5 2. Nf3 ; 1-0
+Nc6 (2... d6 {or 2... f5, then
+1-0} 3. d4) 3. Bb5 1/2-1/2
+% an escaped line
+1. d4 *
+";
+    let games = read_all(text.as_bytes());
+    assert_eq!(games.len(), 2);
+    assert_eq!(mainline_len(&games[0]), 5);
+    assert_eq!(mainline_len(&games[1]), 1);
+}
+
+#[test]
+fn bad_bytes_only_spoil_their_own_game() {
+    let mut text = b"1. e4 {caf\xe9} e5 *\n\n1. d4 \xff5 *\n\n1. c4 *\n".to_vec();
+    text.splice(0..0, *b"\xef\xbb\xbf");
+    let games = read_all(&text);
+    assert_eq!(games.len(), 3);
+    assert_eq!(mainline_len(&games[0]), 2);
+    assert!(games[1].is_err());
+    assert_eq!(mainline_len(&games[2]), 1);
+}
+
+// the same game over and over, produced on demand
+struct Endless {
+    game: &'static [u8],
+    pos: usize,
+}
+
+impl Read for Endless {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let rest = &self.game[self.pos..];
+        let n = rest.len().min(buf.len());
+        buf[..n].copy_from_slice(&rest[..n]);
+        self.pos = (self.pos + n) % self.game.len();
+        Ok(n)
+    }
+}
+
+#[test]
+fn reads_lazily() {
+    let input = BufReader::new(Endless {
+        game: b"[Event \"again\"]\n\n1. e4 e5 2. Nf3 Nc6 1/2-1/2\n\n",
+        pos: 0,
+    });
+    let mut reader = PgnReader::new(input);
+    for game in reader.by_ref().take(1000) {
+        assert_eq!(game.unwrap().mainline().len(), 4);
+    }
+    assert_eq!(reader.games_read(), 1000);
+}
+
+struct Failing;
+
+impl Read for Failing {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::other("disk on fire"))
+    }
+}
+
+#[test]
+fn read_errors_end_the_stream() {
+    let input = BufReader::new(b"1. e4 *\n".chain(Failing));
+    let games: Vec<_> = PgnReader::new(input).collect();
+    assert_eq!(games.len(), 2);
+    assert!(games[0].is_ok());
+    assert!(games[1].as_ref().is_err_and(|e| e.contains("disk on fire")));
+}
+
+#[test]
+fn deep_nesting_is_an_error_not_a_crash() {
+    let opens = format!("1. e4 {}", "(".repeat(200_000));
+    assert!(Pgn::parse(&opens).is_err());
+    let nested = format!("1. e4 {}*", "(e4 ".repeat(20_000));
+    assert!(Pgn::parse(&nested).is_err());
+    // a variation needs a move to be the alternative to
+    assert!(Pgn::parse("(e4) 1. d4 *").is_err());
+
+    // within the limit it reads back as written
+    let text = format!("1. e4 {}{} *", "(1. e4 ".repeat(50), ")".repeat(50));
+    let pgn = Pgn::parse(&text).unwrap();
+    let reread = Pgn::parse(&pgn.to_string()).unwrap();
+    assert_eq!(reread.to_string(), pgn.to_string());
+}
+
+#[test]
+fn deep_variations_are_written_without_recursing() {
+    let board = Board::startpos();
+    let e4 = board.parse_uci_move("e2e4").unwrap();
+    let mut line = vec![PgnMove::new(e4)];
+    for _ in 0..5_000 {
+        let mut pm = PgnMove::new(e4);
+        pm.variations.push(line);
+        line = vec![pm];
+    }
+    let mut pgn = Pgn::new(board);
+    pgn.moves = line;
+    let text = pgn.to_string();
+    assert_eq!(text.matches('(').count(), 5_000);
+    assert_eq!(text.matches(')').count(), 5_000);
+}
+
+#[test]
+fn tag_names_are_symbols() {
+    // a quote in the name would otherwise swallow the tags after it
+    assert!(Pgn::parse("[Ev\"ent] \"x\"]\n[Site \"y\"]\n\n*").is_err());
+    let pgn = Pgn::parse("[Time_Control \"40/7200\"]\n\n*").unwrap();
+    assert_eq!(pgn.tag("Time_Control"), Some("40/7200"));
+}
+
+#[test]
+fn comments_in_a_row_join_up() {
+    let pgn = Pgn::parse("{a} {b} 1. e4 {} ; c\n{d} *").unwrap();
+    assert_eq!(pgn.moves[0].comment_before.as_deref(), Some("a b"));
+    assert_eq!(pgn.moves[0].comment.as_deref(), Some("c d"));
+    let reread = Pgn::parse(&pgn.to_string()).unwrap();
+    assert_eq!(reread.moves, pgn.moves);
+
+    // a brace comment over several lines, then one that needs a ';'
+    let pgn = Pgn::parse("1. e4 {over\ntwo lines} ; with a }\n*").unwrap();
+    assert_eq!(
+        pgn.moves[0].comment.as_deref(),
+        Some("over two lines with a }")
+    );
+    let reread = Pgn::parse(&pgn.to_string()).unwrap();
+    assert_eq!(reread.moves, pgn.moves);
+
+    // a lone carriage return inside a ';' comment
+    let pgn = Pgn::parse("1. e4 ; a\rb\r\n*").unwrap();
+    assert_eq!(pgn.moves[0].comment.as_deref(), Some("a b"));
+    let reread = Pgn::parse(&pgn.to_string()).unwrap();
+    assert_eq!(reread.moves, pgn.moves);
+}
+
+#[test]
+fn a_start_with_the_king_en_prise_is_an_error() {
+    // the a7 pawn could take the king on b8 before White even moves
+    let pgn = "[SetUp \"1\"]\n[FEN \"1k6/P7/8/8/8/8/8/K7 w - - 0 1\"]\n\n1. a8=Q+ *";
+    assert!(Pgn::parse(pgn).is_err());
+}
@@ -0,0 +1,100 @@
+//! The engine pool: every position answered, in the batch's order, with
+//! progress as they finish, over engines that last between batches.
+
+use ananke::board::Board;
+use ananke::engine::Engine;
+use ananke::pool::{EnginePool, Job};
+use ananke::search::Limits;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const FENS: [&str; 4] = [
+    "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1",
+    "k7/8/8/8/8/8/1R6/7K b - - 0 1",
+    "r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+];
+
+fn jobs(depth: u8) -> Vec<Job> {
+    FENS.iter()
+        .map(|fen| Job::new(Board::from_fen(fen).unwrap(), Limits::depth(depth)))
+        .collect()
+}
+
+#[test]
+fn results_come_back_in_the_batch_order() {
+    let pool = EnginePool::new(3);
+    assert_eq!(pool.engines(), 3);
+    let mut seen = Vec::new();
+    let results = pool.analyse(jobs(3), &mut |p| {
+        assert_eq!(p.total, 4);
+        seen.push((p.index, p.done, p.nodes));
+    });
+    let best: Vec<String> = results
+        .iter()
+        .map(|r| r.as_ref().unwrap()[0].best_move.unwrap().to_string())
+        .collect();
+    assert_eq!(best[..3], ["a1a8", "a8a7", "h5f7"]);
+
+    // every position once, counting up, and the nodes adding up
+    let mut indices: Vec<_> = seen.iter().map(|s| s.0).collect();
+    indices.sort();
+    assert_eq!(indices, [0, 1, 2, 3]);
+    assert_eq!(seen.iter().map(|s| s.1).collect::<Vec<_>>(), [1, 2, 3, 4]);
+    let total: u64 = results.iter().map(|r| r.as_ref().unwrap()[0].nodes).sum();
+    assert_eq!(seen.last().unwrap().2, total);
+}
+
+#[test]
+fn one_engine_takes_the_positions_in_turn() {
+    let pool = EnginePool::new(1);
+    let mut order = Vec::new();
+    pool.analyse(jobs(2), &mut |p| order.push(p.index));
+    assert_eq!(order, [0, 1, 2, 3]);
+}
+
+#[test]
+fn the_engines_last_between_batches() {
+    let built = Arc::new(AtomicUsize::new(0));
+    let counter = built.clone();
+    let pool = EnginePool::with(2, move || {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Engine::new()
+    });
+    for _ in 0..3 {
+        let results = pool.analyse(jobs(2), &mut |_| {});
+        assert!(results.iter().all(Result::is_ok));
+    }
+    assert_eq!(built.load(Ordering::Relaxed), 2);
+    // an empty batch is done at once
+    assert!(pool.analyse(Vec::new(), &mut |_| panic!()).is_empty());
+}
+
+#[test]
+fn a_bad_position_fails_alone() {
+    let pool = EnginePool::new(2);
+    let mut batch = jobs(2);
+    // White to move with the black king in check
+    batch[1].board = Board::from_fen("R3k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let results = pool.analyse(batch, &mut |_| {});
+    assert!(results[0].is_ok() && results[2].is_ok() && results[3].is_ok());
+    assert!(results[1].is_err());
+}
+
+#[test]
+fn jobs_can_ask_for_several_lines() {
+    let pool = EnginePool::new(2);
+    let mut batch = jobs(2);
+    batch[0].lines = 3;
+    // checkmated: no moves, so no lines
+    batch[1] = Job::new(
+        Board::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap(),
+        Limits::depth(2),
+    );
+    let results = pool.analyse(batch, &mut |_| {});
+    let lines = results[0].as_ref().unwrap();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0].best_move.unwrap().to_string(), "a1a8");
+    assert!(results[1].as_ref().unwrap().is_empty());
+    assert_eq!(results[2].as_ref().unwrap().len(), 1);
+}
@@ -0,0 +1,105 @@
+//! Predicting the reply: the engine counting how often the position it
+//! expected next is the one it is asked about, and the search reusing its
+//! work when it is.
+
+use ananke::board::Board;
+use ananke::engine::Engine;
+use ananke::search::Limits;
+use ananke::stats::Predictions;
+use ananke::uci::Uci;
+
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+fn start() -> Board {
+    Board::from_fen(START).unwrap()
+}
+
+// the moves from the start to the position the engine expects after it
+// plays `line`
+fn expected(line: &[String]) -> String {
+    format!("{} {}", line[0], line[1])
+}
+
+#[test]
+fn hits_are_counted_when_the_expected_reply_is_played() {
+    let mut engine = Engine::new();
+    engine.set_position(start(), "").unwrap();
+    let result = engine.think(Limits::depth(4));
+    assert_eq!(engine.predictions(), Predictions::default());
+    let line: Vec<String> = result.pv.iter().map(|m| m.to_string()).collect();
+
+    engine.set_position(start(), &expected(&line)).unwrap();
+    engine.think(Limits::depth(4));
+    assert_eq!(engine.predictions(), Predictions { made: 1, hits: 1 });
+
+    // back to the start: not what the last search expected
+    engine.set_position(start(), "").unwrap();
+    engine.think(Limits::depth(4));
+    let predictions = engine.predictions();
+    assert_eq!(predictions, Predictions { made: 2, hits: 1 });
+    assert_eq!(predictions.hit_rate(), Some(0.5));
+
+    engine.new_game();
+    assert_eq!(engine.predictions().hit_rate(), None);
+}
+
+#[test]
+fn searches_with_moves_left_out_predict_nothing() {
+    let mut engine = Engine::new();
+    engine.set_position(start(), "").unwrap();
+    let result = engine.think(Limits::depth(3));
+    let line: Vec<String> = result.pv.iter().map(|m| m.to_string()).collect();
+    engine.set_position(start(), &expected(&line)).unwrap();
+    let first = *engine.board().legal_moves().iter().next().unwrap();
+    engine.think(Limits::depth(3).excluding(&[first]));
+    assert_eq!(engine.predictions(), Predictions::default());
+    engine.think(Limits::depth(3));
+    assert_eq!(engine.predictions(), Predictions { made: 1, hits: 1 });
+}
+
+#[test]
+fn a_predicted_position_is_searched_from_the_last_search_s_work() {
+    let mut engine = Engine::new();
+    engine.set_position(start(), "").unwrap();
+    let result = engine.think(Limits::depth(6));
+    let line: Vec<String> = result.pv.iter().map(|m| m.to_string()).collect();
+
+    engine.set_position(start(), &expected(&line)).unwrap();
+    let warm = engine.think(Limits::depth(4));
+    assert_eq!(engine.predictions().hits, 1);
+
+    let mut fresh = Engine::new();
+    fresh.set_position(start(), &expected(&line)).unwrap();
+    let cold = fresh.think(Limits::depth(4));
+    assert!(warm.nodes < cold.nodes, "{} {}", warm.nodes, cold.nodes);
+}
+
+#[test]
+fn uci_reports_them_with_the_stats() {
+    // one command at a time: in a script, each go would stop the one before
+    let mut uci = Uci::new();
+    let mut out = Vec::new();
+    for command in [
+        "setoption name SearchStats value true",
+        "position startpos",
+        "go depth 3",
+        "position startpos moves e2e4",
+        "go depth 3",
+    ] {
+        uci.handle(command, &mut out).unwrap();
+    }
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out
+        .lines()
+        .filter(|l| l.starts_with("info string predictions "))
+        .collect();
+    // the first search has nothing to go on; the second is of the position
+    // a move in, not two as the first expected, so it misses
+    assert_eq!(
+        lines,
+        [
+            "info string predictions 0 hits 0",
+            "info string predictions 1 hits 0"
+        ]
+    );
+}
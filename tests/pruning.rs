@@ -0,0 +1,52 @@
+//! The fail-soft search and the pruning near the leaves: scores that fail
+//! low say how low, and quiet moves that win are still found.
+
+use ananke::board::Board;
+use ananke::engine::Engine;
+use ananke::search::{Limits, MATE, search};
+use ananke::tt::Bound;
+
+#[test]
+fn failing_moves_keep_their_own_scores() {
+    // with fail-hard scores every move refuted at the root would come back
+    // as exactly the best score; fail-soft says how much worse it is
+    let board =
+        Board::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R w KQkq - 4 4")
+            .unwrap();
+    let result = search(&board, 4);
+    let best = &result.root_moves[0];
+    assert_eq!(best.score, result.score);
+    let others = &result.root_moves[1..];
+    assert!(others.iter().all(|r| r.score <= result.score));
+    assert!(
+        others
+            .iter()
+            .any(|r| r.bound == Bound::Upper && r.score < result.score)
+    );
+}
+
+#[test]
+fn pruning_keeps_quiet_mates() {
+    // every move of the mate is quiet, and the first two don't check
+    let board = Board::from_fen("2k5/8/8/2K5/8/8/8/7R w - - 0 1").unwrap();
+    let result = search(&board, 5);
+    assert_eq!(result.score, MATE - 5);
+}
+
+#[test]
+fn the_params_reach_the_search() {
+    // no pruning at all searches more, and the same as before it was set
+    // once it's back to the default
+    let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R w KQkq - 4 4";
+    let mut engine = Engine::new();
+    engine.set_deterministic(true);
+    engine
+        .set_position(Board::from_fen(fen).unwrap(), "")
+        .unwrap();
+    let pruned = engine.think(Limits::depth(5));
+    engine.set_param("PruningDepth", "0").unwrap();
+    let full = engine.think(Limits::depth(5));
+    assert!(full.nodes > pruned.nodes, "{} {}", full.nodes, pruned.nodes);
+    engine.set_param("PruningDepth", "3").unwrap();
+    assert_eq!(engine.think(Limits::depth(5)), pruned);
+}
@@ -0,0 +1,78 @@
+//! Differential tests against the square-by-square reference generator in
+//! `ananke::testing`, over every position of seeded random games.
+
+use ananke::bitboard::Square;
+use ananke::board::Board;
+use ananke::playout::random_playout;
+use ananke::rng::Rng;
+use ananke::testing::{reference_is_attacked, reference_legal_moves};
+use ananke::types::Color;
+
+const GAMES: usize = 60;
+const PLIES: usize = 150;
+
+// call `f` on every position of `GAMES` random games
+fn for_random_positions(seed: u32, mut f: impl FnMut(&Board)) {
+    let mut rng = Rng::new(seed);
+    for _ in 0..GAMES {
+        let mut board = Board::startpos();
+        for _ in 0..PLIES {
+            f(&board);
+            if board.count_legal_moves() == 0 {
+                break;
+            }
+            board = random_playout(&board, &mut rng, 1);
+        }
+    }
+}
+
+#[test]
+fn legal_moves_match_reference_generator() {
+    for_random_positions(0xC0FFEE, |board| {
+        let mut expected = reference_legal_moves(board);
+        let mut actual = board.legal_moves().as_slice().to_vec();
+        expected.sort_by_key(|m| m.to_string());
+        actual.sort_by_key(|m| m.to_string());
+        assert_eq!(actual, expected, "legal moves differ in {}", board.to_fen());
+    });
+}
+
+#[test]
+fn attacks_match_reference() {
+    for_random_positions(0xA77AC4, |board| {
+        for sq in (0..64).map(Square::new) {
+            for color in [Color::White, Color::Black] {
+                assert_eq!(
+                    board.is_square_attacked(sq, color),
+                    reference_is_attacked(board, sq, color),
+                    "attack on {} by {:?} differs in {}",
+                    sq,
+                    color,
+                    board.to_fen()
+                );
+            }
+        }
+    });
+}
+
+#[test]
+fn reference_matches_known_perft() {
+    fn perft(board: &Board, depth: u8) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        reference_legal_moves(board)
+            .iter()
+            .map(|m| perft(&board.make_move(*m), depth - 1))
+            .sum()
+    }
+    // kiwipete, and the promotion-heavy position 4 of the standard suite
+    let kiwipete =
+        Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+    assert_eq!(perft(&kiwipete, 2), 2039);
+    let position4 =
+        Board::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1")
+            .unwrap();
+    assert_eq!(perft(&position4, 3), 9467);
+}
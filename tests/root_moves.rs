@@ -0,0 +1,117 @@
+//! The root move list kept across iterations: effort spent per move, the
+//! easy move that ends a timed search early, and the per-move breakdown
+//! handed back with the result.
+
+use ananke::board::Board;
+use ananke::eval::Hce;
+use ananke::search::{AlphaBeta, Limits, Progress, SearchResult, Searcher, search_with};
+use ananke::timeman::TimeBudget;
+use ananke::tt::Bound;
+use ananke::uci::Uci;
+use std::time::Duration;
+
+// a timed search on the virtual clock, a thousand nodes a millisecond, so
+// the same search stops on the same node every run
+fn timed(fen: &str, millis: u64) -> SearchResult {
+    let budget = Duration::from_millis(millis);
+    search_with(
+        &Board::from_fen(fen).unwrap(),
+        Limits {
+            nodes_time: Some(1000),
+            ..Limits::time(TimeBudget {
+                optimum: budget,
+                maximum: budget * 4,
+            })
+        },
+    )
+}
+
+#[test]
+fn an_easy_move_is_played_early() {
+    // the king has to take the queen back; nothing else comes close
+    let result = timed(
+        "rnb1kbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBqKBNR w KQkq - 0 3",
+        2000,
+    );
+    assert_eq!(result.best_move.unwrap().to_string(), "e1d1");
+    // well before half the optimum time, where a search otherwise stops
+    assert!(result.nodes < 1_000_000, "{}", result.nodes);
+
+    // a quiet position, where the moves are close, takes its time
+    let result = timed(
+        "r1bqkb1r/pppp1ppp/2n2n2/4p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R w KQkq - 4 4",
+        2000,
+    );
+    assert!(result.nodes >= 1_000_000, "{}", result.nodes);
+}
+
+#[test]
+fn untimed_searches_never_stop_early() {
+    // the same easy move, to a fixed depth: every iteration is searched
+    let board =
+        Board::from_fen("rnb1kbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBqKBNR w KQkq - 0 3").unwrap();
+    assert_eq!(search_with(&board, Limits::depth(7)).depth, 7);
+}
+
+#[test]
+fn every_root_move_is_accounted_for() {
+    let board =
+        Board::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R w KQkq - 4 4")
+            .unwrap();
+    let mut reports = Vec::new();
+    let result = AlphaBeta::new().search(&board, Limits::depth(5), &mut Hce::default(), &mut |p| {
+        if let Progress::Result(r) = p {
+            reports.push(r.clone())
+        }
+    });
+    assert_eq!(reports.last().unwrap().root_moves, result.root_moves);
+
+    let legal = board.legal_moves();
+    assert_eq!(result.root_moves.len(), legal.len());
+    assert!(
+        legal
+            .iter()
+            .all(|m| result.root_moves.iter().any(|r| r.mv == *m))
+    );
+    // the best first, scored exactly, with the whole line
+    let best = &result.root_moves[0];
+    assert_eq!(Some(best.mv), result.best_move);
+    assert_eq!((best.score, best.bound), (result.score, Bound::Exact));
+    assert_eq!(best.pv, result.pv);
+    // the rest no better, and all of them searched
+    assert!(
+        result.root_moves[1..]
+            .iter()
+            .all(|r| r.score <= result.score && r.nodes > 0)
+    );
+    // every node is below one root move or another
+    let below: u64 = result.root_moves.iter().map(|r| r.nodes).sum();
+    assert_eq!(below, result.nodes);
+}
+
+#[test]
+fn root_move_stats_option() {
+    let mut uci = Uci::new();
+    let mut out = Vec::new();
+    for line in [
+        "setoption name RootMoveStats value true",
+        "position startpos",
+        "go depth 3",
+    ] {
+        uci.handle(line, &mut out).unwrap();
+    }
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out
+        .lines()
+        .filter(|l| l.starts_with("info string rootmove "))
+        .collect();
+    assert_eq!(lines.len(), 20, "{}", out);
+    let bestmove = out
+        .lines()
+        .last()
+        .unwrap()
+        .strip_prefix("bestmove ")
+        .unwrap();
+    assert!(lines[0].starts_with(&format!("info string rootmove {} nodes ", bestmove)));
+    assert!(lines[1..].iter().all(|l| l.contains(" upperbound pv ")));
+}
@@ -0,0 +1,125 @@
+use ananke::board::Board;
+use ananke::search::{MATE, delta_margin, is_futile, is_mate_score, search};
+
+fn best(fen: &str, depth: u8) -> (String, i32) {
+    let result = search(&Board::from_fen(fen).unwrap(), depth);
+    (result.best_move.unwrap().to_string(), result.score)
+}
+
+#[test]
+fn finds_mate_in_one() {
+    assert_eq!(
+        best("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 2),
+        ("a1a8".to_string(), MATE - 1)
+    );
+    // scholar's mate
+    let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - 4 4";
+    assert_eq!(best(fen, 3).0, "f3f7");
+}
+
+#[test]
+fn finds_mate_in_two() {
+    // Qxh7+ Kxh7 would lose the queen; the rook lift mates instead
+    let (_, score) = best("6k1/5ppp/8/8/8/8/1Q6/R5K1 w - - 0 1", 3);
+    assert!(is_mate_score(score) && score > 0);
+}
+
+#[test]
+fn takes_free_material() {
+    assert_eq!(best("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", 2).0, "d1d5");
+}
+
+#[test]
+fn sees_the_recapture() {
+    // the pawn is defended, so taking it loses the queen
+    let (m, _) = best("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1", 2);
+    assert_ne!(m, "d1d5");
+}
+
+#[test]
+fn no_moves() {
+    let mated = search(
+        &Board::from_fen("6k1/5ppp/8/8/8/8/5PPP/r5K1 w - - 0 1").unwrap(),
+        3,
+    );
+    assert_eq!((mated.best_move, mated.score), (None, -MATE));
+
+    let stalemate = search(
+        &Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap(),
+        3,
+    );
+    assert_eq!((stalemate.best_move, stalemate.score), (None, 0));
+}
+
+#[test]
+fn delta_pruning_margin_widens_in_the_endgame() {
+    let start = Board::startpos();
+    let pawns = Board::from_fen("4k3/pppp4/8/8/8/8/4PPPP/4K3 w - - 0 1").unwrap();
+    assert_eq!(delta_margin(&start), 200);
+    assert_eq!(delta_margin(&pawns), 500);
+
+    // taking a pawn 400 down: hopeless in the middlegame, worth a look in a
+    // pawn ending
+    let fen = "4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1";
+    let ending = Board::from_fen(fen).unwrap();
+    let exd5 = ending.parse_uci_move("e4d5").unwrap();
+    assert!(!is_futile(&ending, exd5, -400, 0));
+    assert!(is_futile(&ending, exd5, -700, 0));
+    let fen = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2";
+    let opening = Board::from_fen(fen).unwrap();
+    let exd5 = opening.parse_uci_move("e4d5").unwrap();
+    assert!(is_futile(&opening, exd5, -400, 0));
+    assert!(!is_futile(&opening, exd5, -250, 0));
+
+    // a promotion counts what it makes
+    let board = Board::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let a8q = board.parse_uci_move("a7a8q").unwrap();
+    assert!(!is_futile(&board, a8q, -1000, 0));
+}
+
+#[test]
+fn quiescence_still_sees_the_tactics() {
+    // a rook down, the free queen is still taken
+    assert_eq!(best("4k3/8/8/3q4/8/8/r7/3RK3 w - - 0 1", 2).0, "d1d5");
+    // and the defended pawn still isn't
+    let (m, _) = best("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1", 1);
+    assert_ne!(m, "d1d5");
+}
+
+#[test]
+fn quiescence_doesnt_stand_pat_in_check() {
+    // Qxf7 is mate, and quiescence has to look at black's evasions to see it
+    let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - 4 4";
+    assert_eq!(best(fen, 1), ("f3f7".to_string(), MATE - 1));
+}
+
+#[test]
+fn mate_beats_the_fifty_move_rule() {
+    // Ra8 is the hundredth ply without a capture or pawn move, and mate
+    assert_eq!(
+        best("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80", 2),
+        ("a1a8".to_string(), MATE - 1)
+    );
+    // but a move that only checks is still a draw
+    let result = search(
+        &Board::from_fen("6k1/6pp/8/8/8/8/8/R5K1 w - - 99 80").unwrap(),
+        2,
+    );
+    assert!(result.score < 100, "{}", result.score);
+}
+
+#[test]
+fn quiescence_sees_stalemate() {
+    // Bxh7 takes White's last piece that can move: the king is walled in
+    // by its own pawn and the black king, so it's stalemate. Better than
+    // staying a rook for a bishop down, but only a draw, even when the
+    // capture is the last ply and quiescence has to see it
+    let fen = "8/7R/8/8/4b3/p7/P1k5/K7 b - - 0 1";
+    let board = Board::from_fen(fen).unwrap();
+    let after = board.make_move(board.parse_uci_move("e4h7").unwrap());
+    assert_eq!(after.count_legal_moves(), 0);
+    assert!(!after.in_check());
+    for depth in 1..=3 {
+        assert_eq!(best(fen, depth), ("e4h7".to_string(), 0), "depth {}", depth);
+    }
+}
@@ -0,0 +1,266 @@
+//! Pluggable search: alpha-beta and MCTS behind the same `Searcher` trait,
+//! both honouring the limits, reporting progress, and driven by the engine
+//! and the UCI `Search` option alike.
+
+use ananke::board::{Board, UndoInfo};
+use ananke::engine::Engine;
+use ananke::eval::{self, Evaluator, Hce};
+use ananke::mcts::Mcts;
+use ananke::search::{AlphaBeta, Limits, MATE, Progress, SearchResult, Searcher, search_with};
+use ananke::stats::SearchStats;
+use ananke::timeman::TimeBudget;
+use ananke::types::Move;
+use ananke::uci::Uci;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+fn run(
+    searcher: &mut dyn Searcher,
+    fen: &str,
+    limits: Limits,
+) -> (SearchResult, Vec<SearchResult>) {
+    let board = Board::from_fen(fen).unwrap();
+    let mut reports = Vec::new();
+    let result = searcher.search(&board, limits, &mut Hce::default(), &mut |p| {
+        if let Progress::Result(r) = p {
+            reports.push(r.clone())
+        }
+    });
+    (result, reports)
+}
+
+const BACK_RANK: &str = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+
+#[test]
+fn alpha_beta_reports_every_iteration() {
+    let board = Board::startpos();
+    let (result, reports) = run(&mut AlphaBeta::new(), &board.to_fen(), Limits::depth(4));
+    assert_eq!(result, search_with(&board, Limits::depth(4)));
+    let depths: Vec<u8> = reports.iter().map(|r| r.depth).collect();
+    assert_eq!(depths, [1, 2, 3, 4]);
+    assert_eq!(*reports.last().unwrap(), result);
+    assert!(reports.windows(2).all(|w| w[0].nodes < w[1].nodes));
+}
+
+#[test]
+fn mcts_finds_mates_and_material() {
+    let (result, _) = run(&mut Mcts::new(), BACK_RANK, Limits::nodes(3000));
+    assert_eq!(result.best_move.unwrap().to_string(), "a1a8");
+    assert_eq!(result.score, MATE - 1);
+
+    // the queen hangs, and the rook takes it
+    let (result, _) = run(
+        &mut Mcts::new(),
+        "4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1",
+        Limits::nodes(3000),
+    );
+    assert_eq!(result.best_move.unwrap().to_string(), "d2d5");
+    assert!(result.score > 300, "{}", result.score);
+}
+
+#[test]
+fn mcts_honours_the_limits() {
+    let fen = Board::startpos().to_fen();
+    let (result, reports) = run(&mut Mcts::new(), &fen, Limits::nodes(1234));
+    assert_eq!(result.nodes, 1234);
+    // a report each time the tree grows a ply deeper
+    assert!(reports.len() >= 2);
+    assert!(reports.windows(2).all(|w| w[0].depth < w[1].depth));
+    assert_eq!(reports.last().unwrap().depth, result.depth);
+
+    // depth alone: 100 iterations, doubled per ply
+    let (result, _) = run(&mut Mcts::new(), &fen, Limits::depth(3));
+    assert_eq!(result.nodes, 800);
+
+    // virtual time, read every 256 iterations
+    let budget = Duration::from_millis(10);
+    let limits = Limits {
+        nodes_time: Some(100),
+        ..Limits::time(TimeBudget {
+            optimum: budget,
+            maximum: budget,
+        })
+    };
+    let (result, _) = run(&mut Mcts::new(), &fen, limits);
+    assert_eq!(result.nodes, 1024);
+}
+
+#[test]
+fn both_agree_when_the_game_is_over() {
+    for searcher in [&mut AlphaBeta::new() as &mut dyn Searcher, &mut Mcts::new()] {
+        let limits = Limits {
+            contempt: 20,
+            ..Limits::depth(3)
+        };
+        let (mated, _) = run(
+            searcher,
+            "R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1",
+            limits.clone(),
+        );
+        assert_eq!((mated.best_move, mated.score), (None, -MATE));
+        let (stalemate, _) = run(searcher, "7k/5Q2/8/8/8/8/8/6K1 b - - 0 1", limits);
+        assert_eq!((stalemate.best_move, stalemate.score), (None, -20));
+    }
+}
+
+#[test]
+fn engine_still_moves_in_a_game_drawn_by_rule() {
+    let mut engine = Engine::new();
+    // seventy-five moves without a capture or pawn move, and the rook hanging
+    let board = Board::from_fen("4k3/8/8/8/8/8/r7/4K3 w - - 150 80").unwrap();
+    engine.set_position(board.clone(), "").unwrap();
+    assert!(engine.game().is_over());
+    let result = engine.think(Limits::depth(3));
+    assert!(
+        board
+            .legal_moves()
+            .as_slice()
+            .contains(&result.best_move.unwrap())
+    );
+
+    // repeated five times over
+    let moves = "g1f3 g8f6 f3g1 f6g8 ".repeat(4);
+    engine.set_position(Board::startpos(), &moves).unwrap();
+    assert!(engine.game().is_over());
+    assert!(engine.think(Limits::depth(2)).best_move.is_some());
+
+    // only no legal moves at all means no move
+    let mated = Board::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+    engine.set_position(mated, "").unwrap();
+    let result = engine.think(Limits::depth(2));
+    assert_eq!((result.best_move, result.score), (None, -MATE));
+}
+
+// follows the hooks and checks they pair up
+#[derive(Default)]
+struct Counting {
+    depth: usize,
+    max_depth: usize,
+}
+
+impl Evaluator for Counting {
+    fn evaluate(&self, board: &Board) -> i32 {
+        eval::evaluate(board)
+    }
+
+    fn on_make_move(&mut self, _board: &Board, _m: Move, _undo: &UndoInfo) {
+        self.depth += 1;
+        self.max_depth = self.max_depth.max(self.depth);
+    }
+
+    fn on_unmake_move(&mut self) {
+        self.depth = self.depth.checked_sub(1).expect("unmake without make");
+    }
+
+    fn name(&self) -> &str {
+        "counting"
+    }
+}
+
+#[test]
+fn mcts_keeps_the_evaluator_in_step() {
+    let board = Board::startpos();
+    let mut counting = Counting::default();
+    let result = Mcts::new().search(&board, Limits::nodes(2000), &mut counting, &mut |_| {});
+    assert_eq!(counting.depth, 0);
+    assert_eq!(counting.max_depth, result.depth as usize);
+    assert_eq!(
+        result,
+        Mcts::new().search(
+            &board,
+            Limits::nodes(2000),
+            &mut Hce::default(),
+            &mut |_| {}
+        )
+    );
+}
+
+// plays the first legal move, and remembers what it was asked
+#[derive(Default)]
+struct FirstMove {
+    limits: Rc<Cell<Option<Limits>>>,
+}
+
+impl Searcher for FirstMove {
+    fn search(
+        &mut self,
+        board: &Board,
+        limits: Limits,
+        _evaluator: &mut dyn Evaluator,
+        info: &mut dyn FnMut(Progress),
+    ) -> SearchResult {
+        self.limits.set(Some(limits));
+        let best_move = board.legal_moves().iter().next().copied();
+        let result = SearchResult {
+            best_move,
+            pv: best_move.into_iter().collect(),
+            score: 0,
+            depth: 1,
+            nodes: 1,
+            root_moves: Vec::new(),
+            stats: SearchStats::default(),
+        };
+        info(Progress::Result(&result));
+        result
+    }
+
+    fn name(&self) -> &str {
+        "first"
+    }
+}
+
+#[test]
+fn engine_drives_any_searcher() {
+    let mut engine = Engine::new();
+    engine.set_node_budget(Some(500));
+    let first = FirstMove::default();
+    let asked = first.limits.clone();
+    engine.set_searcher(Box::new(first));
+    assert_eq!(engine.searcher().name(), "first");
+    let mut reports = 0;
+    let result = engine.think_with_info(Limits::depth(5), &mut |_| reports += 1);
+    assert_eq!(
+        result.best_move,
+        Board::startpos().legal_moves().iter().next().copied()
+    );
+    assert_eq!(reports, 1);
+    // with the engine's node budget and contempt filled in
+    let limits = asked.take().unwrap();
+    assert_eq!(limits.nodes, Some(500));
+    assert_eq!(limits.contempt, engine.current_contempt());
+
+    // the budget applies whatever the algorithm
+    engine.set_searcher(Box::new(Mcts::new()));
+    let result = engine.think(Limits::depth(5));
+    assert_eq!(result.nodes, 500);
+    assert!(result.best_move.is_some());
+}
+
+fn uci(script: &str) -> String {
+    let mut out = Vec::new();
+    Uci::new().run(script.as_bytes(), &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn uci_search_option() {
+    let out = uci("uci\n");
+    assert!(out.contains("option name Search type combo default alphabeta var alphabeta var mcts"));
+
+    let out = uci("setoption name Search value MCTS\nposition startpos\ngo nodes 700\n");
+    assert!(
+        out.lines()
+            .any(|l| l.starts_with("info depth") && l.contains(" nodes 700 pv ")),
+        "{}",
+        out
+    );
+    assert!(out.contains("bestmove"));
+
+    let out = uci("setoption name Search value minimax\n");
+    assert!(
+        out.starts_with("info string Unknown search: minimax"),
+        "{}",
+        out
+    );
+}
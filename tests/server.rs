@@ -0,0 +1,124 @@
+//! The HTTP analysis server: the JSON it takes and gives, the errors it
+//! answers with, and a request over a real socket.
+
+use ananke::json::{self, Value};
+use ananke::pool::EnginePool;
+use ananke::server::{handle, respond, serve};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+const BACK_RANK: &str = "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1";
+
+fn post(pool: &EnginePool, body: &str) -> (u16, String) {
+    respond("POST", "/analyse", body, pool)
+}
+
+#[test]
+fn analysis_comes_back_as_json() {
+    let pool = EnginePool::new(1);
+    let (status, body) = post(
+        &pool,
+        &format!(r#"{{"fen": "{}", "depth": 3, "multipv": 3}}"#, BACK_RANK),
+    );
+    assert_eq!(status, 200);
+    assert!(body.starts_with(&format!(
+        r#"{{"fen":"{}","bestmove":"a1a8","lines":[{{"multipv":1,"#,
+        BACK_RANK
+    )));
+    assert!(body.contains(r#""score":{"mate":1}"#));
+    assert!(body.contains(r#"{"multipv":3,"depth":3,"#));
+    assert!(!body.contains(r#""multipv":4"#));
+
+    // a time limit alone, or nodes alone, is enough
+    for limit in [r#""movetime": 50"#, r#""nodes": 2000"#] {
+        let (status, body) = post(&pool, &format!(r#"{{"fen": "{}", {}}}"#, BACK_RANK, limit));
+        assert_eq!(status, 200, "{}", body);
+    }
+}
+
+#[test]
+fn bad_requests_say_why() {
+    let pool = EnginePool::new(1);
+    let error = |body: &str| {
+        let (status, answer) = post(&pool, body);
+        assert_eq!(status, 400, "{}", answer);
+        let members = json::parse_object(&answer).unwrap();
+        assert_eq!(members[0].0, "error");
+        match &members[0].1 {
+            Value::String(message) => message.clone(),
+            other => panic!("{:?}", other),
+        }
+    };
+    assert!(error("not json").starts_with("JSON:"));
+    assert_eq!(error(r#"{"depth": 3}"#), "fen is missing");
+    assert_eq!(
+        error(r#"{"fen": "x", "colour": 1}"#),
+        "unknown field: colour"
+    );
+    assert_eq!(
+        error(&format!(r#"{{"fen": "{}", "depth": -1}}"#, BACK_RANK)),
+        "depth must be a whole number"
+    );
+    assert!(error(r#"{"fen": "not a fen"}"#).contains("FEN"));
+    // the side not to move in check
+    assert!(error(r#"{"fen": "R3k3/8/8/8/8/8/8/4K3 w - - 0 1"}"#).contains("check"));
+
+    assert_eq!(respond("GET", "/analyse", "", &pool).0, 405);
+    assert_eq!(respond("POST", "/", "{}", &pool).0, 404);
+}
+
+#[test]
+fn a_whole_http_exchange() {
+    let pool = EnginePool::new(1);
+    let body = format!(r#"{{"fen": "{}", "depth": 2}}"#, BACK_RANK);
+    let request = format!(
+        "POST /analyse HTTP/1.1\r\nHost: localhost\r\ncontent-length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    let mut out = Vec::new();
+    handle(request.as_bytes(), &mut out, &pool).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let (head, answer) = out.split_once("\r\n\r\n").unwrap();
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(head.contains(&format!("Content-Length: {}\r\n", answer.len())));
+    assert!(answer.contains(r#""bestmove":"a1a8""#));
+
+    let mut out = Vec::new();
+    handle("nonsense\r\n\r\n".as_bytes(), &mut out, &pool).unwrap();
+    assert!(String::from_utf8(out).unwrap().starts_with("HTTP/1.1 400 "));
+}
+
+#[test]
+fn requests_are_served_side_by_side() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let pool = Arc::new(EnginePool::new(2));
+    thread::spawn(move || serve(listener, &pool));
+
+    let clients: Vec<_> = (0..3)
+        .map(|_| {
+            thread::spawn(move || {
+                let body = format!(r#"{{"fen": "{}", "depth": 3}}"#, BACK_RANK);
+                let mut stream = TcpStream::connect(address).unwrap();
+                write!(
+                    stream,
+                    "POST /analyse HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .unwrap();
+                let mut answer = String::new();
+                stream.read_to_string(&mut answer).unwrap();
+                answer
+            })
+        })
+        .collect();
+    for client in clients {
+        let answer = client.join().unwrap();
+        assert!(answer.starts_with("HTTP/1.1 200 OK"), "{}", answer);
+        assert!(answer.contains(r#""bestmove":"a1a8""#));
+    }
+}
@@ -0,0 +1,78 @@
+//! Clean shutdown: a signal turning into a quit on the engine's input, and
+//! the engine answering the search it was in before it exits.
+
+use ananke::signals::{self, quit_on_signal};
+use ananke::uci::Uci;
+use std::io::{BufRead, BufReader, Cursor, Read};
+use std::thread;
+use std::time::Duration;
+
+// input that never ends, as a GUI's pipe doesn't while it waits
+struct Waiting;
+
+impl Read for Waiting {
+    fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            thread::sleep(Duration::from_secs(60));
+        }
+    }
+}
+
+// one test, in order: the flag is for the whole process, and once it's set
+// every input ends in a quit
+#[test]
+fn a_signal_stops_an_infinite_search_with_its_answer() {
+    let mut input = quit_on_signal(Cursor::new("uci\nisready\nlast"));
+    let mut text = String::new();
+    input.read_to_string(&mut text).unwrap();
+    assert_eq!(text, "uci\nisready\nlast\n");
+
+    let script = Cursor::new("position startpos\ngo infinite\n").chain(BufReader::new(Waiting));
+    let input = quit_on_signal(BufReader::new(script));
+    thread::spawn(|| {
+        thread::sleep(Duration::from_millis(200));
+        signals::request();
+    });
+    let mut out = Vec::new();
+    Uci::new().run(input, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.lines().any(|l| l.starts_with("info depth ")));
+    assert!(
+        out.lines().last().unwrap().starts_with("bestmove "),
+        "{}",
+        out
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn the_engine_answers_and_exits_on_sigterm() {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ananke"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // kept open: the engine must stop for the signal, not for the input
+    let mut stdin = child.stdin.take().unwrap();
+    stdin
+        .write_all(b"uci\nposition startpos\ngo infinite\n")
+        .unwrap();
+    let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+    lines
+        .by_ref()
+        .map(Result::unwrap)
+        .find(|l| l.starts_with("info depth 2 "))
+        .unwrap();
+    let killed = Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+    let rest: Vec<String> = lines.map(Result::unwrap).collect();
+    assert!(rest.last().unwrap().starts_with("bestmove "), "{:?}", rest);
+    assert!(child.wait().unwrap().success());
+    drop(stdin);
+}
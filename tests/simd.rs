@@ -0,0 +1,68 @@
+//! The vector loops agree with the plain ones, whole registers and the
+//! lanes left over alike, at the ends of the i16 range, and at every clamp.
+
+use ananke::rng::Rng;
+use ananke::simd::{self, scalar};
+
+// lengths below a register, at one, and with lanes left over
+const LENGTHS: [usize; 7] = [0, 1, 15, 16, 17, 64, 301];
+
+fn values(rng: &mut Rng, n: usize) -> Vec<i16> {
+    (0..n).map(|_| rng.next_u32() as i16).collect()
+}
+
+#[test]
+fn adding_and_taking_away_agree() {
+    let mut rng = Rng::new(7);
+    for n in LENGTHS {
+        let (acc, row) = (values(&mut rng, n), values(&mut rng, n));
+        let (mut fast, mut plain) = (acc.clone(), acc.clone());
+        simd::add(&mut fast, &row);
+        scalar::add(&mut plain, &row);
+        assert_eq!(fast, plain, "add, {} lanes", n);
+        simd::sub(&mut fast, &row);
+        scalar::sub(&mut plain, &row);
+        assert_eq!(fast, plain, "sub, {} lanes", n);
+        assert_eq!(fast, acc);
+    }
+}
+
+#[test]
+fn adding_wraps() {
+    let mut acc = vec![i16::MAX; 20];
+    simd::add(&mut acc, &[1; 20]);
+    assert_eq!(acc, [i16::MIN; 20]);
+    simd::sub(&mut acc, &[1; 20]);
+    assert_eq!(acc, [i16::MAX; 20]);
+}
+
+#[test]
+fn clipped_dot_products_agree() {
+    let mut rng = Rng::new(11);
+    for n in LENGTHS {
+        let (acc, weights) = (values(&mut rng, n), values(&mut rng, n));
+        for max in [-1, 0, 1, 255, i16::MAX / 2, i16::MAX / 2 + 1, i16::MAX] {
+            assert_eq!(
+                simd::clipped_dot(&acc, &weights, max),
+                scalar::clipped_dot(&acc, &weights, max),
+                "{} lanes, clamped to {}",
+                n,
+                max
+            );
+        }
+    }
+}
+
+#[test]
+fn clipped_dot_products_reach_the_extremes() {
+    // every product as big as the clamp allows, either way
+    for max in [i16::MAX / 2, i16::MAX] {
+        let acc = vec![i16::MAX; 4096];
+        let most = max as i64 * i16::MIN as i64 * 4096;
+        assert_eq!(simd::clipped_dot(&acc, &[i16::MIN; 4096], max), most);
+        assert_eq!(
+            simd::clipped_dot(&acc, &[i16::MAX; 4096], max),
+            max as i64 * i16::MAX as i64 * 4096
+        );
+    }
+}
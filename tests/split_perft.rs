@@ -0,0 +1,63 @@
+use ananke::board::Board;
+use ananke::perft::{self, WorkResult, WorkUnit};
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+// serialize everything, as if the units went out to other machines
+fn run_remotely(units: &[WorkUnit]) -> Vec<WorkResult> {
+    units
+        .iter()
+        .map(|u| WorkUnit::from_line(&u.to_line()).unwrap())
+        .map(|u| u.run().unwrap().to_line())
+        .map(|line| WorkResult::from_line(&line).unwrap())
+        .collect()
+}
+
+#[test]
+fn split_and_merge_match_perft() {
+    let board = Board::from_fen(KIWIPETE).unwrap();
+    for split_depth in 0..=4 {
+        let units = perft::split(&board, 3, split_depth);
+        let results = run_remotely(&units);
+        assert_eq!(
+            perft::merge(&units, &results),
+            Ok(97862),
+            "split {}",
+            split_depth
+        );
+    }
+}
+
+#[test]
+fn results_can_arrive_in_any_order() {
+    let units = perft::split(&Board::startpos(), 3, 1);
+    let mut results = run_remotely(&units);
+    results.reverse();
+    assert_eq!(perft::merge(&units, &results), Ok(8902));
+}
+
+#[test]
+fn merge_rejects_incomplete_results() {
+    let units = perft::split(&Board::startpos(), 2, 1);
+    let results = run_remotely(&units);
+
+    assert!(perft::merge(&units, &results[1..]).is_err());
+
+    let mut repeated = results.clone();
+    repeated.push(results[0]);
+    assert!(perft::merge(&units, &repeated).is_err());
+
+    let mut unknown = results.clone();
+    unknown.push(WorkResult { id: 999, nodes: 1 });
+    assert!(perft::merge(&units, &unknown).is_err());
+}
+
+#[test]
+fn bad_lines_are_errors() {
+    assert!(
+        WorkUnit::from_line("3 rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_err()
+    );
+    assert!(WorkUnit::from_line("x 3 8/8/8/8/8/8/8/8 w - - 0 1").is_err());
+    assert!(WorkResult::from_line("12").is_err());
+    assert!(WorkResult::from_line("12 lots").is_err());
+}
@@ -0,0 +1,68 @@
+use ananke::board::Board;
+use ananke::search::{Limits, search_with};
+use ananke::strength::{MAX_ELO, MIN_ELO, MatchScore, nodes_for_elo};
+use ananke::uci::Uci;
+
+#[test]
+fn budget_grows_with_elo() {
+    let budgets: Vec<u64> = (MIN_ELO..=MAX_ELO).step_by(50).map(nodes_for_elo).collect();
+    assert!(budgets.windows(2).all(|w| w[0] <= w[1]), "{:?}", budgets);
+    assert!(budgets[0] < *budgets.last().unwrap());
+
+    // out of range targets clamp to the ends of the ladder
+    assert_eq!(nodes_for_elo(0), nodes_for_elo(MIN_ELO));
+    assert_eq!(nodes_for_elo(5000), nodes_for_elo(MAX_ELO));
+}
+
+#[test]
+fn search_stays_within_the_node_budget() {
+    let board = Board::startpos();
+    for budget in [1, 10, 100, 1000, 10_000] {
+        let result = search_with(&board, Limits::nodes(budget));
+        assert!(result.nodes <= budget, "{} > {}", result.nodes, budget);
+        // even a budget too small to finish depth 1 gives a legal move
+        assert!(board.is_legal(result.best_move.unwrap()));
+    }
+    // more nodes buy more depth
+    let shallow = search_with(&board, Limits::nodes(1000));
+    let deep = search_with(&board, Limits::nodes(100_000));
+    assert!(deep.depth > shallow.depth);
+}
+
+#[test]
+fn match_score_to_elo() {
+    let even = MatchScore {
+        wins: 3,
+        draws: 4,
+        losses: 3,
+    };
+    assert_eq!(even.elo_difference(), 0.0);
+    let ahead = MatchScore {
+        wins: 3,
+        draws: 1,
+        losses: 0,
+    };
+    assert!((ahead.score() - 0.875).abs() < 1e-9);
+    assert!((ahead.elo_difference() - 338.0).abs() < 1.0);
+}
+
+#[test]
+fn uci_limit_strength_sets_a_node_budget() {
+    let mut uci = Uci::new();
+    let mut out = Vec::new();
+    let script = "uci\n\
+        setoption name UCI_LimitStrength value true\n\
+        setoption name UCI_Elo value 800\n\
+        position startpos\n\
+        go\n";
+    uci.run(script.as_bytes(), &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("option name UCI_Elo type spin"));
+
+    // the last report is the final answer
+    let info = out.lines().rfind(|l| l.starts_with("info depth")).unwrap();
+    let (_, nodes) = info.split_once(" nodes ").unwrap();
+    let nodes: u64 = nodes.split(' ').next().unwrap().parse().unwrap();
+    assert!(nodes <= nodes_for_elo(800));
+    assert!(out.lines().any(|l| l.starts_with("bestmove ")));
+}
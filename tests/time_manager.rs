@@ -0,0 +1,262 @@
+use ananke::clock::{Clock, ClockMode, TimeControl};
+use ananke::timeman::{
+    ClockState, MIN_THINK_TIME, MOVE_OVERHEAD, TimeBudget, allocate, allocate_with_overhead,
+    fixed_with_overhead,
+};
+use ananke::types::Color;
+use ananke::uci::Uci;
+use std::time::Duration;
+
+fn secs(s: f64) -> Duration {
+    Duration::from_secs_f64(s)
+}
+
+fn state(time: f64, increment: f64, opponent_time: f64, moves_to_go: Option<u32>) -> ClockState {
+    ClockState {
+        time: secs(time),
+        increment: secs(increment),
+        opponent_time: secs(opponent_time),
+        opponent_increment: secs(increment),
+        moves_to_go,
+    }
+}
+
+// play a whole game on a real clock, each side taking `spend` of its
+// budget plus the latency the overhead is there for. Returns the time left
+// for white at the end, or the side that flagged.
+fn simulate(
+    control: TimeControl,
+    moves: usize,
+    spend: fn(TimeBudget) -> Duration,
+) -> Result<Duration, Color> {
+    let mut clock = Clock::new(control, Color::White);
+    for _ in 0..moves * 2 {
+        let us = clock.to_move();
+        let budget = allocate(&ClockState {
+            time: clock.remaining(us),
+            increment: control.bonus,
+            opponent_time: clock.remaining(us.opposite()),
+            opponent_increment: control.bonus,
+            moves_to_go: None,
+        });
+        clock.press_clock_after(spend(budget) + MOVE_OVERHEAD)?;
+    }
+    Ok(clock.remaining(Color::White))
+}
+
+fn control(base: f64, bonus: f64, mode: ClockMode) -> TimeControl {
+    TimeControl {
+        base: secs(base),
+        bonus: secs(bonus),
+        mode,
+    }
+}
+
+#[test]
+fn fischer_adds_the_increment_after_every_move() {
+    let mut clock = Clock::new(control(10.0, 2.0, ClockMode::Increment), Color::White);
+    clock.press_clock_after(secs(0.5)).unwrap();
+    assert_eq!(clock.remaining(Color::White), secs(11.5));
+    assert_eq!(clock.to_move(), Color::Black);
+    clock.press_clock_after(secs(5.0)).unwrap();
+    assert_eq!(clock.remaining(Color::Black), secs(7.0));
+}
+
+#[test]
+fn delay_runs_the_clock_only_after_the_delay() {
+    let mut clock = Clock::new(control(10.0, 2.0, ClockMode::Delay), Color::White);
+    // inside the delay: nothing comes off, and nothing is added either
+    clock.press_clock_after(secs(1.5)).unwrap();
+    assert_eq!(clock.remaining(Color::White), secs(10.0));
+    clock.press_clock_after(secs(5.0)).unwrap();
+    assert_eq!(clock.remaining(Color::Black), secs(7.0));
+    // the delay comes off before the flag is checked: 12s spent is 10s
+    // charged, which White just has, while Black's 9.5s is 7.5s, too many
+    clock.press_clock_after(secs(12.0)).unwrap();
+    assert_eq!(clock.remaining(Color::White), Duration::ZERO);
+    assert_eq!(clock.press_clock_after(secs(9.5)), Err(Color::Black));
+    assert_eq!(clock.flagged(), Some(Color::Black));
+    assert_eq!(clock.remaining(Color::Black), Duration::ZERO);
+}
+
+#[test]
+fn bronstein_gives_back_the_time_used_up_to_the_bonus() {
+    let mut clock = Clock::new(control(10.0, 2.0, ClockMode::Bronstein), Color::White);
+    clock.press_clock_after(secs(1.5)).unwrap();
+    assert_eq!(clock.remaining(Color::White), secs(10.0));
+    clock.press_clock_after(secs(5.0)).unwrap();
+    assert_eq!(clock.remaining(Color::Black), secs(7.0));
+    // unlike a delay, the bonus comes after the move: it can't save a flag
+    clock.press_clock_after(secs(10.0)).unwrap();
+    assert_eq!(clock.remaining(Color::White), secs(2.0));
+    assert_eq!(clock.press_clock_after(secs(7.5)), Err(Color::Black));
+    // and once fallen, the flag stays down
+    assert_eq!(clock.press_clock_after(secs(0.0)), Err(Color::Black));
+    assert_eq!(clock.remaining(Color::White), secs(2.0));
+}
+
+#[test]
+fn sudden_death_never_flags() {
+    let control = TimeControl::fischer(secs(60.0), Duration::ZERO);
+    // a long game still leaves something on the clock
+    let left = simulate(control, 80, |b| b.optimum).unwrap();
+    assert!(left > secs(1.0), "{:?}", left);
+}
+
+#[test]
+fn increment_keeps_the_clock_alive() {
+    let control = TimeControl::fischer(secs(180.0), secs(2.0));
+    let left = simulate(control, 300, |b| b.optimum).unwrap();
+    // settles around a few increments rather than draining to nothing
+    assert!(left > secs(2.0), "{:?}", left);
+}
+
+#[test]
+fn repeating_controls_reach_each_boundary() {
+    // 40 moves in 60 seconds, three times over; the GUI counts movestogo
+    // down and the clock is topped up at each control
+    let mut time = secs(60.0);
+    for _control in 0..3 {
+        for moves_to_go in (1..=40).rev() {
+            let budget = allocate(&state(
+                time.as_secs_f64(),
+                0.0,
+                time.as_secs_f64(),
+                Some(moves_to_go),
+            ));
+            // a single overrun to the hard limit is survivable
+            assert!(budget.maximum + MOVE_OVERHEAD < time);
+            time -= budget.optimum + MOVE_OVERHEAD;
+        }
+        // most of the clock went on this control, not the next
+        assert!(time > secs(0.5) && time < secs(15.0), "{:?}", time);
+        time += secs(60.0);
+    }
+}
+
+#[test]
+fn last_move_before_the_control_uses_most_of_the_clock() {
+    let budget = allocate(&state(10.0, 0.0, 10.0, Some(1)));
+    assert!(budget.optimum > secs(4.0), "{:?}", budget);
+    assert!(budget.maximum > secs(8.0) && budget.maximum < secs(10.0) - MOVE_OVERHEAD);
+
+    // with moves still to go the same clock is spread out
+    let budget = allocate(&state(10.0, 0.0, 10.0, Some(20)));
+    assert!(budget.optimum < secs(1.0));
+}
+
+#[test]
+fn movestogo_zero_is_sudden_death() {
+    assert_eq!(
+        allocate(&state(60.0, 0.0, 60.0, Some(0))),
+        allocate(&state(60.0, 0.0, 60.0, None))
+    );
+}
+
+#[test]
+fn bolder_when_ahead_on_the_clock() {
+    let even = allocate(&state(60.0, 0.0, 60.0, None)).optimum;
+    let ahead = allocate(&state(60.0, 0.0, 20.0, None)).optimum;
+    let behind = allocate(&state(60.0, 0.0, 180.0, None)).optimum;
+    assert!(
+        ahead > even && even > behind,
+        "{:?} {:?} {:?}",
+        ahead,
+        even,
+        behind
+    );
+
+    // a huge lead still doesn't blow the hard limit
+    let budget = allocate(&state(60.0, 0.0, 0.5, None));
+    assert!(budget.optimum <= budget.maximum);
+
+    // no opponent clock: no adjustment
+    assert_eq!(allocate(&state(60.0, 0.0, 0.0, None)).optimum, even);
+}
+
+#[test]
+fn opponent_increment_counts() {
+    // equal clocks, but the opponent gets 5s a move and we get none
+    let mut clock = state(60.0, 0.0, 60.0, None);
+    let even = allocate(&clock).optimum;
+    clock.opponent_increment = secs(5.0);
+    assert!(allocate(&clock).optimum < even);
+}
+
+#[test]
+fn nearly_out_of_time() {
+    // all of it is overhead, so only the floor is left: a tenth of the clock
+    let budget = allocate(&state(0.01, 0.0, 60.0, None));
+    assert_eq!(budget.optimum, Duration::from_millis(1));
+    assert_eq!(budget.maximum, Duration::from_millis(1));
+    // with a little more on it, the usual minimum
+    let budget = allocate(&state(0.2, 0.0, 60.0, None));
+    assert_eq!(budget.optimum, MIN_THINK_TIME);
+}
+
+#[test]
+fn move_overhead_comes_off_the_clock() {
+    let clock = state(10.0, 0.0, 10.0, Some(1));
+    assert_eq!(
+        allocate(&clock),
+        allocate_with_overhead(&clock, MOVE_OVERHEAD)
+    );
+    let slow = allocate_with_overhead(&clock, secs(2.0));
+    assert!(slow.maximum < allocate(&clock).maximum);
+    assert!(slow.maximum <= secs(8.0 * 0.9));
+    // more overhead than time: only the minimum
+    let least = allocate_with_overhead(&clock, secs(30.0));
+    assert_eq!(
+        (least.optimum, least.maximum),
+        (MIN_THINK_TIME, MIN_THINK_TIME)
+    );
+}
+
+#[test]
+fn move_overhead_comes_off_movetime() {
+    let budget = fixed_with_overhead(secs(1.0), MOVE_OVERHEAD);
+    assert_eq!(budget.maximum, secs(1.0) - MOVE_OVERHEAD);
+    assert_eq!(budget.optimum, budget.maximum);
+    let least = fixed_with_overhead(secs(1.0), secs(2.0));
+    assert_eq!(
+        (least.optimum, least.maximum),
+        (MIN_THINK_TIME, MIN_THINK_TIME)
+    );
+    // but never past the move time itself
+    let short = fixed_with_overhead(Duration::from_millis(4), MOVE_OVERHEAD);
+    assert_eq!(short.maximum, Duration::from_millis(4));
+}
+
+fn uci(script: &str) -> String {
+    let mut out = Vec::new();
+    Uci::new().run(script.as_bytes(), &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn uci_move_overhead_option() {
+    let out = uci("uci\n");
+    assert!(out.contains("option name Move Overhead type spin default 30 min 0 max 5000"));
+    let out = uci("setoption name Move Overhead value soon\n");
+    assert!(
+        out.starts_with("info string Invalid Move Overhead"),
+        "{}",
+        out
+    );
+
+    // with all but 10ms of the clock kept back, the answer comes at once
+    let start = std::time::Instant::now();
+    let out = uci("setoption name Move Overhead value 1990\n\
+         position startpos\n\
+         go wtime 2000 btime 2000 movestogo 1\n");
+    assert!(start.elapsed() < secs(1.0), "{:?}", start.elapsed());
+    assert!(out.contains("bestmove "));
+
+    // and the same off a fixed movetime
+    let start = std::time::Instant::now();
+    let out = uci("setoption name Move Overhead value 5000\n\
+         position startpos\n\
+         go movetime 3000\n");
+    assert!(start.elapsed() < secs(1.0), "{:?}", start.elapsed());
+    assert!(out.contains("bestmove "));
+}
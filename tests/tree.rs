@@ -0,0 +1,67 @@
+//! The search tree dump: every node kept down to the ply asked for, in the
+//! shape of the tree, from the iteration the result came from, with what
+//! pruned or settled each one.
+
+use ananke::board::Board;
+use ananke::search::{Limits, search_tree, search_with};
+use ananke::tree::Outcome;
+
+const ITALIAN: &str = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+
+#[test]
+fn the_tree_is_shaped_like_the_search() {
+    let board = Board::from_fen(ITALIAN).unwrap();
+    let (result, tree) = search_tree(&board, Limits::depth(5), 4);
+    // keeping the tree changes nothing about the search
+    let plain = search_with(&board, Limits::depth(5));
+    assert_eq!(
+        (result.best_move, result.score),
+        (plain.best_move, plain.score)
+    );
+    assert_eq!(result.nodes, plain.nodes);
+
+    assert_eq!(tree.depth, result.depth);
+    let root = &tree.nodes[0];
+    assert_eq!((root.parent, root.mv, root.ply), (None, None, 0));
+    assert_eq!(root.score.unwrap().0, result.score);
+    for (i, node) in tree.nodes.iter().enumerate().skip(1) {
+        let parent = &tree.nodes[node.parent.unwrap()];
+        assert!(node.parent.unwrap() < i);
+        assert_eq!(node.ply, parent.ply + 1);
+        assert!(node.ply <= 4);
+        assert!(node.mv.is_some());
+        assert_eq!(node.score.is_none(), node.outcome == Outcome::Pruned);
+    }
+    // every root move is searched
+    let children = tree.nodes.iter().filter(|n| n.parent == Some(0)).count();
+    assert_eq!(children, board.legal_moves().len());
+    let outcomes: Vec<Outcome> = tree.nodes.iter().map(|n| n.outcome).collect();
+    for outcome in [Outcome::Searched, Outcome::Pruned, Outcome::Table] {
+        assert!(outcomes.contains(&outcome), "no {:?}", outcome);
+    }
+}
+
+#[test]
+fn a_cut_off_iteration_leaves_the_last_one_finished() {
+    let board = Board::from_fen(ITALIAN).unwrap();
+    let limits = Limits {
+        nodes: Some(20_000),
+        ..Limits::depth(20)
+    };
+    let (result, tree) = search_tree(&board, limits, 2);
+    assert!(result.depth > 1 && result.depth < 20);
+    assert_eq!(tree.depth, result.depth);
+    assert_eq!(tree.nodes[0].score.unwrap().0, result.score);
+}
+
+#[test]
+fn json_and_graphviz_have_every_node() {
+    let board = Board::from_fen(ITALIAN).unwrap();
+    let (_, tree) = search_tree(&board, Limits::depth(3), 3);
+    let json = tree.to_json();
+    assert_eq!(json.matches("\"id\":").count(), tree.nodes.len());
+    assert!(json.contains("\"outcome\":\"quiescence\""));
+    let dot = tree.to_dot();
+    assert!(dot.starts_with("digraph search {"));
+    assert_eq!(dot.matches(" -> ").count(), tree.nodes.len() - 1);
+}
@@ -0,0 +1,199 @@
+//! The transposition table on its own (replacement, resizing, clearing), and
+//! through the search and the front ends that size it.
+
+use ananke::board::Board;
+use ananke::engine::Engine;
+use ananke::eval::Hce;
+use ananke::search::{AlphaBeta, Limits, MATE, SearchResult, Searcher};
+use ananke::tt::{Bound, TranspositionTable, TtEntry};
+use ananke::uci::Uci;
+use ananke::xboard::Xboard;
+
+fn entry(depth: u8) -> TtEntry {
+    TtEntry {
+        best_move: None,
+        score: depth as i32,
+        depth,
+        bound: Bound::Exact,
+    }
+}
+
+// hashes this small all land in the first slot
+#[test]
+fn deeper_entries_win_within_a_search() {
+    let mut tt = TranspositionTable::new(1);
+    tt.store(1, entry(5));
+    tt.store(2, entry(3));
+    assert_eq!(tt.probe(1), Some(entry(5)));
+    assert_eq!(tt.probe(2), None);
+    // but anything from the next search replaces it
+    tt.new_search();
+    tt.store(2, entry(3));
+    assert_eq!(tt.probe(1), None);
+    assert_eq!(tt.probe(2), Some(entry(3)));
+}
+
+#[test]
+fn a_result_without_a_move_keeps_the_old_one() {
+    let board = Board::startpos();
+    let e4 = board.parse_uci_move("e2e4").unwrap();
+    let mut tt = TranspositionTable::new(1);
+    tt.store(
+        7,
+        TtEntry {
+            best_move: Some(e4),
+            ..entry(2)
+        },
+    );
+    tt.store(7, entry(4));
+    assert_eq!(tt.probe(7).unwrap().best_move, Some(e4));
+    assert_eq!(tt.probe(7).unwrap().depth, 4);
+}
+
+#[test]
+fn resizing_keeps_what_fits() {
+    let mut tt = TranspositionTable::new(2);
+    let hashes: Vec<u64> = (1..=1000u64)
+        .map(|i| i.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        .collect();
+    for &hash in &hashes {
+        tt.store(hash, entry(1));
+    }
+    tt.resize(4);
+    assert_eq!(tt.size_mb(), 4);
+    assert!(hashes.iter().all(|&hash| tt.probe(hash) == Some(entry(1))));
+    // shrinking can only lose entries, never mix them up
+    tt.resize(1);
+    assert!(
+        hashes
+            .iter()
+            .all(|&hash| tt.probe(hash).is_none_or(|e| e == entry(1)))
+    );
+    assert!(tt.hashfull() > 0);
+    tt.clear();
+    assert_eq!(tt.hashfull(), 0);
+}
+
+// the same position searched again with the table from the first time
+fn twice(fen: &str, depth: u8) -> (SearchResult, SearchResult) {
+    let board = Board::from_fen(fen).unwrap();
+    let mut searcher = AlphaBeta::new();
+    let mut search = || {
+        searcher.search(
+            &board,
+            Limits::depth(depth),
+            &mut Hce::default(),
+            &mut |_| {},
+        )
+    };
+    (search(), search())
+}
+
+#[test]
+fn the_table_saves_nodes() {
+    let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R w KQkq - 4 4";
+    let (first, second) = twice(fen, 6);
+    assert_eq!(second.best_move, first.best_move);
+    assert!(
+        second.nodes < first.nodes / 2,
+        "{} {}",
+        first.nodes,
+        second.nodes
+    );
+}
+
+#[test]
+fn mates_come_back_scored_from_the_root() {
+    // mate in two, stored at every ply along the way
+    let (first, second) = twice("k7/8/2K5/8/8/8/8/7R w - - 0 1", 5);
+    assert_eq!(first.score, MATE - 3);
+    assert_eq!(second.score, MATE - 3);
+    assert_eq!(second.pv, first.pv);
+}
+
+#[test]
+fn hash_option_resizes_and_says_so() {
+    let mut uci = Uci::new();
+    let mut out = Vec::new();
+    uci.handle("uci", &mut out).unwrap();
+    uci.handle("setoption name Hash value 3", &mut out).unwrap();
+    uci.handle("setoption name Hash value 0", &mut out).unwrap();
+    uci.handle("setoption name Hash value lots", &mut out)
+        .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("option name Hash type spin default 16 min 1 max 4096\n"));
+    let replies: Vec<&str> = out
+        .lines()
+        .filter(|l| l.starts_with("info string"))
+        .collect();
+    assert_eq!(
+        replies,
+        [
+            "info string Hash 3 MB",
+            "info string Hash 1 MB",
+            "info string Invalid Hash: lots"
+        ]
+    );
+
+    // the table stays the size set when the search changes and back
+    let mut out = Vec::new();
+    uci.handle("setoption name Search value mcts", &mut out)
+        .unwrap();
+    uci.handle("setoption name Hash value 8", &mut out).unwrap();
+    uci.handle("setoption name Search value alphabeta", &mut out)
+        .unwrap();
+    uci.handle("uci", &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with("info string Hash 8 MB, for the alphabeta search\n"));
+    assert!(out.contains("option name Hash type spin default 8 "));
+}
+
+#[test]
+fn xboard_memory_sizes_the_table() {
+    let mut xboard = Xboard::new();
+    let mut out = Vec::new();
+    xboard.handle("protover 2", &mut out).unwrap();
+    xboard.handle("memory 64", &mut out).unwrap();
+    xboard.handle("memory -1", &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains(" memory=1 "));
+    assert!(out.ends_with("Error (bad memory): -1\n"));
+}
+
+// a game through the engine: what one search leaves in the table is there
+// for the next, until a new game
+#[test]
+fn the_table_lasts_the_game() {
+    let think = |engine: &mut Engine, moves: &str| {
+        engine.set_position(Board::startpos(), moves).unwrap();
+        engine.think(Limits::depth(5))
+    };
+    let mut engine = Engine::new();
+    let first = think(&mut engine, "");
+    let reply: Vec<String> = first.pv[..2].iter().map(|m| m.to_string()).collect();
+    let moves = reply.join(" ");
+    let warm = think(&mut engine, &moves);
+    let cold = think(&mut Engine::new(), &moves);
+    // the two can settle on different moves: what the table holds changes
+    // which moves are pruned
+    assert!(warm.nodes < cold.nodes, "{} {}", warm.nodes, cold.nodes);
+
+    engine.new_game();
+    assert_eq!(think(&mut engine, &moves), cold);
+}
+
+#[test]
+fn the_last_best_move_is_searched_first() {
+    // cut off before depth 1 finishes, a search answers with the first
+    // root move: the ordering's pick on an empty table, the move the
+    // earlier search settled on otherwise
+    let board =
+        Board::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R w KQkq - 4 4")
+            .unwrap();
+    let mut searcher = AlphaBeta::new();
+    let mut search = |limits| searcher.search(&board, limits, &mut Hce::default(), &mut |_| {});
+    let cold = search(Limits::nodes(1));
+    let deep = search(Limits::depth(5));
+    assert_ne!(deep.best_move, cold.best_move);
+    assert_eq!(search(Limits::nodes(1)).best_move, deep.best_move);
+}
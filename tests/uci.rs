@@ -0,0 +1,315 @@
+use ananke::board::Board;
+use ananke::uci::Uci;
+use std::io::{BufReader, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn session(script: &str) -> (Uci, String) {
+    let mut uci = Uci::new();
+    let mut out = Vec::new();
+    uci.run(script.as_bytes(), &mut out).unwrap();
+    (uci, String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn handshake() {
+    let (_, out) = session("uci\nisready\nquit\n");
+    let lines: Vec<&str> = out.lines().collect();
+    assert!(lines[0].starts_with("id name ananke"));
+    assert_eq!(&lines[lines.len() - 2..], ["uciok", "readyok"]);
+}
+
+#[test]
+fn position_with_moves() {
+    let (uci, _) = session("position startpos moves e2e4 e7e5 g1f3\n");
+    assert_eq!(
+        uci.board().to_fen(),
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+    );
+}
+
+#[test]
+fn bad_position_keeps_the_old_one() {
+    let (uci, out) = session("position startpos moves e2e4\nposition startpos moves e2e5\n");
+    assert!(out.starts_with("info string"));
+    assert_eq!(
+        uci.board().to_fen(),
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+    );
+}
+
+fn bestmove(out: &str) -> &str {
+    let line = out.lines().find(|l| l.starts_with("bestmove")).unwrap();
+    line.strip_prefix("bestmove ").unwrap()
+}
+
+#[test]
+fn go_answers_with_a_legal_move() {
+    let (_, out) = session("position fen 7k/8/8/8/8/8/8/K5R1 w - - 0 1\ngo depth 2\n");
+    let board = ananke::board::Board::from_fen("7k/8/8/8/8/8/8/K5R1 w - - 0 1").unwrap();
+    assert!(board.parse_uci_move(bestmove(&out)).is_ok());
+    // an info line per iteration
+    let lines: Vec<&str> = out.lines().collect();
+    assert!(lines[0].starts_with("info depth 1 score cp"), "{}", out);
+    assert!(lines[1].starts_with("info depth 2 score cp"), "{}", out);
+    assert!(lines[2].starts_with("bestmove"), "{}", out);
+
+    let (_, out) = session("position fen 7k/6Q1/6K1/8/8/8/8/8 b - - 0 1\ngo\n");
+    assert_eq!(bestmove(&out), "0000");
+}
+
+#[test]
+fn go_reports_mate_scores() {
+    let (_, out) = session("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo depth 2\n");
+    // quiescence sees the mate already at depth 1
+    assert!(out.starts_with("info depth 1 score mate 1 "), "{}", out);
+    assert_eq!(bestmove(&out), "a1a8");
+}
+
+#[test]
+fn go_on_the_clock_stays_inside_its_time() {
+    let start = std::time::Instant::now();
+    let (_, out) = session("position startpos\ngo wtime 2000 btime 2000 movestogo 1\n");
+    // the last move before a control may use most of the clock, not all of it
+    assert!(start.elapsed() < std::time::Duration::from_millis(2000));
+    assert!(
+        ananke::board::Board::startpos()
+            .parse_uci_move(bestmove(&out))
+            .is_ok()
+    );
+
+    let (_, out) = session("position startpos\ngo movetime 50\n");
+    assert!(
+        ananke::board::Board::startpos()
+            .parse_uci_move(bestmove(&out))
+            .is_ok()
+    );
+}
+
+#[test]
+fn go_mate_looks_as_deep_as_the_mate() {
+    // mate in two (Kb6, then the rook) needs three plies
+    let fen = "k7/8/2K5/8/8/8/8/7R w - - 0 1";
+    let (_, out) = session(&format!("position fen {}\ngo mate 2\n", fen));
+    let last = out.lines().rfind(|l| l.starts_with("info depth")).unwrap();
+    assert!(last.starts_with("info depth 3 score mate 2 "), "{}", out);
+    assert!(out.ends_with("bestmove c6b6\n"), "{}", out);
+
+    // and no further without one
+    let (_, out) = session(&format!("position fen {}\ngo mate 1\n", fen));
+    let last = out.lines().rfind(|l| l.starts_with("info depth")).unwrap();
+    assert!(last.starts_with("info depth 1 score cp"), "{}", out);
+
+    // a mate too long to count in plies is as deep as search goes
+    let (_, out) = session(&format!(
+        "position fen {}\ngo depth 3 mate 9223372036854775808\n",
+        fen
+    ));
+    assert!(out.ends_with("bestmove c6b6\n"), "{}", out);
+}
+
+#[test]
+fn go_reports_the_principal_variation() {
+    let (_, out) = session("position startpos moves e2e4 e7e5\ngo depth 4\n");
+    let last = out
+        .lines()
+        .rfind(|l| l.starts_with("info depth 4"))
+        .unwrap();
+    let (_, pv) = last.split_once(" pv ").unwrap();
+    let pv: Vec<&str> = pv.split(' ').collect();
+    assert_eq!(pv.len(), 4, "{}", out);
+    assert_eq!(
+        out.lines().last(),
+        Some(format!("bestmove {}", pv[0]).as_str())
+    );
+    // every move legal where it's played
+    let mut board = Board::startpos();
+    board.apply_uci_moves("e2e4 e7e5").unwrap();
+    for m in pv {
+        board = board.make_move(board.parse_uci_move(m).unwrap());
+    }
+
+    // the mate is the whole line
+    let fen = "k7/8/2K5/8/8/8/8/7R w - - 0 1";
+    let (_, out) = session(&format!("position fen {}\ngo depth 3\n", fen));
+    let last = out.lines().rfind(|l| l.starts_with("info depth")).unwrap();
+    assert!(last.ends_with(" pv c6b6 a8b8 h1h8"), "{}", out);
+}
+
+// output the test can read while the engine is still writing it
+#[derive(Clone, Default)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Shared {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+
+    fn wait_until(&self, done: impl Fn(&str) -> bool) -> String {
+        let start = Instant::now();
+        while !done(&self.text()) {
+            assert!(start.elapsed() < Duration::from_secs(20), "{}", self.text());
+            thread::sleep(Duration::from_millis(5));
+        }
+        self.text()
+    }
+}
+
+#[test]
+fn go_infinite_answers_on_stop() {
+    let (reader, mut writer) = std::io::pipe().unwrap();
+    let out = Shared::default();
+    let engine = {
+        let out = out.clone();
+        thread::spawn(move || Uci::new().run(BufReader::new(reader), out))
+    };
+
+    // the mate is found at once, but the answer waits for stop
+    writeln!(writer, "position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    writeln!(writer, "go infinite").unwrap();
+    out.wait_until(|text| text.contains("score mate 1"));
+    thread::sleep(Duration::from_millis(100));
+    assert!(!out.text().contains("bestmove"));
+    writeln!(writer, "stop").unwrap();
+    let text = out.wait_until(|text| text.contains("bestmove"));
+    assert!(text.ends_with("bestmove a1a8\n"), "{}", text);
+
+    // and stop cuts a search short that would otherwise run for ever
+    writeln!(writer, "position startpos\ngo infinite").unwrap();
+    thread::sleep(Duration::from_millis(100));
+    writeln!(writer, "stop").unwrap();
+    let text = out.wait_until(|text| text.matches("bestmove").count() == 2);
+    assert!(
+        ananke::board::Board::startpos()
+            .parse_uci_move(text.rsplit("bestmove ").next().unwrap().trim())
+            .is_ok()
+    );
+    writeln!(writer, "quit").unwrap();
+    engine.join().unwrap().unwrap();
+
+    // scripted: a stop read before the search starts still stops it, and
+    // so do quit and the end of the input
+    for script in [
+        "go infinite\nstop\n",
+        "go infinite\nquit\n",
+        "go infinite\n",
+    ] {
+        let (_, out) = session(&format!("position startpos\n{}", script));
+        assert_eq!(out.matches("bestmove").count(), 1, "{}", out);
+    }
+    // a stop before the go doesn't count
+    let (_, out) = session("stop\nposition startpos\ngo depth 2\n");
+    assert!(out.contains("info depth 2 "), "{}", out);
+}
+
+#[test]
+fn every_go_gets_exactly_one_bestmove() {
+    let mate = "position fen 7k/6Q1/6K1/8/8/8/8/8 b - - 0 1";
+    for search in ["alphabeta", "mcts"] {
+        for script in [
+            "position startpos\ngo nodes 0\n",
+            "position startpos\ngo movetime 0\n",
+            "position startpos\ngo infinite\nstop\nstop\n",
+            &format!("{}\ngo depth 3\n", mate),
+            &format!("{}\ngo infinite\nquit\n", mate),
+            // a second go ends the first search rather than queueing
+            // behind a go infinite that would never answer
+            "position startpos\ngo infinite\ngo depth 1\n",
+            "position startpos\ngo infinite\ngo infinite\nstop\n",
+        ] {
+            let script = format!("setoption name Search value {}\n{}", search, script);
+            let (_, out) = session(&script);
+            let gos = script.lines().filter(|l| l.starts_with("go")).count();
+            assert_eq!(out.matches("bestmove").count(), gos, "{}\n{}", script, out);
+            if script.contains(mate) {
+                assert!(out.ends_with("bestmove 0000\n"), "{}", out);
+            }
+        }
+    }
+}
+
+#[test]
+fn isready_is_answered_during_a_search() {
+    let (reader, mut writer) = std::io::pipe().unwrap();
+    let out = Shared::default();
+    let engine = {
+        let out = out.clone();
+        thread::spawn(move || Uci::new().run(BufReader::new(reader), out))
+    };
+
+    writeln!(writer, "position startpos\ngo infinite").unwrap();
+    out.wait_until(|text| text.contains("info depth 1 "));
+    writeln!(writer, "isready").unwrap();
+    out.wait_until(|text| text.contains("readyok"));
+    assert!(!out.text().contains("bestmove"));
+
+    // quit ends the search, which still answers, and then the session
+    writeln!(writer, "quit").unwrap();
+    engine.join().unwrap().unwrap();
+    let text = out.text();
+    assert_eq!(text.matches("bestmove").count(), 1, "{}", text);
+    assert_eq!(text.matches("readyok").count(), 1, "{}", text);
+    assert!(
+        text.lines()
+            .all(|line| !line.contains("readyok") || line == "readyok")
+    );
+
+    // after the search, isready is answered in turn as usual
+    let (_, out) = session("isready\nposition startpos\ngo depth 2\nisready\n");
+    assert_eq!(out.matches("readyok").count(), 2, "{}", out);
+}
+
+#[test]
+fn long_searches_report_the_current_move_and_line() {
+    // on the virtual clock a node is a millisecond, so the reports start
+    // a thousand nodes in
+    let script = "setoption name nodestime value 1\n\
+                  setoption name UCI_ShowCurrLine value true\n\
+                  position startpos moves e2e4\n\
+                  go depth 5\n";
+    let (uci, out) = session(script);
+    let board = uci.board().clone();
+    let mut numbers = Vec::new();
+    let mut lines = 0;
+    for line in out.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if let Some(i) = words.iter().position(|&w| w == "currmove") {
+            assert!(board.parse_uci_move(words[i + 1]).is_ok(), "{}", line);
+            assert_eq!(words[i + 2], "currmovenumber");
+            numbers.push(words[i + 3].parse::<usize>().unwrap());
+        }
+        if let Some(moves) = line.strip_prefix("info currline ") {
+            // every line played out from the position, legally
+            let mut position = board.clone();
+            for m in moves.split_whitespace() {
+                let m = position.parse_uci_move(m).unwrap();
+                position = position.make_move(m);
+            }
+            lines += 1;
+        }
+    }
+    // each iteration counts its root moves up from one
+    let legal = board.legal_moves().len();
+    assert!(numbers.contains(&legal));
+    assert!(numbers.windows(2).all(|w| w[1] == w[0] + 1 || w[1] == 1));
+    assert!(lines > 0);
+
+    // without UCI_ShowCurrLine, the moves only
+    let (_, out) = session("setoption name nodestime value 1\ngo depth 5\n");
+    assert!(out.contains(" currmove "));
+    assert!(!out.contains("currline"));
+    // and short searches say nothing of either
+    let (_, out) = session("go depth 5\n");
+    assert!(!out.contains("currmove"));
+}
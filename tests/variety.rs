@@ -0,0 +1,121 @@
+//! Variety in the opening: moves close to the best played at random early
+//! on, the same picks again from the same seed, and the best move as ever
+//! once the opening is over, in analysis, or with no margin.
+
+use ananke::board::{Board, START_FEN};
+use ananke::engine::Engine;
+use ananke::search::Limits;
+use ananke::uci::Uci;
+use ananke::variety::VarietyRules;
+
+const MIDDLEGAME: &str = "r1bq1rk1/pp2bppp/2n1pn2/3p4/2PP4/2N1PN2/PP3PPP/R2QKB1R w KQ - 0 20";
+
+fn engine(margin: i32, seed: u32) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_variety(VarietyRules {
+        margin,
+        seed,
+        ..VarietyRules::default()
+    });
+    engine
+}
+
+// the engine's moves from the start, playing both sides for `plies`
+fn opening(engine: &mut Engine, plies: usize) -> Vec<String> {
+    let mut moves: Vec<String> = Vec::new();
+    for _ in 0..plies {
+        engine
+            .set_position(Board::from_fen(START_FEN).unwrap(), &moves.join(" "))
+            .unwrap();
+        let m = engine.think(Limits::depth(3)).best_move.unwrap();
+        moves.push(m.to_string());
+    }
+    moves
+}
+
+#[test]
+fn seeds_pick_different_openings_and_repeat_their_own() {
+    let openings: Vec<Vec<String>> = (1..=6)
+        .map(|seed| opening(&mut engine(40, seed), 4))
+        .collect();
+    assert!(openings.iter().any(|o| *o != openings[0]), "{:?}", openings);
+    assert_eq!(opening(&mut engine(40, 3), 4), openings[2]);
+}
+
+#[test]
+fn the_picked_move_is_close_to_the_best() {
+    let mut plain = Engine::new();
+    plain.set_position(Board::startpos(), "").unwrap();
+    let lines = plain.think_multipv(Limits::depth(3), 4, &mut |_, _| {});
+    for seed in 1..=6 {
+        let mut engine = engine(40, seed);
+        engine.set_position(Board::startpos(), "").unwrap();
+        let result = engine.think(Limits::depth(3));
+        let line = lines
+            .iter()
+            .find(|line| line.best_move == result.best_move)
+            .unwrap();
+        assert!(line.score >= lines[0].score - 40);
+        assert_eq!(result.score, line.score);
+    }
+}
+
+#[test]
+fn the_best_move_stands_without_a_margin_or_past_the_opening() {
+    let best = |engine: &mut Engine, fen: &str| {
+        engine
+            .set_position(Board::from_fen(fen).unwrap(), "")
+            .unwrap();
+        engine.think(Limits::depth(3)).best_move
+    };
+    let mut plain = Engine::new();
+    for seed in 1..=4 {
+        assert_eq!(
+            best(&mut engine(0, seed), START_FEN),
+            best(&mut plain, START_FEN)
+        );
+        assert_eq!(
+            best(&mut engine(40, seed), MIDDLEGAME),
+            best(&mut plain, MIDDLEGAME)
+        );
+    }
+}
+
+// the first move of a Uci set up with `options`, from each of a few seeds
+fn uci_first_moves(options: &[&str]) -> Vec<String> {
+    (1..=6)
+        .map(|seed| {
+            let mut uci = Uci::new();
+            let mut out = Vec::new();
+            let seed = format!("setoption name VarietySeed value {}", seed);
+            for command in
+                options
+                    .iter()
+                    .copied()
+                    .chain([seed.as_str(), "position startpos", "go depth 3"])
+            {
+                uci.handle(command, &mut out).unwrap();
+            }
+            let out = String::from_utf8(out).unwrap();
+            let last = out.lines().last().unwrap();
+            last.strip_prefix("bestmove ").unwrap().to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn uci_sets_it_and_analysis_turns_it_off() {
+    let mut out = Vec::new();
+    Uci::new().handle("uci", &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("option name Variety type spin default 0 min 0 max 200"));
+    assert!(out.contains("option name VarietyMoves type spin default 8 min 1 max 40"));
+
+    let varied = uci_first_moves(&["setoption name Variety value 40"]);
+    assert!(varied.iter().any(|m| *m != varied[0]), "{:?}", varied);
+    let analysed = uci_first_moves(&[
+        "setoption name Variety value 40",
+        "setoption name UCI_AnalyseMode value true",
+    ]);
+    assert!(analysed.iter().all(|m| *m == analysed[0]), "{:?}", analysed);
+}
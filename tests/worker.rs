@@ -0,0 +1,108 @@
+//! The engine worker thread: searches that come back over the channel,
+//! stops that end them mid-search, and state that lasts between commands.
+
+use ananke::board::Board;
+use ananke::search::Limits;
+use ananke::worker::{Command, Event, Worker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// the next answer, skipping the progress before it
+fn best_move(worker: &Worker) -> String {
+    loop {
+        match worker.recv().unwrap() {
+            Event::BestMove(result) => return result.best_move.unwrap().to_string(),
+            Event::Info(_) => {}
+            Event::Error(e) => panic!("{}", e),
+        }
+    }
+}
+
+fn set_position(worker: &mut Worker, fen: &str, moves: &str) {
+    worker.send(Command::SetPosition {
+        board: Board::from_fen(fen).unwrap(),
+        moves: moves.to_string(),
+    });
+}
+
+#[test]
+fn searches_come_back_in_order() {
+    let mut worker = Worker::new();
+    set_position(&mut worker, "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "");
+    worker.send(Command::Go(Limits::depth(3)));
+    // queued behind the search, and handled once it's over
+    set_position(&mut worker, "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1b1");
+    assert_eq!(best_move(&worker), "a1a8");
+    worker.send(Command::Go(Limits::depth(1)));
+    // Black's move this time, from the back two ranks
+    let reply = best_move(&worker);
+    assert!(matches!(&reply[1..2], "7" | "8"), "{}", reply);
+    assert!(worker.try_recv().is_none());
+}
+
+#[test]
+fn progress_comes_before_the_answer() {
+    let mut worker = Worker::new();
+    worker.send(Command::Go(Limits::depth(3)));
+    let mut depths = Vec::new();
+    let best = loop {
+        match worker.recv().unwrap() {
+            Event::Info(result) => depths.push(result.depth),
+            Event::BestMove(result) => break result,
+            Event::Error(e) => panic!("{}", e),
+        }
+    };
+    assert_eq!(depths, [1, 2, 3]);
+    assert_eq!(best.depth, 3);
+}
+
+#[test]
+fn stop_ends_a_search_at_once() {
+    let mut worker = Worker::new();
+    worker.send(Command::Go(Limits::depth(u8::MAX)));
+    thread::sleep(Duration::from_millis(50));
+    let start = Instant::now();
+    worker.send(Command::Stop);
+    best_move(&worker);
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    // a new go ends the search before it, which still answers first
+    worker.send(Command::Go(Limits::depth(u8::MAX)));
+    thread::sleep(Duration::from_millis(50));
+    worker.send(Command::Go(Limits::depth(2)));
+    best_move(&worker);
+    let second = loop {
+        if let Event::BestMove(result) = worker.recv().unwrap() {
+            break result;
+        }
+    };
+    assert_eq!(second.depth, 2);
+}
+
+#[test]
+fn bad_positions_are_reported() {
+    let mut worker = Worker::new();
+    worker.send(Command::SetPosition {
+        board: Board::startpos(),
+        moves: "e2e5".to_string(),
+    });
+    assert!(matches!(worker.recv(), Some(Event::Error(_))));
+    // the position before stands
+    worker.send(Command::NewGame);
+    worker.send(Command::Go(Limits::depth(1)));
+    best_move(&worker);
+}
+
+#[test]
+fn quit_ends_the_thread() {
+    let mut worker = Worker::new();
+    worker.send(Command::Go(Limits::depth(u8::MAX)));
+    thread::sleep(Duration::from_millis(50));
+    worker.send(Command::Quit);
+    // the search answers, and then there is nothing more
+    best_move(&worker);
+    assert!(worker.recv().is_none());
+    // commands to a thread that's gone are dropped, and so is the handle
+    worker.send(Command::Go(Limits::depth(1)));
+    drop(worker);
+}
@@ -0,0 +1,190 @@
+use ananke::board::Board;
+use ananke::xboard::Xboard;
+
+fn session(script: &str) -> (Xboard, Vec<String>) {
+    let mut xboard = Xboard::new();
+    let mut out = Vec::new();
+    xboard.run(script.as_bytes(), &mut out).unwrap();
+    let lines = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(String::from)
+        .collect();
+    (xboard, lines)
+}
+
+#[test]
+fn handshake() {
+    let (_, out) = session("xboard\nprotover 2\nping 7\n");
+    assert!(out[0].starts_with("feature myname=\"ananke"));
+    assert!(out[0].ends_with("done=1"));
+    assert_eq!(out[1], "pong 7");
+}
+
+#[test]
+fn engine_answers_user_moves() {
+    let (xboard, out) = session("new\nusermove e2e4\n");
+    assert_eq!(out.len(), 1);
+    let reply = out[0].strip_prefix("move ").unwrap();
+    let after_e4 = Board::startpos().make_move(Board::startpos().parse_uci_move("e2e4").unwrap());
+    assert!(after_e4.parse_uci_move(reply).is_ok());
+    assert_eq!(xboard.board().fullmove_number, 2);
+}
+
+#[test]
+fn force_mode_only_records_moves() {
+    let (xboard, out) = session("new\nforce\nusermove e2e4\ne7e5\n");
+    assert!(out.is_empty());
+    assert_eq!(
+        xboard.board().to_fen(),
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"
+    );
+
+    // go hands the side to move to the engine
+    let (_, out) = session("new\nforce\nusermove e2e4\ngo\n");
+    assert!(out[0].starts_with("move "));
+}
+
+#[test]
+fn illegal_moves_are_refused() {
+    let (xboard, out) = session("new\nusermove e2e5\n");
+    assert_eq!(out, ["Illegal move: e2e5"]);
+    assert_eq!(xboard.board().to_fen(), Board::startpos().to_fen());
+}
+
+#[test]
+fn game_end_is_reported() {
+    let (_, out) = session("new\nforce\nf2f3\ne7e5\ng2g4\nd8h4\n");
+    assert_eq!(out, ["0-1 {Checkmate}"]);
+
+    // after a result the engine stops replying until told to play again
+    let (_, out) = session("new\nresult 1-0 {White resigns}\nusermove e2e4\n");
+    assert!(out.is_empty());
+}
+
+#[test]
+fn time_controls_reach_the_search() {
+    use ananke::engine::DEFAULT_DEPTH;
+    use ananke::timeman::{self, ClockState};
+    use std::time::Duration;
+
+    let (xboard, _) = session("new\n");
+    assert_eq!(xboard.limits().depth, DEFAULT_DEPTH);
+    assert_eq!(xboard.limits().time, None);
+
+    // 40 moves in 5 minutes, our clock at 4 minutes and theirs at 3
+    let (xboard, out) = session("new\nforce\nlevel 40 5 0\ntime 24000\notim 18000\n");
+    assert!(out.is_empty());
+    let expected = timeman::allocate(&ClockState {
+        time: Duration::from_secs(240),
+        opponent_time: Duration::from_secs(180),
+        moves_to_go: Some(40),
+        ..ClockState::default()
+    });
+    assert_eq!(xboard.limits().time, Some(expected));
+    assert_eq!(xboard.limits().depth, u8::MAX);
+
+    // before any time command, the level's base time; moves to go count
+    // down through the control
+    let (xboard, _) = session("new\nforce\nlevel 0 2:30 1.5\ne2e4\ne7e5\n");
+    let expected = timeman::allocate(&ClockState {
+        time: Duration::from_secs(150),
+        increment: Duration::from_millis(1500),
+        opponent_increment: Duration::from_millis(1500),
+        ..ClockState::default()
+    });
+    assert_eq!(xboard.limits().time, Some(expected));
+    let (xboard, _) = session("new\nforce\nlevel 40 5 0\ne2e4\ne7e5\n");
+    let expected = timeman::allocate(&ClockState {
+        time: Duration::from_secs(300),
+        moves_to_go: Some(39),
+        ..ClockState::default()
+    });
+    assert_eq!(xboard.limits().time, Some(expected));
+
+    // st is exact but for the overhead, and sd still caps the depth
+    let (xboard, _) = session("new\nlevel 40 5 0\nst 2\nsd 6\ntime 100\n");
+    let limits = xboard.limits();
+    let expected = Duration::from_secs(2) - timeman::MOVE_OVERHEAD;
+    assert_eq!(limits.time.unwrap().maximum, expected);
+    assert_eq!(limits.time.unwrap().optimum, expected);
+    assert_eq!(limits.depth, 6);
+
+    let (_, out) = session("level 40 x 0\nst soon\ntime -1\n");
+    assert_eq!(
+        out,
+        [
+            "Error (bad level): 40 x 0",
+            "Error (bad time): soon",
+            "Error (bad time): -1"
+        ]
+    );
+}
+
+#[test]
+fn move_overhead_option() {
+    use ananke::timeman::{self, ClockState};
+    use std::time::Duration;
+
+    let (_, out) = session("xboard\nprotover 2\n");
+    assert!(out[0].contains(" option=\"Move Overhead -spin 30 0 5000\" "));
+
+    let (xboard, out) = session("option Move Overhead=500\nlevel 0 1 0\nst 2\n");
+    assert!(out.is_empty());
+    assert_eq!(
+        xboard.limits().time.unwrap().maximum,
+        Duration::from_millis(1500)
+    );
+    let (xboard, _) = session("option Move Overhead=500\nlevel 0 1 0\n");
+    let expected = timeman::allocate_with_overhead(
+        &ClockState {
+            time: Duration::from_secs(60),
+            ..ClockState::default()
+        },
+        Duration::from_millis(500),
+    );
+    assert_eq!(xboard.limits().time, Some(expected));
+
+    let (_, out) = session("option Move Overhead=soon\noption Hash=64\n");
+    assert_eq!(
+        out,
+        [
+            "Error (bad option): Move Overhead=soon",
+            "Error (bad option): Hash=64"
+        ]
+    );
+}
+
+#[test]
+fn out_of_range_times_are_refused() {
+    use std::time::Duration;
+
+    let (xboard, out) = session("new\nst 1e30\nst inf\nst NaN\nlevel 40 5 inf\nlevel 40 5 -1\n");
+    assert_eq!(
+        out,
+        [
+            "Error (bad time): 1e30",
+            "Error (bad time): inf",
+            "Error (bad time): NaN",
+            "Error (bad level): 40 5 inf",
+            "Error (bad level): 40 5 -1"
+        ]
+    );
+    assert_eq!(xboard.limits().time, None);
+
+    // minutes that overflow once turned into seconds
+    let (_, out) = session("level 0 18446744073709551615 0\nlevel 0 307445734561825860:59 0\n");
+    assert_eq!(
+        out,
+        [
+            "Error (bad level): 0 18446744073709551615 0",
+            "Error (bad level): 0 307445734561825860:59 0"
+        ]
+    );
+
+    // clocks too big to count in milliseconds are just very long
+    let (xboard, out) = session("new\ntime 18446744073709551615\notim 18446744073709551615\n");
+    assert!(out.is_empty());
+    let time = xboard.limits().time.unwrap();
+    assert!(time.maximum > Duration::from_secs(1 << 40), "{:?}", time);
+}
@@ -0,0 +1,139 @@
+//! The incrementally updated Zobrist hash against a from-scratch hash, and
+//! which parts of the position it does and doesn't see.
+
+use ananke::board::Board;
+use ananke::playout::random_playout;
+use ananke::rng::Rng;
+use ananke::zobrist;
+
+fn play(board: &Board, moves: &str) -> Board {
+    let mut board = board.clone();
+    board.apply_uci_moves(moves).unwrap();
+    board
+}
+
+#[test]
+fn incremental_hash_matches_recompute() {
+    let mut rng = Rng::new(7);
+    for _ in 0..100 {
+        let mut board = Board::startpos();
+        for _ in 0..200 {
+            assert_eq!(board.hash, zobrist::hash(&board), "{}", board.to_fen());
+            // and a board read back from its own FEN agrees
+            let reparsed = Board::from_fen(&board.to_fen()).unwrap();
+            assert_eq!(board.hash, reparsed.hash, "{}", board.to_fen());
+            if board.count_legal_moves() == 0 {
+                break;
+            }
+            board = random_playout(&board, &mut rng, 1);
+        }
+    }
+}
+
+#[test]
+fn special_moves_keep_the_hash_in_step() {
+    for (fen, moves) in [
+        // both castles, rights lost by a rook capture
+        (
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+            "e1g1 e8c8 f1f8 d8f8",
+        ),
+        // en passant, then a promotion with capture
+        ("1n2k3/P7/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6 e8d7 a7b8q"),
+        // underpromotion
+        ("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b7b8n"),
+    ] {
+        let board = play(&Board::from_fen(fen).unwrap(), moves);
+        assert_eq!(board.hash, zobrist::hash(&board), "{} {}", fen, moves);
+    }
+}
+
+#[test]
+fn transpositions_hash_the_same() {
+    let start = Board::startpos();
+    let a = play(&start, "g1f3 g8f6 b1c3 b8c6");
+    let b = play(&start, "b1c3 b8c6 g1f3 g8f6");
+    assert_eq!(a.hash, b.hash);
+
+    // knights out and back: same position, move clocks aside
+    let back = play(&start, "g1f3 g8f6 f3g1 f6g8");
+    assert_eq!(back.hash, start.hash);
+}
+
+#[test]
+fn hash_sees_side_castling_and_en_passant() {
+    let hash = |fen: &str| Board::from_fen(fen).unwrap().hash;
+    let base = hash("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    assert_ne!(base, hash("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1"));
+    assert_ne!(base, hash("r3k2r/8/8/8/8/8/8/R3K2R w Kkq - 0 1"));
+    assert_ne!(base, hash("r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1"));
+
+    // the clocks aren't part of the position
+    assert_eq!(base, hash("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 37 60"));
+
+    let ep = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+    assert_ne!(hash(ep), hash("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1"));
+}
+
+#[test]
+fn color_flipped_boards_are_rehashed() {
+    let board = play(&Board::startpos(), "e2e4 c7c5 g1f3");
+    let flipped = board.color_flipped();
+    assert_eq!(flipped.hash, zobrist::hash(&flipped));
+    assert_ne!(flipped.hash, board.hash);
+    assert_eq!(flipped.color_flipped().hash, board.hash);
+}
+
+#[test]
+fn keys_are_distinct_and_non_zero() {
+    use ananke::bitboard::Square;
+    use ananke::types::{CastlingRights, Color, PieceType};
+    use std::collections::HashSet;
+
+    let mut keys = Vec::new();
+    for color in [Color::White, Color::Black] {
+        for pt in PieceType::iter() {
+            keys.extend((0..64).map(|sq| zobrist::piece_key(color, pt, Square::new(sq))));
+        }
+    }
+    // one key per combination of rights; none at all hashes to zero
+    assert_eq!(zobrist::castling_key(CastlingRights(0)), 0);
+    keys.extend((1..16).map(|rights| zobrist::castling_key(CastlingRights(rights))));
+    keys.extend((0..8).map(|file| zobrist::en_passant_key(Square::new(32 + file))));
+    keys.push(zobrist::side_key());
+
+    assert_eq!(keys.len(), 768 + 15 + 8 + 1);
+    assert!(!keys.contains(&0));
+    assert_eq!(keys.iter().collect::<HashSet<_>>().len(), keys.len());
+}
+
+// every position in the tree under `board`, by hash; panics on two
+// different positions with the same hash
+fn collect(board: &Board, depth: u8, seen: &mut std::collections::HashMap<u64, String>) {
+    // the FEN without its move clocks, which the hash doesn't see
+    let fen = board.to_fen();
+    let position: Vec<&str> = fen.split(' ').take(4).collect();
+    let position = position.join(" ");
+    match seen.get(&board.hash) {
+        Some(other) => assert_eq!(*other, position, "collision on {:016x}", board.hash),
+        None => {
+            seen.insert(board.hash, position);
+        }
+    }
+    if depth == 0 {
+        return;
+    }
+    for m in board.legal_moves().iter() {
+        collect(&board.make_move(*m), depth - 1, seen);
+    }
+}
+
+#[test]
+fn no_collisions_over_perft_trees() {
+    let mut seen = std::collections::HashMap::new();
+    collect(&Board::startpos(), 5, &mut seen);
+    let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    collect(&Board::from_fen(kiwipete).unwrap(), 3, &mut seen);
+    // over a million distinct positions, from five million nodes
+    assert!(seen.len() > 1_000_000, "{}", seen.len());
+}